@@ -0,0 +1,59 @@
+//! Rough timings of the conversion routines under whichever LUT features are
+//! enabled. Compare builds to see what a LUT is worth, for example
+//!
+//! ```text
+//! $ cargo bench
+//! $ cargo bench --no-default-features --features std,l5_to_l8_lut,l6_to_l8_lut,l5_to_s8_lut,l6_to_s8_lut
+//! ```
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use rgb565::convert;
+use rgb565::frame::{self, ColumnOrder};
+use rgb565::Rgb565;
+
+const ROUNDS: u32 = 200;
+
+fn bench<T>(name: &str, mut f: impl FnMut(u16) -> T) {
+	let start = Instant::now();
+
+	for _ in 0..ROUNDS {
+		for i in 0..=u16::MAX {
+			black_box(f(black_box(i)));
+		}
+	}
+
+	let per_pixel = start.elapsed().as_secs_f64() * 1e9 / (ROUNDS as f64 * 65536.0);
+	println!("{:<24} {:>8.3} ns/pixel", name, per_pixel);
+}
+
+/// Times a routine over a whole 1080p frame.
+fn bench_frame(name: &str, mut f: impl FnMut(&[u16], &mut [u16])) {
+	let src: Vec<u16> = (0..1920 * 1080).map(|i| i as u16).collect();
+	let mut dst = vec![0; src.len()];
+	let rounds = ROUNDS / 20;
+	let start = Instant::now();
+
+	for _ in 0..rounds {
+		f(black_box(&src), black_box(&mut dst));
+	}
+
+	let per_pixel = start.elapsed().as_secs_f64() * 1e9 / (rounds as f64 * src.len() as f64);
+	println!("{:<24} {:>8.3} ns/pixel", name, per_pixel);
+}
+
+fn main() {
+	bench("to_rgb888_components", |i| Rgb565::from_rgb565(i).to_rgb888_components());
+	bench("to_srgb888_components", |i| Rgb565::from_rgb565(i).to_srgb888_components());
+	bench("from_rgb888_components", |i| Rgb565::from_rgb888_components(i as u8, (i >> 8) as u8, (i >> 4) as u8));
+	let mut rgb888 = vec![0; 1920 * 1080 * 3];
+	bench_frame("to_rgb888_1080p", |src, _| convert::rgb565_to_rgb888(src, black_box(&mut rgb888)));
+	bench_frame("from_rgb888_1080p", |_, dst| convert::rgb888_to_rgb565(black_box(&rgb888), dst));
+	let table = frame::Correction::new().with_gamma(2.2).to_table();
+	bench_frame("apply_lut_1080p", |src, dst| {
+		dst.copy_from_slice(src);
+		frame::apply_lut(dst, &table);
+	});
+	bench_frame("rotate_1080p", |src, dst| frame::column_major_into(src, 1920, dst, ColumnOrder::CLOCKWISE));
+}
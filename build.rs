@@ -1,62 +1,276 @@
 use std::fs::File;
 use std::io::{BufWriter, Write};
+use std::path::PathBuf;
 
 use with_std::{srgb_transfer, srgb_untransfer};
 
 #[path = "src/std.rs"]
 mod with_std;
 
+fn powf(x: f32, y: f32) -> f32 { x.powf(y) }
+
+#[cfg(feature = "fixed_srgb")]
+#[allow(unused)]
+#[path = "src/fixed_srgb.rs"]
+mod fixed_srgb;
+
 #[path = "src/transforms.rs"]
 mod transforms;
 
-#[allow(unused)]
-macro_rules! pick_empty {
-	(($something:tt); $whatever:tt; $else:tt) => { $whatever };
-	((); $whatever:tt; $else:tt) => { $else }
+/// An entry in a LUT, which is written as little-endian bytes for
+/// `include_bytes!`.
+trait Entry {
+	fn write_bytes(&self, writer: &mut dyn Write) -> ::std::io::Result<()>;
+}
+
+impl Entry for u16 {
+	fn write_bytes(&self, writer: &mut dyn Write) -> ::std::io::Result<()> { writer.write_all(&self.to_le_bytes()) }
+}
+
+/// A LUT that the build script knows how to pick.
+struct Lut {
+	name: &'static str,
+	size: usize,
+	/// Whether the LUT's feature was enabled.
+	enabled: bool,
+	/// Generates the LUT, or `None` if `src/lut.rs` computes it at compile time
+	/// with `const fn`s.
+	generate: Option<fn(&mut dyn Write) -> ::std::io::Result<()>>
+}
+
+macro_rules! generator {
+	() => { None };
+	($func:ident) => { Some($func) }
 }
 
 macro_rules! lut_gen {
-	{$name:ident: $($feature:literal: $func:ident($size:literal $(* $mult:literal)?) => |$i:ident| $calc:expr),*} =>
-{$(#[cfg(feature = $feature)]
-fn $func(writer: &mut dyn Write) -> ::std::io::Result<()> {
+	{$name:ident: $($feature:literal: $size:literal $(* $mult:literal)? $(=> $func:ident: |$i:ident| $calc:expr)?),*} =>
+{$($(fn $func(writer: &mut dyn Write) -> ::std::io::Result<()> {
 	for $i in 0..=$size {
-		writer.write_all(pick_empty!(($($mult)?); { $($calc as [u8; $mult])? }; { [$calc] }).as_slice())?;
+		$calc.write_bytes(writer)?;
 	}
 
 	Ok(())
-}
+})?
 
-)*const $name: &[(&str, usize, fn(&mut dyn Write) -> ::std::io::Result<()>)] = &[$(
-	#[cfg(feature = $feature)]
-	(stringify!($func), $size as usize $(* $mult)?, $func)),*
+)*const $name: &[Lut] = &[$(
+	Lut {
+		name: $feature,
+		size: ($size as usize + 1) $(* $mult)?,
+		enabled: cfg!(feature = $feature),
+		generate: generator!($($func)?)
+	}),*
 ];}
 }
 
+// Everything but the 32 MiB LUTs is computed at compile time, which the
+// compiler couldn't do for those in any reasonable amount of time or memory.
 lut_gen! { LUTS:
-	"swap_components_lut": swap_components_lut(65535u16 * 2) => |i| transforms::swap_components(i).to_le_bytes(),
-	"l5_to_l8_lut": l5_to_l8_lut(31u8) => |i| transforms::l5_to_l8(i),
-	"l6_to_l8_lut": l6_to_l8_lut(63u8) => |i| transforms::l6_to_l8(i),
-	"l5_to_s8_lut": l5_to_s8_lut(31u8) => |i| transforms::l5_to_s8(i),
-	"l6_to_s8_lut": l6_to_s8_lut(63u8) => |i| transforms::l6_to_s8(i),
-	"l565_to_l888_lut": l565_to_l888_lut(65535u16 * 3) => |i| transforms::l565_to_l888(i),
-	"l565_to_s888_lut": l565_to_s888_lut(65535u16 * 3) => |i| transforms::l565_to_s888(i),
-	"l8_to_l5_lut": l8_to_l5_lut(255u8) => |i| transforms::l8_to_l5(i),
-	"l8_to_l6_lut": l8_to_l6_lut(255u8) => |i| transforms::l8_to_l6(i),
-	"s8_to_l5_lut": s8_to_l5_lut(255u8) => |i| transforms::s8_to_l5(i),
-	"s8_to_l6_lut": s8_to_l6_lut(255u8) => |i| transforms::s8_to_l6(i),
-	"l888_to_l565_lut": l888_to_l565_lut(16777215u32 * 2) => |i| transforms::l888_to_l565([(i >> 16) as u8, (i >> 8) as u8, i as u8]).to_le_bytes(),
-	"s888_to_l565_lut": s888_to_l565_lut(16777215u32 * 2) => |i| transforms::s888_to_l565([(i >> 16) as u8, (i >> 8) as u8, i as u8]).to_le_bytes()
+	"swap_components_lut": 65535u16 * 2,
+	"l5_to_l8_lut": 31u8,
+	"l6_to_l8_lut": 63u8,
+	"l5_to_s8_lut": 31u8,
+	"l6_to_s8_lut": 63u8,
+	"l565_to_l888_lut": 65535u16 * 3,
+	"l565_to_s888_lut": 65535u16 * 3,
+	"l8_to_l5_lut": 255u8,
+	"l8_to_l6_lut": 255u8,
+	"s8_to_l5_lut": 255u8,
+	"s8_to_l6_lut": 255u8,
+	"l888_to_l565_lut": 16777215u32 * 2 => l888_to_l565_lut: |i| transforms::l888_to_l565([(i >> 16) as u8, (i >> 8) as u8, i as u8]),
+	"s888_to_l565_lut": 16777215u32 * 2 => s888_to_l565_lut: |i| transforms::s888_to_l565([(i >> 16) as u8, (i >> 8) as u8, i as u8])
+}
+
+/// The order in which `auto_luts` picks LUTs, as long as they fit in the LUT
+/// budget. sRGB comes first because computing it is by far the slowest.
+const AUTO_LUTS: &[&str] = &[
+	"l5_to_s8_lut",
+	"l6_to_s8_lut",
+	"s8_to_l5_lut",
+	"s8_to_l6_lut",
+	"l5_to_l8_lut",
+	"l6_to_l8_lut",
+	"l8_to_l5_lut",
+	"l8_to_l6_lut",
+	"l565_to_s888_lut",
+	"l565_to_l888_lut",
+	"swap_components_lut"
+];
+
+/// The LUT budget for bare-metal targets, which fits every per-channel LUT.
+const BARE_METAL_LUT_BUDGET: usize = 2 * 1024;
+
+/// The LUT budget for hosted targets, which fits everything but the 32 MiB LUTs.
+const HOSTED_LUT_BUDGET: usize = 1024 * 1024;
+
+/// Whether the target has a hardware floating-point unit. Without one, the
+/// per-channel sRGB LUTs are picked regardless of the budget.
+fn target_has_fpu() -> bool {
+	let var = |name: &str| std::env::var(name).unwrap_or_default();
+	let features = var("CARGO_CFG_TARGET_FEATURE");
+	let has_feature = |feature: &str| features.split(',').any(|f| f == feature);
+
+	match var("CARGO_CFG_TARGET_ARCH").as_str() {
+		"arm" => var("CARGO_CFG_TARGET_ABI").ends_with("hf") || ["vfp2", "vfp3", "vfp4", "fp-armv8"].into_iter().any(has_feature),
+		"riscv32" | "riscv64" => has_feature("f"),
+		"avr" | "msp430" => false,
+		_ => var("CARGO_CFG_TARGET_OS") != "none"
+	}
+}
+
+/// Whether the target has the vector instructions the `simd` module is written
+/// for at compile time: NEON on AArch64, or SSSE3 on x86.
+fn target_has_simd() -> bool {
+	let var = |name: &str| std::env::var(name).unwrap_or_default();
+	let features = var("CARGO_CFG_TARGET_FEATURE");
+	let has_feature = |feature: &str| features.split(',').any(|f| f == feature);
+
+	match var("CARGO_CFG_TARGET_ARCH").as_str() {
+		"aarch64" => has_feature("neon"),
+		"x86" | "x86_64" => has_feature("ssse3"),
+		_ => false
+	}
+}
+
+/// Whether to compile in the vectorized kernels. `RGB565_SIMD` turns them on
+/// or off, otherwise they're used with the `simd` feature, or with `auto_luts`
+/// when the target is known to have the instructions for them.
+fn select_simd() -> bool {
+	match std::env::var("RGB565_SIMD").as_deref().map(str::trim) {
+		Ok("1" | "on" | "true") => true,
+		Ok("0" | "off" | "false") => false,
+		Ok(value) => panic!("RGB565_SIMD should be on or off, not {:?}", value),
+		Err(_) => cfg!(feature = "simd") || (cfg!(feature = "auto_luts") && target_has_simd())
+	}
+}
+
+/// Picks the LUTs to include. LUTs whose features are enabled are always
+/// included. Beyond that, `RGB565_LUTS` can list more LUTs by name, otherwise
+/// the `auto_luts` feature picks them based on the target.
+fn select_luts(simd: bool) -> Vec<&'static Lut> {
+	let mut selected: Vec<&Lut> = LUTS.iter().filter(|lut| lut.enabled).collect();
+	let find = |name: &str| LUTS.iter().find(|lut| lut.name == name).unwrap_or_else(|| panic!("unknown LUT {:?}", name));
+
+	if let Ok(names) = std::env::var("RGB565_LUTS") {
+		selected.extend(names.split(',').map(str::trim).filter(|name| !name.is_empty()).map(find));
+	} else if cfg!(feature = "auto_luts") {
+		let mut budget = match std::env::var("RGB565_LUT_BUDGET") {
+			Ok(budget) => budget.trim().parse().expect("RGB565_LUT_BUDGET should be a number of bytes"),
+			Err(_) if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("none") => BARE_METAL_LUT_BUDGET,
+			Err(_) => HOSTED_LUT_BUDGET
+		};
+
+		let has_fpu = target_has_fpu();
+		// The vectorized kernels widen whole frames to 8-bit RGB faster than
+		// the table could, so it would only take up space.
+		let has_simd = simd && target_has_simd();
+
+		for lut in AUTO_LUTS.iter().copied().map(find) {
+			let needs_fpu = matches!(lut.name, "l5_to_s8_lut" | "l6_to_s8_lut" | "s8_to_l5_lut" | "s8_to_l6_lut");
+
+			if has_simd && lut.name == "l565_to_l888_lut" {
+				continue;
+			}
+
+			if lut.size <= budget || (needs_fpu && !has_fpu) {
+				budget = budget.saturating_sub(lut.size);
+				selected.push(lut);
+			}
+		}
+	}
+
+	selected.sort_by_key(|lut| lut.name);
+	selected.dedup_by_key(|lut| lut.name);
+	selected
+}
+
+/// Which sRGB conversions exist, as cfgs for the crate, given whether each LUT
+/// was picked:
+///
+/// - `has_srgb_math` when the sRGB curve can be computed, with `std`, `libm`,
+///   `fast_srgb` or `fixed_srgb`
+/// - `has_srgb` when RGB565 can be converted to 8-bit sRGB
+/// - `has_srgb_channels` when single channels can be converted to 8-bit sRGB
+/// - `has_srgb_decode` when 8-bit sRGB can be converted to RGB565
+fn srgb_cfgs(has_lut: impl Fn(&str) -> bool) -> [(&'static str, bool); 4] {
+	let math = cfg!(any(feature = "std", feature = "libm", feature = "fast_srgb", feature = "fixed_srgb"));
+	let channels = math || (has_lut("l5_to_s8_lut") && has_lut("l6_to_s8_lut"));
+
+	[
+		("has_srgb_math", math),
+		("has_srgb", channels || has_lut("l565_to_s888_lut")),
+		("has_srgb_channels", channels),
+		("has_srgb_decode", math || has_lut("s888_to_l565_lut"))
+	]
 }
 
 fn main() {
+	println!("cargo:rerun-if-changed=build.rs");
+	println!("cargo:rerun-if-changed=src/std.rs");
+	println!("cargo:rerun-if-changed=src/transforms.rs");
+	println!("cargo:rerun-if-changed=src/fixed_srgb.rs");
+	println!("cargo:rerun-if-env-changed=RGB565_LUT_DIR");
+	println!("cargo:rerun-if-env-changed=RGB565_LUTS");
+	println!("cargo:rerun-if-env-changed=RGB565_LUT_BUDGET");
+	println!("cargo:rerun-if-env-changed=RGB565_SIMD");
+
+	let names = LUTS.iter().map(|lut| format!("{:?}", lut.name)).collect::<Vec<_>>();
+	println!("cargo:rustc-check-cfg=cfg(lut, values({}))", names.join(", "));
+	println!("cargo:rustc-check-cfg=cfg(simd_backend)");
+	println!("cargo:rustc-check-cfg=cfg(has_srgb_math, has_srgb, has_srgb_channels, has_srgb_decode)");
+
+	let simd = select_simd();
+
+	if simd {
+		println!("cargo:rustc-cfg=simd_backend");
+	}
+
 	let out_dir = std::env::var("OUT_DIR").unwrap();
 
-	for (name, size, func) in LUTS.iter().copied() {
-		let file = File::create(format!("{}/{}.bin", out_dir, name)).unwrap();
+	// Precomputed LUTs are kept per version and sRGB mode, so that a shared
+	// directory never hands out tables that were computed differently.
+	let mode = if cfg!(feature = "exact_srgb") { "exact_srgb" } else { "srgb" };
+	let lut_dir = std::env::var_os("RGB565_LUT_DIR").map(|dir| PathBuf::from(dir).join(env!("CARGO_PKG_VERSION")).join(mode));
+
+	let luts = select_luts(simd);
+	let has_lut = |name: &str| luts.iter().any(|lut| lut.name == name);
+
+	for (cfg, enabled) in srgb_cfgs(has_lut) {
+		if enabled {
+			println!("cargo:rustc-cfg={}", cfg);
+		}
+	}
+
+	for lut in luts {
+		println!("cargo:rustc-cfg=lut={:?}", lut.name);
+
+		let Some(generate) = lut.generate else {
+			continue;
+		};
+
+		let (name, size) = (lut.name, lut.size);
+		let path = format!("{}/{}.bin", out_dir, name);
+
+		if let Some(lut_dir) = &lut_dir {
+			let precomputed = lut_dir.join(format!("{}.bin", name));
+
+			if std::fs::metadata(&precomputed).is_ok_and(|metadata| metadata.len() == size as u64) {
+				std::fs::copy(&precomputed, &path).unwrap();
+				continue;
+			}
+		}
+
+		let file = File::create(&path).unwrap();
 		file.set_len(size as u64).unwrap();
 
 		let mut writer = BufWriter::with_capacity(size, file);
-		func(&mut writer).unwrap();
+		generate(&mut writer).unwrap();
 		writer.flush().unwrap();
+
+		// Saving the table for next time is best-effort, the directory may
+		// well be read-only.
+		if let Some(lut_dir) = &lut_dir {
+			let _ = std::fs::create_dir_all(lut_dir).and_then(|_| std::fs::copy(&path, lut_dir.join(format!("{}.bin", name))));
+		}
 	}
 }
@@ -0,0 +1,163 @@
+//! Preparing frames for 7-color ACeP ("Gallery") e-paper, which can show
+//! black, white, green, blue, red, yellow and orange, and nothing in between.
+//!
+//! Getting a good picture out of these panels takes a few steps:
+//!
+//! 1. Boost the saturation with [`saturate`], since the panel's inks are much
+//!    duller than the colors they stand for, and an unboosted image comes out
+//!    looking washed out.
+//! 2. Dither the frame onto [`MEASURED`], the colors the panel actually shows,
+//!    with `dither::remap_rgb565`. Dithering onto [`PALETTE`] instead would
+//!    treat the inks as brighter than they are.
+//! 3. Pack the resulting indices two to a byte with [`pack`], for the
+//!    controller's 4-bit-per-pixel format.
+//!
+//! With the `std` feature, `convert` does all three. Palettes here are in the
+//! order the UC8159 and similar controllers number their colors, so an index
+//! into them is the value the controller expects.
+//!
+//! ```
+//! # use rgb565::acep;
+//! let indices = [acep::BLACK, acep::WHITE, acep::RED, acep::YELLOW, acep::BLUE];
+//! let mut packed = [0; 3];
+//! acep::pack(&indices, 5, &mut packed);
+//! assert_eq!(packed, [0x01, 0x45, 0x30]);
+//! ```
+
+use crate::{formats, frame, Converter, Rgb565};
+
+/// The index of black ink.
+pub const BLACK: u8 = 0;
+/// The index of white ink.
+pub const WHITE: u8 = 1;
+/// The index of green ink.
+pub const GREEN: u8 = 2;
+/// The index of blue ink.
+pub const BLUE: u8 = 3;
+/// The index of red ink.
+pub const RED: u8 = 4;
+/// The index of yellow ink.
+pub const YELLOW: u8 = 5;
+/// The index of orange ink.
+pub const ORANGE: u8 = 6;
+/// Not a color, but the value that leaves a pixel as it is, or clears it on
+/// some controllers.
+pub const CLEAN: u8 = 7;
+
+/// The colors each index stands for, for previewing a remapped frame.
+pub const PALETTE: [Rgb565; 7] = [Rgb565(0x0000), Rgb565(0xFFFF), Rgb565(0x07E0), Rgb565(0x001F), Rgb565(0xF800), Rgb565(0xFFE0), Rgb565(0xF9A0)];
+
+/// Roughly the colors a typical panel actually shows for each index, for
+/// dithering onto. In sRGB, these are `#393039`, `#FFFFFF`, `#3A5B46`,
+/// `#3D3B5E`, `#9C484B`, `#D0BE47` and `#B16A49`.
+pub const MEASURED: [Rgb565; 7] = [Rgb565(0x0821), Rgb565(0xFFFF), Rgb565(0x08C1), Rgb565(0x0843), Rgb565(0x5082), Rgb565(0xA402), Rgb565(0x7122)];
+
+/// Scales how far each pixel of `frame` is from gray by `amount`, keeping its
+/// luma. 1 leaves the frame as it is, 0 turns it gray, and anything above 1
+/// boosts the saturation. How much boost looks best depends on the panel and
+/// the image. Pixels go to 8-bit RGB and back with `converter`.
+pub fn saturate(converter: &Converter, frame: &mut [u16], amount: f32) {
+	frame::map_in_place(frame, |color| {
+		let rgb888 = converter.to_rgb888(color);
+		let luma = formats::luma(rgb888) as f32;
+		converter.from_rgb888(rgb888.map(|channel| (luma + (channel as f32 - luma) * amount + 0.5) as u8))
+	});
+}
+
+/// Packs `indices`, a frame of palette indices that is `width` pixels wide,
+/// into `dst` at 4 bits per pixel. Each row starts on a new byte, with its
+/// first pixel in the high nibble, so rows take `width.div_ceil(2)` bytes.
+/// Only the low 4 bits of each index are kept. Rows that don't fit in `dst`
+/// are skipped.
+pub fn pack(indices: &[u8], width: usize, dst: &mut [u8]) {
+	if width == 0 {
+		return;
+	}
+
+	for (row, packed) in indices.chunks_exact(width).zip(dst.chunks_exact_mut(width.div_ceil(2))) {
+		for (pair, dst) in row.chunks(2).zip(packed) {
+			*dst = pair[0] << 4 | pair.get(1).map_or(0, |index| index & 0xF);
+		}
+	}
+}
+
+/// Boosts the saturation of `frame`, which is `width` pixels wide, by
+/// `saturation`, dithers it onto [`MEASURED`] and packs it into `dst`, as
+/// described in the module documentation, converting with `converter`
+/// throughout.
+#[cfg(feature = "std")]
+pub fn convert(converter: &Converter, frame: &[u16], width: usize, saturation: f32, dst: &mut [u8]) {
+	let mut boosted = frame.to_vec();
+	saturate(converter, &mut boosted, saturation);
+
+	let mut indices = vec![0; frame.len()];
+	crate::dither::remap_rgb565(converter, Rgb565::from_rgb565_slice(&boosted), width, &MEASURED, Some(&mut indices), None);
+	pack(&indices, width, dst);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{pack, saturate};
+	use crate::{formats, Converter, Rgb565};
+
+	#[test]
+	#[cfg(has_srgb_decode)]
+	fn palettes() {
+		use super::{MEASURED, PALETTE};
+
+		let ideal = [[0, 0, 0], [255, 255, 255], [0, 255, 0], [0, 0, 255], [255, 0, 0], [255, 255, 0], [255, 128, 0]];
+		let measured = [0x393039, 0xFFFFFF, 0x3A5B46, 0x3D3B5E, 0x9C484B, 0xD0BE47, 0xB16A49u32].map(|hex| [16, 8, 0].map(|shift| (hex >> shift) as u8));
+
+		for i in 0..7 {
+			let [r, g, b] = ideal[i];
+			assert_eq!(PALETTE[i], Rgb565::from_srgb888_components(r, g, b));
+			let [r, g, b] = measured[i];
+			assert_eq!(MEASURED[i], Rgb565::from_srgb888_components(r, g, b));
+		}
+	}
+
+	#[test]
+	fn saturation() {
+		let frame: Vec<u16> = Rgb565::all().step_by(97).map(|color| color.to_rgb565()).collect();
+
+		let mut same = frame.clone();
+		saturate(&Converter::builtin(), &mut same, 1.0);
+		assert_eq!(same, frame);
+
+		let mut gray = frame.clone();
+		saturate(&Converter::builtin(), &mut gray, 0.0);
+
+		for (gray, color) in gray.iter().zip(&frame) {
+			let [r, g, b] = Rgb565::from_rgb565(*gray).to_rgb888_components();
+			let luma = formats::luma(Rgb565::from_rgb565(*color).to_rgb888_components());
+			assert!(r.abs_diff(luma) <= 8 && g.abs_diff(luma) <= 4 && b.abs_diff(luma) <= 8);
+		}
+
+		let mut boosted = vec![0x0000, 0xFFFF];
+		saturate(&Converter::builtin(), &mut boosted, 4.0);
+		assert_eq!(boosted, [0x0000, 0xFFFF]);
+
+		let mut boosted = vec![Rgb565::from_rgb888_components(160, 100, 100).to_rgb565()];
+		saturate(&Converter::builtin(), &mut boosted, 2.0);
+		let [r, g, b] = Rgb565::from_rgb565(boosted[0]).to_rgb888_components();
+		assert!(r > 180 && g < 90 && b < 90, "{:?}", [r, g, b]);
+	}
+
+	#[test]
+	fn packing() {
+		let mut dst = [0xFF; 5];
+		pack(&[1, 2, 3, 4, 5, 6, 0x17], 3, &mut dst);
+		assert_eq!(dst, [0x12, 0x30, 0x45, 0x60, 0xFF]);
+		pack(&[1, 2], 0, &mut dst);
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn convert() {
+		let mut dst = [0; 8];
+		super::convert(&Converter::builtin(), &[0xFFFF; 16], 4, 1.5, &mut dst);
+		assert_eq!(dst, [0x11; 8]);
+		super::convert(&Converter::builtin(), &[0x0000; 16], 4, 1.5, &mut dst);
+		assert_eq!(dst, [0x00; 8]);
+	}
+}
@@ -0,0 +1,466 @@
+//! Checking how well 8-bit images survive being quantized to RGB565, so that
+//! asset pipelines can flag the ones that will look bad on the device before
+//! they ship.
+//!
+//! [`quantization`] converts each pixel to RGB565 and back and counts, per
+//! channel, the pixels that suffer two kinds of damage:
+//!
+//! - **Clipping**: a value that isn't black or full intensity becomes one,
+//!   losing the detail in shadows or highlights. This is rare for linear RGB,
+//!   but converting from sRGB crushes a good part of the darkest values to
+//!   black.
+//! - **Banding**: two neighboring pixels that differ only slightly, like in a
+//!   smooth gradient, end up differing by more, leaving a visible contour
+//!   where there was none. Each pixel is compared with its neighbors to the
+//!   right and below.
+//!
+//! Each is graded by how noticeable it is, as a [`Severity`]. An image with
+//! severe banding is a good candidate for dithering instead.
+//!
+//! [`banding`] looks at a frame that has already been converted, for when the
+//! source isn't around anymore. It finds the contours that quantizing a smooth
+//! gradient leaves behind: flat runs of one level next to flat runs of the
+//! level above or below, along rows and columns. The more of the frame is in
+//! such runs, the more visible the banding, and the more dithering would help.
+//!
+//! [`is_grayscale`] and [`dominant_cast`] look at the colors of a frame, for
+//! deciding whether it can go down a grayscale path, like to e-paper, or
+//! needs a color one.
+//!
+//! ```
+//! # use rgb565::analysis::{self, Severity};
+//! // A gradient in steps of 1, which RGB565 can't follow.
+//! let gradient: Vec<[u8; 3]> = (0..64).map(|i| [i, i, i]).collect();
+//! let report = analysis::quantization(&gradient, 64);
+//! assert!(report.r.banding(Severity::Severe) > 0);
+//! assert_eq!(report.worst(), Some(Severity::Severe));
+//! ```
+
+use crate::Rgb565;
+use crate::lut::unpack_565;
+
+/// How noticeable a problem is.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
+pub enum Severity {
+	/// Unlikely to be noticed.
+	Mild,
+	/// Likely to be noticed when looking for it.
+	Moderate,
+	/// Likely to be noticed at a glance.
+	Severe
+}
+
+impl Severity {
+	/// Grades clipping by how far the value moved, in 8-bit steps: up to half
+	/// of a 5-bit step is mild, and more than one and a half is severe.
+	#[inline]
+	fn of_clipping(error: u8) -> Self {
+		match error {
+			0..=4 => Self::Mild,
+			5..=12 => Self::Moderate,
+			_ => Self::Severe
+		}
+	}
+
+	/// Grades banding by how many times larger the difference between two
+	/// neighbors became: up to twice as large is mild, and more than four
+	/// times is severe.
+	#[inline]
+	fn of_banding(before: u8, after: u8) -> Self {
+		if after <= before * 2 {
+			Self::Mild
+		} else if after <= before * 4 {
+			Self::Moderate
+		} else {
+			Self::Severe
+		}
+	}
+}
+
+/// The largest difference between neighbors that counts as smooth, which is
+/// one 5-bit step.
+const SMOOTH: u8 = 8;
+
+/// How many pixels suffer from each problem in one channel, indexed by
+/// [`Severity`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct ChannelReport {
+	/// The pixels that became black or full intensity when they weren't
+	/// either before.
+	pub clipping: [usize; 3],
+	/// The pixels that ended up differing from a neighbor by more than the
+	/// slight difference they had before.
+	pub banding: [usize; 3]
+}
+
+impl ChannelReport {
+	/// The number of clipped pixels that are at least `severity`.
+	#[inline]
+	#[must_use]
+	pub fn clipped(&self, severity: Severity) -> usize { self.clipping[severity as usize..].iter().sum() }
+
+	/// The number of banding pixels that are at least `severity`.
+	#[inline]
+	#[must_use]
+	pub fn banding(&self, severity: Severity) -> usize { self.banding[severity as usize..].iter().sum() }
+
+	/// The worst problem in the channel, or `None` if there are none.
+	#[must_use]
+	pub fn worst(&self) -> Option<Severity> {
+		[Severity::Severe, Severity::Moderate, Severity::Mild].into_iter().find(|severity| self.clipping[*severity as usize] + self.banding[*severity as usize] > 0)
+	}
+}
+
+/// The results of [`quantization`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct QuantizationReport {
+	/// The number of pixels analyzed.
+	pub pixels: usize,
+	/// The problems in the red channel.
+	pub r: ChannelReport,
+	/// The problems in the green channel.
+	pub g: ChannelReport,
+	/// The problems in the blue channel.
+	pub b: ChannelReport
+}
+
+impl QuantizationReport {
+	/// The worst problem in any channel, or `None` if there are none.
+	#[inline]
+	#[must_use]
+	pub fn worst(&self) -> Option<Severity> { self.r.worst().max(self.g.worst()).max(self.b.worst()) }
+}
+
+fn analyze(src: &[[u8; 3]], width: usize, round_trip: impl Fn([u8; 3]) -> [u8; 3]) -> QuantizationReport {
+	let mut report = QuantizationReport::default();
+
+	if width == 0 {
+		return report;
+	}
+
+	let height = src.len() / width;
+	report.pixels = width * height;
+
+	for y in 0..height {
+		for x in 0..width {
+			let i = y * width + x;
+			let (before, after) = (src[i], round_trip(src[i]));
+			let neighbors = [(x + 1 < width).then(|| i + 1), (y + 1 < height).then(|| i + width)];
+			let neighbors = neighbors.map(|neighbor| neighbor.map(|neighbor| (src[neighbor], round_trip(src[neighbor]))));
+
+			for (c, channel) in [&mut report.r, &mut report.g, &mut report.b].into_iter().enumerate() {
+				if !matches!(before[c], 0 | 255) && matches!(after[c], 0 | 255) {
+					channel.clipping[Severity::of_clipping(before[c].abs_diff(after[c])) as usize] += 1;
+				}
+
+				let banding = neighbors.iter().flatten().filter_map(|(neighbor_before, neighbor_after)| {
+					let (before, after) = (before[c].abs_diff(neighbor_before[c]), after[c].abs_diff(neighbor_after[c]));
+					(before > 0 && before <= SMOOTH && after > before).then(|| Severity::of_banding(before, after))
+				});
+
+				if let Some(severity) = banding.max() {
+					channel.banding[severity as usize] += 1;
+				}
+			}
+		}
+	}
+
+	report
+}
+
+/// Analyzes `src`, a frame of 8-bit RGB that is `width` pixels wide, for
+/// what converting it with [`Rgb565::from_rgb888_components`] would do to it.
+#[must_use]
+pub fn quantization(src: &[[u8; 3]], width: usize) -> QuantizationReport {
+	analyze(src, width, |[r, g, b]| Rgb565::from_rgb888_components(r, g, b).to_rgb888_components())
+}
+
+/// Like [`quantization`], but for a frame of 8-bit sRGB converted with
+/// `Rgb565::from_srgb888_components`.
+#[cfg(has_srgb_decode)]
+#[cfg(has_srgb)]
+#[must_use]
+pub fn quantization_srgb(src: &[[u8; 3]], width: usize) -> QuantizationReport {
+	analyze(src, width, |[r, g, b]| Rgb565::from_srgb888_components(r, g, b).to_srgb888_components())
+}
+
+/// The shortest run of one level that [`banding`] counts as a plateau. Shorter
+/// runs are more likely texture or noise than a flattened gradient.
+const MIN_PLATEAU: usize = 4;
+
+/// The results of [`banding`], per channel in the order red, green, blue.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct BandingReport {
+	/// The number of pixels analyzed.
+	pub pixels: usize,
+	/// The number of contours found, across rows and columns.
+	pub contours: [usize; 3],
+	/// The number of pixels on a plateau next to a contour, counting each
+	/// pixel once for its row and once for its column.
+	pub banded: [usize; 3]
+}
+
+impl BandingReport {
+	/// The share of the frame in the worst channel that is banded, from 0 to
+	/// 1.
+	#[must_use]
+	pub fn score(&self) -> f32 {
+		match self.pixels {
+			0 => 0.0,
+			pixels => *self.banded.iter().max().unwrap_or(&0) as f32 / (pixels * 2) as f32
+		}
+	}
+
+	/// Whether the banding is visible enough that the asset should be
+	/// dithered, which is when a tenth or more of the frame is banded. Use
+	/// [`score`](Self::score) to pick a threshold of your own.
+	#[inline]
+	#[must_use]
+	pub fn needs_dithering(&self) -> bool { self.score() >= 0.1 }
+}
+
+/// Counts the contours along one line of levels and the pixels on the
+/// plateaus next to them, adding them to `contours` and `banded`.
+fn scan(levels: impl Iterator<Item = u8>, contours: &mut usize, banded: &mut usize) {
+	// The run before the current one, and the current one, as their level,
+	// length and whether they're next to a contour.
+	let mut previous: Option<(u8, usize, bool)> = None;
+	let mut current: Option<(u8, usize, bool)> = None;
+
+	let mut finish = |previous: &mut Option<(u8, usize, bool)>, mut run: (u8, usize, bool)| {
+		if let Some((level, len, next_to)) = previous {
+			if level.abs_diff(run.0) == 1 && *len >= MIN_PLATEAU && run.1 >= MIN_PLATEAU {
+				*contours += 1;
+				*next_to = true;
+				run.2 = true;
+			}
+
+			if *next_to {
+				*banded += *len;
+			}
+		}
+
+		*previous = Some(run);
+	};
+
+	for level in levels {
+		match &mut current {
+			Some((current, len, _)) if *current == level => *len += 1,
+			_ => {
+				if let Some(run) = current.replace((level, 1, false)) {
+					finish(&mut previous, run);
+				}
+			}
+		}
+	}
+
+	if let Some(run) = current {
+		finish(&mut previous, run);
+	}
+
+	if let Some((_, len, true)) = previous {
+		*banded += len;
+	}
+}
+
+/// Scores `frame`, which is `width` pixels wide, for visible banding, so that
+/// a pipeline can decide per asset whether to dither it.
+///
+/// ```
+/// # use rgb565::analysis;
+/// // A smooth gradient, converted without dithering.
+/// let frame: Vec<u16> = (0..64u16).map(|x| (x / 4) << 11).collect();
+/// assert!(analysis::banding(&frame, 64).needs_dithering());
+/// ```
+#[must_use]
+pub fn banding(frame: &[u16], width: usize) -> BandingReport {
+	let mut report = BandingReport::default();
+
+	if width == 0 {
+		return report;
+	}
+
+	let height = frame.len() / width;
+	report.pixels = width * height;
+
+	let channel = |c: usize| move |pixel: &u16| {
+		let (r, g, b) = unpack_565(*pixel);
+		[r, g, b][c]
+	};
+
+	for c in 0..3 {
+		let (contours, banded) = (&mut report.contours[c], &mut report.banded[c]);
+
+		for row in frame.chunks_exact(width) {
+			scan(row.iter().map(channel(c)), contours, banded);
+		}
+
+		for x in 0..width {
+			scan(frame[x..].iter().step_by(width).take(height).map(channel(c)), contours, banded);
+		}
+	}
+
+	report
+}
+
+/// Whether every pixel of `frame` is gray, with its 8-bit channels no more
+/// than `tolerance` apart. RGB565 can't store most grays exactly, since green
+/// has one more bit than red and blue, so a `tolerance` of at least 4 is needed
+/// to accept them all.
+#[must_use]
+pub fn is_grayscale(frame: &[u16], tolerance: u8) -> bool {
+	frame.iter().all(|pixel| {
+		let [r, g, b] = Rgb565::from_rgb565(*pixel).to_rgb888_components();
+		r.max(g).max(b) - r.min(g).min(b) <= tolerance
+	})
+}
+
+/// The hue a frame leans towards, as returned by [`dominant_cast`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Cast {
+	/// A hue within 30 degrees of red.
+	Red,
+	/// A hue within 30 degrees of yellow, halfway between red and green.
+	Yellow,
+	/// A hue within 30 degrees of green.
+	Green,
+	/// A hue within 30 degrees of cyan, halfway between green and blue.
+	Cyan,
+	/// A hue within 30 degrees of blue.
+	Blue,
+	/// A hue within 30 degrees of magenta, halfway between blue and red.
+	Magenta
+}
+
+/// How far apart the channels of the average color have to be, in 8-bit
+/// steps, for [`dominant_cast`] to call it a cast.
+const MIN_CAST: f32 = 8.0;
+
+/// The hue that the average color of `frame` leans towards, or `None` if it's
+/// close enough to neutral or the frame is empty. A frame that is gray apart
+/// from a tint, like a sepia photo, has a cast, as does a frame of mostly one
+/// color.
+///
+/// ```
+/// # use rgb565::analysis::{self, Cast};
+/// let sepia = [0x9469, 0x6B25, 0xC60F];
+/// assert_eq!(analysis::dominant_cast(&sepia), Some(Cast::Yellow));
+/// assert_eq!(analysis::dominant_cast(&[0x8410; 3]), None);
+/// ```
+#[must_use]
+pub fn dominant_cast(frame: &[u16]) -> Option<Cast> {
+	if frame.is_empty() {
+		return None;
+	}
+
+	let sums = frame.iter().fold([0u64; 3], |sums, pixel| {
+		let rgb888 = Rgb565::from_rgb565(*pixel).to_rgb888_components();
+		[0, 1, 2].map(|c| sums[c] + rgb888[c] as u64)
+	});
+
+	let [r, g, b] = sums.map(|sum| sum as f32 / frame.len() as f32);
+	let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+	let chroma = max - min;
+
+	if chroma < MIN_CAST {
+		return None;
+	}
+
+	// The hue in sixths of a turn from -1 to 5, the same way HSV computes it.
+	let hue = if max == r {
+		(g - b) / chroma
+	} else if max == g {
+		(b - r) / chroma + 2.0
+	} else {
+		(r - g) / chroma + 4.0
+	};
+
+	let casts = [Cast::Red, Cast::Yellow, Cast::Green, Cast::Cyan, Cast::Blue, Cast::Magenta];
+	Some(casts[(hue + 6.5) as usize % 6])
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{banding, dominant_cast, is_grayscale, quantization, Cast, ChannelReport, Severity};
+
+	#[test]
+	fn clipping() {
+		// Shades of red that land on black or full intensity, and one that
+		// doesn't. Black and white themselves don't count.
+		let report = quantization(&[[3, 0, 0], [7, 0, 0], [254, 0, 0], [100, 0, 0], [255, 255, 255]], 5);
+		assert_eq!(report.pixels, 5);
+		assert_eq!(report.r.clipping, [2, 1, 0]);
+		assert_eq!(report.g, ChannelReport::default());
+		assert_eq!(report.r.clipped(Severity::Moderate), 1);
+	}
+
+	#[test]
+	fn gradients() {
+		// A horizontal gradient in steps of 1 across two rows: red and blue
+		// jump by a whole 5-bit step at each contour, and green by a 6-bit one.
+		let gradient: Vec<[u8; 3]> = (0..2).flat_map(|_| (100..132).map(|i| [i, i, i])).collect();
+		let report = quantization(&gradient, 32);
+
+		assert_eq!(report.r.banding, [0, 0, 8]);
+		assert_eq!(report.g.banding, [0, 16, 0]);
+		assert_eq!(report.r.worst(), Some(Severity::Severe));
+		assert_eq!(report.g.worst(), Some(Severity::Moderate));
+
+		// Flat areas and hard edges are left alone.
+		let flat = [[100, 100, 100], [100, 100, 100], [0, 0, 0], [255, 255, 255]];
+		assert_eq!(quantization(&flat, 2).worst(), None);
+		assert_eq!(quantization(&flat, 0).pixels, 0);
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn srgb() {
+		// Dark sRGB values are crushed to black far harder than linear ones.
+		let shadows: Vec<[u8; 3]> = (1..20).map(|i| [i, i, i]).collect();
+		assert_eq!(super::quantization_srgb(&shadows, 19).r.clipped(Severity::Severe), 7);
+		assert_eq!(quantization(&shadows, 19).r.clipped(Severity::Severe), 0);
+	}
+
+	#[test]
+	fn contours() {
+		// A horizontal gradient of red in runs of 4, over 3 rows.
+		let gradient: Vec<u16> = (0..3).flat_map(|_| (0..32u16).map(|x| (x / 4) << 11)).collect();
+		let report = banding(&gradient, 32);
+		assert_eq!(report.pixels, 96);
+		assert_eq!(report.contours, [3 * 7, 0, 0]);
+		assert_eq!(report.banded, [96, 0, 0]);
+		assert_eq!(report.score(), 0.5);
+		assert!(report.needs_dithering());
+
+		// Runs that are too short, like noise or a dithered gradient, and
+		// jumps of more than one level, like edges, aren't contours.
+		let noise: Vec<u16> = (0..32u16).map(|x| (x / 2) << 11).collect();
+		let edges: Vec<u16> = (0..32u16).map(|x| (x / 4 * 3) << 11).collect();
+		assert_eq!(banding(&noise, 32).contours, [0; 3]);
+		assert_eq!(banding(&edges, 32).contours, [0; 3]);
+		assert!(!banding(&edges, 32).needs_dithering());
+		assert_eq!(banding(&edges, 0).score(), 0.0);
+	}
+
+	#[test]
+	fn grayscale() {
+		let grays: Vec<u16> = (0..=255).map(|i| crate::Rgb565::from_rgb888_components(i, i, i).to_rgb565()).collect();
+		assert!(is_grayscale(&grays, 8));
+		assert!(!is_grayscale(&grays, 7));
+		assert!(!is_grayscale(&[0x8410, 0x8411], 8));
+		assert!(is_grayscale(&[0x8410, 0x8411], 10));
+		assert!(is_grayscale(&[], 0));
+	}
+
+	#[test]
+	fn casts() {
+		for (color, cast) in [(0xF800, Cast::Red), (0xFFE0, Cast::Yellow), (0x07E0, Cast::Green), (0x07FF, Cast::Cyan), (0x001F, Cast::Blue), (0xF81F, Cast::Magenta)] {
+			assert_eq!(dominant_cast(&[color, 0x8410, 0x0000]), Some(cast));
+		}
+
+		// Slightly blue, but not enough to count.
+		assert_eq!(dominant_cast(&[0x8410, 0x8411]), None);
+		assert_eq!(dominant_cast(&[0x8410, 0x8418]), Some(Cast::Blue));
+		assert_eq!(dominant_cast(&[]), None);
+	}
+}
@@ -0,0 +1,413 @@
+//! Animations stored as a series of [assets](mod@crate::asset), for boot
+//! animations and small effects that play from flash.
+//!
+//! Each frame of an animation is a patch drawn over the one before it, so a
+//! frame that only changes a small part of the picture only stores that
+//! part. The first frame covers the whole picture. [`AnimationWriter`] finds
+//! the rectangle that changed between frames on its own.
+//!
+//! An animation is a 16-byte header followed by its frames. All multi-byte
+//! fields are little-endian:
+//!
+//! | Offset | Size | Field                                                  |
+//! |--------|------|--------------------------------------------------------|
+//! | 0      | 4    | Magic, `R5AN`                                          |
+//! | 4      | 2    | Width in pixels                                        |
+//! | 6      | 2    | Height in pixels                                       |
+//! | 8      | 2    | Number of frames                                       |
+//! | 10     | 2    | Reserved, 0                                            |
+//! | 12     | 4    | CRC-32 of the first 12 bytes of the header and the frames |
+//!
+//! Each frame is:
+//!
+//! | Offset | Size | Field                                                  |
+//! |--------|------|--------------------------------------------------------|
+//! | 0      | 2    | How long to show the frame, in milliseconds            |
+//! | 2      | 2    | Left edge of the patch                                 |
+//! | 4      | 2    | Top edge of the patch                                  |
+//! | 6      |      | The patch, as an asset                                 |
+//!
+//! ```
+//! # use rgb565::{animation::{AnimationFrames, AnimationWriter, Player}, Encoding, Rgb565};
+//! let mut frames = [[Rgb565::BLACK; 16]; 3];
+//! frames[1][5] = Rgb565::RED;
+//! frames[2][5] = Rgb565::BLUE;
+//!
+//! let mut buffer = [0; 256];
+//! let mut writer = AnimationWriter::new(&mut buffer, 4, 4, Encoding::Rgb565Le, true).unwrap();
+//!
+//! for frame in &frames {
+//!     writer.push(frame, 100).unwrap();
+//! }
+//!
+//! let len = writer.finish();
+//! let animation = AnimationFrames::parse(&buffer[..len]).unwrap();
+//! let mut player = Player::new(animation, [0u16; 16]).unwrap();
+//!
+//! for expected in &frames {
+//!     let (frame, delay_ms) = player.next_frame().unwrap();
+//!     assert_eq!(delay_ms, 100);
+//!     assert_eq!(frame.pixel(1, 1), Some(expected[5]));
+//! }
+//!
+//! assert!(player.next_frame().is_none());
+//! ```
+
+use crate::asset::{self, crc32, Asset, AssetError};
+use crate::frame::Rect;
+use crate::{Encoding, Framebuffer565, Rgb565};
+
+/// The magic bytes every animation starts with.
+pub const MAGIC: [u8; 4] = *b"R5AN";
+
+/// The length of the header.
+pub const HEADER_LEN: usize = 16;
+
+/// The length of the fields before the patch of each frame.
+pub const FRAME_HEADER_LEN: usize = 6;
+
+/// A frame of an animation, borrowing its patch from the animation.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct AnimationFrame<'a> {
+	/// How long to show the frame, in milliseconds.
+	pub delay_ms: u16,
+	/// Where the left edge of the patch goes, in pixels from the left.
+	pub x: u16,
+	/// Where the top edge of the patch goes, in pixels from the top.
+	pub y: u16,
+	/// The pixels that changed since the frame before, to draw at `x`, `y`.
+	pub patch: Asset<'a>
+}
+
+/// Parses the frame at the start of `bytes`, returning it and whatever comes
+/// after it.
+fn frame(bytes: &[u8]) -> Result<(AnimationFrame<'_>, &[u8]), AssetError> {
+	let (header, rest) = bytes.split_first_chunk::<FRAME_HEADER_LEN>().ok_or(AssetError::TooShort)?;
+	let field = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]);
+	let (patch, rest) = Asset::parse(rest)?;
+	Ok((AnimationFrame { delay_ms: field(0), x: field(2), y: field(4), patch }, rest))
+}
+
+/// An animation parsed from a buffer, borrowing its frames from it.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct AnimationFrames<'a> {
+	/// The width of every frame, in pixels.
+	pub width: u16,
+	/// The height of every frame, in pixels.
+	pub height: u16,
+	len: usize,
+	frames: &'a [u8]
+}
+
+impl<'a> AnimationFrames<'a> {
+	/// Parses an animation from `bytes`. Every frame is checked up front, so
+	/// that playing it can't fail partway through: the checksums have to
+	/// match, every patch has to fit in the picture, and the first has to
+	/// cover all of it.
+	pub fn parse(bytes: &'a [u8]) -> Result<Self, AssetError> {
+		let (header, frames) = bytes.split_first_chunk::<HEADER_LEN>().ok_or(AssetError::TooShort)?;
+		let field = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]);
+
+		if header[..4] != MAGIC {
+			return Err(AssetError::BadMagic);
+		}
+
+		if field(10) != 0 {
+			return Err(AssetError::UnknownFormat);
+		}
+
+		if !crc32(crc32(!0, &header[..12]), frames) != u32::from_le_bytes([header[12], header[13], header[14], header[15]]) {
+			return Err(AssetError::BadChecksum);
+		}
+
+		let (width, height, len) = (field(4), field(6), field(8) as usize);
+		let mut rest = frames;
+
+		for i in 0..len {
+			let (frame, after) = frame(rest)?;
+			let (right, bottom) = (frame.x as u32 + frame.patch.width as u32, frame.y as u32 + frame.patch.height as u32);
+			let covers = (frame.x, frame.y, right, bottom) == (0, 0, width as u32, height as u32);
+
+			if right > width as u32 || bottom > height as u32 || (i == 0 && !covers) {
+				return Err(AssetError::LengthMismatch);
+			}
+
+			rest = after;
+		}
+
+		Ok(Self { width, height, len, frames: &frames[..frames.len() - rest.len()] })
+	}
+
+	/// The number of frames.
+	#[inline]
+	#[must_use]
+	pub fn len(&self) -> usize { self.len }
+
+	/// Whether the animation has no frames.
+	#[inline]
+	#[must_use]
+	pub fn is_empty(&self) -> bool { self.len == 0 }
+
+	/// Returns the frames, in order.
+	pub fn iter(&self) -> impl Iterator<Item = AnimationFrame<'a>> + 'a {
+		let mut rest = self.frames;
+
+		core::iter::from_fn(move || {
+			let (frame, after) = frame(rest).ok()?;
+			rest = after;
+			Some(frame)
+		})
+	}
+}
+
+/// Plays an animation into a framebuffer, drawing each frame over the one
+/// before it.
+pub struct Player<'a, B> {
+	animation: AnimationFrames<'a>,
+	canvas: Framebuffer565<B>,
+	rest: &'a [u8],
+	looping: bool
+}
+
+impl<'a, B: AsRef<[u16]> + AsMut<[u16]>> Player<'a, B> {
+	/// Starts playing `animation` into `buffer`, or returns `None` if `buffer`
+	/// is smaller than the animation.
+	#[must_use]
+	pub fn new(animation: AnimationFrames<'a>, buffer: B) -> Option<Self> {
+		let canvas = Framebuffer565::new(buffer, animation.width as usize, animation.height as usize)?;
+		Some(Self { animation, canvas, rest: animation.frames, looping: false })
+	}
+
+	/// Sets whether the animation starts over after its last frame, instead
+	/// of ending.
+	#[inline]
+	#[must_use]
+	pub fn looping(mut self, looping: bool) -> Self {
+		self.looping = looping;
+		self
+	}
+
+	/// Goes back to the first frame.
+	#[inline]
+	pub fn rewind(&mut self) { self.rest = self.animation.frames; }
+
+	/// Draws the next frame and returns the picture, ready to blit, along with
+	/// how long to show it in milliseconds. Returns `None` once the animation
+	/// is over, or right away if it has no frames.
+	pub fn next_frame(&mut self) -> Option<(Framebuffer565<&[u16]>, u16)> {
+		if self.rest.is_empty() && self.looping {
+			self.rewind();
+		}
+
+		let (frame, rest) = frame(self.rest).ok()?;
+		self.rest = rest;
+		let (x, y, width) = (frame.x as usize, frame.y as usize, frame.patch.width.max(1) as usize);
+
+		for (i, pixel) in frame.patch.pixels().enumerate() {
+			self.canvas.set_pixel(x + i % width, y + i / width, pixel);
+		}
+
+		let whole = Rect::new(0, 0, self.canvas.width(), self.canvas.height());
+		Some((self.canvas.view(whole)?, frame.delay_ms))
+	}
+
+	/// Returns the buffer the animation is drawn into.
+	#[inline]
+	pub fn into_inner(self) -> B { self.canvas.into_inner() }
+}
+
+/// Writes an animation into a buffer, one frame at a time, storing each as
+/// the rectangle that changed since the one before.
+pub struct AnimationWriter<'a, 'f> {
+	dst: &'a mut [u8],
+	width: u16,
+	height: u16,
+	encoding: Encoding,
+	rle: bool,
+	len: usize,
+	frames: u16,
+	previous: Option<&'f [Rgb565]>
+}
+
+impl<'a, 'f> AnimationWriter<'a, 'f> {
+	/// Starts an animation of `width` by `height` pixels at the start of
+	/// `dst`, with patches in `encoding`, run-length encoded if `rle`.
+	pub fn new(dst: &'a mut [u8], width: u16, height: u16, encoding: Encoding, rle: bool) -> Result<Self, AssetError> {
+		if dst.len() < HEADER_LEN {
+			return Err(AssetError::TooShort);
+		}
+
+		Ok(Self { dst, width, height, encoding, rle, len: HEADER_LEN, frames: 0, previous: None })
+	}
+
+	/// Adds `frame`, shown for `delay_ms` milliseconds.
+	///
+	/// Fails with [`AssetError::LengthMismatch`] if `frame` isn't the size of
+	/// the animation or there are already 65535 frames, or with
+	/// [`AssetError::TooShort`] if it doesn't fit. Nothing is added if it
+	/// fails.
+	pub fn push(&mut self, frame: &'f [Rgb565], delay_ms: u16) -> Result<(), AssetError> {
+		let width = self.width as usize;
+
+		if frame.len() != width * self.height as usize || self.frames == u16::MAX {
+			return Err(AssetError::LengthMismatch);
+		}
+
+		// The smallest rectangle holding every pixel that changed.
+		let (mut left, mut top, mut right, mut bottom) = (usize::MAX, usize::MAX, 0, 0);
+
+		match self.previous {
+			Some(previous) => {
+				for (i, _) in frame.iter().zip(previous).enumerate().filter(|(_, (a, b))| a != b) {
+					let (x, y) = (i % width, i / width);
+					(left, top, right, bottom) = (left.min(x), top.min(y), right.max(x + 1), bottom.max(y + 1));
+				}
+			}
+
+			None => (left, top, right, bottom) = (0, 0, width, self.height as usize)
+		}
+
+		let (left, top) = if left > right { (0, 0) } else { (left, top) };
+		let (patch_width, patch_height) = (right.saturating_sub(left), bottom.saturating_sub(top));
+
+		let (header, rest) = self.dst.get_mut(self.len..).and_then(|dst| dst.split_first_chunk_mut::<FRAME_HEADER_LEN>()).ok_or(AssetError::TooShort)?;
+		let pixel = |i: usize| frame[(top + i / patch_width) * width + left + i % patch_width];
+		let written = asset::encode_with(pixel, patch_width * patch_height, patch_width as u16, self.encoding, self.rle, rest)?;
+		header[..2].copy_from_slice(&delay_ms.to_le_bytes());
+		header[2..4].copy_from_slice(&(left as u16).to_le_bytes());
+		header[4..].copy_from_slice(&(top as u16).to_le_bytes());
+
+		self.len += FRAME_HEADER_LEN + written;
+		self.frames += 1;
+		self.previous = Some(frame);
+		Ok(())
+	}
+
+	/// Writes the header and returns the length of the animation.
+	pub fn finish(self) -> usize {
+		let (header, frames) = self.dst.split_at_mut(HEADER_LEN);
+		header[..4].copy_from_slice(&MAGIC);
+		header[4..6].copy_from_slice(&self.width.to_le_bytes());
+		header[6..8].copy_from_slice(&self.height.to_le_bytes());
+		header[8..10].copy_from_slice(&self.frames.to_le_bytes());
+		header[10..12].fill(0);
+		let crc = !crc32(crc32(!0, &header[..12]), &frames[..self.len - HEADER_LEN]);
+		header[12..].copy_from_slice(&crc.to_le_bytes());
+		self.len
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{AnimationFrames, AnimationWriter, Player, FRAME_HEADER_LEN, HEADER_LEN};
+	use crate::asset::{self, AssetError};
+	use crate::{Encoding, Rgb565};
+
+	/// A dot moving across a gradient, 8 by 6 pixels, with a frame in the
+	/// middle that doesn't change anything.
+	fn frames() -> Vec<Vec<Rgb565>> {
+		let background: Vec<Rgb565> = (0..48u32).map(|i| Rgb565::from_rgb565((i * 0x0821) as u16)).collect();
+
+		[0, 1, 1, 9, 30].iter().map(|dot| {
+			let mut frame = background.clone();
+			frame[*dot] = Rgb565::WHITE;
+			frame
+		}).collect()
+	}
+
+	#[test]
+	fn round_trip() {
+		let frames = frames();
+
+		for rle in [false, true] {
+			let mut buffer = vec![0; 1024];
+			let mut writer = AnimationWriter::new(&mut buffer, 8, 6, Encoding::Bgr565Be, rle).unwrap();
+
+			for (i, frame) in frames.iter().enumerate() {
+				writer.push(frame, i as u16 * 10).unwrap();
+			}
+
+			let len = writer.finish();
+			let animation = AnimationFrames::parse(&buffer[..len]).unwrap();
+			assert_eq!((animation.width, animation.height, animation.len()), (8, 6, 5));
+
+			// Only the first frame is stored whole.
+			let patches: Vec<_> = animation.iter().map(|frame| (frame.x, frame.y, frame.patch.width, frame.patch.height)).collect();
+			assert_eq!(patches, [(0, 0, 8, 6), (0, 0, 2, 1), (0, 0, 0, 0), (1, 0, 1, 2), (1, 1, 6, 3)]);
+
+			let mut player = Player::new(animation, vec![0; 48]).unwrap();
+
+			for (i, expected) in frames.iter().enumerate() {
+				let (frame, delay_ms) = player.next_frame().unwrap();
+				assert_eq!(delay_ms, i as u16 * 10);
+				assert!((0..48).all(|p| frame.pixel(p % 8, p / 8) == Some(expected[p])), "frame {}", i);
+			}
+
+			assert!(player.next_frame().is_none());
+			player.rewind();
+			assert_eq!(player.next_frame().map(|(_, delay_ms)| delay_ms), Some(0));
+		}
+	}
+
+	#[test]
+	fn looping() {
+		let frames = frames();
+		let mut buffer = vec![0; 1024];
+		let mut writer = AnimationWriter::new(&mut buffer, 8, 6, Encoding::Rgb565Le, true).unwrap();
+		frames.iter().try_for_each(|frame| writer.push(frame, 1)).unwrap();
+		let len = writer.finish();
+
+		let animation = AnimationFrames::parse(&buffer[..len]).unwrap();
+		let mut player = Player::new(animation, [0; 48]).unwrap().looping(true);
+
+		for i in 0..frames.len() * 3 {
+			let (frame, _) = player.next_frame().unwrap();
+			assert_eq!(frame.pixel(1, 0), Some(frames[i % frames.len()][1]));
+		}
+
+		assert!(Player::new(animation, [0; 47]).is_none());
+
+		let mut empty = [0; HEADER_LEN];
+		let len = AnimationWriter::new(&mut empty, 8, 6, Encoding::Rgb565Le, false).unwrap().finish();
+		let animation = AnimationFrames::parse(&empty[..len]).unwrap();
+		assert!(animation.is_empty());
+		assert!(Player::new(animation, [0; 48]).unwrap().looping(true).next_frame().is_none());
+	}
+
+	#[test]
+	fn errors() {
+		let frames = frames();
+		let mut buffer = vec![0; 1024];
+		let mut writer = AnimationWriter::new(&mut buffer, 8, 6, Encoding::Rgb565Le, false).unwrap();
+		assert_eq!(writer.push(&frames[0][..47], 1), Err(AssetError::LengthMismatch));
+		writer.push(&frames[0], 1).unwrap();
+		writer.push(&frames[3], 1).unwrap();
+		let len = writer.finish();
+		let buffer = &buffer[..len];
+
+		let mut short = [0; HEADER_LEN + FRAME_HEADER_LEN + asset::HEADER_LEN + 95];
+		let mut writer = AnimationWriter::new(&mut short, 8, 6, Encoding::Rgb565Le, false).unwrap();
+		assert_eq!(writer.push(&frames[0], 1), Err(AssetError::TooShort));
+
+		assert_eq!(AnimationFrames::parse(&buffer[..len - 1]), Err(AssetError::BadChecksum));
+
+		let mut bad = buffer.to_vec();
+		bad[0] = b'X';
+		assert_eq!(AnimationFrames::parse(&bad), Err(AssetError::BadMagic));
+
+		let mut bad = buffer.to_vec();
+		bad[10] = 1;
+		assert_eq!(AnimationFrames::parse(&bad), Err(AssetError::UnknownFormat));
+
+		let mut bad = buffer.to_vec();
+		bad[HEADER_LEN + 2] = 1;
+		assert_eq!(AnimationFrames::parse(&bad), Err(AssetError::BadChecksum));
+
+		// A valid checksum over a first frame that's too narrow.
+		let mut narrow = buffer.to_vec();
+		narrow[4] = 9;
+		let crc = !super::crc32(super::crc32(!0, &narrow[..12]), &narrow[HEADER_LEN..]);
+		narrow[12..HEADER_LEN].copy_from_slice(&crc.to_le_bytes());
+		assert_eq!(AnimationFrames::parse(&narrow), Err(AssetError::LengthMismatch));
+	}
+}
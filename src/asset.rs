@@ -0,0 +1,396 @@
+//! A small container for images stored in flash, with a checksum so that a
+//! corrupted or half-written image is caught before it's drawn.
+//!
+//! An asset is an 18-byte header followed by its pixels, either raw or
+//! run-length encoded. All multi-byte fields are little-endian:
+//!
+//! | Offset | Size | Field                                                  |
+//! |--------|------|--------------------------------------------------------|
+//! | 0      | 4    | Magic, `R5IM`                                          |
+//! | 4      | 1    | Format of the pixels, 0 to 3 for [`Encoding::Rgb565Le`], [`Encoding::Rgb565Be`], [`Encoding::Bgr565Le`] and [`Encoding::Bgr565Be`] |
+//! | 5      | 1    | Flags, with bit 0 set if the pixels are run-length encoded, and the rest 0 |
+//! | 6      | 2    | Width in pixels                                        |
+//! | 8      | 2    | Height in pixels                                       |
+//! | 10     | 4    | Length of the pixels in bytes                          |
+//! | 14     | 4    | CRC-32 of the first 14 bytes of the header and the pixels |
+//!
+//! Run-length encoded pixels are a series of packets, each starting with a
+//! byte `n`. If its top bit is set, the next pixel is repeated `(n & 0x7F) + 1`
+//! times, and otherwise the next `n + 1` pixels are taken as they are.
+//!
+//! The CRC is the common CRC-32 of zlib, PNG and Ethernet.
+//!
+//! ```
+//! # use rgb565::{asset::{self, Asset}, Encoding, Rgb565};
+//! let frame = [Rgb565::RED; 64];
+//! let mut buffer = [0; 64];
+//! let len = asset::encode(&frame, 8, Encoding::Rgb565Le, true, &mut buffer).unwrap();
+//! assert_eq!(len, asset::HEADER_LEN + 3);
+//!
+//! let (asset, rest) = Asset::parse(&buffer[..len]).unwrap();
+//! assert_eq!((asset.width, asset.height), (8, 8));
+//! assert!(asset.pixels().eq(frame));
+//! assert!(rest.is_empty());
+//! ```
+
+use core::fmt::{self, Display, Formatter};
+
+use crate::{Encoding, Rgb565};
+
+/// The magic bytes every asset starts with.
+pub const MAGIC: [u8; 4] = *b"R5IM";
+
+/// The length of the header.
+pub const HEADER_LEN: usize = 18;
+
+const FLAG_RLE: u8 = 1;
+
+/// The most pixels one run-length encoded packet can hold.
+const MAX_PACKET: usize = 128;
+
+/// An error from parsing or encoding an asset or a
+/// [bundle](crate::bundle) of them.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum AssetError {
+	/// The buffer is too short for the header or the pixels.
+	TooShort,
+	/// The asset doesn't start with [`MAGIC`].
+	BadMagic,
+	/// The format or flags aren't ones this crate knows.
+	UnknownFormat,
+	/// The checksum doesn't match, so the asset is corrupted.
+	BadChecksum,
+	/// The pixels don't add up to the width and height.
+	LengthMismatch,
+	/// The name of a bundle entry is empty, too long or not UTF-8.
+	InvalidName
+}
+
+impl Display for AssetError {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::TooShort => f.write_str("buffer is too short for the asset"),
+			Self::BadMagic => f.write_str("asset doesn't start with the magic bytes"),
+			Self::UnknownFormat => f.write_str("unknown pixel format or flags"),
+			Self::BadChecksum => f.write_str("checksum doesn't match"),
+			Self::LengthMismatch => f.write_str("pixels don't add up to the width and height"),
+			Self::InvalidName => f.write_str("invalid bundle entry name")
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AssetError {}
+
+const CRC_TABLE: [u32; 256] = {
+	let mut table = [0; 256];
+	let mut i = 0;
+
+	while i < 256 {
+		let mut crc = i as u32;
+		let mut bit = 0;
+
+		while bit < 8 {
+			crc = if crc & 1 != 0 { crc >> 1 ^ 0xEDB8_8320 } else { crc >> 1 };
+			bit += 1;
+		}
+
+		table[i] = crc;
+		i += 1;
+	}
+
+	table
+};
+
+/// Continues a CRC-32 over `bytes`. Start with `!0` and invert the result.
+pub(crate) fn crc32(crc: u32, bytes: &[u8]) -> u32 { bytes.iter().fold(crc, |crc, byte| CRC_TABLE[(crc as u8 ^ byte) as usize] ^ crc >> 8) }
+
+const ENCODINGS: [Encoding; 4] = [Encoding::Rgb565Le, Encoding::Rgb565Be, Encoding::Bgr565Le, Encoding::Bgr565Be];
+
+/// An asset parsed from a buffer, borrowing its pixels from it.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Asset<'a> {
+	/// How each pixel is stored.
+	pub encoding: Encoding,
+	/// Whether the pixels are run-length encoded.
+	pub rle: bool,
+	/// The width of the asset, in pixels.
+	pub width: u16,
+	/// The height of the asset, in pixels.
+	pub height: u16,
+	/// The pixels as stored, in `encoding` and run-length encoded if `rle`.
+	pub payload: &'a [u8]
+}
+
+impl<'a> Asset<'a> {
+	/// Parses an asset from the start of `bytes`, returning it and whatever
+	/// comes after it. The checksum is verified, and so is that the pixels
+	/// add up to the width and height.
+	pub fn parse(bytes: &'a [u8]) -> Result<(Self, &'a [u8]), AssetError> {
+		let (header, rest) = bytes.split_first_chunk::<HEADER_LEN>().ok_or(AssetError::TooShort)?;
+		let field = |at: usize| u32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]]);
+
+		if header[..4] != MAGIC {
+			return Err(AssetError::BadMagic);
+		}
+
+		let encoding = *ENCODINGS.get(header[4] as usize).ok_or(AssetError::UnknownFormat)?;
+
+		if header[5] & !FLAG_RLE != 0 {
+			return Err(AssetError::UnknownFormat);
+		}
+
+		let payload_len = field(10) as usize;
+		let (payload, rest) = rest.split_at_checked(payload_len).ok_or(AssetError::TooShort)?;
+
+		if !crc32(crc32(!0, &header[..14]), payload) != field(14) {
+			return Err(AssetError::BadChecksum);
+		}
+
+		let asset = Self {
+			encoding,
+			rle: header[5] & FLAG_RLE != 0,
+			width: u16::from_le_bytes([header[6], header[7]]),
+			height: u16::from_le_bytes([header[8], header[9]]),
+			payload
+		};
+
+		if asset.stored_pixels() != Some(asset.width as usize * asset.height as usize) {
+			return Err(AssetError::LengthMismatch);
+		}
+
+		Ok((asset, rest))
+	}
+
+	/// The number of pixels in the payload, or `None` if it ends partway
+	/// through a pixel or packet.
+	fn stored_pixels(&self) -> Option<usize> {
+		if !self.rle {
+			return self.payload.len().is_multiple_of(2).then_some(self.payload.len() / 2);
+		}
+
+		let (mut pixels, mut bytes) = (0, self.payload);
+
+		while let Some((&n, rest)) = bytes.split_first() {
+			let (count, len) = match n & 0x80 {
+				0 => (n as usize + 1, (n as usize + 1) * 2),
+				_ => ((n & 0x7F) as usize + 1, 2)
+			};
+
+			bytes = rest.get(len..)?;
+			pixels += count;
+		}
+
+		Some(pixels)
+	}
+
+	/// Returns the pixels of the asset, decoded.
+	pub fn pixels(&self) -> impl Iterator<Item = Rgb565> + 'a {
+		let (encoding, rle) = (self.encoding, self.rle);
+		let mut bytes = self.payload;
+		// The pixel being repeated and how many more times, or how many more
+		// pixels to take as they are.
+		let (mut run, mut literal) = (None::<(Rgb565, usize)>, 0);
+
+		core::iter::from_fn(move || {
+			let take = |bytes: &mut &'a [u8]| {
+				let (pixel, rest) = bytes.split_first_chunk::<2>()?;
+				*bytes = rest;
+				Some(Rgb565::from_bytes(*pixel, encoding))
+			};
+
+			if !rle {
+				return take(&mut bytes);
+			}
+
+			if let Some((pixel, remaining)) = &mut run {
+				let pixel = *pixel;
+				*remaining -= 1;
+
+				if *remaining == 0 {
+					run = None;
+				}
+
+				return Some(pixel);
+			}
+
+			if literal == 0 {
+				let (&n, rest) = bytes.split_first()?;
+				bytes = rest;
+
+				if n & 0x80 != 0 {
+					let pixel = take(&mut bytes)?;
+					let remaining = (n & 0x7F) as usize;
+
+					if remaining > 0 {
+						run = Some((pixel, remaining));
+					}
+
+					return Some(pixel);
+				}
+
+				literal = n as usize + 1;
+			}
+
+			literal -= 1;
+			take(&mut bytes)
+		})
+	}
+}
+
+/// The most bytes that encoding `pixels` pixels can take, for sizing a buffer
+/// for [`encode`].
+#[inline]
+#[must_use]
+pub const fn max_encoded_len(pixels: usize, rle: bool) -> usize { HEADER_LEN + pixels * 2 + if rle { pixels.div_ceil(MAX_PACKET) } else { 0 } }
+
+/// Encodes `frame`, which is `width` pixels wide, into the start of `dst` with
+/// pixels in `encoding`, run-length encoded if `rle`. Returns the number of
+/// bytes written.
+///
+/// Fails with [`AssetError::TooShort`] if `dst` is too short, or with
+/// [`AssetError::LengthMismatch`] if `frame` isn't a whole number of rows or is
+/// too large to describe in the header.
+pub fn encode(frame: &[Rgb565], width: u16, encoding: Encoding, rle: bool, dst: &mut [u8]) -> Result<usize, AssetError> {
+	encode_with(|i| frame[i], frame.len(), width, encoding, rle, dst)
+}
+
+/// Encodes like [`encode`], but with the `len` pixels of the frame given by
+/// `pixel`, so that a frame can be encoded from part of a larger one.
+pub(crate) fn encode_with(pixel: impl Fn(usize) -> Rgb565, len: usize, width: u16, encoding: Encoding, rle: bool, dst: &mut [u8]) -> Result<usize, AssetError> {
+	let height = match len.checked_div(width as usize) {
+		Some(height) if len.is_multiple_of(width as usize) => u16::try_from(height).map_err(|_| AssetError::LengthMismatch)?,
+		None if len == 0 => 0,
+		_ => return Err(AssetError::LengthMismatch)
+	};
+
+	let (header, payload) = dst.split_first_chunk_mut::<HEADER_LEN>().ok_or(AssetError::TooShort)?;
+	let mut written = 0;
+	let mut put = |bytes: &[u8]| -> Result<(), AssetError> {
+		payload.get_mut(written..written + bytes.len()).ok_or(AssetError::TooShort)?.copy_from_slice(bytes);
+		written += bytes.len();
+		Ok(())
+	};
+
+	if !rle {
+		for i in 0..len {
+			put(&pixel(i).to_bytes(encoding))?;
+		}
+	} else {
+		let mut start = 0;
+
+		while start < len {
+			let first = pixel(start);
+			let end = len.min(start + MAX_PACKET);
+			let run = (start..end).take_while(|i| pixel(*i) == first).count();
+
+			if run > 1 {
+				put(&[0x80 | (run - 1) as u8])?;
+				put(&first.to_bytes(encoding))?;
+				start += run;
+				continue;
+			}
+
+			// Take pixels as they are up to the next run of at least two.
+			let literal = (start..end).position(|i| i + 1 < len && pixel(i) == pixel(i + 1)).unwrap_or(end - start);
+			put(&[(literal - 1) as u8])?;
+
+			for i in start..start + literal {
+				put(&pixel(i).to_bytes(encoding))?;
+			}
+
+			start += literal;
+		}
+	}
+
+	let format = ENCODINGS.iter().position(|known| *known == encoding).unwrap_or(0) as u8;
+	header[..4].copy_from_slice(&MAGIC);
+	header[4] = format;
+	header[5] = if rle { FLAG_RLE } else { 0 };
+	header[6..8].copy_from_slice(&width.to_le_bytes());
+	header[8..10].copy_from_slice(&height.to_le_bytes());
+	header[10..14].copy_from_slice(&(written as u32).to_le_bytes());
+	let crc = !crc32(crc32(!0, &header[..14]), &payload[..written]);
+	header[14..].copy_from_slice(&crc.to_le_bytes());
+
+	Ok(HEADER_LEN + written)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{crc32, encode, max_encoded_len, Asset, AssetError, HEADER_LEN};
+	use crate::{Encoding, Rgb565};
+
+	#[test]
+	fn crc() {
+		assert_eq!(!crc32(!0, b"123456789"), 0xCBF4_3926);
+	}
+
+	#[test]
+	fn round_trip() {
+		// Runs, literals, a run longer than a packet and a lone pixel at the
+		// end.
+		let mut frame = vec![Rgb565::RED; 300];
+		frame.extend((0..200).map(Rgb565::from_rgb565));
+		frame.extend([Rgb565::BLUE, Rgb565::BLUE, Rgb565::LIME, Rgb565::WHITE]);
+
+		for encoding in [Encoding::Rgb565Le, Encoding::Rgb565Be, Encoding::Bgr565Le, Encoding::Bgr565Be] {
+			for rle in [false, true] {
+				let mut buffer = vec![0; max_encoded_len(frame.len(), rle)];
+				let len = encode(&frame, 63, encoding, rle, &mut buffer).unwrap();
+
+				let (asset, rest) = Asset::parse(&buffer).unwrap();
+				assert_eq!((asset.encoding, asset.rle, asset.width, asset.height), (encoding, rle, 63, 8));
+				assert!(asset.pixels().eq(frame.iter().copied()));
+				assert_eq!(rest.len(), buffer.len() - len);
+			}
+		}
+
+		let mut buffer = [0; HEADER_LEN];
+		assert_eq!(encode(&[], 0, Encoding::Rgb565Le, true, &mut buffer), Ok(HEADER_LEN));
+		assert_eq!(Asset::parse(&buffer).unwrap().0.pixels().count(), 0);
+	}
+
+	#[test]
+	fn layout() {
+		let mut buffer = [0; 32];
+		let len = encode(&[Rgb565::RED, Rgb565::RED, Rgb565::BLUE], 3, Encoding::Rgb565Be, true, &mut buffer).unwrap();
+		assert_eq!(buffer[..14], *b"R5IM\x01\x01\x03\x00\x01\x00\x06\x00\x00\x00");
+		assert_eq!(buffer[HEADER_LEN..len], [0x81, 0xF8, 0x00, 0x00, 0x00, 0x1F]);
+	}
+
+	#[test]
+	fn errors() {
+		let frame = [Rgb565::RED; 4];
+		let mut buffer = [0; HEADER_LEN + 8];
+		assert_eq!(encode(&frame, 3, Encoding::Rgb565Le, false, &mut buffer), Err(AssetError::LengthMismatch));
+		assert_eq!(encode(&frame, 2, Encoding::Rgb565Le, false, &mut buffer[..HEADER_LEN + 7]), Err(AssetError::TooShort));
+		encode(&frame, 2, Encoding::Rgb565Le, false, &mut buffer).unwrap();
+
+		assert_eq!(Asset::parse(&buffer[..HEADER_LEN + 7]).map(|_| ()), Err(AssetError::TooShort));
+
+		let mut bad = buffer;
+		bad[0] = b'X';
+		assert_eq!(Asset::parse(&bad).map(|_| ()), Err(AssetError::BadMagic));
+
+		let mut bad = buffer;
+		bad[4] = 4;
+		assert_eq!(Asset::parse(&bad).map(|_| ()), Err(AssetError::UnknownFormat));
+
+		let mut bad = buffer;
+		bad[5] = 2;
+		assert_eq!(Asset::parse(&bad).map(|_| ()), Err(AssetError::UnknownFormat));
+
+		let mut bad = buffer;
+		bad[HEADER_LEN + 3] ^= 1;
+		assert_eq!(Asset::parse(&bad).map(|_| ()), Err(AssetError::BadChecksum));
+
+		// A valid checksum over pixels that don't add up.
+		let mut short = [0; HEADER_LEN + 6];
+		encode(&frame[..3], 3, Encoding::Rgb565Le, false, &mut short).unwrap();
+		short[6] = 2;
+		let crc = !crc32(crc32(!0, &short[..14]), &short[HEADER_LEN..]);
+		short[14..HEADER_LEN].copy_from_slice(&crc.to_le_bytes());
+		assert_eq!(Asset::parse(&short).map(|_| ()), Err(AssetError::LengthMismatch));
+	}
+}
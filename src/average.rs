@@ -0,0 +1,125 @@
+use core::iter::Sum;
+
+use crate::Rgb565;
+
+/// The average of any number of colors, computed in linear light.
+///
+/// RGB565 values are linear already, so this sums each channel exactly and
+/// only rounds once, when the average is taken. Averaging sRGB values instead
+/// would come out too dark, since they're gamma encoded.
+///
+/// ```
+/// # use rgb565::{ColorAverage, Rgb565};
+/// let average: ColorAverage = [0x0000, 0xFFFF].map(Rgb565::from_rgb565).into_iter().collect();
+/// assert_eq!(average.average().map(|color| color.to_rgb565_components()), Some([16, 32, 16]));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct ColorAverage {
+	sum: [u64; 3],
+	count: u64
+}
+
+impl ColorAverage {
+	/// An average of no colors.
+	#[must_use]
+	pub const fn new() -> Self { Self { sum: [0; 3], count: 0 } }
+
+	/// Adds `color` to the average.
+	#[inline]
+	pub fn add(&mut self, color: Rgb565) {
+		let [r, g, b] = color.to_rgb565_components();
+		self.sum[0] += r as u64;
+		self.sum[1] += g as u64;
+		self.sum[2] += b as u64;
+		self.count += 1;
+	}
+
+	/// How many colors have been added.
+	#[inline]
+	#[must_use]
+	pub const fn count(&self) -> u64 { self.count }
+
+	/// The average color, rounded to the nearest RGB565 color, or `None` if no
+	/// colors have been added.
+	#[must_use]
+	pub fn average(&self) -> Option<Rgb565> {
+		let [r, g, b] = self.scaled([31, 63, 31])?;
+		Some(Rgb565::from_rgb565_components(r, g, b))
+	}
+
+	/// The average color in 8-bit RGB, which keeps more of its precision than
+	/// [`average`](Self::average), or `None` if no colors have been added.
+	/// Unlike [`Rgb565::to_rgb888_components`], this rounds to the nearest
+	/// value.
+	#[must_use]
+	pub fn average_rgb888(&self) -> Option<[u8; 3]> { self.scaled([255; 3]) }
+
+	/// The average of each channel, scaled from its 5 or 6 bits to `max` and
+	/// rounded to the nearest value.
+	fn scaled(&self, max: [u64; 3]) -> Option<[u8; 3]> {
+		if self.count == 0 {
+			return None;
+		}
+
+		let levels = [31, 63, 31];
+		Some([0, 1, 2].map(|i| {
+			let denominator = self.count * levels[i];
+			((self.sum[i] * max[i] * 2 + denominator) / (denominator * 2)) as u8
+		}))
+	}
+}
+
+impl Extend<Rgb565> for ColorAverage {
+	fn extend<I: IntoIterator<Item = Rgb565>>(&mut self, iter: I) {
+		for color in iter {
+			self.add(color);
+		}
+	}
+}
+
+impl<'a> Extend<&'a Rgb565> for ColorAverage {
+	fn extend<I: IntoIterator<Item = &'a Rgb565>>(&mut self, iter: I) { self.extend(iter.into_iter().copied()) }
+}
+
+impl FromIterator<Rgb565> for ColorAverage {
+	fn from_iter<I: IntoIterator<Item = Rgb565>>(iter: I) -> Self {
+		let mut average = Self::new();
+		average.extend(iter);
+		average
+	}
+}
+
+impl<'a> FromIterator<&'a Rgb565> for ColorAverage {
+	fn from_iter<I: IntoIterator<Item = &'a Rgb565>>(iter: I) -> Self { iter.into_iter().copied().collect() }
+}
+
+impl Sum<Rgb565> for ColorAverage {
+	fn sum<I: Iterator<Item = Rgb565>>(iter: I) -> Self { iter.collect() }
+}
+
+impl<'a> Sum<&'a Rgb565> for ColorAverage {
+	fn sum<I: Iterator<Item = &'a Rgb565>>(iter: I) -> Self { iter.collect() }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::ColorAverage;
+	use crate::Rgb565;
+
+	#[test]
+	fn average() {
+		assert_eq!(ColorAverage::new().average(), None);
+		assert_eq!(ColorAverage::new().average_rgb888(), None);
+
+		for color in Rgb565::all().step_by(97) {
+			let average: ColorAverage = [color; 3].iter().sum();
+			assert_eq!(average.count(), 3);
+			assert_eq!(average.average(), Some(color));
+		}
+
+		let colors = [0xF800, 0xF800, 0x001F].map(Rgb565::from_rgb565);
+		let average: ColorAverage = colors.iter().collect();
+		assert_eq!(average.average().map(|color| color.to_rgb565_components()), Some([21, 0, 10]));
+		assert_eq!(average.average_rgb888(), Some([170, 0, 85]));
+	}
+}
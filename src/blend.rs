@@ -0,0 +1,166 @@
+//! Blend modes other than plain alpha compositing, for particle effects and
+//! lighting overlays.
+//!
+//! Each mode takes the color underneath first and the color going over it
+//! second, though all of them come out the same either way around. The
+//! channels of RGB565 are linear light, so these are the real thing rather
+//! than an approximation of it, and they work on the packed values as far as
+//! they can instead of unpacking every channel:
+//!
+//! - [`add`] adds the channels, saturating at the brightest level, like light
+//!   shining on the same spot.
+//! - [`multiply`] multiplies the channels as fractions of the brightest level,
+//!   like light passing through a filter. It only ever darkens.
+//! - [`screen`] is the opposite of multiply, and only ever brightens.
+//!
+//! For plain alpha compositing, see [`Rgb565::blend`].
+//!
+//! ```
+//! # use rgb565::{blend, Rgb565};
+//! assert_eq!(blend::add(Rgb565::RED, Rgb565::LIME), Rgb565::YELLOW);
+//! assert_eq!(blend::multiply(Rgb565::YELLOW, Rgb565::FUCHSIA), Rgb565::RED);
+//! assert_eq!(blend::screen(Rgb565::RED, Rgb565::BLUE), Rgb565::FUCHSIA);
+//! ```
+
+use crate::Rgb565;
+
+/// Where the channels go when spread across a `u32` by [`spread`]: green in
+/// the top half, clear of red and blue, with room above each channel.
+const SPREAD: u32 = 0x07E0F81F;
+
+/// The bit just above red and blue, and just above green, when spread.
+const CARRY_RB: u32 = 1 << 16 | 1 << 5;
+const CARRY_G: u32 = 1 << 27;
+
+#[inline]
+const fn spread(color: Rgb565) -> u32 { (color.to_rgb565() as u32 | (color.to_rgb565() as u32) << 16) & SPREAD }
+
+#[inline]
+const fn gather(spread: u32) -> Rgb565 { Rgb565::from_rgb565((spread | spread >> 16) as u16) }
+
+/// Adds the channels of `over` to those of `dst`, each staying at the
+/// brightest level instead of wrapping around.
+///
+/// ```
+/// # use rgb565::{blend, Rgb565};
+/// let dim = Rgb565::from_rgb565_components(20, 40, 4);
+/// assert_eq!(blend::add(dim, dim).to_rgb565_components(), [31, 63, 8]);
+/// ```
+#[inline]
+#[must_use]
+pub const fn add(dst: Rgb565, over: Rgb565) -> Rgb565 {
+	let sum = spread(dst) + spread(over);
+	let (rb, g) = (sum & CARRY_RB, sum & CARRY_G);
+
+	// Fills every bit of each channel that carried out of it.
+	let saturated = (rb - (rb >> 5)) | (g - (g >> 6));
+	gather((sum | saturated) & SPREAD)
+}
+
+/// `a * b / max`, rounded to the nearest, for `a` and `b` up to `max`, which is
+/// `2.pow(bits) - 1`.
+#[inline]
+const fn scale(a: u16, b: u16, bits: u32) -> u16 {
+	let product = a * b + (1 << (bits - 1));
+	(product + (product >> bits)) >> bits
+}
+
+/// Multiplies the channels of `dst` and `over`, as fractions of the brightest
+/// level, rounding to the nearest level.
+///
+/// ```
+/// # use rgb565::{blend, Rgb565};
+/// let half = Rgb565::from_rgb565_components(16, 32, 16);
+/// assert_eq!(blend::multiply(half, half).to_rgb565_components(), [8, 16, 8]);
+/// assert_eq!(blend::multiply(Rgb565::TEAL, Rgb565::WHITE), Rgb565::TEAL);
+/// ```
+#[inline]
+#[must_use]
+pub const fn multiply(dst: Rgb565, over: Rgb565) -> Rgb565 {
+	let (a, b) = (dst.to_rgb565(), over.to_rgb565());
+	let r = scale(a >> 11, b >> 11, 5);
+	let g = scale(a >> 5 & 0x3F, b >> 5 & 0x3F, 6);
+	let b = scale(a & 0x1F, b & 0x1F, 5);
+	Rgb565::from_rgb565(r << 11 | g << 5 | b)
+}
+
+/// Screens `over` onto `dst`: multiplies how far each channel of each is from
+/// the brightest level, and takes that from the brightest level.
+///
+/// ```
+/// # use rgb565::{blend, Rgb565};
+/// let half = Rgb565::from_rgb565_components(16, 32, 16);
+/// assert_eq!(blend::screen(half, half).to_rgb565_components(), [24, 48, 24]);
+/// assert_eq!(blend::screen(Rgb565::TEAL, Rgb565::BLACK), Rgb565::TEAL);
+/// ```
+#[inline]
+#[must_use]
+pub const fn screen(dst: Rgb565, over: Rgb565) -> Rgb565 {
+	// Inverting every bit takes each channel from the brightest level at once.
+	const fn invert(color: Rgb565) -> Rgb565 { Rgb565::from_rgb565(!color.to_rgb565()) }
+	invert(multiply(invert(dst), invert(over)))
+}
+
+/// Blends each pixel of `src` over the same pixel of `dst` with `mode`, like
+/// [`frame::map_into`](crate::frame::map_into). If the frames are different
+/// lengths, only as many pixels as fit in the shorter one are blended.
+///
+/// ```
+/// # use rgb565::{blend, Rgb565};
+/// let mut frame = [0x0000, 0x8410, 0xFFFF];
+/// blend::blend_into(&[0x8410; 3], &mut frame, blend::add);
+/// assert_eq!(frame, [0x8410, 0xFFFF, 0xFFFF]);
+/// ```
+#[inline]
+pub fn blend_into(src: &[u16], dst: &mut [u16], mode: impl Fn(Rgb565, Rgb565) -> Rgb565) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	for (dst, src) in dst.iter_mut().zip(src) {
+		*dst = mode(Rgb565::from_rgb565(*dst), Rgb565::from_rgb565(*src)).to_rgb565();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{add, blend_into, multiply, scale, screen};
+	use crate::Rgb565;
+
+	#[test]
+	fn scaling() {
+		for (bits, max) in [(5, 31), (6, 63)] {
+			for a in 0..=max {
+				for b in 0..=max {
+					assert_eq!(scale(a, b, bits), ((a * b) as f32 / max as f32).round() as u16, "{} * {}", a, b);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn modes() {
+		let channels = |color: Rgb565| color.to_rgb565_components().map(|channel| channel as u16);
+		let colors: Vec<Rgb565> = Rgb565::all().step_by(251).chain([Rgb565::BLACK, Rgb565::WHITE]).collect();
+
+		for a in &colors {
+			for b in &colors {
+				let ([ar, ag, ab], [br, bg, bb]) = (channels(*a), channels(*b));
+				assert_eq!(channels(add(*a, *b)), [(ar + br).min(31), (ag + bg).min(63), (ab + bb).min(31)]);
+				assert_eq!(channels(multiply(*a, *b)), [scale(ar, br, 5), scale(ag, bg, 6), scale(ab, bb, 5)]);
+				assert_eq!(channels(screen(*a, *b)), [31 - scale(31 - ar, 31 - br, 5), 63 - scale(63 - ag, 63 - bg, 6), 31 - scale(31 - ab, 31 - bb, 5)]);
+
+				for mode in [add, multiply, screen] {
+					assert_eq!(mode(*a, *b), mode(*b, *a));
+				}
+			}
+
+			assert_eq!(add(*a, Rgb565::BLACK), *a);
+			assert_eq!(multiply(*a, Rgb565::WHITE), *a);
+			assert_eq!(screen(*a, Rgb565::BLACK), *a);
+		}
+
+		let mut frame = [0xF800, 0x07E0];
+		blend_into(&[0x001F], &mut frame, screen);
+		assert_eq!(frame, [0xF81F, 0x07E0]);
+	}
+}
@@ -0,0 +1,373 @@
+//! Converting PNG and JPEG files into RGB565 from build scripts, so that
+//! firmware can embed pixels that are ready to blit instead of decoding images
+//! on the device.
+//!
+//! Add this crate to `[build-dependencies]` with the `buildtools` feature, and
+//! then in `build.rs`:
+//!
+//! ```no_run
+//! # use rgb565::{buildtools::{self, Dither, Options}, dither::ThresholdMap, Encoding};
+//! let options = Options { encoding: Encoding::Rgb565Be, dither: Dither::Ordered(ThresholdMap::BAYER_4), rle: true };
+//! let logo = buildtools::asset("assets/logo.png", &options).unwrap();
+//! buildtools::write_out("logo.r5im", &logo).unwrap();
+//!
+//! let icons = buildtools::bundle(&[("wifi", "assets/wifi.png"), ("battery", "assets/battery.jpg")], &options).unwrap();
+//! buildtools::write_out("icons.r5bn", &icons).unwrap();
+//! ```
+//!
+//! and in the firmware:
+//!
+//! ```ignore
+//! static LOGO: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/logo.r5im"));
+//! ```
+//!
+//! With the `webp` feature, WebP images can be loaded too, and with the `gif`
+//! feature, `animation` also turns animated GIFs into
+//! [animations](mod@crate::animation).
+//!
+//! Images are taken as sRGB, like [`convert::from_rgb_image`] does, and any
+//! alpha channel is dropped. Every image loaded prints a `rerun-if-changed`
+//! line for it, so the build script reruns when it's edited.
+//!
+//! [`convert::from_rgb_image`]: crate::convert::from_rgb_image
+
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use crate::asset::{self, AssetError};
+use crate::bundle::{self, BundleWriter};
+use crate::dither::{self, ThresholdMap};
+use crate::{Converter, Encoding, Rgb565};
+
+/// An error from converting images.
+#[derive(Debug)]
+pub enum BuildError {
+	/// The image couldn't be read or decoded.
+	Image(image::ImageError),
+	/// The output couldn't be written.
+	Io(std::io::Error),
+	/// The image couldn't be put into an asset or bundle, for example
+	/// because of the name it was given.
+	Asset(AssetError),
+	/// The image is wider or taller than 65535 pixels, or a bundle or
+	/// animation has more than 65535 images.
+	TooLarge
+}
+
+impl Display for BuildError {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Image(error) => write!(f, "couldn't load image: {}", error),
+			Self::Io(error) => write!(f, "couldn't write output: {}", error),
+			Self::Asset(error) => write!(f, "couldn't encode asset: {}", error),
+			Self::TooLarge => f.write_str("image or bundle is too large")
+		}
+	}
+}
+
+impl std::error::Error for BuildError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::Image(error) => Some(error),
+			Self::Io(error) => Some(error),
+			Self::Asset(error) => Some(error),
+			Self::TooLarge => None
+		}
+	}
+}
+
+impl From<image::ImageError> for BuildError {
+	fn from(error: image::ImageError) -> Self { Self::Image(error) }
+}
+
+impl From<std::io::Error> for BuildError {
+	fn from(error: std::io::Error) -> Self { Self::Io(error) }
+}
+
+impl From<AssetError> for BuildError {
+	fn from(error: AssetError) -> Self { Self::Asset(error) }
+}
+
+/// How images are brought down to RGB565.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum Dither {
+	/// Each pixel is converted to the nearest color, like
+	/// [`Rgb565::from_srgb888_components`].
+	#[default]
+	None,
+	/// Ordered dithering against a threshold map, like
+	/// [`dither::ordered_frame`].
+	Ordered(ThresholdMap<'static>),
+	/// Floyd-Steinberg error diffusion, like [`dither::floyd_steinberg`].
+	FloydSteinberg
+}
+
+/// How images are converted and stored.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Options {
+	/// The byte order and channel order of the pixels.
+	pub encoding: Encoding,
+	/// How colors between the levels of RGB565 are approximated.
+	pub dither: Dither,
+	/// Whether assets and bundles are run-length encoded. Raw pixels never
+	/// are.
+	pub rle: bool
+}
+
+impl Default for Options {
+	/// Little-endian RGB565, undithered and without run-length encoding.
+	fn default() -> Self { Self { encoding: Encoding::Rgb565Le, dither: Dither::None, rle: false } }
+}
+
+/// An image converted to RGB565.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Frame {
+	/// The width of the image, in pixels.
+	pub width: u16,
+	/// The height of the image, in pixels.
+	pub height: u16,
+	/// The pixels, row by row from the top left.
+	pub pixels: Vec<Rgb565>
+}
+
+/// Converts an sRGB channel to the linear 8-bit values that the dithering
+/// routines quantize, rounding to the nearest.
+fn linearize(s8: u8) -> u8 { (crate::lut::srgb_untransfer(s8 as f32 / 255.0) * 255.0 + 0.5) as u8 }
+
+/// Converts an sRGB image to RGB565, dithered as `dither` says.
+#[must_use]
+pub fn quantize(image: &image::RgbImage, dither: Dither) -> Vec<Rgb565> {
+	let (src, width) = (image.as_raw().as_chunks::<3>().0, image.width() as usize);
+	let mut dst = vec![Rgb565::default(); src.len()];
+
+	if dither == Dither::None {
+		for (dst, [r, g, b]) in dst.iter_mut().zip(src) {
+			*dst = Rgb565::from_srgb888_components(*r, *g, *b);
+		}
+
+		return dst;
+	}
+
+	let linear: Vec<[u8; 3]> = src.iter().map(|pixel| pixel.map(linearize)).collect();
+
+	match dither {
+		Dither::Ordered(map) => dither::ordered_frame(&map, &linear, width, &mut dst),
+		_ => dither::floyd_steinberg(&Converter::builtin(), &linear, width, &mut dst)
+	}
+
+	dst
+}
+
+/// Loads the PNG or JPEG at `path`, or with the `webp` feature a WebP, and
+/// converts it to RGB565, dithered as `dither` says. The format is picked by
+/// the extension. Animated PNGs and WebPs load as their first frame, which is
+/// the still that viewers without animation support show.
+pub fn load(path: impl AsRef<Path>, dither: Dither) -> Result<Frame, BuildError> {
+	let path = path.as_ref();
+	println!("cargo:rerun-if-changed={}", path.display());
+
+	let image = image::open(path)?.into_rgb8();
+	let width = u16::try_from(image.width()).map_err(|_| BuildError::TooLarge)?;
+	let height = u16::try_from(image.height()).map_err(|_| BuildError::TooLarge)?;
+	Ok(Frame { width, height, pixels: quantize(&image, dither) })
+}
+
+/// Loads the image at `path` as nothing but its pixels, one row after
+/// another in `options.encoding`.
+pub fn raw(path: impl AsRef<Path>, options: &Options) -> Result<Vec<u8>, BuildError> {
+	let frame = load(path, options.dither)?;
+	Ok(frame.pixels.iter().flat_map(|pixel| pixel.to_bytes(options.encoding)).collect())
+}
+
+/// Loads the image at `path` as an asset, in the format of the
+/// [`asset`](mod@asset) module.
+pub fn asset(path: impl AsRef<Path>, options: &Options) -> Result<Vec<u8>, BuildError> {
+	let frame = load(path, options.dither)?;
+	let mut buffer = vec![0; asset::max_encoded_len(frame.pixels.len(), options.rle)];
+	let len = asset::encode(&frame.pixels, frame.width, options.encoding, options.rle, &mut buffer)?;
+	buffer.truncate(len);
+	Ok(buffer)
+}
+
+/// Loads each image of `images`, given as its name and path, into a bundle
+/// of the [`bundle`](mod@bundle) format, in order.
+pub fn bundle(images: &[(&str, impl AsRef<Path>)], options: &Options) -> Result<Vec<u8>, BuildError> {
+	let capacity = u16::try_from(images.len()).map_err(|_| BuildError::TooLarge)?;
+	let frames = images.iter().map(|(_, path)| load(path, options.dither)).collect::<Result<Vec<_>, _>>()?;
+	let len = frames.iter().map(|frame| bundle::ENTRY_LEN + asset::max_encoded_len(frame.pixels.len(), options.rle)).sum::<usize>();
+
+	let mut buffer = vec![0; bundle::HEADER_LEN + len];
+	let mut writer = BundleWriter::new(&mut buffer, capacity)?;
+
+	for ((name, _), frame) in images.iter().zip(&frames) {
+		writer.push(name, &frame.pixels, frame.width, options.encoding, options.rle)?;
+	}
+
+	let len = writer.finish();
+	buffer.truncate(len);
+	Ok(buffer)
+}
+
+/// Decodes the animated GIF at `path` into an
+/// [animation](crate::animation), each frame converted like [`load`] does and
+/// shown for as long as the GIF says, to the millisecond.
+///
+/// Frames are composited the way a browser would show them, so each one is
+/// a whole picture, and [`AnimationWriter`](crate::animation::AnimationWriter)
+/// then stores only what changed from the one before. Ordered dithering
+/// keeps that small, since pixels that don't change stay the same, but error
+/// diffusion lets any change ripple over the rest of the frame.
+#[cfg(feature = "gif")]
+pub fn animation(path: impl AsRef<Path>, options: &Options) -> Result<Vec<u8>, BuildError> {
+	use image::AnimationDecoder;
+
+	let path = path.as_ref();
+	println!("cargo:rerun-if-changed={}", path.display());
+
+	let file = std::io::BufReader::new(std::fs::File::open(path)?);
+	let decoder = image::codecs::gif::GifDecoder::new(file)?;
+	let (width, height) = image::ImageDecoder::dimensions(&decoder);
+	let (width, height) = (u16::try_from(width).map_err(|_| BuildError::TooLarge)?, u16::try_from(height).map_err(|_| BuildError::TooLarge)?);
+
+	let mut frames = Vec::new();
+
+	for frame in decoder.into_frames() {
+		let frame = frame?;
+		let (numerator, denominator) = frame.delay().numer_denom_ms();
+		let delay_ms = u16::try_from(numerator / denominator.max(1)).unwrap_or(u16::MAX);
+		let image = image::DynamicImage::ImageRgba8(frame.into_buffer()).into_rgb8();
+		frames.push((quantize(&image, options.dither), delay_ms));
+	}
+
+	if frames.len() > u16::MAX as usize {
+		return Err(BuildError::TooLarge);
+	}
+
+	let max_frame = crate::animation::FRAME_HEADER_LEN + asset::max_encoded_len(width as usize * height as usize, options.rle);
+	let mut buffer = vec![0; crate::animation::HEADER_LEN + max_frame * frames.len()];
+	let mut writer = crate::animation::AnimationWriter::new(&mut buffer, width, height, options.encoding, options.rle)?;
+
+	for (pixels, delay_ms) in &frames {
+		writer.push(pixels, *delay_ms)?;
+	}
+
+	let len = writer.finish();
+	buffer.truncate(len);
+	Ok(buffer)
+}
+
+/// Writes `bytes` to `name` in the `OUT_DIR` of the build script and returns
+/// its path, for `include_bytes!(concat!(env!("OUT_DIR"), "/name"))`.
+pub fn write_out(name: &str, bytes: &[u8]) -> Result<PathBuf, BuildError> {
+	let dir = std::env::var_os("OUT_DIR").ok_or_else(|| std::io::Error::other("OUT_DIR isn't set, so this isn't a build script"))?;
+	let path = Path::new(&dir).join(name);
+	std::fs::write(&path, bytes)?;
+	Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{BuildError, Dither, Options};
+	use crate::asset::{Asset, AssetError};
+	use crate::bundle::Bundle;
+	use crate::dither::ThresholdMap;
+	use crate::{Encoding, Rgb565};
+
+	#[test]
+	fn conversions() {
+		let dir = std::env::temp_dir().join(format!("rgb565-buildtools-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+
+		let gradient = image::RgbImage::from_fn(16, 4, |x, _| image::Rgb([x as u8 * 17, 128, 255 - x as u8 * 17]));
+		gradient.save(dir.join("gradient.png")).unwrap();
+		image::RgbImage::from_pixel(2, 3, image::Rgb([255, 0, 0])).save(dir.join("red.jpg")).unwrap();
+
+		let options = Options { encoding: Encoding::Rgb565Be, ..Options::default() };
+		let raw = super::raw(dir.join("gradient.png"), &options).unwrap();
+		let expected: Vec<u8> = gradient.pixels().flat_map(|pixel| Rgb565::from_srgb888_components(pixel[0], pixel[1], pixel[2]).to_rgb565_be()).collect();
+		assert_eq!(raw, expected);
+
+		let options = Options { rle: true, dither: Dither::Ordered(ThresholdMap::BAYER_4), ..options };
+		let bytes = super::asset(dir.join("gradient.png"), &options).unwrap();
+		let (asset, rest) = Asset::parse(&bytes).unwrap();
+		assert_eq!((asset.encoding, asset.rle, asset.width, asset.height, rest.len()), (Encoding::Rgb565Be, true, 16, 4, 0));
+		assert!(asset.pixels().zip(super::load(dir.join("gradient.png"), options.dither).unwrap().pixels).all(|(a, b)| a == b));
+
+		let bytes = super::bundle(&[("gradient", dir.join("gradient.png")), ("red", dir.join("red.jpg"))], &options).unwrap();
+		let bundle = Bundle::parse(&bytes).unwrap();
+		let red = bundle.find("red").unwrap().asset().unwrap();
+		assert_eq!((red.width, red.height), (2, 3));
+		assert!(red.pixels().all(|pixel| pixel.r5() >= 30 && pixel.g6() <= 1 && pixel.b5() <= 1));
+
+		assert!(matches!(super::bundle(&[("way_too_long_name", dir.join("red.jpg"))], &options), Err(BuildError::Asset(AssetError::InvalidName))));
+		assert!(matches!(super::raw(dir.join("missing.png"), &options), Err(BuildError::Image(_))));
+
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	#[cfg(feature = "gif")]
+	fn gif() {
+		use crate::animation::{AnimationFrames, Player};
+
+		let path = std::env::temp_dir().join(format!("rgb565-buildtools-{}.gif", std::process::id()));
+		let frames = [[255, 0, 0], [0, 0, 255], [0, 255, 0]].into_iter().enumerate().map(|(i, color)| {
+			let mut image = image::RgbaImage::from_pixel(5, 4, image::Rgba([255; 4]));
+			image.put_pixel(i as u32, 1, image::Rgba([color[0], color[1], color[2], 255]));
+			image::Frame::from_parts(image, 0, 0, image::Delay::from_numer_denom_ms(30 * (i as u32 + 1), 1))
+		});
+
+		let mut encoder = image::codecs::gif::GifEncoder::new(std::fs::File::create(&path).unwrap());
+		encoder.encode_frames(frames).unwrap();
+		drop(encoder);
+
+		let options = Options { rle: true, ..Options::default() };
+		let bytes = super::animation(&path, &options).unwrap();
+		let animation = AnimationFrames::parse(&bytes).unwrap();
+		assert_eq!((animation.width, animation.height, animation.len()), (5, 4, 3));
+
+		// Each frame only changes the dot it moves to and the one it leaves.
+		assert!(animation.iter().skip(1).all(|frame| frame.patch.width == 2 && frame.patch.height == 1));
+
+		let mut player = Player::new(animation, vec![0; 20]).unwrap();
+
+		for (i, color) in [Rgb565::RED, Rgb565::BLUE, Rgb565::LIME].into_iter().enumerate() {
+			let (frame, delay_ms) = player.next_frame().unwrap();
+			assert_eq!(delay_ms, 30 * (i as u16 + 1));
+			assert_eq!(frame.pixel(i, 1), Some(color));
+			assert_eq!(frame.pixel(4, 3), Some(Rgb565::WHITE));
+		}
+
+		std::fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	#[cfg(feature = "webp")]
+	fn webp() {
+		let path = std::env::temp_dir().join(format!("rgb565-buildtools-{}.webp", std::process::id()));
+		let image = image::RgbImage::from_fn(7, 3, |x, y| image::Rgb([x as u8 * 40, y as u8 * 100, 200]));
+		image.write_with_encoder(image::codecs::webp::WebPEncoder::new_lossless(std::fs::File::create(&path).unwrap())).unwrap();
+
+		let frame = super::load(&path, Dither::None).unwrap();
+		assert_eq!((frame.width, frame.height), (7, 3));
+		assert_eq!(frame.pixels, super::quantize(&image, Dither::None));
+
+		std::fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn dithering() {
+		// A gray between two levels of RGB565 comes out as a mix of both, with
+		// either kind of dithering, and as just one without.
+		let gray = image::RgbImage::from_pixel(8, 8, image::Rgb([103; 3]));
+
+		for dither in [Dither::Ordered(ThresholdMap::BAYER_4), Dither::FloydSteinberg] {
+			let mut levels: Vec<u8> = super::quantize(&gray, dither).iter().map(|pixel| pixel.g6()).collect();
+			levels.dedup();
+			assert!(levels.len() > 1, "{:?}", dither);
+		}
+
+		let pixels = super::quantize(&gray, Dither::None);
+		assert!(pixels.iter().all(|pixel| *pixel == Rgb565::from_srgb888_components(103, 103, 103)));
+	}
+}
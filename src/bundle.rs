@@ -0,0 +1,297 @@
+//! Bundles of named [assets](crate::asset), so that all of the images of a
+//! firmware can go into flash as one blob and be looked up by name, instead of
+//! an `include_bytes!` and a hand-kept offset for each.
+//!
+//! A bundle is a 12-byte header, an index of 20-byte entries, then the assets
+//! themselves. All multi-byte fields are little-endian:
+//!
+//! | Offset | Size | Field                                                  |
+//! |--------|------|--------------------------------------------------------|
+//! | 0      | 4    | Magic, `R5BN`                                          |
+//! | 4      | 2    | Number of entries in the index                         |
+//! | 6      | 2    | Reserved, 0                                            |
+//! | 8      | 4    | CRC-32 of the first 8 bytes of the header and the index |
+//!
+//! Each entry of the index is:
+//!
+//! | Offset | Size | Field                                                  |
+//! |--------|------|--------------------------------------------------------|
+//! | 0      | 12   | Name, UTF-8 padded with zeros                          |
+//! | 12     | 4    | Offset of the asset from the start of the bundle       |
+//! | 16     | 4    | Length of the asset in bytes                           |
+//!
+//! Nothing is copied when reading a bundle, so on a microcontroller it can be
+//! parsed straight out of memory-mapped flash, and the pixels of its assets
+//! read from there as they're drawn. Each asset carries its own checksum, which
+//! is checked when [`Entry::asset`] parses it rather than for the whole bundle
+//! up front, so that looking up one image doesn't mean reading all of them.
+//!
+//! ```
+//! # use rgb565::{bundle::{Bundle, BundleWriter}, Encoding, Rgb565};
+//! let mut buffer = [0; 256];
+//! let mut writer = BundleWriter::new(&mut buffer, 2).unwrap();
+//! writer.push("logo", &[Rgb565::RED; 64], 8, Encoding::Rgb565Le, true).unwrap();
+//! writer.push("cursor", &[Rgb565::WHITE; 4], 2, Encoding::Rgb565Le, false).unwrap();
+//! let len = writer.finish();
+//!
+//! let bundle = Bundle::parse(&buffer[..len]).unwrap();
+//! assert_eq!(bundle.len(), 2);
+//! let logo = bundle.find("logo").unwrap().asset().unwrap();
+//! assert_eq!((logo.width, logo.height), (8, 8));
+//! assert!(logo.pixels().all(|pixel| pixel == Rgb565::RED));
+//! ```
+
+use crate::asset::{self, crc32, Asset, AssetError};
+use crate::{Encoding, Rgb565};
+
+/// The magic bytes every bundle starts with.
+pub const MAGIC: [u8; 4] = *b"R5BN";
+
+/// The length of the header.
+pub const HEADER_LEN: usize = 12;
+
+/// The length of each entry of the index.
+pub const ENTRY_LEN: usize = 20;
+
+/// The most bytes the name of an entry can have.
+pub const NAME_LEN: usize = 12;
+
+/// An entry of a bundle, borrowing its name and asset from the bundle.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Entry<'a> {
+	/// The name of the entry, up to [`NAME_LEN`] bytes.
+	pub name: &'a str,
+	/// The asset as stored, from its header to the end of its pixels.
+	pub bytes: &'a [u8]
+}
+
+impl<'a> Entry<'a> {
+	/// Parses the asset of this entry, verifying its checksum.
+	pub fn asset(&self) -> Result<Asset<'a>, AssetError> { Asset::parse(self.bytes).map(|(asset, _)| asset) }
+}
+
+/// Reads the entry at the start of `entry` out of `bundle`.
+fn entry<'a>(bundle: &'a [u8], entry: &'a [u8]) -> Result<Entry<'a>, AssetError> {
+	let field = |at: usize| u32::from_le_bytes([entry[at], entry[at + 1], entry[at + 2], entry[at + 3]]) as usize;
+	let name = &entry[..NAME_LEN];
+	let name = &name[..name.iter().position(|byte| *byte == 0).unwrap_or(NAME_LEN)];
+
+	if name.is_empty() {
+		return Err(AssetError::InvalidName);
+	}
+
+	let bytes = field(NAME_LEN).checked_add(field(NAME_LEN + 4)).and_then(|end| bundle.get(field(NAME_LEN)..end)).ok_or(AssetError::TooShort)?;
+	let name = core::str::from_utf8(name).map_err(|_| AssetError::InvalidName)?;
+	Ok(Entry { name, bytes })
+}
+
+/// A bundle parsed from a buffer, borrowing its index and assets from it.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Bundle<'a> {
+	bytes: &'a [u8],
+	index: &'a [u8]
+}
+
+impl<'a> Bundle<'a> {
+	/// Parses a bundle from `bytes`. The checksum of the index is verified,
+	/// and so is that every entry has a name and lies within `bytes`, but not
+	/// the assets themselves.
+	pub fn parse(bytes: &'a [u8]) -> Result<Self, AssetError> {
+		let (header, rest) = bytes.split_first_chunk::<HEADER_LEN>().ok_or(AssetError::TooShort)?;
+
+		if header[..4] != MAGIC {
+			return Err(AssetError::BadMagic);
+		}
+
+		if header[6..8] != [0, 0] {
+			return Err(AssetError::UnknownFormat);
+		}
+
+		let count = u16::from_le_bytes([header[4], header[5]]) as usize;
+		let index = rest.get(..count * ENTRY_LEN).ok_or(AssetError::TooShort)?;
+
+		if !crc32(crc32(!0, &header[..8]), index) != u32::from_le_bytes([header[8], header[9], header[10], header[11]]) {
+			return Err(AssetError::BadChecksum);
+		}
+
+		for raw in index.chunks_exact(ENTRY_LEN) {
+			entry(bytes, raw)?;
+		}
+
+		Ok(Self { bytes, index })
+	}
+
+	/// The number of entries.
+	#[inline]
+	#[must_use]
+	pub fn len(&self) -> usize { self.index.len() / ENTRY_LEN }
+
+	/// Whether the bundle has no entries.
+	#[inline]
+	#[must_use]
+	pub fn is_empty(&self) -> bool { self.index.is_empty() }
+
+	/// Returns the entry at `index`, in the order they were written.
+	#[must_use]
+	pub fn get(&self, index: usize) -> Option<Entry<'a>> { entry(self.bytes, self.index.chunks_exact(ENTRY_LEN).nth(index)?).ok() }
+
+	/// Returns the entries, in the order they were written.
+	pub fn entries(&self) -> impl Iterator<Item = Entry<'a>> + 'a {
+		let bytes = self.bytes;
+		self.index.chunks_exact(ENTRY_LEN).filter_map(move |raw| entry(bytes, raw).ok())
+	}
+
+	/// Returns the entry named `name`.
+	#[must_use]
+	pub fn find(&self, name: &str) -> Option<Entry<'a>> { self.entries().find(|entry| entry.name == name) }
+}
+
+/// Writes a bundle into a buffer, one asset at a time.
+///
+/// The index goes before the assets, so its size has to be known up front.
+/// The buffer needs [`HEADER_LEN`] bytes, [`ENTRY_LEN`] for each entry the
+/// index has room for, and [`asset::max_encoded_len`] for each asset.
+pub struct BundleWriter<'a> {
+	dst: &'a mut [u8],
+	capacity: usize,
+	count: usize,
+	len: usize
+}
+
+impl<'a> BundleWriter<'a> {
+	/// Starts a bundle at the start of `dst`, with room in the index for
+	/// `capacity` entries. Fails with [`AssetError::TooShort`] if `dst` doesn't
+	/// even have room for the index.
+	pub fn new(dst: &'a mut [u8], capacity: u16) -> Result<Self, AssetError> {
+		let len = HEADER_LEN + capacity as usize * ENTRY_LEN;
+		dst.get_mut(..len).ok_or(AssetError::TooShort)?.fill(0);
+		Ok(Self { dst, capacity: capacity as usize, count: 0, len })
+	}
+
+	/// Encodes `frame` like [`asset::encode`] and adds it to the bundle as
+	/// `name`.
+	///
+	/// Fails with [`AssetError::InvalidName`] if `name` is empty, longer than
+	/// [`NAME_LEN`] bytes, contains a zero byte or is already taken, or with
+	/// [`AssetError::TooShort`] if the index is full or the asset doesn't fit.
+	/// Nothing is added if it fails.
+	pub fn push(&mut self, name: &str, frame: &[Rgb565], width: u16, encoding: Encoding, rle: bool) -> Result<(), AssetError> {
+		let taken = self.dst[HEADER_LEN..][..self.count * ENTRY_LEN].chunks_exact(ENTRY_LEN).any(|raw| raw[..NAME_LEN].split(|byte| *byte == 0).next() == Some(name.as_bytes()));
+
+		if name.is_empty() || name.len() > NAME_LEN || name.contains('\0') || taken {
+			return Err(AssetError::InvalidName);
+		}
+
+		if self.count == self.capacity {
+			return Err(AssetError::TooShort);
+		}
+
+		let written = asset::encode(frame, width, encoding, rle, &mut self.dst[self.len..])?;
+		let raw = &mut self.dst[HEADER_LEN + self.count * ENTRY_LEN..][..ENTRY_LEN];
+		raw[..name.len()].copy_from_slice(name.as_bytes());
+		raw[NAME_LEN..NAME_LEN + 4].copy_from_slice(&(self.len as u32).to_le_bytes());
+		raw[NAME_LEN + 4..].copy_from_slice(&(written as u32).to_le_bytes());
+
+		self.count += 1;
+		self.len += written;
+		Ok(())
+	}
+
+	/// Writes the header and returns the length of the bundle. Any room left
+	/// in the index goes unused.
+	pub fn finish(self) -> usize {
+		let (header, rest) = self.dst.split_at_mut(HEADER_LEN);
+		header[..4].copy_from_slice(&MAGIC);
+		header[4..6].copy_from_slice(&(self.count as u16).to_le_bytes());
+		header[6..8].fill(0);
+		let crc = !crc32(crc32(!0, &header[..8]), &rest[..self.count * ENTRY_LEN]);
+		header[8..].copy_from_slice(&crc.to_le_bytes());
+		self.len
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Bundle, BundleWriter, ENTRY_LEN, HEADER_LEN};
+	use crate::asset::{self, AssetError};
+	use crate::{Encoding, Rgb565};
+
+	#[test]
+	fn round_trip() {
+		let gradient: Vec<Rgb565> = (0..64).map(Rgb565::from_rgb565).collect();
+		let mut buffer = vec![0; HEADER_LEN + 3 * ENTRY_LEN + asset::max_encoded_len(64, true) + asset::max_encoded_len(16, false)];
+		let mut writer = BundleWriter::new(&mut buffer, 3).unwrap();
+		writer.push("gradient", &gradient, 16, Encoding::Bgr565Be, true).unwrap();
+		writer.push("twelve_bytes", &[Rgb565::BLUE; 16], 4, Encoding::Rgb565Le, false).unwrap();
+		let len = writer.finish();
+
+		let bundle = Bundle::parse(&buffer[..len]).unwrap();
+		assert_eq!(bundle.len(), 2);
+		assert_eq!(bundle.entries().map(|entry| entry.name).collect::<Vec<_>>(), ["gradient", "twelve_bytes"]);
+		assert_eq!(bundle.get(1), bundle.find("twelve_bytes"));
+		assert_eq!((bundle.get(2), bundle.find("missing")), (None, None));
+
+		let asset = bundle.get(0).unwrap().asset().unwrap();
+		assert_eq!((asset.encoding, asset.width, asset.height), (Encoding::Bgr565Be, 16, 4));
+		assert!(asset.pixels().eq(gradient));
+		assert!(bundle.find("twelve_bytes").unwrap().asset().unwrap().pixels().eq([Rgb565::BLUE; 16]));
+
+		let mut empty = [0; HEADER_LEN];
+		let len = BundleWriter::new(&mut empty, 0).unwrap().finish();
+		assert!(Bundle::parse(&empty[..len]).unwrap().is_empty());
+	}
+
+	#[test]
+	fn layout() {
+		let mut buffer = [0; 64];
+		let mut writer = BundleWriter::new(&mut buffer, 1).unwrap();
+		writer.push("dot", &[Rgb565::RED], 1, Encoding::Rgb565Le, false).unwrap();
+		assert_eq!(writer.finish(), HEADER_LEN + ENTRY_LEN + asset::HEADER_LEN + 2);
+		assert_eq!(buffer[..8], *b"R5BN\x01\x00\x00\x00");
+		assert_eq!(buffer[HEADER_LEN..HEADER_LEN + ENTRY_LEN], *b"dot\0\0\0\0\0\0\0\0\0\x20\0\0\0\x14\0\0\0");
+		assert_eq!(buffer[32..36], asset::MAGIC);
+	}
+
+	#[test]
+	fn errors() {
+		let mut buffer = [0; 128];
+		assert!(BundleWriter::new(&mut buffer[..HEADER_LEN + ENTRY_LEN - 1], 1).is_err());
+
+		let mut writer = BundleWriter::new(&mut buffer, 2).unwrap();
+		let push = |writer: &mut BundleWriter, name| writer.push(name, &[Rgb565::RED; 4], 2, Encoding::Rgb565Le, false);
+		assert_eq!(push(&mut writer, ""), Err(AssetError::InvalidName));
+		assert_eq!(push(&mut writer, "thirteen_byte"), Err(AssetError::InvalidName));
+		assert_eq!(push(&mut writer, "nul\0"), Err(AssetError::InvalidName));
+		assert_eq!(push(&mut writer, "a"), Ok(()));
+		assert_eq!(push(&mut writer, "a"), Err(AssetError::InvalidName));
+		assert_eq!(writer.push("big", &[Rgb565::RED; 64], 8, Encoding::Rgb565Le, false), Err(AssetError::TooShort));
+		assert_eq!(push(&mut writer, "b"), Ok(()));
+		assert_eq!(push(&mut writer, "c"), Err(AssetError::TooShort));
+		let len = writer.finish();
+		let buffer = &buffer[..len];
+
+		assert_eq!(Bundle::parse(&buffer[..HEADER_LEN + ENTRY_LEN]), Err(AssetError::TooShort));
+
+		let mut bad = buffer.to_vec();
+		bad[0] = b'X';
+		assert_eq!(Bundle::parse(&bad), Err(AssetError::BadMagic));
+
+		let mut bad = buffer.to_vec();
+		bad[6] = 1;
+		assert_eq!(Bundle::parse(&bad), Err(AssetError::UnknownFormat));
+
+		let mut bad = buffer.to_vec();
+		bad[HEADER_LEN] = b'b';
+		assert_eq!(Bundle::parse(&bad), Err(AssetError::BadChecksum));
+
+		// Corrupting an asset only shows when it's parsed.
+		let mut bad = buffer.to_vec();
+		bad[len - 1] ^= 1;
+		let bundle = Bundle::parse(&bad).unwrap();
+		assert!(bundle.find("a").unwrap().asset().is_ok());
+		assert_eq!(bundle.find("b").unwrap().asset(), Err(AssetError::BadChecksum));
+
+		// An index with a valid checksum pointing past the end.
+		assert_eq!(Bundle::parse(&buffer[..len - 1]), Err(AssetError::TooShort));
+	}
+}
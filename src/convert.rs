@@ -0,0 +1,131 @@
+//! Converting whole buffers between RGB565 and packed 8-bit RGB or sRGB.
+//!
+//! These do the same as calling the methods on [`Rgb565`](crate::Rgb565) for
+//! every pixel. Each converts as many pixels as fit in both slices, with 8-bit
+//! pixels packed as three bytes each. They're shorthands for the routines in
+//! [`frame`] that take a [`Converter`], with [`Converter::builtin`], which
+//! also give a runtime choice of LUTs or a table of your own.
+//!
+//! With the `simd` feature, the conversions between RGB565 and 8-bit RGB use
+//! NEON on AArch64, and SSSE3 on x86 when it's enabled at compile time (for
+//! example with `-C target-cpu=x86-64-v2`) or, with `std`, found at runtime.
+//! The results are identical either way. The sRGB conversions are table
+//! lookups, so they stay scalar.
+//!
+//! With the `image` feature, `from_rgb_image` and `to_rgb_image` convert
+//! whole images of the `image` crate, treating them as sRGB.
+//!
+//! ```
+//! # use rgb565::convert;
+//! let mut frame = [0; 2];
+//! convert::rgb888_to_rgb565(&[255, 0, 0, 0, 0, 255], &mut frame);
+//! assert_eq!(frame, [0xF800, 0x001F]);
+//!
+//! let mut rgb888 = [0; 6];
+//! convert::rgb565_to_rgb888(&frame, &mut rgb888);
+//! assert_eq!(rgb888, [255, 0, 0, 0, 0, 255]);
+//! ```
+
+use crate::{frame, Converter};
+
+/// Converts packed 8-bit RGB to RGB565, like
+/// [`Rgb565::from_rgb888_components`](crate::Rgb565::from_rgb888_components).
+#[inline]
+pub fn rgb888_to_rgb565(src: &[u8], dst: &mut [u16]) { frame::convert_rgb888_slice_to_rgb565(&Converter::builtin(), src, dst); }
+
+/// Converts RGB565 to packed 8-bit RGB, like
+/// [`Rgb565::to_rgb888_components`](crate::Rgb565::to_rgb888_components).
+#[inline]
+pub fn rgb565_to_rgb888(src: &[u16], dst: &mut [u8]) { frame::convert_rgb565_slice_to_rgb888(&Converter::builtin(), src, dst); }
+
+/// Converts packed 8-bit sRGB to RGB565, like
+/// [`Rgb565::from_srgb888_components`](crate::Rgb565::from_srgb888_components).
+#[cfg(has_srgb_decode)]
+#[inline]
+pub fn srgb888_to_rgb565(src: &[u8], dst: &mut [u16]) { frame::convert_srgb888_slice_to_rgb565(&Converter::builtin(), src, dst); }
+
+/// Converts RGB565 to packed 8-bit sRGB, like
+/// [`Rgb565::to_srgb888_components`](crate::Rgb565::to_srgb888_components).
+#[cfg(has_srgb)]
+#[inline]
+pub fn rgb565_to_srgb888(src: &[u16], dst: &mut [u8]) { frame::convert_rgb565_slice_to_srgb888(&Converter::builtin(), src, dst); }
+
+/// Converts an image loaded with the `image` crate, which are sRGB, to a frame
+/// of RGB565.
+#[cfg(feature = "image")]
+#[must_use]
+pub fn from_rgb_image(image: &image::RgbImage) -> Vec<u16> {
+	let mut frame = vec![0; image.width() as usize * image.height() as usize];
+	srgb888_to_rgb565(image.as_raw(), &mut frame);
+	frame
+}
+
+/// Converts a frame of RGB565 to an sRGB image for the `image` crate to save,
+/// or returns `None` if the frame is shorter than `width` by `height` pixels.
+///
+/// ```
+/// # use rgb565::convert;
+/// let frame = [0xF800, 0x07E0, 0x001F, 0xFFFF];
+/// let image = convert::to_rgb_image(&frame, 2, 2).unwrap();
+/// assert_eq!(convert::from_rgb_image(&image), frame);
+/// ```
+#[cfg(feature = "image")]
+#[must_use]
+pub fn to_rgb_image(frame: &[u16], width: u32, height: u32) -> Option<image::RgbImage> {
+	let len = (width as usize).checked_mul(height as usize)?;
+	let mut srgb888 = vec![0; len.checked_mul(3)?];
+	rgb565_to_srgb888(frame.get(..len)?, &mut srgb888);
+	image::RgbImage::from_raw(width, height, srgb888)
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	#[cfg(all(has_srgb, has_srgb_decode))]
+	fn matches_per_pixel() {
+		use crate::Rgb565;
+
+		let frame: Vec<u16> = (0..=u16::MAX).collect();
+		let mut rgb888 = vec![0; frame.len() * 3 + 2];
+		let mut srgb888 = vec![0; frame.len() * 3];
+		super::rgb565_to_rgb888(&frame, &mut rgb888);
+		super::rgb565_to_srgb888(&frame, &mut srgb888);
+		assert_eq!(rgb888[frame.len() * 3..], [0, 0]);
+
+		for (i, pixel) in frame.iter().enumerate() {
+			let color = Rgb565::from_rgb565(*pixel);
+			assert_eq!(rgb888[i * 3..i * 3 + 3], color.to_rgb888_components());
+			assert_eq!(srgb888[i * 3..i * 3 + 3], color.to_srgb888_components());
+		}
+
+		let mut back = vec![0; frame.len() + 1];
+		super::rgb888_to_rgb565(&rgb888, &mut back);
+		assert_eq!(back[..frame.len()], frame);
+		assert_eq!(back[frame.len()], 0);
+
+		super::srgb888_to_rgb565(&srgb888[..30], &mut back);
+
+		for (i, pixel) in back[..10].iter().enumerate() {
+			let [r, g, b] = [0, 1, 2].map(|c| srgb888[i * 3 + c]);
+			assert_eq!(*pixel, Rgb565::from_srgb888_components(r, g, b).to_rgb565());
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "image")]
+	fn rgb_image() {
+		use crate::Rgb565;
+
+		let frame: Vec<u16> = Rgb565::all().step_by(7).map(|color| color.to_rgb565()).take(40 * 30).collect();
+		let image = super::to_rgb_image(&frame, 40, 30).unwrap();
+		assert_eq!(image.dimensions(), (40, 30));
+		assert_eq!(image.get_pixel(3, 2).0, Rgb565::from_rgb565(frame[2 * 40 + 3]).to_srgb888_components());
+
+		let back = super::from_rgb_image(&image);
+		let expected: Vec<u16> = image.pixels().map(|pixel| Rgb565::from_srgb888_components(pixel[0], pixel[1], pixel[2]).to_rgb565()).collect();
+		assert_eq!(back, expected);
+
+		assert!(super::to_rgb_image(&frame, 40, 31).is_none());
+		assert!(super::to_rgb_image(&frame, 0, 0).is_some());
+	}
+}
@@ -0,0 +1,327 @@
+use crate::{lut, Rgb565, RoundingMode};
+
+/// How a [`Converter`] performs one kind of conversion.
+#[derive(Copy, Clone, Debug)]
+enum Backend<'a, T> {
+	/// Whatever the crate was built with, using LUTs where they are enabled.
+	Builtin,
+	/// Computed on the spot, regardless of which LUTs are enabled.
+	Arithmetic,
+	/// Looked up in a table supplied at runtime.
+	Table(&'a [T]),
+	/// Computed on the spot, scaling channels as the [`RoundingMode`] says.
+	Rounded(RoundingMode),
+	/// Converted a block at a time by the vectorized kernels, and computed on
+	/// the spot for single pixels.
+	#[cfg(simd_backend)]
+	Simd
+}
+
+impl<T> Backend<'_, T> {
+	/// Counts a conversion against this backend when the `stats` feature is
+	/// enabled.
+	#[inline]
+	fn counted(&self) -> &Self {
+		#[cfg(feature = "stats")]
+		crate::stats::conversion(match self {
+			Backend::Builtin => crate::stats::Backend::Builtin,
+			Backend::Arithmetic | Backend::Rounded(_) => crate::stats::Backend::Arithmetic,
+			Backend::Table(_) => crate::stats::Backend::Table,
+			#[cfg(simd_backend)]
+			Backend::Simd => crate::stats::Backend::Arithmetic
+		});
+
+		self
+	}
+
+	/// Whether whole frames may be handed to the vectorized kernels, which give
+	/// the same results as both the builtin conversions and the arithmetic.
+	#[cfg(simd_backend)]
+	#[inline]
+	fn vectorized(&self) -> bool { matches!(self, Backend::Builtin | Backend::Simd) }
+}
+
+/// Converts between RGB565 and 8-bit RGB or sRGB using strategies chosen at
+/// runtime.
+///
+/// The methods on [`Rgb565`] always use whichever LUTs the crate was built
+/// with. A `Converter` instead decides per conversion whether to use those, to
+/// compute the result on the spot, to look it up in a table supplied at
+/// runtime (say, one loaded from external flash), or to vectorize it once the
+/// CPU turns out to support that, so that one binary can pick the best
+/// strategy for the device it finds itself on. Every routine that converts
+/// whole buffers takes one.
+///
+/// ```
+/// # use rgb565::{Converter, Rgb565};
+/// let converter = Converter::arithmetic();
+/// assert_eq!(converter.to_rgb888(Rgb565::from_rgb565(0xF800)), [255, 0, 0]);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Converter<'a> {
+	to_rgb888: Backend<'a, [u8; 3]>,
+	to_srgb888: Backend<'a, [u8; 3]>,
+	from_rgb888: Backend<'a, u16>,
+	from_srgb888: Backend<'a, u16>
+}
+
+impl Converter<'static> {
+	/// A converter that behaves exactly like the methods on [`Rgb565`], using
+	/// whichever LUTs the crate was built with.
+	#[must_use]
+	pub const fn builtin() -> Self {
+		Self { to_rgb888: Backend::Builtin, to_srgb888: Backend::Builtin, from_rgb888: Backend::Builtin, from_srgb888: Backend::Builtin }
+	}
+
+	/// A converter that computes every conversion on the spot, which is useful
+	/// when the LUTs would only thrash a small cache. Converting sRGB this way
+	/// needs one of the `std`, `libm`, `fast_srgb` or `fixed_srgb` features;
+	/// without them, sRGB is converted with the built in LUTs instead.
+	#[must_use]
+	pub const fn arithmetic() -> Self {
+		Self { to_rgb888: Backend::Arithmetic, to_srgb888: Backend::Arithmetic, from_rgb888: Backend::Arithmetic, from_srgb888: Backend::Arithmetic }
+	}
+
+	/// A converter that converts whole buffers between RGB565 and 8-bit RGB
+	/// with the vectorized kernels, or `None` if the crate was built without
+	/// them (see the `simd` feature) or the CPU doesn't have the instructions
+	/// they need. Single pixels are computed on the spot, and sRGB is converted
+	/// like [`Converter::builtin`] does.
+	///
+	/// [`Converter::builtin`] uses the kernels too when they're available, so
+	/// this is for insisting on them, for example so that a table can be
+	/// loaded for the CPUs that don't have them.
+	///
+	/// ```
+	/// # use rgb565::{Converter, Rgb565};
+	/// let converter = Converter::simd().unwrap_or_else(Converter::arithmetic);
+	/// assert_eq!(converter.to_rgb888(Rgb565::from_rgb565(0xF800)), [255, 0, 0]);
+	/// ```
+	#[must_use]
+	pub fn simd() -> Option<Self> {
+		#[cfg(simd_backend)]
+		if crate::simd::detected() {
+			return Some(Self { to_rgb888: Backend::Simd, to_srgb888: Backend::Builtin, from_rgb888: Backend::Simd, from_srgb888: Backend::Builtin });
+		}
+
+		None
+	}
+}
+
+impl<'a> Converter<'a> {
+	/// Scales channels as `rounding` says when converting to and from 8-bit
+	/// RGB, instead of like the methods on [`Rgb565`], to match what a
+	/// display controller does bit for bit.
+	///
+	/// ```
+	/// # use rgb565::{Converter, Rgb565, RoundingMode};
+	/// let converter = Converter::builtin().with_rounding(RoundingMode::BitReplicate);
+	/// assert_eq!(converter.to_rgb888(Rgb565::from_rgb565_components(16, 32, 16)), [132, 130, 132]);
+	/// assert_eq!(converter.from_rgb888([132, 130, 132]), Rgb565::from_rgb565_components(16, 32, 16));
+	/// ```
+	#[must_use]
+	pub const fn with_rounding(mut self, rounding: RoundingMode) -> Self {
+		self.to_rgb888 = Backend::Rounded(rounding);
+		self.from_rgb888 = Backend::Rounded(rounding);
+		self
+	}
+
+	/// Uses `table`, indexed by the RGB565 value, to convert to 8-bit RGB.
+	#[must_use]
+	pub const fn with_rgb888_table(mut self, table: &'a [[u8; 3]; 65536]) -> Self {
+		self.to_rgb888 = Backend::Table(table);
+		self
+	}
+
+	/// Uses `table`, indexed by the RGB565 value, to convert to 8-bit sRGB.
+	#[must_use]
+	pub const fn with_srgb888_table(mut self, table: &'a [[u8; 3]; 65536]) -> Self {
+		self.to_srgb888 = Backend::Table(table);
+		self
+	}
+
+	/// Uses `table`, indexed by the 8-bit RGB value as `0xRRGGBB`, to convert
+	/// from 8-bit RGB.
+	#[must_use]
+	pub const fn with_from_rgb888_table(mut self, table: &'a [u16; 1 << 24]) -> Self {
+		self.from_rgb888 = Backend::Table(table);
+		self
+	}
+
+	/// Uses `table`, indexed by the 8-bit sRGB value as `0xRRGGBB`, to convert
+	/// from 8-bit sRGB.
+	#[must_use]
+	pub const fn with_from_srgb888_table(mut self, table: &'a [u16; 1 << 24]) -> Self {
+		self.from_srgb888 = Backend::Table(table);
+		self
+	}
+
+	/// Converts `color` to 8-bit RGB, like [`Rgb565::to_rgb888_components`].
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn to_rgb888(&self, color: Rgb565) -> [u8; 3] {
+		match *self.to_rgb888.counted() {
+			Backend::Builtin => color.to_rgb888_components(),
+			Backend::Arithmetic => lut::transforms::l565_to_l888(color.to_rgb565()),
+			#[cfg(simd_backend)]
+			Backend::Simd => lut::transforms::l565_to_l888(color.to_rgb565()),
+			Backend::Table(table) => table.get(color.to_rgb565() as usize).copied().unwrap_or_default(),
+			Backend::Rounded(rounding) => color.to_rgb888_components_rounded(rounding)
+		}
+	}
+
+	/// Converts `color` to 8-bit sRGB, like [`Rgb565::to_srgb888_components`].
+	#[cfg(has_srgb)]
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn to_srgb888(&self, color: Rgb565) -> [u8; 3] {
+		match *self.to_srgb888.counted() {
+			Backend::Builtin => color.to_srgb888_components(),
+			#[cfg(simd_backend)]
+			Backend::Simd => color.to_srgb888_components(),
+			#[cfg(has_srgb_math)]
+			Backend::Arithmetic | Backend::Rounded(_) => lut::transforms::l565_to_s888(color.to_rgb565()),
+			#[cfg(not(has_srgb_math))]
+			Backend::Arithmetic | Backend::Rounded(_) => color.to_srgb888_components(),
+			Backend::Table(table) => table.get(color.to_rgb565() as usize).copied().unwrap_or_default()
+		}
+	}
+
+	/// Converts 8-bit RGB to RGB565, like [`Rgb565::from_rgb888_components`].
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn from_rgb888(&self, [r, g, b]: [u8; 3]) -> Rgb565 {
+		match *self.from_rgb888.counted() {
+			Backend::Builtin => Rgb565::from_rgb888_components(r, g, b),
+			Backend::Arithmetic => Rgb565::from_rgb565(lut::transforms::l888_to_l565([r, g, b])),
+			#[cfg(simd_backend)]
+			Backend::Simd => Rgb565::from_rgb565(lut::transforms::l888_to_l565([r, g, b])),
+			Backend::Table(table) => Rgb565::from_rgb565(table.get(u32::from_be_bytes([0, r, g, b]) as usize).copied().unwrap_or_default()),
+			Backend::Rounded(rounding) => Rgb565::from_rgb888_components_rounded(r, g, b, rounding)
+		}
+	}
+
+	/// Converts 8-bit sRGB to RGB565, like [`Rgb565::from_srgb888_components`].
+	#[cfg(has_srgb_decode)]
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn from_srgb888(&self, [r, g, b]: [u8; 3]) -> Rgb565 {
+		match *self.from_srgb888.counted() {
+			Backend::Builtin => Rgb565::from_srgb888_components(r, g, b),
+			#[cfg(simd_backend)]
+			Backend::Simd => Rgb565::from_srgb888_components(r, g, b),
+			#[cfg(has_srgb_math)]
+			Backend::Arithmetic | Backend::Rounded(_) => Rgb565::from_rgb565(lut::transforms::s888_to_l565([r, g, b])),
+			#[cfg(not(has_srgb_math))]
+			Backend::Arithmetic | Backend::Rounded(_) => Rgb565::from_srgb888_components(r, g, b),
+			Backend::Table(table) => Rgb565::from_rgb565(table.get(u32::from_be_bytes([0, r, g, b]) as usize).copied().unwrap_or_default())
+		}
+	}
+
+	/// Converts as many whole blocks of packed 8-bit RGB in `src` to RGB565 in
+	/// `dst` as the vectorized kernels can, if they're available and
+	/// [`from_rgb888`](Self::from_rgb888) allows them, and returns the parts
+	/// left to convert a pixel at a time.
+	#[inline]
+	pub(crate) fn vectorize_from_rgb888<'s, 'd>(&self, src: &'s [u8], dst: &'d mut [u16]) -> (&'s [u8], &'d mut [u16]) {
+		#[cfg(simd_backend)]
+		if self.from_rgb888.vectorized() {
+			#[cfg(feature = "stats")]
+			let len = dst.len();
+			let (src, dst) = crate::simd::rgb888_to_rgb565(src, dst);
+			#[cfg(feature = "stats")]
+			crate::stats::conversions(crate::stats::Backend::Simd, len - dst.len());
+			return (src, dst);
+		}
+
+		(src, dst)
+	}
+
+	/// Converts as many whole blocks of RGB565 in `src` to packed 8-bit RGB in
+	/// `dst` as the vectorized kernels can, like
+	/// [`vectorize_from_rgb888`](Self::vectorize_from_rgb888).
+	#[inline]
+	pub(crate) fn vectorize_to_rgb888<'s, 'd>(&self, src: &'s [u16], dst: &'d mut [u8]) -> (&'s [u16], &'d mut [u8]) {
+		#[cfg(simd_backend)]
+		if self.to_rgb888.vectorized() {
+			#[cfg(feature = "stats")]
+			let len = src.len();
+			let (src, dst) = crate::simd::rgb565_to_rgb888(src, dst);
+			#[cfg(feature = "stats")]
+			crate::stats::conversions(crate::stats::Backend::Simd, len - src.len());
+			return (src, dst);
+		}
+
+		(src, dst)
+	}
+}
+
+impl Default for Converter<'static> {
+	fn default() -> Self { Self::builtin() }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Converter;
+	use crate::{frame, Rgb565, RoundingMode};
+
+	#[test]
+	fn backends_agree() {
+		let mut table = vec![[0u8; 3]; 65536];
+
+		for (i, entry) in table.iter_mut().enumerate() {
+			*entry = Rgb565::from_rgb565(i as u16).to_rgb888_components();
+		}
+
+		let builtin = Converter::builtin();
+		let arithmetic = Converter::arithmetic();
+		let table = Converter::arithmetic().with_rgb888_table(table.as_slice().try_into().unwrap());
+
+		for i in 0..=u16::MAX {
+			let color = Rgb565::from_rgb565(i);
+			let rgb888 = builtin.to_rgb888(color);
+
+			assert_eq!(arithmetic.to_rgb888(color), rgb888);
+			assert_eq!(table.to_rgb888(color), rgb888);
+			#[cfg(has_srgb)]
+			assert_eq!(builtin.to_srgb888(color), arithmetic.to_srgb888(color));
+			assert_eq!(builtin.from_rgb888(rgb888), arithmetic.from_rgb888(rgb888));
+		}
+	}
+
+	#[test]
+	fn simd() {
+		let Some(simd) = Converter::simd() else {
+			return;
+		};
+
+		let frame: Vec<u16> = (0..=u16::MAX).collect();
+		let (mut vectorized, mut computed) = (vec![0; frame.len() * 3 + 5], vec![0; frame.len() * 3 + 5]);
+		frame::convert_rgb565_slice_to_rgb888(&simd, &frame, &mut vectorized);
+		frame::convert_rgb565_slice_to_rgb888(&Converter::arithmetic(), &frame, &mut computed);
+		assert_eq!(vectorized, computed);
+
+		let bytes: Vec<u8> = (0..=u8::MAX).cycle().take(256 * 3 * 3 + 5).collect();
+		let (mut vectorized, mut computed) = (vec![0; bytes.len() / 3], vec![0; bytes.len() / 3]);
+		frame::convert_rgb888_slice_to_rgb565(&simd, &bytes, &mut vectorized);
+		frame::convert_rgb888_slice_to_rgb565(&Converter::arithmetic(), &bytes, &mut computed);
+		assert_eq!(vectorized, computed);
+	}
+
+	#[test]
+	fn rounding() {
+		let converter = Converter::builtin().with_rounding(RoundingMode::Nearest);
+
+		for i in 0..=u16::MAX {
+			let color = Rgb565::from_rgb565(i);
+			assert_eq!(converter.to_rgb888(color), color.to_rgb888_components_rounded(RoundingMode::Nearest));
+			assert_eq!(converter.from_rgb888(converter.to_rgb888(color)), color);
+			#[cfg(has_srgb)]
+			assert_eq!(converter.to_srgb888(color), color.to_srgb888_components());
+		}
+	}
+}
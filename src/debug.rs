@@ -0,0 +1,425 @@
+//! Dumping small frames as text, for logging them over a serial console and
+//! reconstructing them on the host.
+//!
+//! A dump starts with the width of the frame in decimal followed by a colon,
+//! then the pixels. [`dump_hex`] writes each pixel as four hex digits, most
+//! significant first, which is easy to read by eye. [`dump_base64`] writes the
+//! pixels as little-endian bytes in standard base64, which is a third shorter.
+//! Whitespace in the pixel data is ignored when parsing, so dumps survive being
+//! wrapped across several lines of a log.
+//!
+//! ```
+//! # use rgb565::debug;
+//! let frame = [0xF800, 0x07E0, 0x001F, 0xFFFF];
+//! let dump = debug::dump_hex(&frame, 2);
+//! assert_eq!(dump, "2:F80007E0001FFFFF");
+//! assert_eq!(debug::parse_hex(&dump), Ok((2, frame.to_vec())));
+//! ```
+//!
+//! Raw dumps of memory, like a framebuffer read out over a debugger, have no
+//! header to say what they hold. [`parse_raw`] reads them in whatever format a
+//! [`FormatDescriptor`] describes.
+//!
+//! Glitches that only show up once in a while are hard to catch as they
+//! happen. A [`FrameRecorder`] keeps the last few frames around instead, so
+//! that they can be dumped once something notices that it went wrong.
+
+use std::collections::VecDeque;
+use std::fmt::{self, Display, Formatter, Write};
+
+use crate::descriptor::FormatDescriptor;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// An error from parsing a dump.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ParseError {
+	/// The dump doesn't start with a width and a colon.
+	MissingWidth,
+	/// The pixel data contains a character that isn't valid in its encoding.
+	InvalidCharacter(char),
+	/// The pixel data ends partway through a pixel.
+	Truncated,
+	/// The number of pixels isn't a multiple of the width.
+	NotRectangular,
+	/// The format of a raw dump has a channel with no bits or more than 8.
+	InvalidFormat
+}
+
+impl Display for ParseError {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::MissingWidth => f.write_str("dump doesn't start with a width"),
+			Self::InvalidCharacter(c) => write!(f, "invalid character {:?} in pixel data", c),
+			Self::Truncated => f.write_str("pixel data ends partway through a pixel"),
+			Self::NotRectangular => f.write_str("number of pixels isn't a multiple of the width"),
+			Self::InvalidFormat => f.write_str("format has a channel with no bits or more than 8")
+		}
+	}
+}
+
+impl std::error::Error for ParseError {}
+
+/// Dumps a frame `width` pixels wide as hex.
+#[must_use]
+pub fn dump_hex(frame: &[u16], width: usize) -> String {
+	let mut dump = format!("{}:", width);
+
+	for pixel in frame {
+		let _ = write!(dump, "{:04X}", pixel);
+	}
+
+	dump
+}
+
+/// Dumps a frame `width` pixels wide as base64.
+#[must_use]
+pub fn dump_base64(frame: &[u16], width: usize) -> String {
+	let mut dump = format!("{}:", width);
+	let bytes = frame.iter().flat_map(|pixel| pixel.to_le_bytes()).collect::<Vec<_>>();
+
+	for chunk in bytes.chunks(3) {
+		let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| group | (*byte as u32) << (16 - i * 8));
+
+		for i in 0..4 {
+			dump.push(if i <= chunk.len() { BASE64[(group >> (18 - i * 6)) as usize & 63] as char } else { '=' });
+		}
+	}
+
+	dump
+}
+
+/// Parses a dump from [`dump_hex`], returning the width and the pixels.
+pub fn parse_hex(dump: &str) -> Result<(usize, Vec<u16>), ParseError> {
+	let (width, data) = split_header(dump)?;
+	let digits = data.chars().filter(|c| !c.is_ascii_whitespace()).map(|c| c.to_digit(16).ok_or(ParseError::InvalidCharacter(c))).collect::<Result<Vec<_>, _>>()?;
+
+	if digits.len() % 4 != 0 {
+		return Err(ParseError::Truncated);
+	}
+
+	let frame = digits.chunks(4).map(|digits| digits.iter().fold(0u16, |pixel, digit| pixel << 4 | *digit as u16)).collect();
+	check_rectangular(width, frame)
+}
+
+/// Parses a dump from [`dump_base64`], returning the width and the pixels.
+pub fn parse_base64(dump: &str) -> Result<(usize, Vec<u16>), ParseError> {
+	let (width, data) = split_header(dump)?;
+	let data = data.chars().filter(|c| !c.is_ascii_whitespace()).collect::<Vec<_>>();
+	let padding = data.iter().rev().take_while(|c| **c == '=').count();
+
+	if data.len() % 4 != 0 || padding > 2 {
+		return Err(ParseError::Truncated);
+	}
+
+	let mut bytes = Vec::with_capacity(data.len() / 4 * 3);
+
+	for chunk in data[..data.len() - padding].chunks(4) {
+		let mut group = 0u32;
+
+		for (i, c) in chunk.iter().enumerate() {
+			let sextet = BASE64.iter().position(|b| *b as char == *c).ok_or(ParseError::InvalidCharacter(*c))?;
+			group |= (sextet as u32) << (18 - i * 6);
+		}
+
+		bytes.extend_from_slice(&group.to_be_bytes()[1..chunk.len()]);
+	}
+
+	if bytes.len() % 2 != 0 {
+		return Err(ParseError::Truncated);
+	}
+
+	let frame = bytes.chunks(2).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]])).collect();
+	check_rectangular(width, frame)
+}
+
+/// Parses a raw dump of pixels in `format`, `width` pixels wide, returning
+/// them as RGB565. Fails with [`ParseError::InvalidFormat`] if `format` isn't
+/// [valid](FormatDescriptor::is_valid).
+pub fn parse_raw(dump: &[u8], width: usize, format: FormatDescriptor) -> Result<Vec<u16>, ParseError> {
+	if !format.is_valid() {
+		return Err(ParseError::InvalidFormat);
+	}
+
+	let pixel_len = format.bytes_per_pixel();
+
+	if !dump.len().is_multiple_of(pixel_len) {
+		return Err(ParseError::Truncated);
+	}
+
+	let frame = dump.chunks_exact(pixel_len).map(|bytes| format.decode(bytes).map(|color| color.to_rgb565())).collect::<Option<_>>().ok_or(ParseError::InvalidFormat)?;
+	check_rectangular(width, frame).map(|(_, frame)| frame)
+}
+
+fn split_header(dump: &str) -> Result<(usize, &str), ParseError> {
+	let (width, data) = dump.trim().split_once(':').ok_or(ParseError::MissingWidth)?;
+	Ok((width.parse().map_err(|_| ParseError::MissingWidth)?, data))
+}
+
+fn check_rectangular(width: usize, frame: Vec<u16>) -> Result<(usize, Vec<u16>), ParseError> {
+	match frame.len().checked_rem(width) {
+		Some(0) => Ok((width, frame)),
+		None if frame.is_empty() => Ok((width, frame)),
+		_ => Err(ParseError::NotRectangular)
+	}
+}
+
+/// A frame kept by a [`FrameRecorder`], other than the latest.
+#[derive(Clone, Debug)]
+enum Recorded {
+	Full(Vec<u16>),
+	/// What to put back into the frame after this one to get this one: the
+	/// start and length of each run of pixels that differ, and their values
+	/// here, one run after another.
+	Patch { runs: Vec<(usize, usize)>, pixels: Vec<u16> }
+}
+
+/// Keeps the last few frames rendered, for dumping after the fact when an
+/// intermittent glitch is noticed on a device.
+///
+/// Frames can optionally be kept as the pixels that differ from the frame
+/// after them, which takes a lot less memory when little changes from one to
+/// the next. Only the latest frame is then kept whole.
+///
+/// ```
+/// # use rgb565::debug::{self, FrameRecorder};
+/// let mut recorder = FrameRecorder::new(2, 3).compressed(true);
+///
+/// for i in 0..5 {
+///     recorder.record(&[0, 0, 0, i]);
+/// }
+///
+/// assert_eq!(recorder.frames(), [[0, 0, 0, 2], [0, 0, 0, 3], [0, 0, 0, 4]]);
+/// assert_eq!(recorder.dump_hex().lines().next(), Some("2:0000000000000002"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FrameRecorder {
+	width: usize,
+	capacity: usize,
+	compressed: bool,
+	latest: Option<Vec<u16>>,
+	older: VecDeque<Recorded>
+}
+
+impl FrameRecorder {
+	/// Starts recording the last `capacity` frames, each `width` pixels wide.
+	#[must_use]
+	pub fn new(width: usize, capacity: usize) -> Self { Self { width, capacity, compressed: false, latest: None, older: VecDeque::new() } }
+
+	/// Sets whether frames other than the latest are kept as the pixels that
+	/// changed, instead of whole. Only affects frames recorded from now on.
+	#[inline]
+	#[must_use]
+	pub fn compressed(mut self, compressed: bool) -> Self {
+		self.compressed = compressed;
+		self
+	}
+
+	/// Records `frame`, forgetting the oldest if there are already as many as
+	/// the recorder has room for.
+	pub fn record(&mut self, frame: &[u16]) {
+		if self.capacity == 0 {
+			return;
+		}
+
+		if let Some(latest) = self.latest.take() {
+			self.older.push_back(if self.compressed && latest.len() == frame.len() { patch(&latest, frame) } else { Recorded::Full(latest) });
+		}
+
+		while self.older.len() >= self.capacity {
+			self.older.pop_front();
+		}
+
+		self.latest = Some(frame.to_vec());
+	}
+
+	/// The number of frames recorded.
+	#[inline]
+	#[must_use]
+	pub fn len(&self) -> usize { self.older.len() + self.latest.is_some() as usize }
+
+	/// Whether no frames have been recorded since the recorder was created or
+	/// cleared.
+	#[inline]
+	#[must_use]
+	pub fn is_empty(&self) -> bool { self.latest.is_none() }
+
+	/// Forgets every frame recorded.
+	pub fn clear(&mut self) {
+		self.latest = None;
+		self.older.clear();
+	}
+
+	/// Returns the frames recorded, oldest first.
+	#[must_use]
+	pub fn frames(&self) -> Vec<Vec<u16>> {
+		let Some(latest) = &self.latest else { return Vec::new() };
+		let mut frames = vec![latest.clone()];
+
+		for recorded in self.older.iter().rev() {
+			let frame = match recorded {
+				Recorded::Full(frame) => frame.clone(),
+
+				Recorded::Patch { runs, pixels } => {
+					let mut frame = frames[frames.len() - 1].clone();
+					let mut pixels = pixels.iter();
+
+					for (start, len) in runs {
+						frame[*start..start + len].iter_mut().zip(&mut pixels).for_each(|(dst, pixel)| *dst = *pixel);
+					}
+
+					frame
+				}
+			};
+
+			frames.push(frame);
+		}
+
+		frames.reverse();
+		frames
+	}
+
+	/// Dumps the frames recorded like [`dump_hex`], oldest first, one per line.
+	#[must_use]
+	pub fn dump_hex(&self) -> String { self.frames().iter().map(|frame| dump_hex(frame, self.width)).collect::<Vec<_>>().join("\n") }
+
+	/// Dumps the frames recorded like [`dump_base64`], oldest first, one per
+	/// line.
+	#[must_use]
+	pub fn dump_base64(&self) -> String { self.frames().iter().map(|frame| dump_base64(frame, self.width)).collect::<Vec<_>>().join("\n") }
+
+	/// Writes the frames recorded as PNGs named `0.png`, `1.png` and so on,
+	/// oldest first, into `dir`. Needs the `testing` feature.
+	#[cfg(feature = "testing")]
+	pub fn write_pngs(&self, dir: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+		for (i, frame) in self.frames().iter().enumerate() {
+			let rgb888 = frame.iter().flat_map(|pixel| crate::Rgb565::from_rgb565(*pixel).to_rgb888_components()).collect::<Vec<_>>();
+			let height = frame.len().checked_div(self.width).unwrap_or(0);
+			crate::testing::write_png(&dir.as_ref().join(format!("{}.png", i)), self.width, height, &rgb888)?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Finds what to put back into `new` to get `old`, which are the same length.
+fn patch(old: &[u16], new: &[u16]) -> Recorded {
+	let (mut runs, mut pixels) = (Vec::<(usize, usize)>::new(), Vec::new());
+
+	for (i, (old, _)) in old.iter().zip(new).enumerate().filter(|(_, (old, new))| old != new) {
+		match runs.last_mut() {
+			Some((start, len)) if *start + *len == i => *len += 1,
+			_ => runs.push((i, 1))
+		}
+
+		pixels.push(*old);
+	}
+
+	Recorded::Patch { runs, pixels }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn round_trip() {
+		for len in 0..8 {
+			let frame = (0..len * 2).map(|i| (i as u16).wrapping_mul(0x9E37)).collect::<Vec<_>>();
+			assert_eq!(parse_hex(&dump_hex(&frame, 2)), Ok((2, frame.clone())));
+			assert_eq!(parse_base64(&dump_base64(&frame, 2)), Ok((2, frame)));
+		}
+	}
+
+	#[test]
+	fn base64() {
+		assert_eq!(dump_base64(&[0xF800, 0x07E0, 0x001F], 3), "3:APjgBx8A");
+		assert_eq!(dump_base64(&[0xF800], 1), "1:APg=");
+		assert_eq!(parse_base64("1:\n  AP\r\ng=\n"), Ok((1, vec![0xF800])));
+	}
+
+	#[test]
+	fn errors() {
+		assert_eq!(parse_hex("F800"), Err(ParseError::MissingWidth));
+		assert_eq!(parse_hex("1:F80"), Err(ParseError::Truncated));
+		assert_eq!(parse_hex("1:F80G"), Err(ParseError::InvalidCharacter('G')));
+		assert_eq!(parse_hex("2:F800"), Err(ParseError::NotRectangular));
+		assert_eq!(parse_hex("0:F800"), Err(ParseError::NotRectangular));
+		assert_eq!(parse_hex("0:"), Ok((0, vec![])));
+		assert_eq!(parse_base64("1:AP"), Err(ParseError::Truncated));
+		assert_eq!(parse_base64("1:AA=="), Err(ParseError::Truncated));
+	}
+
+	#[test]
+	fn raw() {
+		use crate::descriptor::{ByteOrder, ChannelOrder, Transfer};
+		use crate::{Encoding, Rgb565};
+
+		let frame = [0xF800, 0x07E0, 0x001F, 0xFFFF];
+		let dump = frame.iter().flat_map(|pixel| Rgb565::from_rgb565(*pixel).to_bytes(Encoding::Bgr565Be)).collect::<Vec<_>>();
+		assert_eq!(parse_raw(&dump, 2, FormatDescriptor::from_encoding(Encoding::Bgr565Be)), Ok(frame.to_vec()));
+		assert_eq!(parse_raw(&dump[..7], 2, FormatDescriptor::from_encoding(Encoding::Bgr565Be)), Err(ParseError::Truncated));
+		assert_eq!(parse_raw(&dump, 3, FormatDescriptor::from_encoding(Encoding::Bgr565Be)), Err(ParseError::NotRectangular));
+
+		let rgb888 = FormatDescriptor { bits: [8; 3], order: ChannelOrder::Rgb, byte_order: ByteOrder::Big, transfer: Transfer::Linear };
+		assert_eq!(parse_raw(&[255, 0, 0, 0, 0, 255], 1, rgb888), Ok(vec![0xF800, 0x001F]));
+		assert_eq!(parse_raw(&[], 1, FormatDescriptor { bits: [0; 3], ..rgb888 }), Err(ParseError::InvalidFormat));
+		assert_eq!(parse_raw(&[0; 4], 1, FormatDescriptor { bits: [9, 8, 8], ..rgb888 }), Err(ParseError::InvalidFormat));
+	}
+
+	#[test]
+	fn recorder() {
+		let frames = (0..10u16).map(|i| (0..64).map(|p| if p / 8 == i % 8 { 0xFFFF } else { p }).collect::<Vec<_>>()).collect::<Vec<_>>();
+
+		for compressed in [false, true] {
+			let mut recorder = FrameRecorder::new(8, 4).compressed(compressed);
+			assert!(recorder.is_empty());
+			assert!(recorder.frames().is_empty());
+
+			for (i, frame) in frames.iter().enumerate() {
+				recorder.record(frame);
+				assert_eq!(recorder.len(), (i + 1).min(4));
+				assert_eq!(recorder.frames(), frames[(i + 1).saturating_sub(4)..=i]);
+			}
+
+			// Two rows change from one frame to the next.
+			let stored = recorder.older.iter().map(|recorded| match recorded {
+				Recorded::Full(frame) => frame.len(),
+				Recorded::Patch { pixels, .. } => pixels.len()
+			});
+			assert_eq!(stored.collect::<Vec<_>>(), if compressed { [16; 3] } else { [64; 3] });
+
+			let dumps = recorder.dump_hex().lines().map(parse_hex).collect::<Result<Vec<_>, _>>().unwrap();
+			assert!(dumps.iter().map(|(_, frame)| frame).eq(&frames[6..]));
+			let dumps = recorder.dump_base64().lines().map(parse_base64).collect::<Result<Vec<_>, _>>().unwrap();
+			assert!(dumps.iter().map(|(_, frame)| frame).eq(&frames[6..]));
+
+			// A frame of a different size is kept whole.
+			recorder.record(&[1, 2]);
+			recorder.record(&[1, 3]);
+			assert_eq!(recorder.frames(), [frames[8].clone(), frames[9].clone(), vec![1, 2], vec![1, 3]]);
+
+			recorder.clear();
+			assert!(recorder.is_empty());
+		}
+
+		let mut recorder = FrameRecorder::new(1, 0);
+		recorder.record(&[1]);
+		assert!(recorder.is_empty());
+	}
+
+	#[cfg(feature = "testing")]
+	#[test]
+	fn recorder_pngs() {
+		let dir = std::env::temp_dir().join(format!("rgb565-recorder-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+
+		let mut recorder = FrameRecorder::new(2, 2).compressed(true);
+		recorder.record(&[0xF800, 0x07E0]);
+		recorder.record(&[0xF800, 0x001F]);
+		recorder.write_pngs(&dir).unwrap();
+
+		assert!(dir.join("0.png").is_file() && dir.join("1.png").is_file());
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+}
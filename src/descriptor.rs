@@ -0,0 +1,245 @@
+//! Compact descriptions of pixel formats, for tools on either end of a link or
+//! a dump to agree on how to read pixels without being told out of band.
+//!
+//! A [`FormatDescriptor`] says how many bits each channel has, which order the
+//! channels are packed in, which order the bytes of a pixel are in, and
+//! whether the channels are linear or sRGB. It fits in two bytes:
+//!
+//! | Byte | Bits | Field                                                    |
+//! |------|------|----------------------------------------------------------|
+//! | 0    | 7-4  | Bits of red, 1 to 8                                      |
+//! | 0    | 3-0  | Bits of green, 1 to 8                                    |
+//! | 1    | 7-4  | Bits of blue, 1 to 8                                     |
+//! | 1    | 3    | Channel order, 0 for RGB and 1 for BGR                   |
+//! | 1    | 2    | Byte order, 0 for little-endian and 1 for big-endian     |
+//! | 1    | 1-0  | Transfer function, 0 for linear and 1 for sRGB           |
+//!
+//! Pixels take as few whole bytes as hold all of their channels, with the
+//! channels packed into the lowest bits, the first one highest, like
+//! `rrrrrggggggbbbbb` for RGB565.
+//!
+//! ```
+//! # use rgb565::{descriptor::FormatDescriptor, Encoding};
+//! let descriptor = FormatDescriptor::from_encoding(Encoding::Rgb565Be);
+//! assert_eq!(descriptor.to_bytes(), [0x56, 0x54]);
+//! assert_eq!(FormatDescriptor::from_bytes([0x56, 0x54]), Some(descriptor));
+//! assert_eq!(descriptor.encoding(), Some(Encoding::Rgb565Be));
+//! assert_eq!(descriptor.bytes_per_pixel(), 2);
+//! ```
+
+use crate::Encoding;
+
+/// Which order the channels of a pixel are packed in, from the highest bits.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ChannelOrder {
+	/// Red in the highest bits and blue in the lowest.
+	Rgb,
+	/// Blue in the highest bits and red in the lowest.
+	Bgr
+}
+
+/// Which order the bytes of a pixel are in.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ByteOrder {
+	/// The lowest byte first.
+	Little,
+	/// The highest byte first.
+	Big
+}
+
+/// How the values of the channels relate to light.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Transfer {
+	/// Linear, like RGB565 and the plain 8-bit conversions of this crate.
+	Linear,
+	/// Encoded with the sRGB curve, like most images.
+	Srgb
+}
+
+/// The format of a pixel. See the [module documentation](self).
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct FormatDescriptor {
+	/// The bits of red, green and blue, in that order whatever the channel
+	/// order, each from 1 to 8.
+	pub bits: [u8; 3],
+	/// Which order the channels are packed in.
+	pub order: ChannelOrder,
+	/// Which order the bytes of each pixel are in.
+	pub byte_order: ByteOrder,
+	/// Whether the channels are linear or sRGB.
+	pub transfer: Transfer
+}
+
+impl FormatDescriptor {
+	/// Describes RGB565 stored in `encoding`.
+	#[must_use]
+	pub const fn from_encoding(encoding: Encoding) -> Self {
+		let (order, byte_order) = match encoding {
+			Encoding::Rgb565Le => (ChannelOrder::Rgb, ByteOrder::Little),
+			Encoding::Rgb565Be => (ChannelOrder::Rgb, ByteOrder::Big),
+			Encoding::Bgr565Le => (ChannelOrder::Bgr, ByteOrder::Little),
+			Encoding::Bgr565Be => (ChannelOrder::Bgr, ByteOrder::Big)
+		};
+
+		Self { bits: [5, 6, 5], order, byte_order, transfer: Transfer::Linear }
+	}
+
+	/// The encoding this describes, or `None` if it isn't linear RGB565.
+	#[must_use]
+	pub const fn encoding(&self) -> Option<Encoding> {
+		match (self.bits, self.transfer, self.order, self.byte_order) {
+			([5, 6, 5], Transfer::Linear, ChannelOrder::Rgb, ByteOrder::Little) => Some(Encoding::Rgb565Le),
+			([5, 6, 5], Transfer::Linear, ChannelOrder::Rgb, ByteOrder::Big) => Some(Encoding::Rgb565Be),
+			([5, 6, 5], Transfer::Linear, ChannelOrder::Bgr, ByteOrder::Little) => Some(Encoding::Bgr565Le),
+			([5, 6, 5], Transfer::Linear, ChannelOrder::Bgr, ByteOrder::Big) => Some(Encoding::Bgr565Be),
+			_ => None
+		}
+	}
+
+	/// The number of bytes each pixel takes.
+	#[inline]
+	#[must_use]
+	pub const fn bytes_per_pixel(&self) -> usize { (self.bits[0] as usize + self.bits[1] as usize + self.bits[2] as usize).div_ceil(8) }
+
+	/// Packs the descriptor into two bytes.
+	#[must_use]
+	pub const fn to_bytes(&self) -> [u8; 2] {
+		let order = match self.order {
+			ChannelOrder::Rgb => 0,
+			ChannelOrder::Bgr => 1
+		};
+
+		let byte_order = match self.byte_order {
+			ByteOrder::Little => 0,
+			ByteOrder::Big => 1
+		};
+
+		let transfer = match self.transfer {
+			Transfer::Linear => 0,
+			Transfer::Srgb => 1
+		};
+
+		[self.bits[0] << 4 | self.bits[1], self.bits[2] << 4 | order << 3 | byte_order << 2 | transfer]
+	}
+
+	/// Whether every channel has from 1 to 8 bits, which is what this crate
+	/// can read and [`from_bytes`](Self::from_bytes) accepts.
+	#[must_use]
+	pub const fn is_valid(&self) -> bool {
+		let bits = self.bits;
+		bits[0] != 0 && bits[0] <= 8 && bits[1] != 0 && bits[1] <= 8 && bits[2] != 0 && bits[2] <= 8
+	}
+
+	/// Unpacks a descriptor from two bytes, or returns `None` if a channel
+	/// has no bits or more than 8, or the transfer function is unknown.
+	#[must_use]
+	pub const fn from_bytes(bytes: [u8; 2]) -> Option<Self> {
+		let bits = [bytes[0] >> 4, bytes[0] & 0xF, bytes[1] >> 4];
+
+		let order = if bytes[1] & 0b1000 == 0 { ChannelOrder::Rgb } else { ChannelOrder::Bgr };
+		let byte_order = if bytes[1] & 0b100 == 0 { ByteOrder::Little } else { ByteOrder::Big };
+
+		let transfer = match bytes[1] & 0b11 {
+			0 => Transfer::Linear,
+			1 => Transfer::Srgb,
+			_ => return None
+		};
+
+		let descriptor = Self { bits, order, byte_order, transfer };
+
+		if !descriptor.is_valid() {
+			return None;
+		}
+
+		Some(descriptor)
+	}
+
+	/// Reads a pixel in this format from `bytes`, which should be
+	/// [`bytes_per_pixel`](Self::bytes_per_pixel) long, or returns `None` if
+	/// it isn't or a channel isn't from 1 to 8 bits. Channels are scaled to the
+	/// nearest level of RGB565, so formats that are RGB565 come through
+	/// exactly.
+	#[cfg(has_srgb_decode)]
+	#[must_use]
+	pub fn decode(&self, bytes: &[u8]) -> Option<crate::Rgb565> {
+		use crate::{Rgb565, RoundingMode};
+
+		if bytes.len() != self.bytes_per_pixel() || !self.is_valid() {
+			return None;
+		}
+
+		let word = match self.byte_order {
+			ByteOrder::Little => bytes.iter().rev().fold(0u32, |word, byte| word << 8 | *byte as u32),
+			ByteOrder::Big => bytes.iter().fold(0u32, |word, byte| word << 8 | *byte as u32)
+		};
+
+		let [r, g, b] = self.bits;
+		let (first, last) = match self.order {
+			ChannelOrder::Rgb => (r, b),
+			ChannelOrder::Bgr => (b, r)
+		};
+
+		let channel = |shift: u8, bits: u8| RoundingMode::Nearest.expand((word >> shift) as u8 & ((1u16 << bits) - 1) as u8, bits as u32);
+		let (first, middle, last) = (channel(g + last, first), channel(last, g), channel(0, last));
+		let [r, b] = match self.order {
+			ChannelOrder::Rgb => [first, last],
+			ChannelOrder::Bgr => [last, first]
+		};
+
+		Some(match self.transfer {
+			Transfer::Linear => Rgb565::from_rgb888_components_rounded(r, middle, b, RoundingMode::Nearest),
+			Transfer::Srgb => Rgb565::from_srgb888_components(r, middle, b)
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{ByteOrder, ChannelOrder, FormatDescriptor, Transfer};
+	use crate::Encoding;
+
+	#[test]
+	fn bytes() {
+		for encoding in [Encoding::Rgb565Le, Encoding::Rgb565Be, Encoding::Bgr565Le, Encoding::Bgr565Be] {
+			let descriptor = FormatDescriptor::from_encoding(encoding);
+			assert_eq!(FormatDescriptor::from_bytes(descriptor.to_bytes()), Some(descriptor));
+			assert_eq!(descriptor.encoding(), Some(encoding));
+		}
+
+		let rgb888 = FormatDescriptor { bits: [8; 3], order: ChannelOrder::Bgr, byte_order: ByteOrder::Little, transfer: Transfer::Srgb };
+		assert_eq!(rgb888.to_bytes(), [0x88, 0x89]);
+		assert_eq!(FormatDescriptor::from_bytes([0x88, 0x89]), Some(rgb888));
+		assert_eq!((rgb888.encoding(), rgb888.bytes_per_pixel()), (None, 3));
+		assert!(rgb888.is_valid());
+		assert!(!FormatDescriptor { bits: [0, 8, 8], ..rgb888 }.is_valid());
+		assert!(!FormatDescriptor { bits: [8, 8, 9], ..rgb888 }.is_valid());
+
+		assert_eq!(FormatDescriptor::from_bytes([0x06, 0x50]), None);
+		assert_eq!(FormatDescriptor::from_bytes([0x59, 0x50]), None);
+		assert_eq!(FormatDescriptor::from_bytes([0x56, 0x52]), None);
+	}
+
+	#[test]
+	#[cfg(has_srgb_decode)]
+	fn decoding() {
+		use crate::Rgb565;
+
+		for encoding in [Encoding::Rgb565Le, Encoding::Rgb565Be, Encoding::Bgr565Le, Encoding::Bgr565Be] {
+			let descriptor = FormatDescriptor::from_encoding(encoding);
+
+			for color in Rgb565::all() {
+				assert_eq!(descriptor.decode(&color.to_bytes(encoding)), Some(color));
+			}
+		}
+
+		let rgb888 = FormatDescriptor { bits: [8; 3], order: ChannelOrder::Bgr, byte_order: ByteOrder::Little, transfer: Transfer::Srgb };
+		assert_eq!(rgb888.decode(&[0x30, 0x20, 0x10]), Some(Rgb565::from_srgb888_components(0x10, 0x20, 0x30)));
+		assert_eq!(rgb888.decode(&[0x30, 0x20]), None);
+		assert_eq!(FormatDescriptor { bits: [0, 8, 8], ..rgb888 }.decode(&[0x30, 0x20]), None);
+		assert_eq!(FormatDescriptor { bits: [9, 8, 7], ..rgb888 }.decode(&[0x30, 0x20, 0x10]), None);
+
+		let rgb332 = FormatDescriptor { bits: [3, 3, 2], order: ChannelOrder::Rgb, byte_order: ByteOrder::Big, transfer: Transfer::Linear };
+		assert_eq!(rgb332.decode(&[0b11100011]), Some(Rgb565::from_rgb565_components(31, 0, 31)));
+		assert_eq!(rgb332.decode(&[0b01010001]).map(|color| color.to_rgb565_components()), Some([9, 36, 10]));
+	}
+}
@@ -0,0 +1,206 @@
+//! Conversion between frames and Windows device-independent bitmaps (DIBs),
+//! as used by `CreateDIBSection`, `StretchDIBits` and the `CF_DIB` clipboard
+//! format.
+//!
+//! RGB565 DIBs are 16 bits per pixel with `BI_BITFIELDS` compression and the
+//! masks `0xF800`, `0x07E0` and `0x001F`. Their rows are padded to a multiple
+//! of four bytes and, unless the height in the header is negative, stored from
+//! the bottom of the image up.
+//!
+//! ```
+//! # use rgb565::{dib::{Dib, RowOrder}, Rgb565};
+//! let dib = Dib { width: 3, height: 2, row_order: RowOrder::BottomUp };
+//! let frame = [Rgb565::from_rgb565(0xF800); 6];
+//!
+//! let mut bytes = vec![0; dib.size()];
+//! dib.write(&frame, &mut bytes);
+//!
+//! let mut header = dib.header().to_vec();
+//! header.extend_from_slice(&bytes);
+//! assert_eq!(Dib::parse(&header), Some(dib));
+//! ```
+
+use crate::Rgb565;
+
+/// The length of the header written by [`Dib::header`], which is a
+/// `BITMAPINFOHEADER` followed by the three color masks.
+pub const HEADER_LEN: usize = 52;
+
+const BITMAPINFOHEADER_LEN: u32 = 40;
+const BI_BITFIELDS: u32 = 3;
+const MASKS: [u32; 3] = [0xF800, 0x07E0, 0x001F];
+
+/// The order in which a DIB stores its rows.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum RowOrder {
+	/// The first row is the top of the image. The header's height is negative.
+	TopDown,
+	/// The first row is the bottom of the image. This is the default for DIBs.
+	BottomUp
+}
+
+/// The shape of an RGB565 DIB.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Dib {
+	/// The width of the image, in pixels.
+	pub width: usize,
+	/// The height of the image, in pixels.
+	pub height: usize,
+	/// The order the rows are stored in.
+	pub row_order: RowOrder
+}
+
+impl Dib {
+	/// Parses the header of an RGB565 DIB, which must start with a
+	/// `BITMAPINFOHEADER` or one of its successors. Returns `None` if it isn't
+	/// 16 bits per pixel with RGB565 masks, or if its pixel data would be too
+	/// large for its size to fit in the header.
+	#[must_use]
+	pub fn parse(header: &[u8]) -> Option<Self> {
+		let u16_at = |offset: usize| Some(u16::from_le_bytes(*header.get(offset..)?.first_chunk()?));
+		let u32_at = |offset: usize| Some(u32::from_le_bytes(*header.get(offset..)?.first_chunk()?));
+
+		let size = u32_at(0)?;
+		let (width, height) = (u32_at(4)? as i32, u32_at(8)? as i32);
+
+		if size < BITMAPINFOHEADER_LEN || width < 0 || u16_at(14)? != 16 || u32_at(16)? != BI_BITFIELDS {
+			return None;
+		}
+
+		// The masks follow a plain `BITMAPINFOHEADER`, but are part of the
+		// header itself in `BITMAPV4HEADER` and later.
+		let masks = [u32_at(40)?, u32_at(44)?, u32_at(48)?];
+
+		if masks != MASKS {
+			return None;
+		}
+
+		let row_order = if height < 0 { RowOrder::TopDown } else { RowOrder::BottomUp };
+		let dib = Self { width: width as usize, height: height.unsigned_abs() as usize, row_order };
+
+		// The width and height can each be up to `i32::MAX`, which would
+		// overflow `size` on 32-bit targets. The size has to fit in the
+		// header's 32-bit size field anyway.
+		let size = (dib.width.checked_mul(2)?.checked_add(3)? & !3).checked_mul(dib.height)?;
+		u32::try_from(size).ok()?;
+		Some(dib)
+	}
+
+	/// Returns the `BITMAPINFOHEADER` and color masks for this DIB, which is
+	/// what `BITMAPINFO` holds for `BI_BITFIELDS` DIBs.
+	#[must_use]
+	pub fn header(&self) -> [u8; HEADER_LEN] {
+		let height = match self.row_order {
+			RowOrder::TopDown => -(self.height as i32),
+			RowOrder::BottomUp => self.height as i32
+		};
+
+		let mut header = [0; HEADER_LEN];
+		header[0..4].copy_from_slice(&BITMAPINFOHEADER_LEN.to_le_bytes());
+		header[4..8].copy_from_slice(&(self.width as i32).to_le_bytes());
+		header[8..12].copy_from_slice(&height.to_le_bytes());
+		header[12..14].copy_from_slice(&1u16.to_le_bytes());
+		header[14..16].copy_from_slice(&16u16.to_le_bytes());
+		header[16..20].copy_from_slice(&BI_BITFIELDS.to_le_bytes());
+		header[20..24].copy_from_slice(&(self.size() as u32).to_le_bytes());
+
+		for (i, mask) in MASKS.iter().enumerate() {
+			header[40 + i * 4..44 + i * 4].copy_from_slice(&mask.to_le_bytes());
+		}
+
+		header
+	}
+
+	/// The length of a row in bytes, including padding.
+	#[inline]
+	#[must_use]
+	pub const fn stride(&self) -> usize { (self.width * 2 + 3) & !3 }
+
+	/// The length of the pixel data in bytes.
+	#[inline]
+	#[must_use]
+	pub const fn size(&self) -> usize { self.stride() * self.height }
+
+	/// The index of the DIB row that holds row `y` of the image.
+	#[inline]
+	fn row(&self, y: usize) -> usize {
+		match self.row_order {
+			RowOrder::TopDown => y,
+			RowOrder::BottomUp => self.height - 1 - y
+		}
+	}
+
+	/// Writes `frame`, whose rows are as wide as this DIB and start at the top
+	/// of the image, to the pixel data of this DIB. Rows that don't fit in
+	/// `dst` are skipped and padding is left as it is.
+	pub fn write(&self, frame: &[Rgb565], dst: &mut [u8]) {
+		for (y, row) in frame.chunks_exact(self.width.max(1)).take(self.height).enumerate() {
+			let start = self.row(y) * self.stride();
+
+			if let Some(dst) = dst.get_mut(start..start + self.width * 2) {
+				for (dst, pixel) in dst.chunks_exact_mut(2).zip(row) {
+					dst.copy_from_slice(&pixel.to_rgb565().to_le_bytes());
+				}
+			}
+		}
+	}
+
+	/// Reads the pixel data of this DIB into `frame`, whose rows are as wide as
+	/// this DIB and start at the top of the image. Rows that don't exist in
+	/// `src` are skipped.
+	pub fn read(&self, src: &[u8], frame: &mut [Rgb565]) {
+		for (y, row) in frame.chunks_exact_mut(self.width.max(1)).take(self.height).enumerate() {
+			let start = self.row(y) * self.stride();
+
+			if let Some(src) = src.get(start..start + self.width * 2) {
+				for (pixel, src) in row.iter_mut().zip(src.chunks_exact(2)) {
+					*pixel = Rgb565::from_rgb565(u16::from_le_bytes([src[0], src[1]]));
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Dib, RowOrder};
+	use crate::Rgb565;
+
+	#[test]
+	fn row_order() {
+		let frame = [0x0001, 0x0002, 0x0003, 0x0004, 0x0005, 0x0006].map(Rgb565::from_rgb565);
+
+		let dib = Dib { width: 3, height: 2, row_order: RowOrder::BottomUp };
+		assert_eq!(dib.stride(), 8);
+
+		let mut bytes = [0xAA; 16];
+		dib.write(&frame, &mut bytes);
+		assert_eq!(bytes, [4, 0, 5, 0, 6, 0, 0xAA, 0xAA, 1, 0, 2, 0, 3, 0, 0xAA, 0xAA]);
+
+		let mut read = [Rgb565::default(); 6];
+		dib.read(&bytes, &mut read);
+		assert_eq!(read, frame);
+
+		let dib = Dib { row_order: RowOrder::TopDown, ..dib };
+		dib.write(&frame, &mut bytes);
+		assert_eq!(bytes, [1, 0, 2, 0, 3, 0, 0xAA, 0xAA, 4, 0, 5, 0, 6, 0, 0xAA, 0xAA]);
+	}
+
+	#[test]
+	fn header() {
+		let dib = Dib { width: 320, height: 240, row_order: RowOrder::TopDown };
+		let header = dib.header();
+		assert_eq!(header[8..12], (-240i32).to_le_bytes());
+		assert_eq!(Dib::parse(&header), Some(dib));
+
+		let mut rgb555 = header;
+		rgb555[40..52].copy_from_slice(&[0x00, 0x7C, 0, 0, 0xE0, 0x03, 0, 0, 0x1F, 0, 0, 0]);
+		assert_eq!(Dib::parse(&rgb555), None);
+		assert_eq!(Dib::parse(&header[..40]), None);
+
+		let mut huge = header;
+		huge[4..8].copy_from_slice(&i32::MAX.to_le_bytes());
+		huge[8..12].copy_from_slice(&i32::MIN.to_le_bytes());
+		assert_eq!(Dib::parse(&huge), None);
+	}
+}
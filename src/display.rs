@@ -0,0 +1,267 @@
+use crate::Rgb565;
+
+/// A 3x3 matrix acting on column vectors, stored row by row.
+pub type Matrix3 = [[f32; 3]; 3];
+
+/// The chromaticities of a color space's primaries and white point, as CIE
+/// 1931 xy coordinates.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Primaries {
+	/// The chromaticity of the red primary.
+	pub red: [f32; 2],
+	/// The chromaticity of the green primary.
+	pub green: [f32; 2],
+	/// The chromaticity of the blue primary.
+	pub blue: [f32; 2],
+	/// The chromaticity of the white point.
+	pub white: [f32; 2]
+}
+
+impl Primaries {
+	/// The primaries of sRGB (and Rec. 709), with a D65 white point.
+	pub const SRGB: Self = Self { red: [0.64, 0.33], green: [0.30, 0.60], blue: [0.15, 0.06], white: [0.3127, 0.3290] };
+
+	/// The primaries of Display P3 (and DCI-P3 with a D65 white point).
+	pub const DISPLAY_P3: Self = Self { red: [0.680, 0.320], green: [0.265, 0.690], blue: [0.150, 0.060], white: [0.3127, 0.3290] };
+
+	/// The primaries of Adobe RGB (1998).
+	pub const ADOBE_RGB: Self = Self { red: [0.64, 0.33], green: [0.21, 0.71], blue: [0.15, 0.06], white: [0.3127, 0.3290] };
+
+	/// Returns the matrix that converts linear RGB with these primaries to CIE
+	/// XYZ, normalized so that white has a Y of 1.
+	#[must_use]
+	pub fn to_xyz(&self) -> Matrix3 {
+		let xyz = |[x, y]: [f32; 2]| [x / y, 1.0, (1.0 - x - y) / y];
+		let [r, g, b] = [xyz(self.red), xyz(self.green), xyz(self.blue)];
+		let primaries = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+		let [sr, sg, sb] = mul_vector(&invert(&primaries), xyz(self.white));
+		primaries.map(|[r, g, b]| [r * sr, g * sg, b * sb])
+	}
+
+	/// Returns the matrix that converts linear RGB with these primaries to
+	/// linear RGB with the `to` primaries. No chromatic adaptation is done, so
+	/// the white points should match.
+	#[must_use]
+	pub fn conversion_to(&self, to: &Primaries) -> Matrix3 { mul_matrix(&invert(&to.to_xyz()), &self.to_xyz()) }
+}
+
+/// Converts linear Display P3 to linear sRGB. This is
+/// `Primaries::DISPLAY_P3.conversion_to(&Primaries::SRGB)`, precomputed.
+const DISPLAY_P3_TO_SRGB: Matrix3 = [
+	[1.2249401, -0.2249404, 0.0],
+	[-0.0420569, 1.0420571, 0.0],
+	[-0.0196376, -0.0786361, 1.0982735]
+];
+
+/// Converts linear Adobe RGB (1998) to linear sRGB. This is
+/// `Primaries::ADOBE_RGB.conversion_to(&Primaries::SRGB)`, precomputed.
+const ADOBE_RGB_TO_SRGB: Matrix3 = [
+	[1.3983557, -0.3983557, 0.0],
+	[0.0, 1.0, 0.0],
+	[0.0, -0.0429289, 1.0429289]
+];
+
+/// The gamma of Adobe RGB (1998), which is 2 51/256.
+const ADOBE_RGB_GAMMA: f32 = 563.0 / 256.0;
+
+/// How a color space encodes linear light.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TransferFunction {
+	/// Values are linear light already.
+	Linear,
+	/// Values are linear light raised to the power of `1 / gamma`.
+	Gamma(f32),
+	/// The piecewise sRGB curve.
+	Srgb
+}
+
+impl TransferFunction {
+	/// Encodes a linear light value between 0 and 1.
+	#[must_use]
+	pub fn encode(&self, linear: f32) -> f32 {
+		match *self {
+			Self::Linear => linear,
+			Self::Gamma(gamma) => linear.powf(1.0 / gamma),
+			Self::Srgb => crate::lut::srgb_transfer(linear)
+		}
+	}
+
+	/// Decodes an encoded value between 0 and 1 back to linear light.
+	#[must_use]
+	pub fn decode(&self, encoded: f32) -> f32 {
+		match *self {
+			Self::Linear => encoded,
+			Self::Gamma(gamma) => encoded.powf(gamma),
+			Self::Srgb => crate::lut::srgb_untransfer(encoded)
+		}
+	}
+}
+
+/// A minimal description of a display, applied as the last step of converting
+/// to RGB565, for basic color management without ICC profiles.
+///
+/// The conversions on [`Rgb565`] assume the display has sRGB primaries and
+/// turns RGB565 values into light linearly. A `DisplayProfile` takes linear
+/// sRGB, converts it to the display's primaries with a 3x3 matrix and encodes
+/// it with the display's transfer function before quantizing it, so colors
+/// come out right on panels with non-sRGB primaries.
+///
+/// ```
+/// # use rgb565::{DisplayProfile, Primaries, TransferFunction};
+/// let panel = Primaries { red: [0.66, 0.32], green: [0.28, 0.64], blue: [0.15, 0.05], white: [0.3127, 0.3290] };
+/// let profile = DisplayProfile::from_primaries(&panel, TransferFunction::Gamma(2.2));
+/// let orange = profile.from_srgb888(255, 128, 0);
+/// ```
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DisplayProfile {
+	/// Converts linear sRGB to linear RGB in the display's primaries.
+	pub matrix: Matrix3,
+	/// How the display turns values into light.
+	pub transfer: TransferFunction
+}
+
+impl DisplayProfile {
+	/// The profile the conversions on [`Rgb565`] assume: sRGB primaries, and
+	/// linear light.
+	pub const IDENTITY: Self = Self { matrix: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], transfer: TransferFunction::Linear };
+
+	/// Creates a profile from its matrix and transfer function directly.
+	#[must_use]
+	pub const fn new(matrix: Matrix3, transfer: TransferFunction) -> Self { Self { matrix, transfer } }
+
+	/// Creates the profile for a display with the given primaries and transfer
+	/// function.
+	#[must_use]
+	pub fn from_primaries(primaries: &Primaries, transfer: TransferFunction) -> Self {
+		Self::new(Primaries::SRGB.conversion_to(primaries), transfer)
+	}
+
+	/// Converts linear sRGB, with channels between 0 and 1, for the display.
+	/// Colors the display can't show are clipped.
+	#[must_use]
+	pub fn from_linear(&self, rgb: [f32; 3]) -> Rgb565 {
+		let [r, g, b] = mul_vector(&self.matrix, rgb).map(|channel| self.transfer.encode(channel.clamp(0.0, 1.0)));
+		let quantize = |channel: f32, max: f32| (channel * max + 0.5) as u8;
+		Rgb565::from_rgb565_components(quantize(r, 31.0), quantize(g, 63.0), quantize(b, 31.0))
+	}
+
+	/// Converts 8-bit sRGB for the display.
+	#[must_use]
+	pub fn from_srgb888(&self, r: u8, g: u8, b: u8) -> Rgb565 {
+		self.from_linear([r, g, b].map(|channel| TransferFunction::Srgb.decode(channel as f32 / 255.0)))
+	}
+
+	/// Converts 8-bit Display P3 for the display.
+	#[must_use]
+	pub fn from_display_p3(&self, r: u8, g: u8, b: u8) -> Rgb565 {
+		let p3 = [r, g, b].map(|channel| TransferFunction::Srgb.decode(channel as f32 / 255.0));
+		self.from_linear(mul_vector(&DISPLAY_P3_TO_SRGB, p3))
+	}
+
+	/// Converts 8-bit Adobe RGB (1998) for the display.
+	#[must_use]
+	pub fn from_adobe_rgb(&self, r: u8, g: u8, b: u8) -> Rgb565 {
+		let adobe = [r, g, b].map(|channel| TransferFunction::Gamma(ADOBE_RGB_GAMMA).decode(channel as f32 / 255.0));
+		self.from_linear(mul_vector(&ADOBE_RGB_TO_SRGB, adobe))
+	}
+
+	/// Converts 8-bit linear RGB for the display.
+	#[must_use]
+	pub fn from_rgb888(&self, r: u8, g: u8, b: u8) -> Rgb565 { self.from_linear([r, g, b].map(|channel| channel as f32 / 255.0)) }
+
+	/// Converts a color that is already RGB565 (as produced by the conversions
+	/// on [`Rgb565`]) for the display.
+	#[must_use]
+	pub fn apply(&self, color: Rgb565) -> Rgb565 {
+		let [r, g, b] = color.to_rgb565_components();
+		self.from_linear([r as f32 / 31.0, g as f32 / 63.0, b as f32 / 31.0])
+	}
+}
+
+pub(crate) fn mul_vector(m: &Matrix3, v: [f32; 3]) -> [f32; 3] { m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2]) }
+
+fn mul_matrix(a: &Matrix3, b: &Matrix3) -> Matrix3 {
+	core::array::from_fn(|i| core::array::from_fn(|j| a[i][0] * b[0][j] + a[i][1] * b[1][j] + a[i][2] * b[2][j]))
+}
+
+pub(crate) fn invert(m: &Matrix3) -> Matrix3 {
+	let [[a, b, c], [d, e, f], [g, h, i]] = *m;
+	let (ca, cb, cc) = (e * i - f * h, f * g - d * i, d * h - e * g);
+	let det = a * ca + b * cb + c * cc;
+
+	[
+		[ca / det, (c * h - b * i) / det, (b * f - c * e) / det],
+		[cb / det, (a * i - c * g) / det, (c * d - a * f) / det],
+		[cc / det, (b * g - a * h) / det, (a * e - b * d) / det]
+	]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{DisplayProfile, Primaries, TransferFunction};
+	use crate::Rgb565;
+
+	#[test]
+	fn srgb_display_is_identity() {
+		let profile = DisplayProfile::from_primaries(&Primaries::SRGB, TransferFunction::Linear);
+
+		for (row, expected) in profile.matrix.iter().zip(DisplayProfile::IDENTITY.matrix) {
+			for (actual, expected) in row.iter().zip(expected) {
+				assert!((actual - expected).abs() < 1e-4, "{:?}", profile.matrix);
+			}
+		}
+
+		for i in (0..=u16::MAX).step_by(11) {
+			assert_eq!(profile.apply(Rgb565::from_rgb565(i)), Rgb565::from_rgb565(i));
+		}
+	}
+
+	#[test]
+	fn wide_gamut_display_desaturates() {
+		let profile = DisplayProfile::from_primaries(&Primaries::DISPLAY_P3, TransferFunction::Linear);
+		let [r, g, b] = profile.from_srgb888(255, 0, 0).to_rgb565_components();
+
+		assert!(r < 31 && g > 0 && b > 0, "{:?}", [r, g, b]);
+		assert_eq!(profile.from_srgb888(255, 255, 255), Rgb565::from_rgb565(0xFFFF));
+	}
+
+	#[test]
+	fn display_p3() {
+		let matrix = Primaries::DISPLAY_P3.conversion_to(&Primaries::SRGB);
+
+		for (row, expected) in matrix.iter().zip(super::DISPLAY_P3_TO_SRGB) {
+			for (actual, expected) in row.iter().zip(expected) {
+				assert!((actual - expected).abs() < 1e-5, "{:?}", matrix);
+			}
+		}
+
+		for s8 in 0..=255 {
+			let expected = Rgb565::from_srgb888_components(s8, s8, s8).to_rgb565_components();
+			let actual = Rgb565::from_display_p3_components(s8, s8, s8).to_rgb565_components();
+
+			for (expected, actual) in expected.into_iter().zip(actual) {
+				assert!(expected.abs_diff(actual) <= 1, "{}: {} vs {}", s8, expected, actual);
+			}
+		}
+
+		// P3 red is more saturated than sRGB can show, so it clips to sRGB red.
+		assert_eq!(Rgb565::from_display_p3_components(255, 0, 0), Rgb565::from_rgb565(0xF800));
+	}
+
+	#[test]
+	fn adobe_rgb() {
+		let matrix = Primaries::ADOBE_RGB.conversion_to(&Primaries::SRGB);
+
+		for (row, expected) in matrix.iter().zip(super::ADOBE_RGB_TO_SRGB) {
+			for (actual, expected) in row.iter().zip(expected) {
+				assert!((actual - expected).abs() < 1e-5, "{:?}", matrix);
+			}
+		}
+
+		assert_eq!(Rgb565::from_adobe_rgb_components(255, 255, 255), Rgb565::from_rgb565(0xFFFF));
+		assert_eq!(Rgb565::from_adobe_rgb_components(0, 0, 0), Rgb565::from_rgb565(0));
+
+		// Adobe RGB green is more saturated than sRGB can show, so it clips.
+		assert_eq!(Rgb565::from_adobe_rgb_components(0, 255, 0), Rgb565::from_rgb565(0x07E0));
+	}
+}
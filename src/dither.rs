@@ -0,0 +1,378 @@
+//! Dithering 8-bit RGB down to RGB565, to hide the banding that straight
+//! truncation to 5 and 6 bits leaves in gradients.
+//!
+//! Ordered dithering compares each pixel against a [`ThresholdMap`] tiled
+//! across the frame. It needs no state beyond the pixel's coordinates, so it
+//! can be applied to pixels one at a time as they're generated. The Bayer
+//! matrices are built in, but any square, power-of-two-sized map works, such
+//! as a void-and-cluster map tuned for a particular panel.
+//!
+//! ```
+//! # use rgb565::dither::{self, ThresholdMap};
+//! assert_eq!(dither::ordered(&ThresholdMap::BAYER_4, 0, 0, [255; 3]).to_rgb565(), 0xFFFF);
+//!
+//! static MAP: ThresholdMap = match ThresholdMap::new(&[0, 2, 3, 1], 2) {
+//!     Some(map) => map,
+//!     None => panic!()
+//! };
+//!
+//! assert_eq!(MAP, ThresholdMap::BAYER_2);
+//! ```
+//!
+//! Error diffusion instead carries the difference between each pixel and the
+//! color it was quantized to over to its neighbors, which looks smoother but
+//! has to process the frame in order. `floyd_steinberg` does this for RGB565,
+//! and for displays with only a handful of colors, like color e-paper, `remap`
+//! does it for a palette. Both need the `std` feature.
+
+use crate::{Converter, Rgb565};
+
+/// A square matrix of thresholds for ordered dithering, tiled across the
+/// frame. Each entry is the rank of its threshold among all entries, so an
+/// `n` by `n` map holds values below `n * n`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct ThresholdMap<'a> {
+	ranks: &'a [u16],
+	size_log2: u32
+}
+
+/// The ranks of the `size` by `size` Bayer matrix, where `size` is a power of
+/// two. Each level of the recursion is one pair of bits of the rank, with the
+/// lowest bits of the coordinates giving the highest bits of the rank.
+const fn bayer<const N: usize>(size: usize) -> [u16; N] {
+	let mut ranks = [0; N];
+	let levels = size.trailing_zeros();
+	let mut i = 0;
+
+	while i < N {
+		let (x, y) = (i % size, i / size);
+		let mut level = 0;
+
+		while level < levels {
+			let pair = ((x >> level ^ y >> level) & 1) << 1 | (y >> level & 1);
+			ranks[i] |= (pair << (2 * (levels - 1 - level))) as u16;
+			level += 1;
+		}
+
+		i += 1;
+	}
+
+	ranks
+}
+
+static BAYER_2: [u16; 4] = bayer(2);
+static BAYER_4: [u16; 16] = bayer(4);
+static BAYER_8: [u16; 64] = bayer(8);
+
+impl ThresholdMap<'static> {
+	/// The 2 by 2 Bayer matrix, which gives 4 shades between levels.
+	pub const BAYER_2: Self = Self { ranks: &BAYER_2, size_log2: 1 };
+	/// The 4 by 4 Bayer matrix, which gives 16 shades between levels.
+	pub const BAYER_4: Self = Self { ranks: &BAYER_4, size_log2: 2 };
+	/// The 8 by 8 Bayer matrix, which gives 64 shades between levels. RGB565
+	/// has at most 8 shades of 8-bit RGB between levels, so larger maps only
+	/// spread the pattern out.
+	pub const BAYER_8: Self = Self { ranks: &BAYER_8, size_log2: 3 };
+}
+
+impl<'a> ThresholdMap<'a> {
+	/// Uses `ranks`, in rows from the top left, as a `size` by `size` map.
+	/// Returns `None` unless `size` is a power of two no larger than 256,
+	/// `ranks` holds exactly `size * size` entries and each is below that.
+	#[must_use]
+	pub const fn new(ranks: &'a [u16], size: usize) -> Option<Self> {
+		if !size.is_power_of_two() || size > 256 || ranks.len() != size * size {
+			return None;
+		}
+
+		let mut i = 0;
+
+		while i < ranks.len() {
+			if ranks[i] as usize >= ranks.len() {
+				return None;
+			}
+
+			i += 1;
+		}
+
+		Some(Self { ranks, size_log2: size.trailing_zeros() })
+	}
+
+	/// The width and height of the map.
+	#[inline]
+	#[must_use]
+	pub const fn size(&self) -> usize { 1 << self.size_log2 }
+
+	/// The rank of the threshold at `x`, `y`, which wrap around the map.
+	#[inline]
+	#[must_use]
+	pub fn rank(&self, x: usize, y: usize) -> u16 {
+		let mask = self.size() - 1;
+		self.ranks.get((y & mask) << self.size_log2 | x & mask).copied().unwrap_or(0)
+	}
+
+	/// Quantizes an 8-bit channel to `max + 1` levels, rounding up when the
+	/// fractional part exceeds the threshold at `rank`.
+	#[inline]
+	pub(crate) fn quantize(&self, value: u8, max: u32, rank: u16) -> u8 {
+		// Thresholds sit in the middle of their rank's share of [0, 1), which
+		// is (2 * rank + 1) / (2 * n * n).
+		let cells = 2u32 << (self.size_log2 * 2);
+		((value as u32 * max * cells + (2 * rank as u32 + 1) * 255) / (255 * cells)) as u8
+	}
+}
+
+/// Dithers a single 8-bit RGB pixel at `x`, `y` to RGB565.
+#[inline]
+#[must_use]
+pub fn ordered(map: &ThresholdMap, x: usize, y: usize, [r, g, b]: [u8; 3]) -> Rgb565 {
+	let rank = map.rank(x, y);
+	Rgb565::from_rgb565_components(map.quantize(r, 31, rank), map.quantize(g, 63, rank), map.quantize(b, 31, rank))
+}
+
+/// Dithers a frame of 8-bit RGB pixels that is `width` pixels wide into `dst`,
+/// as many pixels as fit.
+pub fn ordered_frame(map: &ThresholdMap, src: &[[u8; 3]], width: usize, dst: &mut [Rgb565]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	for (i, (dst, src)) in dst.iter_mut().zip(src).enumerate() {
+		*dst = ordered(map, i % width.max(1), i / width.max(1), *src);
+	}
+}
+
+/// Returns the index of the entry in `palette` closest to `color`, in 8-bit RGB
+/// as `converter` expands the entries, or `None` if the palette is empty.
+#[must_use]
+pub fn nearest(converter: &Converter, palette: &[Rgb565], color: [u8; 3]) -> Option<usize> {
+	nearest_rgb888(palette.iter().map(|entry| converter.to_rgb888(*entry)), color.map(i32::from))
+}
+
+fn nearest_rgb888(palette: impl Iterator<Item = [u8; 3]>, color: [i32; 3]) -> Option<usize> {
+	let distance = |entry: [u8; 3]| (0..3).map(|i| (entry[i] as i32 - color[i]).pow(2)).sum::<i32>();
+	palette.enumerate().min_by_key(|(_, entry)| distance(*entry)).map(|(i, _)| i)
+}
+
+/// Diffuses error over a frame of `len` pixels, `width` pixels wide, with the
+/// Floyd-Steinberg weights. `quantize` gets each pixel with the error carried
+/// over to it and returns the color it was quantized to.
+#[cfg(feature = "std")]
+fn diffuse(len: usize, width: usize, mut pixel: impl FnMut(usize) -> [u8; 3], mut quantize: impl FnMut(usize, [i32; 3]) -> [u8; 3]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	let width = width.max(1);
+	// Errors are kept in sixteenths, for the current row and the next, with a
+	// column of padding on either side.
+	let mut errors = vec![[0i32; 3]; (width + 2) * 2];
+
+	for start in (0..len).step_by(width) {
+		let (current, next) = errors.split_at_mut(width + 2);
+		next.fill([0; 3]);
+
+		for x in 0..width.min(len - start) {
+			let source = pixel(start + x);
+			let value = [0, 1, 2].map(|c| (source[c] as i32 + current[x + 1][c] / 16).clamp(0, 255));
+			let chosen = quantize(start + x, value);
+
+			for c in 0..3 {
+				let error = value[c] - chosen[c] as i32;
+				current[x + 2][c] += error * 7;
+				next[x][c] += error * 3;
+				next[x + 1][c] += error * 5;
+				next[x + 2][c] += error;
+			}
+		}
+
+		errors.rotate_left(width + 2);
+	}
+}
+
+/// Dithers a frame of 8-bit RGB pixels that is `width` pixels wide into `dst`
+/// with Floyd-Steinberg error diffusion, as many pixels as fit.
+///
+/// Each channel is rounded to the nearest RGB565 level, and the difference
+/// from that level, as `converter` expands it back to 8-bit RGB, is spread
+/// over the pixels to the right and below, so flat areas and gradients keep
+/// their average color instead of banding.
+///
+/// ```
+/// # use rgb565::{dither, Converter, Rgb565};
+/// let gray = [[4; 3]; 16];
+/// let mut dst = [Rgb565::default(); 16];
+/// dither::floyd_steinberg(&Converter::builtin(), &gray, 4, &mut dst);
+/// // 4 falls between the first two levels of red, 0 and 8.
+/// let red: Vec<u8> = dst.iter().map(|pixel| pixel.to_rgb565_components()[0]).collect();
+/// assert!(red.contains(&0) && red.contains(&1));
+/// ```
+#[cfg(feature = "std")]
+pub fn floyd_steinberg(converter: &Converter, src: &[[u8; 3]], width: usize, dst: &mut [Rgb565]) {
+	// Round to the nearest level, since truncating would carry the same
+	// error in one direction over the whole frame.
+	let round = |value: i32, max: i32| ((value * max + 127) / 255) as u8;
+
+	diffuse(src.len(), width, |i| src[i], |i, [r, g, b]| {
+		let color = Rgb565::from_rgb565_components(round(r, 31), round(g, 63), round(b, 31));
+
+		if let Some(dst) = dst.get_mut(i) {
+			*dst = color;
+		}
+
+		converter.to_rgb888(color)
+	});
+}
+
+/// Maps a frame of 8-bit RGB pixels that is `width` pixels wide onto
+/// `palette` with Floyd-Steinberg dithering, writing the index of each pixel's
+/// palette entry to `indices` and the entry itself to `pixels`, as many as
+/// fit in each. Either output can be left out. The palette is compared
+/// against as `converter` expands it to 8-bit RGB.
+///
+/// Palette entries past 256 are never chosen, since indices are bytes.
+///
+/// ```
+/// # use rgb565::{dither, Converter, Rgb565};
+/// let palette = [Rgb565::from_rgb565(0x0000), Rgb565::from_rgb565(0xFFFF)];
+/// let gray = [[128; 3]; 16];
+/// let mut indices = [0; 16];
+/// dither::remap(&Converter::builtin(), &gray, 4, &palette, Some(&mut indices), None);
+/// assert_eq!(indices.iter().filter(|index| **index == 1).count(), 8);
+/// ```
+#[cfg(feature = "std")]
+pub fn remap(converter: &Converter, src: &[[u8; 3]], width: usize, palette: &[Rgb565], indices: Option<&mut [u8]>, pixels: Option<&mut [Rgb565]>) {
+	remap_with(converter, src.len(), width, |i| src[i], palette, indices, pixels);
+}
+
+/// Like [`remap`], but for a frame of RGB565 pixels, which `converter` expands
+/// to 8-bit RGB too.
+#[cfg(feature = "std")]
+pub fn remap_rgb565(converter: &Converter, src: &[Rgb565], width: usize, palette: &[Rgb565], indices: Option<&mut [u8]>, pixels: Option<&mut [Rgb565]>) {
+	remap_with(converter, src.len(), width, |i| converter.to_rgb888(src[i]), palette, indices, pixels);
+}
+
+#[cfg(feature = "std")]
+fn remap_with(converter: &Converter, len: usize, width: usize, pixel: impl FnMut(usize) -> [u8; 3], palette: &[Rgb565], mut indices: Option<&mut [u8]>, mut pixels: Option<&mut [Rgb565]>) {
+	let palette = &palette[..palette.len().min(256)];
+	let rgb888 = palette.iter().map(|color| converter.to_rgb888(*color)).collect::<Vec<_>>();
+
+	if rgb888.is_empty() {
+		return;
+	}
+
+	diffuse(len, width, pixel, |i, value| {
+		let index = nearest_rgb888(rgb888.iter().copied(), value).unwrap_or(0);
+
+		if let Some(dst) = indices.as_deref_mut().and_then(|indices| indices.get_mut(i)) {
+			*dst = index as u8;
+		}
+
+		if let Some(dst) = pixels.as_deref_mut().and_then(|pixels| pixels.get_mut(i)) {
+			*dst = palette[index];
+		}
+
+		rgb888[index]
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{nearest, ordered, ordered_frame, ThresholdMap};
+	use crate::{Converter, Rgb565};
+
+	const MAP: ThresholdMap = match ThresholdMap::new(&[0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5], 4) {
+		Some(map) => map,
+		None => panic!()
+	};
+
+	#[test]
+	fn validation() {
+		assert_eq!(ThresholdMap::new(&[0, 1, 2], 3), None);
+		assert_eq!(ThresholdMap::new(&[0, 1, 2], 2), None);
+		assert_eq!(ThresholdMap::new(&[0, 1, 2, 4], 2), None);
+		assert_eq!(ThresholdMap::new(&[0], 1).map(|map| map.size()), Some(1));
+		assert_eq!(MAP.rank(5, 6), MAP.rank(1, 2));
+	}
+
+	#[test]
+	fn bayer() {
+		assert_eq!(ThresholdMap::BAYER_4, MAP);
+
+		for map in [ThresholdMap::BAYER_2, ThresholdMap::BAYER_4, ThresholdMap::BAYER_8] {
+			let size = map.size();
+			assert_eq!(ThresholdMap::new(map.ranks, size), Some(map));
+
+			let mut ranks = map.ranks.to_vec();
+			ranks.sort_unstable();
+			assert!(ranks.iter().copied().eq(0..(size * size) as u16));
+		}
+
+		// Each 2 by 2 block of a larger matrix covers one rank from each
+		// quarter of the range.
+		let quarters = [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(x, y)| ThresholdMap::BAYER_8.rank(x, y) / 16);
+		assert_eq!(quarters, [0, 2, 3, 1]);
+	}
+
+	#[test]
+	fn preserves_average() {
+		for value in 0..=255u8 {
+			let src = [[value; 3]; 16];
+			let mut dst = [Rgb565::default(); 16];
+			ordered_frame(&MAP, &src, 4, &mut dst);
+
+			let sum = dst.iter().map(|color| color.to_rgb565_components()[0] as u32).sum::<u32>();
+			let expected = value as u32 * 31 * 16 / 255;
+			assert!(sum.abs_diff(expected) <= 1, "{} -> {} instead of {}", value, sum, expected);
+		}
+	}
+
+	#[test]
+	fn extremes() {
+		for x in 0..4 {
+			for y in 0..4 {
+				assert_eq!(ordered(&MAP, x, y, [0; 3]).to_rgb565(), 0x0000);
+				assert_eq!(ordered(&MAP, x, y, [255; 3]).to_rgb565(), 0xFFFF);
+			}
+		}
+	}
+
+	#[test]
+	fn nearest_entry() {
+		let palette = [0x0000, 0xF800, 0x07E0, 0x001F, 0xFFFF].map(Rgb565::from_rgb565);
+		assert_eq!(nearest(&Converter::builtin(), &palette, [200, 30, 10]), Some(1));
+		assert_eq!(nearest(&Converter::builtin(), &palette, [200, 200, 210]), Some(4));
+		assert_eq!(nearest(&Converter::builtin(), &[], [0; 3]), None);
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn floyd_steinberg() {
+		for value in [0, 1, 4, 100, 130, 254, 255] {
+			let src = [[value; 3]; 64];
+			let mut dst = [Rgb565::default(); 64];
+			super::floyd_steinberg(&Converter::builtin(), &src, 8, &mut dst);
+
+			let average = dst.iter().map(|color| color.to_rgb888_components()[1] as u32).sum::<u32>() as f32 / 64.0;
+			assert!((average - value as f32).abs() < 1.0, "{} -> {}", value, average);
+		}
+
+		let mut dst = [Rgb565::default(); 4];
+		super::floyd_steinberg(&Converter::builtin(), &[[255; 3]; 4], 2, &mut dst);
+		assert!(dst.iter().all(|color| color.to_rgb565() == 0xFFFF));
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn remap() {
+		let palette = [0x0000, 0xF800, 0xFFFF].map(Rgb565::from_rgb565);
+		let src = [Rgb565::from_rgb565(0x7800); 64];
+		let mut indices = [0; 64];
+		let mut pixels = [Rgb565::default(); 64];
+		super::remap_rgb565(&Converter::builtin(), &src, 8, &palette, Some(&mut indices), Some(&mut pixels));
+
+		// Half-intensity red comes out as roughly equal parts red and black.
+		let red = indices.iter().filter(|index| **index == 1).count();
+		assert!((28..=36).contains(&red), "{}", red);
+		assert!(indices.iter().zip(pixels).all(|(index, pixel)| palette[*index as usize] == pixel));
+		assert!(!indices.contains(&2));
+	}
+}
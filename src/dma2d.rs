@@ -0,0 +1,301 @@
+//! Helpers for the DMA2D (Chrom-ART) peripheral found on many STM32s.
+//!
+//! A [`Descriptor`] holds the register values for a single DMA2D transfer, such
+//! as filling a rectangle with a color or copying one between frames while
+//! converting its pixel format. Write them to the peripheral with whatever
+//! register access crate you use, writing `cr` last since it starts the
+//! transfer:
+//!
+//! ```ignore
+//! let d = Descriptor::fill(out, 64, 32, Rgb565::from_rgb565(0xF800)).unwrap();
+//! dma2d.fgmar.write(|w| unsafe { w.bits(d.fgmar) });
+//! // ... and so on for the other registers ...
+//! dma2d.cr.write(|w| unsafe { w.bits(d.cr) });
+//! ```
+//!
+//! Every descriptor can also be run in software with
+//! [`Descriptor::run_in_software`], which produces the same bytes the DMA2D
+//! would, so the same code works on chips without a DMA2D and in tests on the
+//! host. Like the DMA2D, pixel format conversions expand channels to 8 bits by
+//! replicating their top bits and reduce them again by truncating.
+
+use crate::Rgb565;
+
+/// A pixel format the DMA2D can read and write.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ColorMode {
+	/// 32-bit ARGB, stored as a little-endian `u32`.
+	Argb8888,
+	/// 24-bit RGB, stored as the bytes blue, green, red.
+	Rgb888,
+	/// RGB565, stored as a little-endian `u16`.
+	Rgb565,
+	/// 1 bit of alpha and 5 bits of each channel, stored as a little-endian
+	/// `u16`.
+	Argb1555
+}
+
+impl ColorMode {
+	/// The value of the `CM` field for this format, which is the same in
+	/// `FGPFCCR`, `BGPFCCR` and `OPFCCR`.
+	#[inline]
+	#[must_use]
+	pub const fn bits(self) -> u32 {
+		match self {
+			Self::Argb8888 => 0b000,
+			Self::Rgb888 => 0b001,
+			Self::Rgb565 => 0b010,
+			Self::Argb1555 => 0b011
+		}
+	}
+
+	/// The format with the given `CM` field, if it's one this crate knows.
+	#[inline]
+	#[must_use]
+	pub const fn from_bits(bits: u32) -> Option<Self> {
+		match bits {
+			0b000 => Some(Self::Argb8888),
+			0b001 => Some(Self::Rgb888),
+			0b010 => Some(Self::Rgb565),
+			0b011 => Some(Self::Argb1555),
+			_ => None
+		}
+	}
+
+	/// The size of a pixel in this format, in bytes.
+	#[inline]
+	#[must_use]
+	pub const fn bytes_per_pixel(self) -> usize {
+		match self {
+			Self::Argb8888 => 4,
+			Self::Rgb888 => 3,
+			Self::Rgb565 | Self::Argb1555 => 2
+		}
+	}
+
+	/// Packs an ARGB8888 color into this format, as a register value.
+	#[must_use]
+	pub const fn pack(self, [a, r, g, b]: [u8; 4]) -> u32 {
+		let (a, r, g, b) = (a as u32, r as u32, g as u32, b as u32);
+
+		match self {
+			Self::Argb8888 => a << 24 | r << 16 | g << 8 | b,
+			Self::Rgb888 => r << 16 | g << 8 | b,
+			Self::Rgb565 => (r >> 3) << 11 | (g >> 2) << 5 | b >> 3,
+			Self::Argb1555 => (a >> 7) << 15 | (r >> 3) << 10 | (g >> 3) << 5 | b >> 3
+		}
+	}
+
+	/// Unpacks a register value in this format to ARGB8888.
+	#[must_use]
+	pub const fn unpack(self, value: u32) -> [u8; 4] {
+		const fn expand5(v: u32) -> u8 { ((v & 31) << 3 | (v & 31) >> 2) as u8 }
+		const fn expand6(v: u32) -> u8 { ((v & 63) << 2 | (v & 63) >> 4) as u8 }
+
+		match self {
+			Self::Argb8888 => [(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8],
+			Self::Rgb888 => [255, (value >> 16) as u8, (value >> 8) as u8, value as u8],
+			Self::Rgb565 => [255, expand5(value >> 11), expand6(value >> 5), expand5(value)],
+			Self::Argb1555 => [if value & 0x8000 != 0 { 255 } else { 0 }, expand5(value >> 10), expand5(value >> 5), expand5(value)]
+		}
+	}
+}
+
+/// Where the DMA2D reads or writes pixels.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Surface {
+	/// The address of the first pixel.
+	pub address: u32,
+	/// The distance in pixels from the start of one row to the start of the
+	/// next.
+	pub stride: u16,
+	/// The format of the pixels.
+	pub format: ColorMode
+}
+
+impl Surface {
+	/// A surface of `format` pixels starting at `address`, with rows `stride`
+	/// pixels apart.
+	#[inline]
+	#[must_use]
+	pub const fn new(address: u32, stride: u16, format: ColorMode) -> Self { Self { address, stride, format } }
+}
+
+/// `CR.MODE` for memory-to-memory transfers, which copy without converting.
+const MODE_M2M: u32 = 0b00;
+/// `CR.MODE` for memory-to-memory transfers with pixel format conversion.
+const MODE_M2M_PFC: u32 = 0b01;
+/// `CR.MODE` for register-to-memory transfers, which fill with `OCOLR`.
+const MODE_R2M: u32 = 0b11;
+/// `CR.START`.
+const START: u32 = 1;
+
+/// The register values for a single DMA2D transfer. Registers that a transfer
+/// doesn't use are zero.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct Descriptor {
+	/// The control register, with the mode and start bit.
+	pub cr: u32,
+	/// The foreground memory address register.
+	pub fgmar: u32,
+	/// The foreground offset register, the pixels skipped after each line.
+	pub fgor: u32,
+	/// The foreground pixel format converter control register.
+	pub fgpfccr: u32,
+	/// The output color register, the color fills write.
+	pub ocolr: u32,
+	/// The output memory address register.
+	pub omar: u32,
+	/// The output offset register, the pixels skipped after each line.
+	pub oor: u32,
+	/// The output pixel format converter control register.
+	pub opfccr: u32,
+	/// The number of lines register, with the pixels per line from bit 16 and
+	/// the number of lines in the low 16 bits.
+	pub nlr: u32
+}
+
+impl Descriptor {
+	/// Fills a `width` by `height` rectangle starting at `out` with `color`.
+	/// Returns `None` if the rectangle is too large for the DMA2D or wider than
+	/// the stride.
+	#[must_use]
+	pub fn fill(out: Surface, width: u16, height: u16, color: Rgb565) -> Option<Self> {
+		let color = out.format.pack(ColorMode::Rgb565.unpack(color.to_rgb565() as u32));
+
+		Some(Self {
+			cr: MODE_R2M << 16 | START,
+			ocolr: color,
+			omar: out.address,
+			oor: line_offset(out, width)?,
+			opfccr: out.format.bits(),
+			nlr: lines(width, height)?,
+			..Self::default()
+		})
+	}
+
+	/// Copies a `width` by `height` rectangle from `src` to `out`, converting
+	/// it to the format of `out` on the way. Returns `None` if the rectangle is
+	/// too large for the DMA2D or wider than either stride.
+	#[must_use]
+	pub fn copy(src: Surface, out: Surface, width: u16, height: u16) -> Option<Self> {
+		let mode = if src.format == out.format { MODE_M2M } else { MODE_M2M_PFC };
+
+		Some(Self {
+			cr: mode << 16 | START,
+			fgmar: src.address,
+			fgor: line_offset(src, width)?,
+			fgpfccr: src.format.bits(),
+			omar: out.address,
+			oor: line_offset(out, width)?,
+			opfccr: out.format.bits(),
+			nlr: lines(width, height)?,
+			..Self::default()
+		})
+	}
+
+	/// Performs the transfer in software. `fg` and `out` start at the
+	/// addresses in `fgmar` and `omar`, which are otherwise ignored. Pixels
+	/// that fall outside of `fg` or `out` are skipped, as are transfers in
+	/// formats or modes this crate doesn't know.
+	pub fn run_in_software(&self, fg: &[u8], out: &mut [u8]) {
+		let (Some(fg_format), Some(out_format)) = (ColorMode::from_bits(self.fgpfccr & 0xF), ColorMode::from_bits(self.opfccr & 0x7)) else {
+			return;
+		};
+
+		let mode = self.cr >> 16 & 0b11;
+		let (width, height) = ((self.nlr >> 16 & 0x3FFF) as usize, (self.nlr & 0xFFFF) as usize);
+		// Without conversion, the DMA2D writes pixels in the foreground format.
+		let out_format = if mode == MODE_M2M { fg_format } else { out_format };
+		let (fg_bpp, out_bpp) = (fg_format.bytes_per_pixel(), out_format.bytes_per_pixel());
+		let (fg_stride, out_stride) = (width + (self.fgor & 0xFFFF) as usize, width + (self.oor & 0xFFFF) as usize);
+
+		for y in 0..height {
+			for x in 0..width {
+				let value = match mode {
+					MODE_R2M => self.ocolr,
+					MODE_M2M | MODE_M2M_PFC => {
+						let index = (y * fg_stride + x) * fg_bpp;
+
+						let Some(bytes) = fg.get(index..index + fg_bpp) else {
+							continue;
+						};
+
+						let value = bytes.iter().rev().fold(0, |value, byte| value << 8 | *byte as u32);
+
+						if mode == MODE_M2M_PFC {
+							out_format.pack(fg_format.unpack(value))
+						} else {
+							value
+						}
+					}
+
+					_ => return
+				};
+
+				let index = (y * out_stride + x) * out_bpp;
+
+				if let Some(bytes) = out.get_mut(index..index + out_bpp) {
+					bytes.copy_from_slice(&value.to_le_bytes()[..out_bpp]);
+				}
+			}
+		}
+	}
+}
+
+fn line_offset(surface: Surface, width: u16) -> Option<u32> {
+	surface.stride.checked_sub(width).map(u32::from).filter(|offset| *offset <= 0x3FFF)
+}
+
+fn lines(width: u16, height: u16) -> Option<u32> {
+	(width <= 0x3FFF).then_some((width as u32) << 16 | height as u32)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{ColorMode, Descriptor, Surface};
+	use crate::Rgb565;
+
+	#[test]
+	fn fill() {
+		let out = Surface::new(0x2000_0000, 4, ColorMode::Rgb565);
+		let descriptor = Descriptor::fill(out, 2, 2, Rgb565::from_rgb565(0xF81F)).unwrap();
+		assert_eq!(descriptor.cr, 0x0003_0001);
+		assert_eq!(descriptor.ocolr, 0xF81F);
+		assert_eq!(descriptor.oor, 2);
+		assert_eq!(descriptor.nlr, 0x0002_0002);
+
+		let mut frame = [0u8; 4 * 3 * 2];
+		descriptor.run_in_software(&[], &mut frame);
+		assert_eq!(frame, [0x1F, 0xF8, 0x1F, 0xF8, 0, 0, 0, 0, 0x1F, 0xF8, 0x1F, 0xF8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+		assert_eq!(Descriptor::fill(out, 5, 1, Rgb565::default()), None);
+	}
+
+	#[test]
+	fn copy() {
+		let src = Surface::new(0, 2, ColorMode::Rgb565);
+		let out = Surface::new(0, 2, ColorMode::Rgb888);
+		let descriptor = Descriptor::copy(src, out, 2, 1).unwrap();
+		assert_eq!(descriptor.cr, 0x0001_0001);
+		assert_eq!(descriptor.fgpfccr, 0b010);
+		assert_eq!(descriptor.opfccr, 0b001);
+
+		let mut rgb888 = [0u8; 6];
+		descriptor.run_in_software(&[0x00, 0xF8, 0xE0, 0x07], &mut rgb888);
+		assert_eq!(rgb888, [0, 0, 255, 0, 255, 0]);
+
+		let mut rgb565 = [0u8; 4];
+		Descriptor::copy(out, src, 2, 1).unwrap().run_in_software(&rgb888, &mut rgb565);
+		assert_eq!(rgb565, [0x00, 0xF8, 0xE0, 0x07]);
+	}
+
+	#[test]
+	fn round_trip() {
+		for format in [ColorMode::Argb8888, ColorMode::Rgb888, ColorMode::Rgb565] {
+			for value in 0..=u16::MAX as u32 {
+				assert_eq!(ColorMode::Rgb565.pack(format.unpack(format.pack(ColorMode::Rgb565.unpack(value)))), value);
+			}
+		}
+	}
+}
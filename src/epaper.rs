@@ -0,0 +1,243 @@
+//! Splitting frames into the bit planes that grayscale e-paper controllers
+//! take.
+//!
+//! Controllers like these have RAM for only one bit per pixel, but two or
+//! more banks of it, usually called "old" and "new" or "black" and "red".
+//! Their grayscale waveforms drive each pixel according to the combination of
+//! its bits across the banks, so a 4-level image is sent as two 1-bit planes,
+//! one for each bit of each pixel's level.
+//!
+//! [`bit_planes`] produces those planes straight from RGB565, taking the luma
+//! of each pixel and quantizing it to as many levels as the planes can hold.
+//! Which plane goes into which bank, and whether black is 0 or 1, depends on
+//! the controller and its waveform, so check the datasheet and swap or invert
+//! the planes to match.
+//!
+//! Tri-color panels work the same way, except that one bank holds black and
+//! white and the other says which pixels are red (or yellow) instead.
+//! [`tri_color`] sorts the pixels of a frame between the two.
+//!
+//! ```
+//! # use rgb565::{epaper, Converter};
+//! let frame = [0x0000, 0x52AA, 0xAD55, 0xFFFF];
+//! let (mut old, mut new) = ([0; 1], [0; 1]);
+//! epaper::bit_planes(&Converter::builtin(), &frame, 4, None, [&mut old, &mut new]);
+//! assert_eq!((old, new), ([0b0011_0000], [0b0101_0000]));
+//! ```
+
+use crate::dither::ThresholdMap;
+use crate::{formats, Converter, Rgb565};
+
+/// Quantizes the luma of each pixel of `frame`, which is `width` pixels wide
+/// and expanded to 8-bit RGB with `converter`, to a level with `N` bits and packs bit `N - 1 - i` of each level into
+/// `planes[i]`, so the first plane gets the most significant bits. `N` is
+/// from 1 to 8, so 2 gives 4 gray levels and 4 gives 16.
+///
+/// Each plane is packed like [`frame::threshold`](crate::frame::threshold)
+/// packs its bits: one bit per pixel, with each row starting on a new byte
+/// and its first pixel in the most significant bit. Rows that don't fit in
+/// every plane are skipped.
+///
+/// Levels are rounded to the nearest, or dithered against `dither` if it's
+/// given, which hides the banding that so few levels would otherwise leave
+/// in gradients.
+pub fn bit_planes<const N: usize>(converter: &Converter, frame: &[u16], width: usize, dither: Option<&ThresholdMap>, mut planes: [&mut [u8]; N]) {
+	const { assert!(N >= 1 && N <= 8, "levels must have from 1 to 8 bits") };
+
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	if width == 0 {
+		return;
+	}
+
+	let (max, row_len) = ((1 << N) - 1, width.div_ceil(8));
+	let rows = planes.iter().map(|plane| plane.len() / row_len).min().unwrap_or(0);
+
+	for (y, row) in frame.chunks_exact(width).take(rows).enumerate() {
+		let start = y * row_len;
+
+		for plane in &mut planes {
+			plane[start..start + row_len].fill(0);
+		}
+
+		for (x, pixel) in row.iter().enumerate() {
+			let luma = formats::luma(converter.to_rgb888(Rgb565::from_rgb565(*pixel)));
+			let level = match dither {
+				Some(map) => map.quantize(luma, max, map.rank(x, y)),
+				None => ((luma as u32 * max + 127) / 255) as u8
+			};
+
+			for (i, plane) in planes.iter_mut().enumerate() {
+				plane[start + x / 8] |= (level >> (N - 1 - i) & 1) << (7 - x % 8);
+			}
+		}
+	}
+}
+
+/// Which pixels [`tri_color`] counts as red, by their 8-bit RGB channels.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct RedThresholds {
+	/// The least red a red pixel can have.
+	pub min: u8,
+	/// How much more red than either green or blue a red pixel has to have.
+	pub margin: u8
+}
+
+impl RedThresholds {
+	/// Counts pixels as red with at least `min` red, and `margin` more than
+	/// either green or blue.
+	#[inline]
+	#[must_use]
+	pub const fn new(min: u8, margin: u8) -> Self { Self { min, margin } }
+
+	/// Whether the 8-bit RGB color counts as red.
+	#[inline]
+	#[must_use]
+	pub fn is_red(&self, [r, g, b]: [u8; 3]) -> bool { r >= self.min && r.saturating_sub(g.max(b)) >= self.margin }
+}
+
+impl Default for RedThresholds {
+	/// Red of at least half intensity and a quarter more than green or blue.
+	fn default() -> Self { Self::new(128, 64) }
+}
+
+/// Splits `frame`, which is `width` pixels wide, into the black and red planes
+/// of a tri-color panel, judging each pixel by its 8-bit RGB as `converter`
+/// expands it. Pixels that `thresholds` counts as red are set in `red` and
+/// left white in `black`. The rest are clear in `red`, and set in `black` if
+/// they're light and clear if they're dark, by whether their luma rounds to
+/// white or dithered against `dither` if it's given.
+///
+/// Both planes are packed like the planes of [`bit_planes`], and rows that
+/// don't fit in both are skipped. Invert either plane if the controller takes
+/// the opposite polarity.
+///
+/// ```
+/// # use rgb565::epaper::{self, RedThresholds};
+/// # use rgb565::Converter;
+/// let frame = [0xF800, 0xFFFF, 0x0000, 0xFFE0];
+/// let (mut black, mut red) = ([0; 1], [0; 1]);
+/// epaper::tri_color(&Converter::builtin(), &frame, 4, RedThresholds::default(), None, &mut black, &mut red);
+/// assert_eq!((black, red), ([0b1101_0000], [0b1000_0000]));
+/// ```
+pub fn tri_color(converter: &Converter, frame: &[u16], width: usize, thresholds: RedThresholds, dither: Option<&ThresholdMap>, black: &mut [u8], red: &mut [u8]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	if width == 0 {
+		return;
+	}
+
+	let row_len = width.div_ceil(8);
+	let rows = black.chunks_exact_mut(row_len).zip(red.chunks_exact_mut(row_len));
+
+	for (y, (row, (black, red))) in frame.chunks_exact(width).zip(rows).enumerate() {
+		black.fill(0);
+		red.fill(0);
+
+		for (x, pixel) in row.iter().enumerate() {
+			let rgb888 = converter.to_rgb888(Rgb565::from_rgb565(*pixel));
+			let bit = 0x80 >> (x % 8);
+
+			if thresholds.is_red(rgb888) {
+				black[x / 8] |= bit;
+				red[x / 8] |= bit;
+				continue;
+			}
+
+			let luma = formats::luma(rgb888);
+			let white = match dither {
+				Some(map) => map.quantize(luma, 1, map.rank(x, y)) != 0,
+				None => luma >= 128
+			};
+
+			if white {
+				black[x / 8] |= bit;
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{bit_planes, tri_color, RedThresholds};
+	use crate::dither::ThresholdMap;
+	use crate::{frame, Converter};
+
+	#[test]
+	fn levels() {
+		// A spread of grays from black to white, on two rows of 9 pixels so
+		// each row spills into a second byte.
+		let grays = [0x0000, 0x1082, 0x2104, 0x738E, 0x8410, 0xDEFB, 0xFFFF];
+		let frame: Vec<u16> = (0..18).map(|i| grays[i % 7]).collect();
+
+		let mut planes = [[0xFF; 4]; 4];
+		let [a, b, c, d] = &mut planes;
+		bit_planes(&Converter::builtin(), &frame, 9, None, [a, b, c, d]);
+
+		for (i, pixel) in frame.iter().enumerate() {
+			let (x, y) = (i % 9, i / 9);
+			let level = planes.map(|plane| plane[y * 2 + x / 8] >> (7 - x % 8) & 1);
+			let level = level.iter().fold(0, |level, bit| level << 1 | bit);
+			let luma = crate::formats::luma(crate::Rgb565::from_rgb565(*pixel).to_rgb888_components());
+			assert_eq!(level as u32, (luma as u32 * 15 + 127) / 255, "pixel {}", i);
+		}
+
+		// The padding at the end of each row is cleared.
+		assert_eq!(planes[0][1] & 0x7F, 0);
+
+		// One plane is the same as a threshold at the middle.
+		let (mut plane, mut thresholded) = ([0; 4], [0; 4]);
+		bit_planes(&Converter::builtin(), &frame, 9, None, [&mut plane]);
+		frame::threshold(&Converter::builtin(), &frame, 9, 128, &mut thresholded);
+		assert_eq!(plane, thresholded);
+
+		// Rows that don't fit in every plane are skipped.
+		let (mut short, mut long) = ([0; 2], [0xFF; 4]);
+		bit_planes(&Converter::builtin(), &frame, 9, None, [&mut short, &mut long]);
+		assert_eq!(long[2..], [0xFF, 0xFF]);
+	}
+
+	#[test]
+	fn tri_colors() {
+		let thresholds = RedThresholds::default();
+		assert!(thresholds.is_red([255, 0, 0]));
+		assert!(thresholds.is_red([200, 100, 136]));
+		assert!(!thresholds.is_red([200, 100, 137]));
+		assert!(!thresholds.is_red([127, 0, 0]));
+		assert!(!thresholds.is_red([255, 255, 255]));
+
+		// Red, dark red, yellow, white, black and gray, over two rows of 9
+		// so each spills into a second byte.
+		let frame: Vec<u16> = [0xF800, 0x4000, 0xFFE0, 0xFFFF, 0x0000, 0x8410].into_iter().cycle().take(18).collect();
+		let (mut black, mut red) = ([0xFF; 4], [0xFF; 4]);
+		tri_color(&Converter::builtin(), &frame, 9, thresholds, None, &mut black, &mut red);
+		assert_eq!(black, [0b1011_0110, 0b1000_0000, 0b1011_0110, 0b1000_0000]);
+		assert_eq!(red, [0b1000_0010, 0b0000_0000, 0b0001_0000, 0b0000_0000]);
+
+		// With no thresholds at all, everything is red.
+		let (mut black, mut red) = ([0; 4], [0; 4]);
+		tri_color(&Converter::builtin(), &frame, 9, RedThresholds::new(0, 0), None, &mut black, &mut red);
+		assert_eq!((black, red), ([0xFF, 0x80, 0xFF, 0x80], [0xFF, 0x80, 0xFF, 0x80]));
+
+		// Rows that don't fit in both planes are skipped.
+		let (mut black, mut red) = ([0xFF; 4], [0xFF; 2]);
+		tri_color(&Converter::builtin(), &frame, 9, thresholds, None, &mut black, &mut red);
+		assert_eq!(black[2..], [0xFF, 0xFF]);
+
+		let (mut black, mut red) = ([0; 4], [0; 4]);
+		tri_color(&Converter::builtin(), &[0x8410; 4 * 4], 4, thresholds, Some(&ThresholdMap::BAYER_4), &mut black, &mut red);
+		assert_eq!(black.iter().map(|byte| byte.count_ones()).sum::<u32>(), 8);
+		assert_eq!(red, [0; 4]);
+	}
+
+	#[test]
+	fn dithered() {
+		let frame = [0x8410; 4 * 4];
+		let mut plane = [0; 4];
+		bit_planes(&Converter::builtin(), &frame, 4, Some(&ThresholdMap::BAYER_4), [&mut plane]);
+		let set: u32 = plane.iter().map(|byte| byte.count_ones()).sum();
+		assert_eq!(set, 8);
+	}
+}
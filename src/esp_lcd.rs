@@ -0,0 +1,158 @@
+//! Helpers for the LCD peripherals of the ESP32-S3 as driven by `esp_lcd` or
+//! `esp-hal`.
+//!
+//! The RGB (parallel) peripheral reads frames as native little-endian RGB565,
+//! but the i80 and SPI peripherals shift each pixel out a byte at a time, low
+//! byte first, while panels expect the high byte first. Frames for those have
+//! to be stored with their bytes swapped, which is what [`copy_for`] and
+//! [`swap_bytes`] are for.
+//!
+//! ```
+//! # use rgb565::{esp_lcd, Rgb565};
+//! let frame = [Rgb565::from_rgb565(0xF800); 4];
+//! let mut dma = [0u16; 4];
+//! esp_lcd::copy_for(esp_lcd::Bus::Spi, &frame, &mut dma);
+//! assert_eq!(dma, [0x00F8; 4]);
+//! ```
+
+use crate::{Encoding, Rgb565};
+
+/// The alignment that frames in PSRAM need for the GDMA to read them, which is
+/// the size of a cache line.
+pub const DMA_ALIGNMENT: usize = 64;
+
+/// An LCD peripheral of the ESP32-S3.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Bus {
+	/// The RGB (parallel) peripheral, as used by `esp_lcd_new_rgb_panel`.
+	Rgb,
+	/// The i80 (8080) peripheral, as used by `esp_lcd_new_i80_bus`.
+	I80,
+	/// The SPI peripheral, as used by `esp_lcd_new_panel_io_spi`.
+	Spi
+}
+
+impl Bus {
+	/// The encoding frames have to be in for the peripheral to send the right
+	/// bytes to the panel. Frames are then sent as-is, so this assumes
+	/// `esp_lcd`'s own byte swapping is turned off.
+	#[inline]
+	#[must_use]
+	pub const fn encoding(self) -> Encoding {
+		match self {
+			Self::Rgb => Encoding::Rgb565Le,
+			Self::I80 | Self::Spi => Encoding::Rgb565Be
+		}
+	}
+}
+
+/// Copies `src` into `dst` in the byte order `bus` expects, as many pixels as
+/// fit.
+#[inline]
+pub fn copy_for(bus: Bus, src: &[Rgb565], dst: &mut [u16]) {
+	let encoding = bus.encoding();
+
+	for (dst, src) in dst.iter_mut().zip(src) {
+		*dst = u16::from_ne_bytes(src.to_bytes(encoding));
+	}
+}
+
+/// Swaps the bytes of every pixel in `frame`, converting between the byte
+/// order of the RGB peripheral and that of the i80 and SPI peripherals.
+#[inline]
+pub fn swap_bytes(frame: &mut [u16]) {
+	#[cfg(simd_backend)]
+	let frame = crate::simd::swap_bytes(frame);
+
+	for pixel in frame {
+		*pixel = pixel.swap_bytes();
+	}
+}
+
+/// One cache line of pixels, to give [`AlignedFrame`] its alignment.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone)]
+#[repr(C, align(64))]
+struct Line([u16; DMA_ALIGNMENT / 2]);
+
+/// A frame of raw RGB565 values that starts on a [`DMA_ALIGNMENT`] boundary,
+/// so that it can be handed to the GDMA even when it's allocated in PSRAM.
+#[cfg(feature = "std")]
+pub struct AlignedFrame {
+	lines: Vec<Line>,
+	len: usize
+}
+
+#[cfg(feature = "std")]
+impl AlignedFrame {
+	/// Allocates a black frame `width` pixels wide and `height` pixels tall.
+	#[must_use]
+	pub fn new(width: usize, height: usize) -> Self {
+		let len = width * height;
+		Self { lines: vec![Line([0; DMA_ALIGNMENT / 2]); len.div_ceil(DMA_ALIGNMENT / 2)], len }
+	}
+
+	/// Returns the frame as bytes, for passing to `esp_lcd_panel_draw_bitmap`.
+	#[inline]
+	#[must_use]
+	pub fn as_bytes(&self) -> &[u8] {
+		// SAFETY: `u16` has no padding and `u8` has no alignment requirement.
+		unsafe { core::slice::from_raw_parts(self.as_ptr().cast(), self.len * 2) }
+	}
+}
+
+#[cfg(feature = "std")]
+impl core::ops::Deref for AlignedFrame {
+	type Target = [u16];
+
+	#[inline]
+	fn deref(&self) -> &[u16] {
+		// SAFETY: `Line` is `repr(C)` around a `[u16; 32]` and its size is a
+		// multiple of its alignment, so a slice of lines has no padding between
+		// its pixels, and there are at least `len` of them.
+		unsafe { core::slice::from_raw_parts(self.lines.as_ptr().cast(), self.len) }
+	}
+}
+
+#[cfg(feature = "std")]
+impl core::ops::DerefMut for AlignedFrame {
+	#[inline]
+	fn deref_mut(&mut self) -> &mut [u16] {
+		// SAFETY: As above.
+		unsafe { core::slice::from_raw_parts_mut(self.lines.as_mut_ptr().cast(), self.len) }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{copy_for, swap_bytes, Bus};
+	use crate::Rgb565;
+
+	#[test]
+	fn byte_order() {
+		let frame = [Rgb565::from_rgb565(0x1234)];
+		let mut rgb = [0u16];
+		let mut spi = [0u16];
+		copy_for(Bus::Rgb, &frame, &mut rgb);
+		copy_for(Bus::Spi, &frame, &mut spi);
+		assert_eq!(rgb, [0x1234]);
+		assert_eq!(spi[0].to_ne_bytes(), [0x12, 0x34]);
+
+		swap_bytes(&mut rgb);
+		assert_eq!(rgb, spi);
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn aligned_frame() {
+		use super::{AlignedFrame, DMA_ALIGNMENT};
+
+		let mut frame = AlignedFrame::new(7, 5);
+		assert_eq!(frame.len(), 35);
+		assert_eq!(frame.as_ptr() as usize % DMA_ALIGNMENT, 0);
+
+		frame[34] = 0xF800;
+		assert_eq!(frame.as_bytes().len(), 70);
+		assert_eq!(frame.as_bytes()[68..], 0xF800u16.to_ne_bytes());
+	}
+}
@@ -0,0 +1,98 @@
+//! A fast approximation of `powf`, for computing sRGB where the real thing is
+//! too slow, like on microcontrollers without an FPU.
+//!
+//! `x` to the power of `y` is `exp2(y * log2(x))`. The exponent of a float
+//! already is the integer part of its `log2`, and the integer part of an
+//! `exp2` goes straight into the exponent of the result, so only the
+//! fractional parts need approximating, which polynomials do well over such a
+//! short range. That's a handful of multiplications and additions instead of
+//! the dozens `powf` takes.
+
+/// The coefficients of a polynomial for `log2(m) / (m - 1)` with `m` from 1
+/// to 2, from the constant term up. Multiplying by `m - 1` keeps `log2(1)`
+/// exactly 0, so white stays white. The absolute error of the result is below
+/// 5.1e-5.
+const LOG2: [f32; 5] = [2.8836856, -2.5078816, 1.4678978, -0.45976278, 0.05866494];
+
+/// The coefficients of a polynomial for `(exp2(f) - 1) / f` with `f` from 0
+/// to 1, from the constant term up. Adding 1 after multiplying by `f` keeps
+/// `exp2(0)` exactly 1. The relative error of the result is below 7.4e-6.
+const EXP2: [f32; 4] = [0.693134, 0.24064705, 0.05344103, 0.012763114];
+
+#[inline]
+fn polynomial(coefficients: &[f32], x: f32) -> f32 { coefficients.iter().rev().fold(0.0, |sum, coefficient| sum * x + coefficient) }
+
+/// `log2(x)` for positive, normal `x`.
+#[inline]
+fn log2(x: f32) -> f32 {
+	let bits = x.to_bits();
+	let exponent = (bits >> 23) as i32 - 127;
+	let mantissa = f32::from_bits(bits & 0x007F_FFFF | 0x3F80_0000);
+	exponent as f32 + (mantissa - 1.0) * polynomial(&LOG2, mantissa)
+}
+
+/// `exp2(x)`, flushing to 0 below the range of normal floats and saturating
+/// to infinity above it.
+#[inline]
+fn exp2(x: f32) -> f32 {
+	let truncated = x as i32;
+	let floor = truncated - (x < truncated as f32) as i32;
+	let fraction = x - floor as f32;
+
+	match floor {
+		..-126 => 0.0,
+		128.. => f32::INFINITY,
+		_ => f32::from_bits(((floor + 127) as u32) << 23) * (1.0 + fraction * polynomial(&EXP2, fraction))
+	}
+}
+
+/// `x` to the power of `y`, for positive `x`, with a relative error below
+/// 1e-4 for the exponents of the sRGB curve.
+#[inline]
+pub fn powf(x: f32, y: f32) -> f32 { exp2(y * log2(x)) }
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn powf() {
+		for i in 1..=10000 {
+			let x = i as f32 / 10000.0;
+
+			for y in [1.0 / 2.4, 2.4] {
+				let (approx, exact) = (super::powf(x, y), x.powf(y));
+				assert!((approx / exact - 1.0).abs() < 1e-4, "{}^{}: {} != {}", x, y, approx, exact);
+			}
+		}
+
+		assert_eq!(super::powf(1.0, 2.4), 1.0);
+		assert_eq!(super::powf(1.0, 1.0 / 2.4), 1.0);
+		assert_eq!(super::exp2(-200.0), 0.0);
+		assert_eq!(super::exp2(200.0), f32::INFINITY);
+		assert!((super::exp2(-2.5) - 2f32.powf(-2.5)).abs() < 1e-6);
+	}
+
+	/// The approximation is close enough that every conversion between 8-bit
+	/// sRGB and 5- or 6-bit linear RGB comes out the same as with `f32::powf`.
+	#[test]
+	#[cfg(not(feature = "exact_srgb"))]
+	#[allow(clippy::excessive_precision)]
+	fn conversions() {
+		use crate::lut::transforms::{l5_to_s8, l6_to_s8, s8_to_l5, s8_to_l6};
+
+		let transfer = |v: f32| if v < 0.0031308 { v * 12.9232102 } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 };
+		let untransfer = |v: f32| if v < 0.0404599 { v / 12.9232102 } else { ((v + 0.055) / 1.055).powf(2.4) };
+
+		for l5 in 0..32 {
+			assert_eq!(l5_to_s8(l5), (transfer(l5 as f32 / 31.0) * 255.0) as u8, "{}", l5);
+		}
+
+		for l6 in 0..64 {
+			assert_eq!(l6_to_s8(l6), (transfer(l6 as f32 / 63.0) * 255.0) as u8, "{}", l6);
+		}
+
+		for s8 in 0..=255 {
+			assert_eq!(s8_to_l5(s8), (untransfer(s8 as f32 / 255.0) * 31.999) as u8, "{}", s8);
+			assert_eq!(s8_to_l6(s8), (untransfer(s8 as f32 / 255.0) * 63.999) as u8, "{}", s8);
+		}
+	}
+}
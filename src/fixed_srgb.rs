@@ -0,0 +1,215 @@
+//! The sRGB transfer function in fixed point, with nothing but integer math.
+//!
+//! Everything here is a `const fn`, so it works in `no_std` without `libm`,
+//! comes out the same on every target, and can compute colors at compile
+//! time. With the `fixed_srgb` feature, the sRGB conversions of [`Rgb565`]
+//! use it too. The sRGB LUTs are always computed with it.
+//!
+//! The powers of the sRGB curve are rational, `5 / 12` and `12 / 5`, so they
+//! come down to integer roots and multiplications instead of a `powf`. These
+//! are done in Q24, 24 fractional bits, which is precise enough that every
+//! conversion between 8-bit sRGB and 5- or 6-bit linear RGB comes out exactly
+//! as it does with floats.
+//!
+//! ```
+//! # use rgb565::fixed_srgb;
+//! const GRAY: [u8; 3] = fixed_srgb::l565_to_s888(0x8410);
+//! assert_eq!(GRAY, [190, 188, 190]);
+//! assert_eq!(fixed_srgb::srgb_transfer(fixed_srgb::ONE / 2), 48192);
+//! ```
+//!
+//! [`Rgb565`]: crate::Rgb565
+
+/// 1.0 in the Q16 fixed point that [`srgb_transfer`] and [`srgb_untransfer`]
+/// take and return.
+pub const ONE: u32 = 1 << 16;
+
+const FRACTION_BITS: u32 = 24;
+const ONE_Q24: u64 = 1 << FRACTION_BITS;
+
+/// The constants of the curve, as numerators over 10^7. See `std.rs` for why
+/// there are two sets of them.
+const DENOMINATOR: u64 = 10_000_000;
+const LINEAR_THRESHOLD: u64 = 31_308;
+#[cfg(not(feature = "exact_srgb"))]
+const LINEAR_SLOPE: u64 = 129_232_102;
+#[cfg(feature = "exact_srgb")]
+const LINEAR_SLOPE: u64 = 129_200_000;
+#[cfg(not(feature = "exact_srgb"))]
+const ENCODED_THRESHOLD: u64 = 404_599;
+#[cfg(feature = "exact_srgb")]
+const ENCODED_THRESHOLD: u64 = 404_500;
+
+/// The largest integer whose `n`th power is at most `v`, found one bit at a
+/// time.
+const fn root(v: u128, n: u32) -> u64 {
+	let mut root = 0;
+	let mut bit = (128 - v.leading_zeros()).div_ceil(n);
+
+	while bit > 0 {
+		bit -= 1;
+		let candidate = root | 1 << bit;
+
+		if (candidate as u128).pow(n) <= v {
+			root = candidate;
+		}
+	}
+
+	root
+}
+
+/// The `n`th root of `x`, both in Q24.
+const fn nth_root(x: u64, n: u32) -> u64 { root((x as u128) << (FRACTION_BITS * (n - 1)), n) }
+
+const fn mul(a: u64, b: u64) -> u64 { (a * b) >> FRACTION_BITS }
+
+/// Linear to encoded, both in Q24.
+const fn transfer(linear: u64) -> u64 {
+	if linear * DENOMINATOR < LINEAR_THRESHOLD * ONE_Q24 {
+		return linear * LINEAR_SLOPE / DENOMINATOR;
+	}
+
+	// linear^(5 / 12), taking the root first so that small values don't lose
+	// their precision to the power.
+	let twelfth = nth_root(nth_root(nth_root(linear, 3), 2), 2);
+	let squared = mul(twelfth, twelfth);
+	let power = mul(mul(squared, squared), twelfth);
+	(power * 1055 - ONE_Q24 * 55) / 1000
+}
+
+/// Encoded to linear, both in Q24.
+const fn untransfer(encoded: u64) -> u64 {
+	if encoded * DENOMINATOR < ENCODED_THRESHOLD * ONE_Q24 {
+		return encoded * DENOMINATOR / LINEAR_SLOPE;
+	}
+
+	// x^(12 / 5) is x^2 times the fifth root of x^2.
+	let x = (encoded * 1000 + ONE_Q24 * 55) / 1055;
+	let squared = mul(x, x);
+	mul(squared, nth_root(squared, 5))
+}
+
+/// Converts a linear value to sRGB, both in Q16 with [`ONE`] as 1.0. Values
+/// above `ONE` are treated as `ONE`.
+#[inline]
+#[must_use]
+pub const fn srgb_transfer(linear: u32) -> u32 {
+	let linear = if linear > ONE { ONE } else { linear };
+	((transfer((linear as u64) << 8) + 128) >> 8) as u32
+}
+
+/// Converts an sRGB value to linear, both in Q16 with [`ONE`] as 1.0. Values
+/// above `ONE` are treated as `ONE`.
+#[inline]
+#[must_use]
+pub const fn srgb_untransfer(encoded: u32) -> u32 {
+	let encoded = if encoded > ONE { ONE } else { encoded };
+	((untransfer((encoded as u64) << 8) + 128) >> 8) as u32
+}
+
+/// Scales an encoded value in Q24 to 8 bits, truncating like the float
+/// conversions do. Those land a hair under 255 for white, so it's 254 here
+/// too, to keep this the same as them and their LUTs.
+const fn to_s8(encoded: u64) -> u8 {
+	let s8 = (encoded * 255).saturating_sub(1) >> FRACTION_BITS;
+	if s8 > 255 { 255 } else { s8 as u8 }
+}
+
+/// Converts a linear 5-bit channel, from 0 to 31, to 8-bit sRGB.
+#[inline]
+#[must_use]
+pub const fn l5_to_s8(l5: u8) -> u8 { to_s8(transfer(l5 as u64 * ONE_Q24 / 31)) }
+
+/// Converts a linear 6-bit channel, from 0 to 63, to 8-bit sRGB.
+#[inline]
+#[must_use]
+pub const fn l6_to_s8(l6: u8) -> u8 { to_s8(transfer(l6 as u64 * ONE_Q24 / 63)) }
+
+/// Converts an 8-bit sRGB channel to linear 5 bits, from 0 to 31.
+#[inline]
+#[must_use]
+pub const fn s8_to_l5(s8: u8) -> u8 { ((untransfer(s8 as u64 * ONE_Q24 / 255) * 31_999 / 1000) >> FRACTION_BITS) as u8 }
+
+/// Converts an 8-bit sRGB channel to linear 6 bits, from 0 to 63.
+#[inline]
+#[must_use]
+pub const fn s8_to_l6(s8: u8) -> u8 { ((untransfer(s8 as u64 * ONE_Q24 / 255) * 63_999 / 1000) >> FRACTION_BITS) as u8 }
+
+/// Converts a raw RGB565 value to 8-bit sRGB channels, in the order red,
+/// green, blue.
+#[must_use]
+pub const fn l565_to_s888(l565: u16) -> [u8; 3] {
+	[l5_to_s8((l565 >> 11 & 0b11111) as u8), l6_to_s8((l565 >> 5 & 0b111111) as u8), l5_to_s8((l565 & 0b11111) as u8)]
+}
+
+/// Converts 8-bit sRGB channels, in the order red, green, blue, to a raw
+/// RGB565 value.
+#[must_use]
+pub const fn s888_to_l565(s888: [u8; 3]) -> u16 {
+	let [r, g, b] = s888;
+	(s8_to_l5(r) as u16) << 11 | (s8_to_l6(g) as u16) << 5 | s8_to_l5(b) as u16
+}
+
+#[cfg(test)]
+mod tests {
+	#![allow(clippy::excessive_precision)]
+
+	use super::ONE;
+
+	#[cfg(not(feature = "exact_srgb"))]
+	const LINEAR_SLOPE: f32 = 12.9232102;
+	#[cfg(feature = "exact_srgb")]
+	const LINEAR_SLOPE: f32 = 12.92;
+	#[cfg(not(feature = "exact_srgb"))]
+	const ENCODED_THRESHOLD: f32 = 0.0404599;
+	#[cfg(feature = "exact_srgb")]
+	const ENCODED_THRESHOLD: f32 = 0.04045;
+
+	fn transfer(v: f32) -> f32 { if v < 0.0031308 { v * LINEAR_SLOPE } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 } }
+
+	fn untransfer(v: f32) -> f32 { if v < ENCODED_THRESHOLD { v / LINEAR_SLOPE } else { ((v + 0.055) / 1.055).powf(2.4) } }
+
+	#[test]
+	fn root() {
+		assert_eq!(super::root(0, 3), 0);
+		assert_eq!(super::root(26, 3), 2);
+		assert_eq!(super::root(27, 3), 3);
+		assert_eq!(super::root(u128::MAX >> 8, 5), (1 << 24) - 1);
+		assert_eq!(super::nth_root(super::ONE_Q24 / 4, 2), super::ONE_Q24 / 2);
+	}
+
+	#[test]
+	fn curve() {
+		for i in 0..=ONE {
+			let v = i as f64 / ONE as f64;
+			let transferred = super::srgb_transfer(i) as f64 / ONE as f64;
+			let untransferred = super::srgb_untransfer(i) as f64 / ONE as f64;
+			assert!((transferred - transfer(v as f32) as f64).abs() < 2e-5, "{}", i);
+			assert!((untransferred - untransfer(v as f32) as f64).abs() < 2e-5, "{}", i);
+		}
+
+		assert_eq!((super::srgb_transfer(0), super::srgb_transfer(ONE)), (0, ONE));
+		assert_eq!((super::srgb_untransfer(0), super::srgb_untransfer(ONE)), (0, ONE));
+		assert_eq!(super::srgb_transfer(u32::MAX), ONE);
+	}
+
+	/// Every conversion comes out the same as with floats, so that switching
+	/// to this doesn't change any colors.
+	#[test]
+	fn conversions() {
+		for l5 in 0..32 {
+			assert_eq!(super::l5_to_s8(l5), (transfer(l5 as f32 / 31.0) * 255.0) as u8, "{}", l5);
+		}
+
+		for l6 in 0..64 {
+			assert_eq!(super::l6_to_s8(l6), (transfer(l6 as f32 / 63.0) * 255.0) as u8, "{}", l6);
+		}
+
+		for s8 in 0..=255 {
+			assert_eq!(super::s8_to_l5(s8), (untransfer(s8 as f32 / 255.0) * 31.999) as u8, "{}", s8);
+			assert_eq!(super::s8_to_l6(s8), (untransfer(s8 as f32 / 255.0) * 63.999) as u8, "{}", s8);
+		}
+
+		assert_eq!(super::s888_to_l565(super::l565_to_s888(0x8410)), 0x8410);
+	}
+}
@@ -0,0 +1,459 @@
+//! The other pixel formats that tend to show up next to RGB565, and a complete
+//! set of conversions between them.
+//!
+//! Every pair of [`Rgb565`], [`Rgb666`], [`Rgb555`], [`Argb1555`],
+//! [`Argb4444`], [`Rgb332`], [`Rgb888`] and [`Gray8`] converts with `From`,
+//! except that converting a format with alpha to one without is `TryFrom` and
+//! fails with [`TranslucentError`] unless the color is fully opaque. This lets
+//! generic code convert between formats with `From` and `TryFrom` bounds
+//! without knowing the concrete pair.
+//!
+//! Conversions go through 8-bit ARGB, rounding each channel to the nearest
+//! value, so converting to a format at least as precise and back again gives
+//! the same color. `Rgb565` itself goes through
+//! [`Rgb565::to_rgb888_components`] and [`Rgb565::from_rgb888_components`],
+//! and between `Rgb565` and `Rgb666` it uses [`Rgb666::from_rgb565`] and
+//! [`Rgb666::to_rgb565`], which keep the round trip exact. Converting to
+//! [`Gray8`] takes the luma of the color.
+//!
+//! ```
+//! # use rgb565::{formats::{Argb4444, Rgb332}, Rgb565};
+//! let red = Rgb565::from_rgb565(0xF800);
+//! assert_eq!(Rgb332::from(red).to_rgb332(), 0b11100000);
+//! assert_eq!(Rgb565::try_from(Argb4444::from(red)), Ok(red));
+//! assert!(Rgb565::try_from(Argb4444::from_argb4444(0x0F00)).is_err());
+//! ```
+
+use core::fmt::{self, Display, Formatter};
+
+use crate::{Rgb565, Rgb666};
+
+/// The error from converting a color that isn't fully opaque to a format
+/// without alpha.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct TranslucentError;
+
+impl Display for TranslucentError {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result { f.write_str("color is not fully opaque") }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TranslucentError {}
+
+/// An RGB555 color, packed as `xrrrrrgggggbbbbb` with the top bit ignored.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Default)]
+pub struct Rgb555(u16);
+
+/// An ARGB1555 color, packed as `arrrrrgggggbbbbb`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Default)]
+pub struct Argb1555(u16);
+
+/// An ARGB4444 color, packed as `aaaarrrrggggbbbb`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Default)]
+pub struct Argb4444(u16);
+
+/// An RGB332 color, packed as `rrrgggbb`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Default)]
+pub struct Rgb332(u8);
+
+/// An 8-bit RGB color.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Default)]
+pub struct Rgb888 {
+	r: u8,
+	g: u8,
+	b: u8
+}
+
+/// An 8-bit gray level.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Default)]
+pub struct Gray8(u8);
+
+impl Rgb555 {
+	/// From rgb555, where the colors are packed as `xrrrrrgggggbbbbb`. The top
+	/// bit is cleared.
+	#[inline]
+	#[must_use]
+	pub const fn from_rgb555(packed: u16) -> Self { Self(packed & 0x7FFF) }
+
+	/// To rgb555, where the colors are packed as `0rrrrrgggggbbbbb`
+	#[inline]
+	#[must_use]
+	pub const fn to_rgb555(&self) -> u16 { self.0 }
+
+	/// From rgb555 components, which are masked to 5 bits.
+	#[inline]
+	#[must_use]
+	pub const fn from_rgb555_components(r: u8, g: u8, b: u8) -> Self { Self(pack([(r, 5), (g, 5), (b, 5)]) as u16) }
+
+	/// To rgb555 components, which each fit into 5 bits.
+	#[inline]
+	#[must_use]
+	pub const fn to_rgb555_components(&self) -> [u8; 3] { unpack(self.0 as u32, [5, 5, 5]) }
+}
+
+impl Argb1555 {
+	/// From argb1555, where the colors are packed as `arrrrrgggggbbbbb`
+	#[inline]
+	#[must_use]
+	pub const fn from_argb1555(packed: u16) -> Self { Self(packed) }
+
+	/// To argb1555, where the colors are packed as `arrrrrgggggbbbbb`
+	#[inline]
+	#[must_use]
+	pub const fn to_argb1555(&self) -> u16 { self.0 }
+
+	/// From argb1555 components, where `a` is masked to 1 bit and the rest to
+	/// 5 bits.
+	#[inline]
+	#[must_use]
+	pub const fn from_argb1555_components(a: u8, r: u8, g: u8, b: u8) -> Self { Self(pack([(a, 1), (r, 5), (g, 5), (b, 5)]) as u16) }
+
+	/// To argb1555 components, where `a` fits into 1 bit and the rest into 5
+	/// bits.
+	#[inline]
+	#[must_use]
+	pub const fn to_argb1555_components(&self) -> [u8; 4] { unpack(self.0 as u32, [1, 5, 5, 5]) }
+}
+
+impl Argb4444 {
+	/// From argb4444, where the colors are packed as `aaaarrrrggggbbbb`
+	#[inline]
+	#[must_use]
+	pub const fn from_argb4444(packed: u16) -> Self { Self(packed) }
+
+	/// To argb4444, where the colors are packed as `aaaarrrrggggbbbb`
+	#[inline]
+	#[must_use]
+	pub const fn to_argb4444(&self) -> u16 { self.0 }
+
+	/// From argb4444 components, which are masked to 4 bits.
+	#[inline]
+	#[must_use]
+	pub const fn from_argb4444_components(a: u8, r: u8, g: u8, b: u8) -> Self { Self(pack([(a, 4), (r, 4), (g, 4), (b, 4)]) as u16) }
+
+	/// To argb4444 components, which each fit into 4 bits.
+	#[inline]
+	#[must_use]
+	pub const fn to_argb4444_components(&self) -> [u8; 4] { unpack(self.0 as u32, [4, 4, 4, 4]) }
+}
+
+impl Rgb332 {
+	/// From rgb332, where the colors are packed as `rrrgggbb`
+	#[inline]
+	#[must_use]
+	pub const fn from_rgb332(packed: u8) -> Self { Self(packed) }
+
+	/// To rgb332, where the colors are packed as `rrrgggbb`
+	#[inline]
+	#[must_use]
+	pub const fn to_rgb332(&self) -> u8 { self.0 }
+
+	/// From rgb332 components, where `r` and `g` are masked to 3 bits and `b`
+	/// to 2 bits.
+	#[inline]
+	#[must_use]
+	pub const fn from_rgb332_components(r: u8, g: u8, b: u8) -> Self { Self(pack([(r, 3), (g, 3), (b, 2)]) as u8) }
+
+	/// To rgb332 components, where `r` and `g` fit into 3 bits and `b` into 2
+	/// bits.
+	#[inline]
+	#[must_use]
+	pub const fn to_rgb332_components(&self) -> [u8; 3] { unpack(self.0 as u32, [3, 3, 2]) }
+}
+
+impl Rgb888 {
+	/// From 8-bit components.
+	#[inline]
+	#[must_use]
+	pub const fn from_rgb888_components(r: u8, g: u8, b: u8) -> Self { Self { r, g, b } }
+
+	/// To 8-bit components, in the order red, green, blue.
+	#[inline]
+	#[must_use]
+	pub const fn to_rgb888_components(&self) -> [u8; 3] { [self.r, self.g, self.b] }
+}
+
+impl Gray8 {
+	/// From an 8-bit level, from 0 for black to 255 for white.
+	#[inline]
+	#[must_use]
+	pub const fn from_gray8(level: u8) -> Self { Self(level) }
+
+	/// To an 8-bit level, from 0 for black to 255 for white.
+	#[inline]
+	#[must_use]
+	pub const fn to_gray8(&self) -> u8 { self.0 }
+}
+
+/// Packs channels, given as values and their widths in bits, from the most
+/// significant to the least. Each value is masked to its width.
+#[inline]
+const fn pack<const N: usize>(channels: [(u8, u32); N]) -> u32 {
+	let mut packed = 0;
+	let mut i = 0;
+
+	while i < N {
+		let (value, bits) = channels[i];
+		packed = packed << bits | value as u32 & ((1 << bits) - 1);
+		i += 1;
+	}
+
+	packed
+}
+
+/// Unpacks channels of the given widths in bits, from the most significant to
+/// the least.
+#[inline]
+const fn unpack<const N: usize>(mut packed: u32, bits: [u32; N]) -> [u8; N] {
+	let mut channels = [0; N];
+	let mut i = N;
+
+	while i > 0 {
+		i -= 1;
+		channels[i] = (packed & ((1 << bits[i]) - 1)) as u8;
+		packed >>= bits[i];
+	}
+
+	channels
+}
+
+/// Widens a channel of `bits` bits to 8 bits, rounding to the nearest value.
+#[inline]
+const fn widen(value: u8, bits: u32) -> u8 {
+	let max = (1 << bits) - 1;
+	((value as u16 * 255 + max / 2) / max) as u8
+}
+
+/// Narrows an 8-bit channel to `bits` bits, rounding to the nearest value.
+#[inline]
+const fn narrow(value: u8, bits: u32) -> u8 {
+	let max = (1 << bits) - 1;
+	((value as u16 * max + 127) / 255) as u8
+}
+
+/// The luma of an 8-bit RGB color, with the Rec. 601 weights in 256ths.
+#[inline]
+pub(crate) const fn luma([r, g, b]: [u8; 3]) -> u8 { ((r as u32 * 77 + g as u32 * 150 + b as u32 * 29 + 128) >> 8) as u8 }
+
+/// The representation every conversion goes through.
+trait Argb8888: Copy {
+	fn to_argb8888(self) -> [u8; 4];
+
+	/// From 8-bit ARGB, ignoring alpha if the format doesn't have it.
+	fn from_argb8888(argb: [u8; 4]) -> Self;
+}
+
+macro_rules! opaque {
+	($($format:ty: |$self:ident| $to:expr, |$r:ident, $g:ident, $b:ident| $from:expr;)+) => {
+		$(impl Argb8888 for $format {
+			#[inline]
+			fn to_argb8888($self) -> [u8; 4] { let [r, g, b] = $to; [255, r, g, b] }
+
+			#[inline]
+			fn from_argb8888([_, $r, $g, $b]: [u8; 4]) -> Self { $from }
+		})+
+	}
+}
+
+opaque! {
+	Rgb565: |self| self.to_rgb888_components(), |r, g, b| Rgb565::from_rgb888_components(r, g, b);
+	Rgb666: |self| self.to_rgb666_components().map(|c| widen(c, 6)), |r, g, b| Rgb666::from_rgb666_components(narrow(r, 6), narrow(g, 6), narrow(b, 6));
+	Rgb555: |self| self.to_rgb555_components().map(|c| widen(c, 5)), |r, g, b| Rgb555::from_rgb555_components(narrow(r, 5), narrow(g, 5), narrow(b, 5));
+	Rgb332: |self| { let [r, g, b] = self.to_rgb332_components(); [widen(r, 3), widen(g, 3), widen(b, 2)] }, |r, g, b| Rgb332::from_rgb332_components(narrow(r, 3), narrow(g, 3), narrow(b, 2));
+	Rgb888: |self| self.to_rgb888_components(), |r, g, b| Rgb888::from_rgb888_components(r, g, b);
+	Gray8: |self| [self.0; 3], |r, g, b| Gray8(luma([r, g, b]));
+}
+
+impl Argb8888 for Argb1555 {
+	#[inline]
+	fn to_argb8888(self) -> [u8; 4] {
+		let [a, r, g, b] = self.to_argb1555_components();
+		[widen(a, 1), widen(r, 5), widen(g, 5), widen(b, 5)]
+	}
+
+	#[inline]
+	fn from_argb8888(argb: [u8; 4]) -> Self {
+		let [a, r, g, b] = argb;
+		Self::from_argb1555_components(narrow(a, 1), narrow(r, 5), narrow(g, 5), narrow(b, 5))
+	}
+}
+
+impl Argb8888 for Argb4444 {
+	#[inline]
+	fn to_argb8888(self) -> [u8; 4] { self.to_argb4444_components().map(|c| widen(c, 4)) }
+
+	#[inline]
+	fn from_argb8888(argb: [u8; 4]) -> Self {
+		let [a, r, g, b] = argb.map(|c| narrow(c, 4));
+		Self::from_argb4444_components(a, r, g, b)
+	}
+}
+
+/// Implements `From` for every pair of formats where it can't fail.
+macro_rules! from {
+	($($src:ty => [$($dst:ty),+];)+) => {
+		$($(impl From<$src> for $dst {
+			#[inline]
+			fn from(color: $src) -> Self { Self::from_argb8888(color.to_argb8888()) }
+		})+)+
+	}
+}
+
+/// Implements `TryFrom` from formats with alpha to formats without.
+macro_rules! try_from {
+	($($src:ty => [$($dst:ty),+];)+) => {
+		$($(impl TryFrom<$src> for $dst {
+			type Error = TranslucentError;
+
+			#[inline]
+			fn try_from(color: $src) -> Result<Self, TranslucentError> {
+				match color.to_argb8888() {
+					argb @ [255, ..] => Ok(Self::from_argb8888(argb)),
+					_ => Err(TranslucentError)
+				}
+			}
+		})+)+
+	}
+}
+
+from! {
+	Rgb565 => [Rgb555, Argb1555, Argb4444, Rgb332, Rgb888, Gray8];
+	Rgb666 => [Rgb555, Argb1555, Argb4444, Rgb332, Rgb888, Gray8];
+	Rgb555 => [Rgb565, Rgb666, Argb1555, Argb4444, Rgb332, Rgb888, Gray8];
+	Rgb332 => [Rgb565, Rgb666, Rgb555, Argb1555, Argb4444, Rgb888, Gray8];
+	Rgb888 => [Rgb565, Rgb666, Rgb555, Argb1555, Argb4444, Rgb332, Gray8];
+	Gray8 => [Rgb565, Rgb666, Rgb555, Argb1555, Argb4444, Rgb332, Rgb888];
+	Argb1555 => [Argb4444];
+	Argb4444 => [Argb1555];
+}
+
+try_from! {
+	Argb1555 => [Rgb565, Rgb666, Rgb555, Rgb332, Rgb888, Gray8];
+	Argb4444 => [Rgb565, Rgb666, Rgb555, Rgb332, Rgb888, Gray8];
+}
+
+impl From<Rgb565> for Rgb666 {
+	#[inline]
+	fn from(color: Rgb565) -> Self { Self::from_rgb565(color) }
+}
+
+impl From<Rgb666> for Rgb565 {
+	#[inline]
+	fn from(color: Rgb666) -> Self { color.to_rgb565() }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Argb1555, Argb4444, Argb8888, Gray8, Rgb332, Rgb555, Rgb888, TranslucentError};
+	use crate::{Rgb565, Rgb666};
+
+	/// Every color of a format, or a spread of them for the larger formats.
+	fn samples<T: Argb8888>(raw: impl Fn(u32) -> T, bits: u32) -> impl Iterator<Item = T> {
+		(0..1u32 << bits).step_by(1 << bits.saturating_sub(16)).map(raw)
+	}
+
+	/// Checks that converting every sample of `P` to `Q` goes through 8-bit
+	/// ARGB.
+	fn check<P, Q>(samples: impl Iterator<Item = P>)
+	where P: Argb8888 + core::fmt::Debug, Q: Argb8888 + From<P> + Eq + core::fmt::Debug {
+		for color in samples {
+			assert_eq!(Q::from(color), Q::from_argb8888(color.to_argb8888()), "{:?}", color);
+		}
+	}
+
+	/// Checks that every sample of `P` survives a round trip through `Q`.
+	fn round_trip<P, Q>(samples: impl Iterator<Item = P>)
+	where P: From<Q> + Copy + Eq + core::fmt::Debug, Q: From<P> {
+		for color in samples {
+			assert_eq!(P::from(Q::from(color)), color);
+		}
+	}
+
+	fn check_try<P, Q>(samples: impl Iterator<Item = P>)
+	where P: Argb8888 + core::fmt::Debug, Q: Argb8888 + TryFrom<P, Error = TranslucentError> + Eq + core::fmt::Debug {
+		for color in samples {
+			let argb = color.to_argb8888();
+			let expected = if argb[0] == 255 { Ok(Q::from_argb8888(argb)) } else { Err(TranslucentError) };
+			assert_eq!(Q::try_from(color), expected, "{:?}", color);
+		}
+	}
+
+	fn rgb565() -> impl Iterator<Item = Rgb565> { samples(|i| Rgb565::from_rgb565(i as u16), 16) }
+	fn rgb666() -> impl Iterator<Item = Rgb666> { samples(|i| Rgb666::from_rgb666_components((i >> 12) as u8, (i >> 6) as u8, i as u8), 18) }
+	fn rgb555() -> impl Iterator<Item = Rgb555> { samples(|i| Rgb555::from_rgb555(i as u16), 15) }
+	fn argb1555() -> impl Iterator<Item = Argb1555> { samples(|i| Argb1555::from_argb1555(i as u16), 16) }
+	fn argb4444() -> impl Iterator<Item = Argb4444> { samples(|i| Argb4444::from_argb4444(i as u16), 16) }
+	fn rgb332() -> impl Iterator<Item = Rgb332> { samples(|i| Rgb332::from_rgb332(i as u8), 8) }
+	fn rgb888() -> impl Iterator<Item = Rgb888> { samples(|i| Rgb888::from_rgb888_components((i >> 16) as u8, (i >> 8) as u8, i as u8), 24) }
+	fn gray8() -> impl Iterator<Item = Gray8> { samples(|i| Gray8::from_gray8(i as u8), 8) }
+
+	#[test]
+	fn packing() {
+		assert_eq!(Rgb555::from_rgb555_components(31, 0, 1).to_rgb555(), 0x7C01);
+		assert_eq!(Argb1555::from_argb1555(0xFC01).to_argb1555_components(), [1, 31, 0, 1]);
+		assert_eq!(Argb4444::from_argb4444_components(15, 1, 2, 3).to_argb4444(), 0xF123);
+		assert_eq!(Rgb332::from_rgb332(0b10101011).to_rgb332_components(), [5, 2, 3]);
+		assert_eq!(Rgb555::from_rgb555(0xFFFF).to_rgb555(), 0x7FFF);
+	}
+
+	#[test]
+	fn opaque_matrix() {
+		check::<_, Rgb888>(rgb565());
+		check::<_, Rgb555>(rgb565());
+		check::<_, Rgb332>(rgb565());
+		check::<_, Gray8>(rgb565());
+		check::<_, Rgb888>(rgb666());
+		check::<_, Rgb332>(rgb666());
+		check::<_, Rgb555>(rgb666());
+		check::<_, Rgb666>(rgb555());
+		check::<_, Rgb565>(rgb332());
+		check::<_, Gray8>(rgb888());
+		check::<_, Rgb666>(gray8());
+
+		round_trip::<_, Rgb888>(rgb565());
+		round_trip::<_, Rgb888>(rgb666());
+		round_trip::<_, Rgb565>(rgb555());
+		round_trip::<_, Rgb666>(rgb555());
+		round_trip::<_, Rgb888>(rgb555());
+		round_trip::<_, Rgb565>(rgb332());
+		round_trip::<_, Rgb666>(rgb332());
+		round_trip::<_, Rgb555>(rgb332());
+		round_trip::<_, Rgb888>(rgb332());
+		round_trip::<_, Rgb888>(gray8());
+		round_trip::<_, Rgb666>(rgb565());
+	}
+
+	#[test]
+	fn alpha_matrix() {
+		check::<_, Argb1555>(rgb565());
+		check::<_, Argb1555>(rgb555());
+		check::<_, Argb4444>(rgb332());
+		check::<_, Argb4444>(gray8());
+		check::<_, Argb1555>(argb4444());
+		check::<_, Argb4444>(argb1555());
+
+		check_try::<_, Rgb565>(argb1555());
+		check_try::<_, Rgb555>(argb1555());
+		check_try::<_, Gray8>(argb1555());
+		check_try::<_, Rgb666>(argb4444());
+		check_try::<_, Rgb332>(argb4444());
+		check_try::<_, Rgb888>(argb4444());
+		assert_eq!(Rgb555::try_from(Argb1555::from(Rgb555::from_rgb555(0x1234))), Ok(Rgb555::from_rgb555(0x1234)));
+	}
+
+	#[test]
+	fn rgb666_from_rgb565() {
+		for color in rgb565() {
+			assert_eq!(Rgb666::from(color), Rgb666::from_rgb565(color));
+			assert_eq!(Rgb565::from(Rgb666::from(color)), color);
+		}
+	}
+
+	#[test]
+	fn gray() {
+		assert_eq!(Gray8::from(Rgb888::from_rgb888_components(255, 255, 255)).to_gray8(), 255);
+		assert_eq!(Gray8::from(Rgb565::from_rgb565(0x0000)).to_gray8(), 0);
+		assert!(Gray8::from(Rgb565::from_rgb565(0x07E0)).to_gray8() > Gray8::from(Rgb565::from_rgb565(0xF800)).to_gray8());
+		assert_eq!(Rgb888::from(Gray8::from_gray8(77)).to_rgb888_components(), [77; 3]);
+	}
+}
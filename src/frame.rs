@@ -0,0 +1,1040 @@
+//! Operations over whole frames of raw RGB565 values.
+//!
+//! Frames are plain slices of `u16`s as they come out of
+//! [`Rgb565::to_rgb565`], in whatever order the pixels happen to be in.
+//! Functions that care about the layout of the frame take its stride, which is
+//! the distance in pixels from the start of one row to the start of the next.
+
+use crate::{formats, lut, Converter, PanelProfile, Rgb565};
+
+/// A rectangle of pixels within a frame.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct Rect {
+	/// The left edge, in pixels from the left of the frame.
+	pub x: usize,
+	/// The top edge, in pixels from the top of the frame.
+	pub y: usize,
+	/// The width, in pixels.
+	pub width: usize,
+	/// The height, in pixels.
+	pub height: usize
+}
+
+impl Rect {
+	/// The rectangle `width` by `height` pixels with its top left corner at
+	/// `x`, `y`.
+	#[inline]
+	#[must_use]
+	pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Self { Self { x, y, width, height } }
+}
+
+/// How many pixels are processed per iteration. Working on a few pixels at a
+/// time lets the compiler use wide loads and stores and unroll the loop.
+const CHUNK: usize = 4;
+
+/// Replaces every pixel of `frame` with the result of calling `f` on it.
+///
+/// ```
+/// # use rgb565::{frame, Rgb565};
+/// let mut frame = [0xF800, 0x07E0, 0x001F];
+/// frame::map_in_place(&mut frame, |color| Rgb565::from_rgb565(!color.to_rgb565()));
+/// assert_eq!(frame, [0x07FF, 0xF81F, 0xFFE0]);
+/// ```
+#[inline]
+pub fn map_in_place(frame: &mut [u16], mut f: impl FnMut(Rgb565) -> Rgb565) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	let (chunks, rest) = frame.as_chunks_mut::<CHUNK>();
+
+	for chunk in chunks {
+		*chunk = chunk.map(|pixel| f(Rgb565::from_rgb565(pixel)).to_rgb565());
+	}
+
+	for pixel in rest {
+		*pixel = f(Rgb565::from_rgb565(*pixel)).to_rgb565();
+	}
+}
+
+/// Writes the result of calling `f` on every pixel of `src` to the same pixel
+/// of `dst`. If the frames are different lengths, only as many pixels as fit
+/// in the shorter one are processed.
+#[inline]
+pub fn map_into(src: &[u16], dst: &mut [u16], mut f: impl FnMut(Rgb565) -> Rgb565) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	let len = src.len().min(dst.len());
+	let (src, dst) = (&src[..len], &mut dst[..len]);
+
+	let (src_chunks, src_rest) = src.as_chunks::<CHUNK>();
+	let (dst_chunks, dst_rest) = dst.as_chunks_mut::<CHUNK>();
+
+	for (src, dst) in src_chunks.iter().zip(dst_chunks) {
+		*dst = src.map(|pixel| f(Rgb565::from_rgb565(pixel)).to_rgb565());
+	}
+
+	for (src, dst) in src_rest.iter().zip(dst_rest) {
+		*dst = f(Rgb565::from_rgb565(*src)).to_rgb565();
+	}
+}
+
+/// Swaps the red and blue channels of every pixel of `frame`, converting it
+/// between RGB565 and BGR565.
+///
+/// This works on four pixels at a time with plain bit twiddling rather than
+/// going through `swap_components_lut`, so bulk swaps stay fast even on builds
+/// that leave out that LUT.
+#[inline]
+#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+pub fn swap_components(frame: &mut [u16]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	let (chunks, rest) = frame.as_chunks_mut::<4>();
+
+	for chunk in chunks {
+		let [a, b, c, d] = *chunk;
+		let x4 = lut::transforms::swap_components_x4(a as u64 | (b as u64) << 16 | (c as u64) << 32 | (d as u64) << 48);
+		*chunk = [x4 as u16, (x4 >> 16) as u16, (x4 >> 32) as u16, (x4 >> 48) as u16];
+	}
+
+	for pixel in rest {
+		*pixel = lut::transforms::swap_components(*pixel);
+	}
+}
+
+/// Replaces every pixel of `frame` with its entry in `table`, like the color
+/// lookup table of a display controller. Any per-pixel color adjustment can be
+/// baked into a table once, for example with `Correction`, and then applied
+/// to every frame at close to the speed of a copy.
+///
+/// ```
+/// # use rgb565::frame;
+/// let mut inverted = [0; 65536];
+///
+/// for (i, entry) in inverted.iter_mut().enumerate() {
+///     *entry = !(i as u16);
+/// }
+///
+/// let mut frame = [0xF800, 0x07E0];
+/// frame::apply_lut(&mut frame, &inverted);
+/// assert_eq!(frame, [0x07FF, 0xF81F]);
+/// ```
+#[inline]
+#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+pub fn apply_lut(frame: &mut [u16], table: &[u16; 65536]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	for pixel in frame {
+		*pixel = table[*pixel as usize];
+	}
+}
+
+/// Color adjustments composed into a single table for [`apply_lut`].
+///
+/// Each channel is first raised to the power of `1 / gamma`, then multiplied
+/// by the brightness and by its white balance gain, and finally rounded back
+/// to its 5 or 6 bits. Channels are treated as linear values from 0 to 1.
+///
+/// ```
+/// # use rgb565::frame::{self, Correction};
+/// let table = Correction::new().with_brightness(0.5).with_white_balance([1.0, 1.0, 0.0]).to_table();
+///
+/// let mut frame = [0xFFFF];
+/// frame::apply_lut(&mut frame, &table);
+/// assert_eq!(frame, [0x8400]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Correction {
+	gamma: f32,
+	brightness: f32,
+	white_balance: [f32; 3]
+}
+
+#[cfg(feature = "std")]
+impl Correction {
+	/// A correction that leaves every color as it is.
+	#[must_use]
+	pub const fn new() -> Self { Self { gamma: 1.0, brightness: 1.0, white_balance: [1.0; 3] } }
+
+	/// Raises every channel to the power of `1 / gamma`, so gammas above 1
+	/// brighten the midtones and gammas below 1 darken them.
+	#[must_use]
+	pub const fn with_gamma(mut self, gamma: f32) -> Self {
+		self.gamma = gamma;
+		self
+	}
+
+	/// Multiplies every channel by `brightness`.
+	#[must_use]
+	pub const fn with_brightness(mut self, brightness: f32) -> Self {
+		self.brightness = brightness;
+		self
+	}
+
+	/// Multiplies the red, green and blue channels by their gains.
+	#[must_use]
+	pub const fn with_white_balance(mut self, gains: [f32; 3]) -> Self {
+		self.white_balance = gains;
+		self
+	}
+
+	/// Applies the correction to a single color.
+	#[must_use]
+	pub fn apply(&self, color: Rgb565) -> Rgb565 {
+		let [r, g, b] = color.to_rgb565_components();
+		let adjust = |value: u8, max: f32, gain: f32| ((value as f32 / max).powf(self.gamma.recip()) * self.brightness * gain).clamp(0.0, 1.0).mul_add(max, 0.5) as u8;
+		let [gr, gg, gb] = self.white_balance;
+		Rgb565::from_rgb565_components(adjust(r, 31.0, gr), adjust(g, 63.0, gg), adjust(b, 31.0, gb))
+	}
+
+	/// Fills `table` with the correction of every color, for [`apply_lut`].
+	pub fn fill_table(&self, table: &mut [u16; 65536]) {
+		for (i, entry) in table.iter_mut().enumerate() {
+			*entry = self.apply(Rgb565::from_rgb565(i as u16)).to_rgb565();
+		}
+	}
+
+	/// Returns the correction of every color as a table, for [`apply_lut`].
+	#[must_use]
+	pub fn to_table(&self) -> Box<[u16; 65536]> {
+		let mut table: Box<[u16; 65536]> = vec![0; 65536].into_boxed_slice().try_into().expect("table has 65536 entries");
+		self.fill_table(&mut table);
+		table
+	}
+}
+
+#[cfg(feature = "std")]
+impl Default for Correction {
+	fn default() -> Self { Self::new() }
+}
+
+/// Converts packed RGB888 pixels, three bytes each, to RGB565 with
+/// [`Converter::from_rgb888`]. As many pixels are converted as fit in both
+/// slices.
+///
+/// Where the converter allows it, whole blocks of pixels are converted with
+/// the vectorized kernels of the `simd` feature, which give the same results.
+///
+/// ```
+/// # use rgb565::{frame, Converter};
+/// let mut frame = [0; 2];
+/// frame::convert_rgb888_slice_to_rgb565(&Converter::arithmetic(), &[255, 0, 0, 0, 0, 255], &mut frame);
+/// assert_eq!(frame, [0xF800, 0x001F]);
+/// ```
+#[inline]
+pub fn convert_rgb888_slice_to_rgb565(converter: &Converter, src: &[u8], dst: &mut [u16]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	let (src, dst) = converter.vectorize_from_rgb888(src, dst);
+
+	for (dst, src) in dst.iter_mut().zip(src.as_chunks::<3>().0) {
+		*dst = converter.from_rgb888(*src).to_rgb565();
+	}
+}
+
+/// Converts RGB565 pixels to packed RGB888 with [`Converter::to_rgb888`]. As
+/// many pixels are converted as fit in both slices, vectorized like
+/// [`convert_rgb888_slice_to_rgb565`].
+#[inline]
+pub fn convert_rgb565_slice_to_rgb888(converter: &Converter, src: &[u16], dst: &mut [u8]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	let (src, dst) = converter.vectorize_to_rgb888(src, dst);
+
+	for (dst, src) in dst.as_chunks_mut::<3>().0.iter_mut().zip(src) {
+		*dst = converter.to_rgb888(Rgb565::from_rgb565(*src));
+	}
+}
+
+/// Converts packed 8-bit sRGB pixels, three bytes each, to RGB565 with
+/// [`Converter::from_srgb888`]. As many pixels are converted as fit in both
+/// slices.
+#[cfg(has_srgb_decode)]
+#[inline]
+pub fn convert_srgb888_slice_to_rgb565(converter: &Converter, src: &[u8], dst: &mut [u16]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	for (dst, src) in dst.iter_mut().zip(src.as_chunks::<3>().0) {
+		*dst = converter.from_srgb888(*src).to_rgb565();
+	}
+}
+
+/// Converts RGB565 pixels to packed 8-bit sRGB with
+/// [`Converter::to_srgb888`]. As many pixels are converted as fit in both
+/// slices.
+#[cfg(has_srgb)]
+#[inline]
+pub fn convert_rgb565_slice_to_srgb888(converter: &Converter, src: &[u16], dst: &mut [u8]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	for (dst, src) in dst.as_chunks_mut::<3>().0.iter_mut().zip(src) {
+		*dst = converter.to_srgb888(Rgb565::from_rgb565(*src));
+	}
+}
+
+/// Converts packed 8-bit sRGB pixels, three bytes each, to RGB565 corrected
+/// for a panel with [`PanelProfile::convert_srgb888`]. As many pixels are
+/// converted as fit in both slices.
+///
+/// ```
+/// # use rgb565::{frame, PanelProfile};
+/// // A panel that needs every channel at half the code it would otherwise get.
+/// let half = |max: u16| core::array::from_fn(|s8| (s8 as u16 * max / 255 / 2) as u8);
+/// let profile = PanelProfile::from_tables(half(31), half(63), half(31));
+/// let mut frame = [0; 2];
+/// frame::convert_srgb888_slice_with_profile(&[255, 255, 255, 0, 0, 0], &profile, &mut frame);
+/// assert_eq!(frame, [0x7BEF, 0x0000]);
+/// ```
+#[inline]
+#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+pub fn convert_srgb888_slice_with_profile(src: &[u8], profile: &PanelProfile, dst: &mut [u16]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	for (dst, [r, g, b]) in dst.iter_mut().zip(src.as_chunks::<3>().0) {
+		*dst = profile.convert_srgb888(*r, *g, *b).to_rgb565();
+	}
+}
+
+/// Converts RGBA8888 pixels, four bytes each as most image decoders produce
+/// them, to RGB565 with [`Converter::from_rgb888`], ignoring alpha. As many
+/// pixels are converted as fit in both slices.
+///
+/// ```
+/// # use rgb565::{frame, Converter};
+/// let rgba = [255, 0, 0, 128, 0, 0, 255, 0];
+/// let mut frame = [0; 2];
+/// frame::convert_rgba8888_slice_to_rgb565(&Converter::builtin(), &rgba, &mut frame);
+/// assert_eq!(frame, [0xF800, 0x001F]);
+/// ```
+#[inline]
+pub fn convert_rgba8888_slice_to_rgb565(converter: &Converter, src: &[u8], dst: &mut [u16]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	for (dst, [r, g, b, _]) in dst.iter_mut().zip(src.as_chunks::<4>().0) {
+		*dst = converter.from_rgb888([*r, *g, *b]).to_rgb565();
+	}
+}
+
+/// Converts RGB565 pixels to RGBA8888 with [`Converter::to_rgb888`], with an
+/// alpha of 255. As many pixels are converted as fit in both slices.
+#[inline]
+pub fn convert_rgb565_slice_to_rgba8888(converter: &Converter, src: &[u16], dst: &mut [u8]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	for (dst, src) in dst.as_chunks_mut::<4>().0.iter_mut().zip(src) {
+		let [r, g, b] = converter.to_rgb888(Rgb565::from_rgb565(*src));
+		*dst = [r, g, b, 255];
+	}
+}
+
+/// Expands 8-bit grayscale pixels, as from thermal, time-of-flight or mono
+/// cameras, to RGB565 gray with [`Converter::from_rgb888`]. As many pixels are
+/// converted as fit in both slices.
+///
+/// Only the 256 possible levels go through the converter. Every pixel after
+/// that is a single lookup, as with [`convert_gray8_slice_with_colormap`].
+#[inline]
+pub fn convert_gray8_slice_to_rgb565(converter: &Converter, src: &[u8], dst: &mut [u16]) {
+	let colormap = core::array::from_fn(|level| converter.from_rgb888([level as u8; 3]).to_rgb565());
+	convert_gray8_slice_with_colormap(src, &colormap, dst);
+}
+
+/// Maps 8-bit grayscale pixels to RGB565 through `colormap`, which holds the
+/// color of each level, like the false color palettes of thermal cameras. As
+/// many pixels are converted as fit in both slices.
+///
+/// ```
+/// # use rgb565::{frame, Rgb565};
+/// // Black to red.
+/// let colormap = core::array::from_fn(|level| Rgb565::from_rgb888_components(level as u8, 0, 0).to_rgb565());
+/// let mut frame = [0; 2];
+/// frame::convert_gray8_slice_with_colormap(&[0, 255], &colormap, &mut frame);
+/// assert_eq!(frame, [0x0000, 0xF800]);
+/// ```
+#[inline]
+#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+pub fn convert_gray8_slice_with_colormap(src: &[u8], colormap: &[u16; 256], dst: &mut [u16]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	for (dst, level) in dst.iter_mut().zip(src) {
+		*dst = colormap[*level as usize];
+	}
+}
+
+/// Spreads the luma of the pixels of `frame` evenly over the whole range by
+/// histogram equalization, which brings out detail in low-contrast images like
+/// dim camera feeds.
+///
+/// Each pixel's channels are scaled together to move it to its new luma, so
+/// hues are kept as far as the channels allow, going to 8-bit RGB and back
+/// with `converter`. A frame with only one luma is left alone.
+#[inline]
+pub fn equalize(converter: &Converter, frame: &mut [u16]) {
+	let cumulative = cumulative_histogram(converter, frame);
+	let darkest = cumulative.iter().copied().find(|count| *count != 0).unwrap_or(0);
+	let range = (frame.len() - darkest) as u64;
+
+	if range == 0 {
+		return;
+	}
+
+	// In `u64`, so that `count * 255` can't overflow on 32-bit targets.
+	let mapping = cumulative.map(|count| ((count.saturating_sub(darkest) as u64 * 255 + range / 2) / range) as u8);
+	scale_luma(converter, frame, &mapping);
+}
+
+/// Stretches the luma of the pixels of `frame` so that the darkest `low_pct`
+/// percent become black and the brightest `100 - high_pct` percent become
+/// white, with everything in between spread linearly. This is cheaper than
+/// [`equalize`] and keeps the relative brightness of the rest of the frame.
+///
+/// Each pixel's channels are scaled together, as with [`equalize`]. A frame
+/// whose percentiles land on the same luma is left alone.
+///
+/// ```
+/// # use rgb565::{frame, Converter, Rgb565};
+/// let mut frame: Vec<u16> = (0..100).map(|i| Rgb565::from_rgb888_components(100 + i / 2, 100 + i / 2, 100 + i / 2).to_rgb565()).collect();
+/// frame::auto_levels(&Converter::builtin(), &mut frame, 1.0, 99.0);
+///
+/// let (min, max) = frame::channel_bounds(&frame).unwrap();
+/// assert_eq!(min.to_rgb565(), 0x0000);
+/// assert!(max.to_rgb888_components().iter().all(|channel| *channel >= 240));
+/// ```
+#[inline]
+pub fn auto_levels(converter: &Converter, frame: &mut [u16], low_pct: f32, high_pct: f32) {
+	let cumulative = cumulative_histogram(converter, frame);
+	let percentile = |pct: f32| {
+		let count = (frame.len() as f32 * pct.clamp(0.0, 100.0) / 100.0) as usize;
+		cumulative.iter().position(|total| *total > count).unwrap_or(255) as u32
+	};
+
+	let (low, high) = (percentile(low_pct), percentile(high_pct));
+
+	if high <= low {
+		return;
+	}
+
+	let mapping = core::array::from_fn(|level| ((level as u32).clamp(low, high) - low) * 255 / (high - low)).map(|level| level as u8);
+	scale_luma(converter, frame, &mapping);
+}
+
+/// Binarizes `frame`, which is `width` pixels wide, into `dst` as one bit per
+/// pixel: set for pixels whose luma, from 8-bit RGB as `converter` expands it,
+/// is at least `cutoff`, and clear for the rest. Each row starts on a new byte, with its first pixel in the most
+/// significant bit, so rows take `width.div_ceil(8)` bytes. Rows that don't
+/// fit in `dst` are skipped.
+///
+/// ```
+/// # use rgb565::{frame, Converter};
+/// let frame = [0xFFFF, 0x0000, 0xFFFF, 0x0000, 0x0000, 0xFFFF, 0x0000, 0xFFFF, 0xFFFF];
+/// let mut packed = [0; 2];
+/// frame::threshold(&Converter::builtin(), &frame, 9, 128, &mut packed);
+/// assert_eq!(packed, [0b10100101, 0b10000000]);
+/// ```
+pub fn threshold(converter: &Converter, frame: &[u16], width: usize, cutoff: u8, dst: &mut [u8]) {
+	binarize(frame, width, dst, |_, _, pixel| luma(converter, pixel) >= cutoff);
+}
+
+/// Binarizes `frame`, which is `width` pixels wide, like [`threshold`], but
+/// against the mean luma of the square of pixels within `radius` of each
+/// pixel instead of a fixed cutoff. A pixel's bit is clear if it's more than
+/// `offset` darker than that mean, and set otherwise. This copes with uneven
+/// lighting across the frame, which a fixed cutoff doesn't.
+#[cfg(feature = "std")]
+pub fn threshold_adaptive(converter: &Converter, frame: &[u16], width: usize, radius: usize, offset: u8, dst: &mut [u8]) {
+	let height = frame.len().checked_div(width).unwrap_or(0);
+
+	// Sums of the luma of every pixel above and to the left of each point,
+	// with a row and column of zeros in front. These are `u64`s, since the
+	// sums of frames of more than 16 megapixels wouldn't fit in a `u32`.
+	let mut integral = vec![0u64; (width + 1) * (height + 1)];
+
+	for y in 0..height {
+		let mut row = 0;
+
+		for x in 0..width {
+			row += luma(converter, frame[y * width + x]) as u64;
+			integral[(y + 1) * (width + 1) + x + 1] = integral[y * (width + 1) + x + 1] + row;
+		}
+	}
+
+	binarize(frame, width, dst, |x, y, pixel| {
+		let (x0, y0) = (x.saturating_sub(radius), y.saturating_sub(radius));
+		let (x1, y1) = ((x + radius + 1).min(width), (y + radius + 1).min(height));
+		let at = |x: usize, y: usize| integral[y * (width + 1) + x];
+		let sum = at(x1, y1) + at(x0, y0) - at(x1, y0) - at(x0, y1);
+		let mean = sum / ((x1 - x0) * (y1 - y0)) as u64;
+		luma(converter, pixel) as u64 + offset as u64 >= mean
+	});
+}
+
+/// Packs the result of `set` for every pixel of `frame` into `dst`, as
+/// described in [`threshold`].
+fn binarize(frame: &[u16], width: usize, dst: &mut [u8], mut set: impl FnMut(usize, usize, u16) -> bool) {
+	if width == 0 {
+		return;
+	}
+
+	for (y, (row, packed)) in frame.chunks_exact(width).zip(dst.chunks_exact_mut(width.div_ceil(8))).enumerate() {
+		packed.fill(0);
+
+		for (x, pixel) in row.iter().enumerate() {
+			packed[x / 8] |= (set(x, y, *pixel) as u8) << (7 - x % 8);
+		}
+	}
+}
+
+/// The number of pixels of `frame` with each luma or less.
+fn cumulative_histogram(converter: &Converter, frame: &[u16]) -> [usize; 256] {
+	let mut histogram = [0usize; 256];
+
+	for pixel in frame {
+		histogram[luma(converter, *pixel) as usize] += 1;
+	}
+
+	for level in 1..256 {
+		histogram[level] += histogram[level - 1];
+	}
+
+	histogram
+}
+
+/// The luma of a raw RGB565 pixel, from 8-bit RGB as `converter` expands it.
+#[inline]
+fn luma(converter: &Converter, pixel: u16) -> u8 { formats::luma(converter.to_rgb888(Rgb565::from_rgb565(pixel))) }
+
+/// Moves every pixel of `frame` from its luma to its entry in `mapping` by
+/// scaling its channels.
+fn scale_luma(converter: &Converter, frame: &mut [u16], mapping: &[u8; 256]) {
+	// The gain for each luma, in 256ths.
+	let gains: [u32; 256] = core::array::from_fn(|level| ((mapping[level] as u32) << 8) / (level as u32).max(1));
+
+	map_in_place(frame, |color| {
+		let rgb888 = converter.to_rgb888(color);
+		let gain = gains[formats::luma(rgb888) as usize];
+		converter.from_rgb888(rgb888.map(|channel| ((channel as u32 * gain + 128) >> 8).min(255) as u8))
+	});
+}
+
+/// How [`split_channels_into`] and [`merge_channels`] store each channel.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub enum ChannelDepth {
+	/// As stored in RGB565, 5 bits for red and blue and 6 for green.
+	#[default]
+	Native,
+	/// Expanded to 8 bits, like [`Rgb565::to_rgb888_components`].
+	Expanded
+}
+
+/// Splits the pixels of `frame` into separate red, green and blue planes, one
+/// byte per pixel each, at the given depth. Expanded channels are converted
+/// with `converter`. As many pixels are split as fit in every plane.
+#[inline]
+pub fn split_channels_into(converter: &Converter, frame: &[u16], depth: ChannelDepth, r: &mut [u8], g: &mut [u8], b: &mut [u8]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	for (pixel, ((r, g), b)) in frame.iter().zip(r.iter_mut().zip(g.iter_mut()).zip(b.iter_mut())) {
+		let color = Rgb565::from_rgb565(*pixel);
+
+		[*r, *g, *b] = match depth {
+			ChannelDepth::Native => color.to_rgb565_components(),
+			ChannelDepth::Expanded => converter.to_rgb888(color)
+		};
+	}
+}
+
+/// Splits the pixels of `frame` into separate red, green and blue planes, one
+/// byte per pixel each, at the given depth.
+///
+/// ```
+/// # use rgb565::{frame::{self, ChannelDepth}, Converter};
+/// let (r, g, b) = frame::split_channels(&Converter::builtin(), &[0xF800, 0x07E0], ChannelDepth::Native);
+/// assert_eq!((r, g, b), (vec![31, 0], vec![0, 63], vec![0, 0]));
+/// ```
+#[cfg(feature = "std")]
+#[must_use]
+pub fn split_channels(converter: &Converter, frame: &[u16], depth: ChannelDepth) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+	let (mut r, mut g, mut b) = (vec![0; frame.len()], vec![0; frame.len()], vec![0; frame.len()]);
+	split_channels_into(converter, frame, depth, &mut r, &mut g, &mut b);
+	(r, g, b)
+}
+
+/// Merges separate red, green and blue planes at the given depth back into
+/// `dst`, the inverse of [`split_channels_into`]. Native values that are too
+/// wide for their channel are masked, and expanded ones are converted with
+/// `converter`. As many pixels are merged as fit in
+/// `dst` and are in every plane.
+#[inline]
+pub fn merge_channels(converter: &Converter, r: &[u8], g: &[u8], b: &[u8], depth: ChannelDepth, dst: &mut [u16]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	for (dst, ((r, g), b)) in dst.iter_mut().zip(r.iter().zip(g).zip(b)) {
+		*dst = match depth {
+			ChannelDepth::Native => Rgb565::from_rgb565_components(r & 0b11111, g & 0b111111, b & 0b11111),
+			ChannelDepth::Expanded => converter.from_rgb888([*r, *g, *b])
+		}.to_rgb565();
+	}
+}
+
+/// Folds every pixel of `frame` into an accumulator, starting with `init`.
+///
+/// ```
+/// # use rgb565::frame;
+/// let frame = [0x0001, 0x0002, 0x0003];
+/// assert_eq!(frame::fold(&frame, 0, |sum, color| sum + color.to_rgb565()), 6);
+/// ```
+#[inline]
+pub fn fold<B>(frame: &[u16], init: B, mut f: impl FnMut(B, Rgb565) -> B) -> B {
+	let (chunks, rest) = frame.as_chunks::<CHUNK>();
+	let acc = chunks.iter().fold(init, |acc, chunk| chunk.iter().fold(acc, |acc, pixel| f(acc, Rgb565::from_rgb565(*pixel))));
+	rest.iter().fold(acc, |acc, pixel| f(acc, Rgb565::from_rgb565(*pixel)))
+}
+
+/// Returns the smallest and largest value of each channel in `frame`, packed
+/// into an `Rgb565` each, or `None` if the frame is empty.
+#[must_use]
+pub fn channel_bounds(frame: &[u16]) -> Option<(Rgb565, Rgb565)> {
+	if frame.is_empty() {
+		return None;
+	}
+
+	let (min, max) = fold(frame, ([u8::MAX; 3], [0; 3]), |(mut min, mut max), color| {
+		for (i, channel) in color.to_rgb565_components().into_iter().enumerate() {
+			min[i] = min[i].min(channel);
+			max[i] = max[i].max(channel);
+		}
+
+		(min, max)
+	});
+
+	Some((Rgb565::from_rgb565_components(min[0], min[1], min[2]), Rgb565::from_rgb565_components(max[0], max[1], max[2])))
+}
+
+/// Returns the smallest rectangle containing every pixel of `frame` (which is
+/// `width` pixels wide) that `predicate` returns `true` for, or `None` if there
+/// are no such pixels. This is what you want for cropping a sprite down to its
+/// non-background pixels.
+///
+/// ```
+/// # use rgb565::{frame::{self, Rect}, Rgb565};
+/// let frame = [0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0];
+///
+/// let bounds = frame::bounding_box(&frame, 4, |color| color != Rgb565::default());
+/// assert_eq!(bounds, Some(Rect::new(1, 1, 2, 2)));
+/// ```
+#[must_use]
+pub fn bounding_box(frame: &[u16], width: usize, mut predicate: impl FnMut(Rgb565) -> bool) -> Option<Rect> {
+	if width == 0 {
+		return None;
+	}
+
+	let mut bounds: Option<(usize, usize, usize, usize)> = None;
+
+	for (y, row) in frame.chunks_exact(width).enumerate() {
+		for (x, pixel) in row.iter().enumerate() {
+			if predicate(Rgb565::from_rgb565(*pixel)) {
+				bounds = Some(match bounds {
+					Some((x0, y0, x1, y1)) => (x0.min(x), y0, x1.max(x), y1.max(y)),
+					None => (x, y, x, y)
+				});
+			}
+		}
+	}
+
+	bounds.map(|(x0, y0, x1, y1)| Rect::new(x0, y0, x1 - x0 + 1, y1 - y0 + 1))
+}
+
+/// Returns how many pixels of `frame` are exactly `color`.
+#[must_use]
+pub fn count(frame: &[u16], color: Rgb565) -> usize {
+	let color = color.to_rgb565();
+	fold(frame, 0, |count, pixel| count + (pixel.to_rgb565() == color) as usize)
+}
+
+/// Iterates over the pixels of `rect` within `frame`, row by row, along with
+/// their coordinates within the frame. Rows are `stride` pixels apart. Parts
+/// of `rect` that fall outside the frame are skipped.
+///
+/// ```
+/// # use rgb565::{frame::{self, Rect}, Rgb565};
+/// let mut frame = [0; 12];
+///
+/// for (x, y, pixel) in frame::region_mut(&mut frame, 4, Rect::new(1, 1, 2, 2)) {
+///     *pixel = Rgb565::from_rgb565((y * 4 + x) as u16);
+/// }
+///
+/// assert_eq!(frame, [0, 0, 0, 0, 0, 5, 6, 0, 0, 9, 10, 0]);
+/// ```
+pub fn region_mut(frame: &mut [u16], stride: usize, rect: Rect) -> impl Iterator<Item = (usize, usize, &mut Rgb565)> {
+	let rows = if stride == 0 { 0 } else { rect.height };
+
+	Rgb565::from_rgb565_slice_mut(frame).chunks_mut(stride.max(1)).enumerate().skip(rect.y).take(rows).flat_map(move |(y, row)| {
+		let end = rect.x.saturating_add(rect.width).min(row.len());
+		let start = rect.x.min(end);
+		row[start..end].iter_mut().enumerate().map(move |(i, pixel)| (start + i, y, pixel))
+	})
+}
+
+/// Like [`region_mut`], but for reading.
+pub fn region(frame: &[u16], stride: usize, rect: Rect) -> impl Iterator<Item = (usize, usize, Rgb565)> + '_ {
+	let rows = if stride == 0 { 0 } else { rect.height };
+
+	frame.chunks(stride.max(1)).enumerate().skip(rect.y).take(rows).flat_map(move |(y, row)| {
+		let end = rect.x.saturating_add(rect.width).min(row.len());
+		let start = rect.x.min(end);
+		row[start..end].iter().enumerate().map(move |(i, pixel)| (start + i, y, Rgb565::from_rgb565(*pixel)))
+	})
+}
+
+/// The order in which [`column_major`] visits the pixels of a frame.
+///
+/// By default columns are visited from left to right and each column from top
+/// to bottom, which transposes the frame. Visiting each column from bottom to
+/// top rotates the frame 90 degrees clockwise, and visiting the columns from
+/// right to left rotates it 90 degrees counterclockwise.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct ColumnOrder {
+	/// Visit the columns from right to left instead of left to right.
+	pub right_to_left: bool,
+	/// Visit each column from bottom to top instead of top to bottom.
+	pub bottom_to_top: bool
+}
+
+impl ColumnOrder {
+	/// Transposes the frame.
+	pub const TRANSPOSE: Self = Self { right_to_left: false, bottom_to_top: false };
+	/// Rotates the frame 90 degrees clockwise.
+	pub const CLOCKWISE: Self = Self { right_to_left: false, bottom_to_top: true };
+	/// Rotates the frame 90 degrees counterclockwise.
+	pub const COUNTERCLOCKWISE: Self = Self { right_to_left: true, bottom_to_top: false };
+}
+
+/// Iterates over the pixels of `frame`, which is `width` pixels wide, column
+/// by column instead of row by row. This is the order panel controllers that
+/// are addressed by page and column expect, so pixels can be serialized
+/// straight to the panel without rotating them into another buffer first.
+///
+/// ```
+/// # use rgb565::frame::{self, ColumnOrder};
+/// let frame = [1, 2, 3, 4, 5, 6];
+/// let bytes: Vec<u8> = frame::column_major(&frame, 3, ColumnOrder::CLOCKWISE).flat_map(|color| color.to_rgb565_be()).collect();
+/// assert_eq!(bytes, [0, 4, 0, 1, 0, 5, 0, 2, 0, 6, 0, 3]);
+/// ```
+pub fn column_major(frame: &[u16], width: usize, order: ColumnOrder) -> impl Iterator<Item = Rgb565> + '_ {
+	let height = frame.len().checked_div(width).unwrap_or(0);
+
+	(0..width).flat_map(move |column| {
+		let x = if order.right_to_left { width - 1 - column } else { column };
+
+		(0..height).map(move |row| {
+			let y = if order.bottom_to_top { height - 1 - row } else { row };
+			Rgb565::from_rgb565(frame.get(y * width + x).copied().unwrap_or_default())
+		})
+	})
+}
+
+/// The width and height of the tiles that [`column_major_into`] works in. A
+/// tile of the source and of the destination together take 4 KiB, which fits
+/// comfortably in the L1 cache of anything with one.
+const TILE: usize = 32;
+
+/// Writes the pixels of `src`, which is `width` pixels wide, to `dst` in
+/// column-major order. See [`column_major`].
+///
+/// Rather than sweeping `dst` in order, which would read `src` a column at a
+/// time and miss the cache on nearly every pixel of a large frame, this works
+/// through the frame in square tiles.
+pub fn column_major_into(src: &[u16], width: usize, dst: &mut [u16], order: ColumnOrder) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	let height = src.len().checked_div(width).unwrap_or(0);
+	let src = &src[..width * height];
+
+	for tile_y in (0..height).step_by(TILE) {
+		let rows = tile_y..(tile_y + TILE).min(height);
+
+		for tile_x in (0..width).step_by(TILE) {
+			let columns = tile_x..(tile_x + TILE).min(width);
+
+			for y in rows.clone() {
+				let row = if order.bottom_to_top { height - 1 - y } else { y };
+
+				for (x, pixel) in columns.clone().zip(&src[y * width + columns.start..y * width + columns.end]) {
+					let column = if order.right_to_left { width - 1 - x } else { x };
+
+					if let Some(dst) = dst.get_mut(column * height + row) {
+						*dst = *pixel;
+					}
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{Converter, Rgb565};
+
+	#[test]
+	fn map() {
+		let src: Vec<u16> = (0..=u16::MAX).step_by(7).collect();
+		let swap = |color: Rgb565| Rgb565::from_bgr565(color.to_rgb565());
+
+		let mut in_place = src.clone();
+		super::map_in_place(&mut in_place, swap);
+
+		let mut into = vec![0; src.len() + 3];
+		super::map_into(&src, &mut into, swap);
+
+		for (i, pixel) in src.iter().enumerate() {
+			assert_eq!(in_place[i], swap(Rgb565::from_rgb565(*pixel)).to_rgb565());
+			assert_eq!(into[i], in_place[i]);
+		}
+
+		assert_eq!(into[src.len()..], [0, 0, 0]);
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn correction() {
+		use super::Correction;
+
+		let identity = Correction::new().to_table();
+		assert!(identity.iter().enumerate().all(|(i, entry)| *entry == i as u16));
+
+		let correction = Correction::new().with_gamma(2.2).with_brightness(0.9).with_white_balance([1.0, 0.8, 1.2]);
+		let table = correction.to_table();
+		let mut frame: Vec<u16> = (0..=u16::MAX).step_by(5).collect();
+		let expected: Vec<u16> = frame.iter().map(|pixel| correction.apply(Rgb565::from_rgb565(*pixel)).to_rgb565()).collect();
+		super::apply_lut(&mut frame, &table);
+		assert_eq!(frame, expected);
+
+		// A gamma above 1 brightens the midtones but leaves the ends alone.
+		let brighter = Correction::new().with_gamma(2.0);
+		assert_eq!(brighter.apply(Rgb565::from_rgb565(0xFFFF)).to_rgb565(), 0xFFFF);
+		assert_eq!(brighter.apply(Rgb565::from_rgb565(0x0000)).to_rgb565(), 0x0000);
+		assert_eq!(brighter.apply(Rgb565::from_rgb565_components(8, 16, 8)).to_rgb565_components(), [16, 32, 16]);
+	}
+
+	#[test]
+	fn rgb888() {
+		use crate::convert;
+
+		let src: Vec<u16> = (0..=u16::MAX).collect();
+		let mut expected = vec![0; src.len() * 3];
+		convert::rgb565_to_rgb888(&src, &mut expected);
+
+		for converter in [Converter::builtin(), Converter::arithmetic()] {
+			let mut rgb = vec![0; src.len() * 3 + 2];
+			super::convert_rgb565_slice_to_rgb888(&converter, &src, &mut rgb);
+			assert_eq!((&rgb[..src.len() * 3], &rgb[src.len() * 3..]), (&expected[..], &[0; 2][..]));
+
+			let mut dst = vec![0; src.len()];
+			super::convert_rgb888_slice_to_rgb565(&converter, &rgb, &mut dst);
+			assert_eq!(dst, src);
+		}
+	}
+
+	#[test]
+	fn profile() {
+		use crate::PanelProfile;
+
+		let profile = PanelProfile::from_tables(core::array::from_fn(|i| i as u8), core::array::from_fn(|i| !i as u8), core::array::from_fn(|i| (i / 8) as u8));
+		let src: Vec<u8> = (0..=u8::MAX).cycle().take(256 * 3 * 3 + 2).collect();
+		let mut frame = vec![0xAAAA; src.len() / 3 + 1];
+		super::convert_srgb888_slice_with_profile(&src, &profile, &mut frame);
+
+		for (pixel, [r, g, b]) in frame.iter().zip(src.as_chunks::<3>().0) {
+			assert_eq!(*pixel, profile.convert_srgb888(*r, *g, *b).to_rgb565());
+		}
+
+		assert_eq!(frame[src.len() / 3], 0xAAAA);
+	}
+
+	#[test]
+	fn rgba8888() {
+		let converter = Converter::builtin();
+		let src: Vec<u16> = (0..=u16::MAX).collect();
+		let mut rgba = vec![0; src.len() * 4 + 3];
+		super::convert_rgb565_slice_to_rgba8888(&converter, &src, &mut rgba);
+		assert!(rgba.chunks_exact(4).all(|pixel| pixel[3] == 255));
+		assert_eq!(rgba[src.len() * 4..], [0; 3]);
+
+		rgba.chunks_exact_mut(4).for_each(|pixel| pixel[3] = 0);
+		let mut dst = vec![0; src.len()];
+		super::convert_rgba8888_slice_to_rgb565(&converter, &rgba, &mut dst);
+		assert_eq!(dst, src);
+	}
+
+	#[test]
+	fn gray8() {
+		let src: Vec<u8> = (0..=255).collect();
+		let mut dst = vec![0; 257];
+		super::convert_gray8_slice_to_rgb565(&Converter::builtin(), &src, &mut dst);
+
+		for (level, pixel) in src.iter().zip(&dst) {
+			assert_eq!(*pixel, Rgb565::from_rgb888_components(*level, *level, *level).to_rgb565());
+		}
+
+		assert_eq!(dst[256], 0);
+	}
+
+	#[test]
+	fn equalize() {
+		// A dim, low-contrast gradient of grays.
+		let mut frame: Vec<u16> = (0..64).map(|i| Rgb565::from_rgb888_components(40 + i / 4, 40 + i / 4, 40 + i / 4).to_rgb565()).collect();
+		super::equalize(&Converter::builtin(), &mut frame);
+
+		let (min, max) = super::channel_bounds(&frame).unwrap();
+		assert!(max.to_rgb565_components()[1] - min.to_rgb565_components()[1] >= 56);
+		assert!(frame.windows(2).all(|pair| super::luma(&Converter::builtin(), pair[0]) <= super::luma(&Converter::builtin(), pair[1])));
+
+		let mut uniform = [0x1234; 16];
+		super::equalize(&Converter::builtin(), &mut uniform);
+		assert_eq!(uniform, [0x1234; 16]);
+	}
+
+	#[test]
+	fn auto_levels() {
+		let mut frame: Vec<u16> = (0..64).map(|i| Rgb565::from_rgb888_components(64 + i, 64 + i, 64 + i).to_rgb565()).collect();
+		let before = frame.clone();
+		super::auto_levels(&Converter::builtin(), &mut frame, 0.0, 100.0);
+		assert!(frame.windows(2).all(|pair| super::luma(&Converter::builtin(), pair[0]) <= super::luma(&Converter::builtin(), pair[1])));
+		assert_eq!(frame[0], 0x0000);
+		assert_ne!(frame, before);
+
+		let mut uniform = [0x1234; 16];
+		super::auto_levels(&Converter::builtin(), &mut uniform, 5.0, 95.0);
+		assert_eq!(uniform, [0x1234; 16]);
+	}
+
+	#[test]
+	fn threshold() {
+		let frame: Vec<u16> = (0..20).map(|i| if i % 3 == 0 { 0xFFFF } else { 0x0000 }).collect();
+		let mut packed = [0xAA; 4];
+		super::threshold(&Converter::builtin(), &frame, 10, 1, &mut packed);
+		assert_eq!(packed, [0b10010010, 0b01000000, 0b00100100, 0b10000000]);
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn threshold_adaptive() {
+		// A dark pixel on a gradient that a fixed cutoff can't separate.
+		let mut gradient: Vec<u16> = (0..64).map(|i| Rgb565::from_rgb888_components(i * 4, i * 4, i * 4).to_rgb565()).collect();
+		gradient[40] = 0x0000;
+		let mut packed = [0; 8];
+		super::threshold_adaptive(&Converter::builtin(), &gradient, 64, 3, 8, &mut packed);
+		assert_eq!(packed, [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0b01111111, 0xFF, 0xFF]);
+	}
+
+	#[test]
+	fn channels() {
+		use super::ChannelDepth;
+
+		let frame: Vec<u16> = (0..=u16::MAX).collect();
+
+		for depth in [ChannelDepth::Native, ChannelDepth::Expanded] {
+			let (mut r, mut g, mut b) = (vec![0; frame.len()], vec![0; frame.len()], vec![0; frame.len()]);
+			super::split_channels_into(&Converter::builtin(), &frame, depth, &mut r, &mut g, &mut b);
+			let mut merged = vec![0; frame.len()];
+			super::merge_channels(&Converter::builtin(), &r, &g, &b, depth, &mut merged);
+			assert_eq!(merged, frame);
+		}
+
+		let (mut r, mut g, mut b) = ([0], [0], [0]);
+		super::split_channels_into(&Converter::builtin(), &[0x8410], ChannelDepth::Expanded, &mut r, &mut g, &mut b);
+		assert_eq!([r[0], g[0], b[0]], Rgb565::from_rgb565(0x8410).to_rgb888_components());
+	}
+
+	#[test]
+	fn swap_components() {
+		let src: Vec<u16> = (0..=u16::MAX).collect();
+		let mut frame = src[3..].to_vec();
+		super::swap_components(&mut frame);
+
+		for (src, swapped) in src[3..].iter().zip(frame) {
+			assert_eq!(Rgb565::from_rgb565(*src).to_bgr565(), swapped);
+		}
+	}
+
+	#[test]
+	fn reductions() {
+		let frame = [0x0000, 0xF800, 0x07E0, 0xF800, 0x0841];
+		let (min, max) = super::channel_bounds(&frame).unwrap();
+
+		assert_eq!(min.to_rgb565(), 0x0000);
+		assert_eq!(max.to_rgb565(), 0xFFE1);
+		assert_eq!(super::channel_bounds(&[]), None);
+		assert_eq!(super::count(&frame, Rgb565::from_rgb565(0xF800)), 2);
+		assert_eq!(super::bounding_box(&frame, 2, |color| color.to_rgb565() == 0xF800), Some(super::Rect::new(1, 0, 1, 2)));
+		assert_eq!(super::bounding_box(&frame, 2, |_| false), None);
+	}
+
+	#[test]
+	fn regions() {
+		// 3x3 pixels, with a stride of 4.
+		let mut frame = [0; 11];
+
+		for (x, y, pixel) in super::region_mut(&mut frame, 4, super::Rect::new(1, 1, 5, 5)) {
+			*pixel = Rgb565::from_rgb565_components(x as u8, y as u8, 0);
+		}
+
+		let region: Vec<_> = super::region(&frame, 4, super::Rect::new(0, 1, 3, 3)).map(|(x, y, color)| (x, y, color.to_rgb565_components())).collect();
+
+		assert_eq!(region, [
+			(0, 1, [0, 0, 0]), (1, 1, [1, 1, 0]), (2, 1, [2, 1, 0]),
+			(0, 2, [0, 0, 0]), (1, 2, [1, 2, 0]), (2, 2, [2, 2, 0])
+		]);
+
+		assert_eq!(frame[7], 0b0001100000100000);
+		assert_eq!(super::region_mut(&mut frame, 0, super::Rect::new(0, 0, 1, 1)).count(), 0);
+	}
+
+	#[test]
+	fn column_major() {
+		use super::ColumnOrder;
+
+		let frame = [1, 2, 3, 4, 5, 6];
+		let mut dst = [0; 6];
+
+		super::column_major_into(&frame, 3, &mut dst, ColumnOrder::TRANSPOSE);
+		assert_eq!(dst, [1, 4, 2, 5, 3, 6]);
+		super::column_major_into(&frame, 3, &mut dst, ColumnOrder::CLOCKWISE);
+		assert_eq!(dst, [4, 1, 5, 2, 6, 3]);
+		super::column_major_into(&frame, 3, &mut dst, ColumnOrder::COUNTERCLOCKWISE);
+		assert_eq!(dst, [3, 6, 2, 5, 1, 4]);
+		super::column_major_into(&frame, 3, &mut dst, ColumnOrder { right_to_left: true, bottom_to_top: true });
+		assert_eq!(dst, [6, 3, 5, 2, 4, 1]);
+		assert_eq!(super::column_major(&frame, 0, ColumnOrder::TRANSPOSE).count(), 0);
+	}
+
+	#[test]
+	fn column_major_tiles() {
+		use super::ColumnOrder;
+
+		// Big enough, and odd enough, to leave partial tiles on both edges.
+		let (width, height) = (77, 45);
+		let frame: Vec<u16> = (0..width * height).map(|i| i as u16).collect();
+		let mut dst = vec![0; frame.len()];
+
+		for order in [ColumnOrder::TRANSPOSE, ColumnOrder::CLOCKWISE, ColumnOrder::COUNTERCLOCKWISE] {
+			super::column_major_into(&frame, width, &mut dst, order);
+			assert!(dst.iter().copied().eq(super::column_major(&frame, width, order).map(|color| color.to_rgb565())));
+		}
+	}
+}
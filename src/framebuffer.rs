@@ -0,0 +1,925 @@
+//! A rectangle of RGB565 pixels to draw into and blit from.
+//!
+//! [`Framebuffer565`] wraps any buffer of raw RGB565 values, whether it's a
+//! borrowed slice, an array or a `Vec`, along with its size and stride. Taking
+//! the stride separately lets a framebuffer describe part of a larger frame,
+//! like a window of the display or one sprite of a sheet.
+//!
+//! ```
+//! # use rgb565::{Framebuffer565, Rgb565};
+//! let mut pixels = [0u16; 4 * 3];
+//! let mut frame = Framebuffer565::new(&mut pixels[..], 4, 3).unwrap();
+//! let sprite = Framebuffer565::new([0xFFFF; 4], 2, 2).unwrap();
+//!
+//! frame.blit(&sprite, 3, -1);
+//! assert_eq!(frame.pixel(3, 0), Some(Rgb565::from_rgb565(0xFFFF)));
+//! assert_eq!(frame.pixel(3, 1), Some(Rgb565::from_rgb565(0x0000)));
+//! ```
+
+use crate::dither::{self, ThresholdMap};
+use crate::frame::Rect;
+use crate::Rgb565;
+
+/// A `width` by `height` rectangle of raw RGB565 pixels, stored row by row in
+/// a buffer with rows `stride` pixels apart.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Framebuffer565<B> {
+	buffer: B,
+	width: usize,
+	height: usize,
+	stride: usize
+}
+
+/// Which pixels of a source to draw, and how much of each.
+///
+/// Masks are the same size as whatever they mask.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Mask<'a> {
+	/// One bit per pixel, set for pixels that are drawn. Each row starts on a
+	/// new byte, with its first pixel in the most significant bit, as
+	/// [`frame::threshold`](crate::frame::threshold) produces them.
+	Bits(&'a [u8]),
+	/// One byte of coverage per pixel, from 0 for pixels that aren't drawn to
+	/// 255 for pixels that are drawn opaquely, blending in between.
+	Coverage(&'a [u8]),
+	/// One byte of coverage per subpixel, three per pixel, for panels with
+	/// red, green and blue subpixels side by side in that order. This is what
+	/// font rasterizers produce when rendering at three times the horizontal
+	/// resolution. Each channel is blended by its own coverage, after
+	/// filtering across neighboring subpixels to keep colored fringes down.
+	Subpixel(&'a [u8])
+}
+
+/// The weights of the filter `Mask::Subpixel` applies, in 256ths, centered on
+/// each subpixel. These are FreeType's default LCD filter.
+const SUBPIXEL_FILTER: [u32; 5] = [8, 77, 86, 77, 8];
+
+/// How far the fixed borders of a nine-patch extend into its source, in
+/// pixels from each edge.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct NinePatch {
+	/// The width of the left border, in pixels from the left edge.
+	pub left: usize,
+	/// The height of the top border, in pixels from the top edge.
+	pub top: usize,
+	/// The width of the right border, in pixels from the right edge.
+	pub right: usize,
+	/// The height of the bottom border, in pixels from the bottom edge.
+	pub bottom: usize
+}
+
+impl NinePatch {
+	/// Borders of `left`, `top`, `right` and `bottom` pixels, each measured
+	/// in from that edge of the source.
+	#[inline]
+	#[must_use]
+	pub const fn new(left: usize, top: usize, right: usize, bottom: usize) -> Self { Self { left, top, right, bottom } }
+
+	/// Borders of `inset` pixels on every side.
+	#[inline]
+	#[must_use]
+	pub const fn uniform(inset: usize) -> Self { Self::new(inset, inset, inset, inset) }
+}
+
+/// How the edges and center of a nine-patch fill the space between its
+/// corners.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub enum PatchFill {
+	/// Scaled to fit, by repeating or skipping pixels.
+	#[default]
+	Stretch,
+	/// Repeated from the start as many times as fit.
+	Tile
+}
+
+/// Which way a gradient runs.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub enum Direction {
+	/// From the left edge to the right edge.
+	#[default]
+	Horizontal,
+	/// From the top edge to the bottom edge.
+	Vertical
+}
+
+impl PatchFill {
+	/// Maps a coordinate `d` along an axis of the destination, which is `len`
+	/// pixels long, to a coordinate of the source, which is `src_len` pixels
+	/// long with borders of `start` and `end` pixels.
+	#[inline]
+	fn map(self, d: usize, len: usize, src_len: usize, start: usize, end: usize) -> usize {
+		if d < start {
+			return d;
+		}
+
+		if d + end >= len {
+			return src_len.saturating_sub(len - d);
+		}
+
+		let (middle, src_middle) = (len - start - end, src_len.saturating_sub(start + end).max(1));
+
+		start + match self {
+			Self::Stretch => (d - start) * src_middle / middle,
+			Self::Tile => (d - start) % src_middle
+		}
+	}
+}
+
+impl Mask<'_> {
+	/// The coverage of each channel of the pixel at `x`, `y` of a mask `width`
+	/// pixels wide, or 0 if the mask doesn't cover it.
+	#[inline]
+	fn channels(&self, width: usize, x: usize, y: usize) -> [u8; 3] {
+		match *self {
+			Self::Bits(bits) => match bits.get(y * width.div_ceil(8) + x / 8) {
+				Some(byte) if byte & 0x80 >> (x % 8) != 0 => [255; 3],
+				_ => [0; 3]
+			},
+			Self::Coverage(coverage) => [coverage.get(y * width + x).copied().unwrap_or(0); 3],
+			Self::Subpixel(coverage) => {
+				let row = coverage.get(y * width * 3..(y + 1) * width * 3).unwrap_or_default();
+
+				[0, 1, 2].map(|channel| {
+					let center = x * 3 + channel;
+					let sum = SUBPIXEL_FILTER.iter().enumerate().map(|(i, weight)| {
+						let subpixel = (center + i).checked_sub(2).and_then(|i| row.get(i));
+						weight * subpixel.copied().unwrap_or(0) as u32
+					});
+
+					((sum.sum::<u32>() + 128) >> 8) as u8
+				})
+			}
+		}
+	}
+}
+
+impl<B: AsRef<[u16]>> Framebuffer565<B> {
+	/// Wraps `buffer` as a framebuffer with rows `width` pixels apart, or
+	/// returns `None` if the buffer is too short.
+	#[must_use]
+	pub fn new(buffer: B, width: usize, height: usize) -> Option<Self> { Self::with_stride(buffer, width, height, width) }
+
+	/// Wraps `buffer` as a framebuffer with rows `stride` pixels apart, or
+	/// returns `None` if the buffer is too short or `stride` is less than
+	/// `width`.
+	#[must_use]
+	pub fn with_stride(buffer: B, width: usize, height: usize, stride: usize) -> Option<Self> {
+		let len = match height {
+			0 => 0,
+			_ => stride.checked_mul(height - 1)?.checked_add(width)?
+		};
+
+		(stride >= width && buffer.as_ref().len() >= len).then_some(Self { buffer, width, height, stride })
+	}
+
+	/// The width of the framebuffer, in pixels.
+	#[inline]
+	#[must_use]
+	pub fn width(&self) -> usize { self.width }
+
+	/// The height of the framebuffer, in pixels.
+	#[inline]
+	#[must_use]
+	pub fn height(&self) -> usize { self.height }
+
+	/// The distance in pixels from the start of one row to the start of the
+	/// next.
+	#[inline]
+	#[must_use]
+	pub fn stride(&self) -> usize { self.stride }
+
+	/// Returns the buffer the framebuffer wraps.
+	#[inline]
+	#[must_use]
+	pub fn into_inner(self) -> B { self.buffer }
+
+	/// The pixels of row `y`, or `None` if it's out of bounds.
+	#[inline]
+	#[must_use]
+	pub fn row(&self, y: usize) -> Option<&[u16]> {
+		if y >= self.height {
+			return None;
+		}
+
+		self.buffer.as_ref().get(y * self.stride..y * self.stride + self.width)
+	}
+
+	/// The pixel at `x`, `y`, or `None` if it's out of bounds.
+	#[inline]
+	#[must_use]
+	pub fn pixel(&self, x: usize, y: usize) -> Option<Rgb565> { self.row(y)?.get(x).copied().map(Rgb565::from_rgb565) }
+
+	/// Borrows the part of the framebuffer covered by `rect` as a framebuffer
+	/// of its own, or returns `None` if `rect` doesn't fit inside it.
+	///
+	/// ```
+	/// # use rgb565::{frame::Rect, Framebuffer565};
+	/// let frame = Framebuffer565::new([1, 2, 3, 4, 5, 6], 3, 2).unwrap();
+	/// let view = frame.view(Rect::new(1, 0, 2, 2)).unwrap();
+	/// assert_eq!((view.row(0), view.row(1)), (Some(&[2, 3][..]), Some(&[5, 6][..])));
+	/// assert!(frame.view(Rect::new(2, 0, 2, 2)).is_none());
+	/// ```
+	#[must_use]
+	pub fn view(&self, rect: Rect) -> Option<Framebuffer565<&[u16]>> {
+		if rect.x.checked_add(rect.width)? > self.width || rect.y.checked_add(rect.height)? > self.height {
+			return None;
+		}
+
+		let buffer = self.buffer.as_ref().get(rect.y * self.stride + rect.x..).unwrap_or_default();
+		Framebuffer565::with_stride(buffer, rect.width, rect.height, self.stride)
+	}
+}
+
+impl<B: AsRef<[u16]> + AsMut<[u16]>> Framebuffer565<B> {
+	/// The pixels of row `y`, or `None` if it's out of bounds.
+	#[inline]
+	#[must_use]
+	pub fn row_mut(&mut self, y: usize) -> Option<&mut [u16]> {
+		if y >= self.height {
+			return None;
+		}
+
+		self.buffer.as_mut().get_mut(y * self.stride..y * self.stride + self.width)
+	}
+
+	/// Sets the pixel at `x`, `y`, if it's in bounds.
+	#[inline]
+	pub fn set_pixel(&mut self, x: usize, y: usize, color: Rgb565) {
+		if let Some(pixel) = self.row_mut(y).and_then(|row| row.get_mut(x)) {
+			*pixel = color.to_rgb565();
+		}
+	}
+
+	/// Blends `color` over the pixel at `x`, `y` by `coverage`, if it's in
+	/// bounds. See [`blend_coverage`].
+	#[inline]
+	pub fn blend_pixel(&mut self, x: usize, y: usize, color: Rgb565, coverage: u8) {
+		if let Some(pixel) = self.row_mut(y).and_then(|row| row.get_mut(x)) {
+			*pixel = blend_coverage(Rgb565::from_rgb565(*pixel), color, coverage).to_rgb565();
+		}
+	}
+
+	/// Fills the whole framebuffer with `color`.
+	#[inline]
+	pub fn fill(&mut self, color: Rgb565) { self.fill_rect(Rect::new(0, 0, self.width, self.height), color); }
+
+	/// Fills the part of `rect` that's in bounds with `color`.
+	pub fn fill_rect(&mut self, rect: Rect, color: Rgb565) {
+		let right = rect.x.saturating_add(rect.width).min(self.width);
+
+		for y in rect.y..rect.y.saturating_add(rect.height).min(self.height) {
+			if let Some(row) = self.row_mut(y).and_then(|row| row.get_mut(rect.x..right)) {
+				row.fill(color.to_rgb565());
+			}
+		}
+	}
+
+	/// Draws a horizontal line `width` pixels long, starting at `x`, `y` and
+	/// going right. Each row is filled as one slice, so this is much faster
+	/// than setting the pixels one at a time.
+	#[inline]
+	pub fn hline(&mut self, x: i32, y: i32, width: usize, color: Rgb565) {
+		if let (Ok(y), (x, width)) = (usize::try_from(y), clip(x, width)) {
+			self.fill_rect(Rect::new(x, y, width, 1), color);
+		}
+	}
+
+	/// Draws a vertical line `height` pixels long, starting at `x`, `y` and
+	/// going down.
+	#[inline]
+	pub fn vline(&mut self, x: i32, y: i32, height: usize, color: Rgb565) {
+		if let (Ok(x), (y, height)) = (usize::try_from(x), clip(y, height)) {
+			self.fill_rect(Rect::new(x, y, 1, height), color);
+		}
+	}
+
+	/// Draws a line from `x0`, `y0` to `x1`, `y1`, including both ends, with
+	/// Bresenham's algorithm. Any part of it outside the framebuffer is
+	/// clipped.
+	///
+	/// ```
+	/// # use rgb565::{Framebuffer565, Rgb565};
+	/// let mut frame = Framebuffer565::new([0u16; 4 * 2], 4, 2).unwrap();
+	/// frame.line(0, 0, 3, 1, Rgb565::from_rgb565(1));
+	/// assert_eq!(frame.into_inner(), [1, 1, 0, 0, 0, 0, 1, 1]);
+	/// ```
+	pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Rgb565) {
+		if y0 == y1 {
+			return self.hline(x0.min(x1), y0, (x0.abs_diff(x1) as usize).saturating_add(1), color);
+		}
+
+		if x0 == x1 {
+			return self.vline(x0, y0.min(y1), (y0.abs_diff(y1) as usize).saturating_add(1), color);
+		}
+
+		let (dx, dy) = (x0.abs_diff(x1) as i64, -(y0.abs_diff(y1) as i64));
+		let (step_x, step_y) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+		let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+
+		loop {
+			if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
+				self.set_pixel(x, y, color);
+			}
+
+			if x == x1 && y == y1 {
+				break;
+			}
+
+			let doubled = error * 2;
+
+			if doubled >= dy {
+				error += dy;
+				x += step_x;
+			}
+
+			if doubled <= dx {
+				error += dx;
+				y += step_y;
+			}
+		}
+	}
+
+	/// Draws the outline of `rect`, one pixel thick and inside its bounds.
+	///
+	/// ```
+	/// # use rgb565::{frame::Rect, Framebuffer565, Rgb565};
+	/// let mut frame = Framebuffer565::new([0u16; 3 * 3], 3, 3).unwrap();
+	/// frame.stroke_rect(Rect::new(0, 0, 3, 3), Rgb565::from_rgb565(1));
+	/// assert_eq!(frame.into_inner(), [1, 1, 1, 1, 0, 1, 1, 1, 1]);
+	/// ```
+	pub fn stroke_rect(&mut self, rect: Rect, color: Rgb565) {
+		if rect.width == 0 || rect.height == 0 {
+			return;
+		}
+
+		let (right, bottom) = (rect.x.saturating_add(rect.width - 1), rect.y.saturating_add(rect.height - 1));
+		self.fill_rect(Rect::new(rect.x, rect.y, rect.width, 1), color);
+		self.fill_rect(Rect::new(rect.x, bottom, rect.width, 1), color);
+		self.fill_rect(Rect::new(rect.x, rect.y, 1, rect.height), color);
+		self.fill_rect(Rect::new(right, rect.y, 1, rect.height), color);
+	}
+
+	/// Fills a circle of `radius` pixels around the center of the pixel at
+	/// `cx`, `cy`, with its edge anti-aliased by blending.
+	///
+	/// ```
+	/// # use rgb565::{Framebuffer565, Rgb565};
+	/// let mut frame = Framebuffer565::new([0u16; 9 * 9], 9, 9).unwrap();
+	/// frame.fill_circle(4, 4, 4, Rgb565::from_rgb565(0xFFFF));
+	/// assert_eq!(frame.pixel(4, 4), Some(Rgb565::from_rgb565(0xFFFF)));
+	/// assert_eq!(frame.pixel(0, 0), Some(Rgb565::from_rgb565(0x0000)));
+	/// ```
+	pub fn fill_circle(&mut self, cx: i32, cy: i32, radius: u32, color: Rgb565) {
+		let (x, y) = (cx.saturating_sub_unsigned(radius), cy.saturating_sub_unsigned(radius));
+		let size = radius as usize * 2 + 1;
+		let extent = 1 + 4 * radius as i64;
+		self.fill_rounded((x, y), size, size, [1, 1, extent, extent], radius as i64, color);
+	}
+
+	/// Fills `rect` with its corners rounded off to `radius` pixels, with
+	/// their edges anti-aliased by blending. The radius is limited to half of
+	/// the shorter side, which makes a pill shape.
+	///
+	/// ```
+	/// # use rgb565::{frame::Rect, Framebuffer565, Rgb565};
+	/// let mut frame = Framebuffer565::new([0u16; 8 * 8], 8, 8).unwrap();
+	/// frame.fill_rounded_rect(Rect::new(0, 0, 8, 8), 3, Rgb565::from_rgb565(0xFFFF));
+	/// assert_eq!(frame.pixel(0, 4), Some(Rgb565::from_rgb565(0xFFFF)));
+	/// assert_eq!(frame.pixel(0, 0), Some(Rgb565::from_rgb565(0x0000)));
+	/// ```
+	pub fn fill_rounded_rect(&mut self, rect: Rect, radius: usize, color: Rgb565) {
+		let (x, y) = (i32::try_from(rect.x).unwrap_or(i32::MAX), i32::try_from(rect.y).unwrap_or(i32::MAX));
+		let radius = radius.min(rect.width.min(rect.height) / 2) as i64;
+		let bounds = [0, 0, 2 * rect.width as i64, 2 * rect.height as i64];
+		self.fill_rounded((x, y), rect.width, rect.height, bounds, radius, color);
+	}
+
+	/// Fills a rounded rectangle within the `width` by `height` pixels at `x`,
+	/// `y`. Its left, top, right and bottom edges are given by `bounds` in
+	/// half pixels from `x`, `y`, so that shapes can be centered on pixels as
+	/// well as between them.
+	///
+	/// Coverage is `radius - distance + 1/2`, the distance being from the
+	/// center of a pixel to the nearest point on the inside of the corners.
+	/// That's linear in the squared distance across the one pixel wide edge,
+	/// so it's computed from that instead, without square roots.
+	fn fill_rounded(&mut self, (x, y): (i32, i32), width: usize, height: usize, bounds: [i64; 4], radius: i64, color: Rgb565) {
+		let [left, top, right, bottom] = bounds;
+		let corner = |position: usize, start: i64, end: i64| {
+			let center = 2 * position as i64 + 1;
+			(start + 2 * radius - center).max(center - (end - 2 * radius)).max(0)
+		};
+
+		let outer = (2 * radius + 1).pow(2);
+
+		self.draw(width, height, x, y, |sx, sy, dst| {
+			let (dx, dy) = (corner(sx, left, right), corner(sy, top, bottom));
+			let coverage = ((outer - dx * dx - dy * dy) * 255 / (8 * radius).max(1)).clamp(0, 255);
+			Some(blend_coverage(dst, color, coverage as u8))
+		});
+	}
+
+	/// Copies `src` with its top left corner at `x`, `y`. Any part of it that
+	/// falls outside the framebuffer is clipped.
+	pub fn blit(&mut self, src: &Framebuffer565<impl AsRef<[u16]>>, x: i32, y: i32) {
+		self.draw(src.width, src.height, x, y, |sx, sy, _| src.pixel(sx, sy));
+	}
+
+	/// Copies the pixels of `src` that `mask` draws, which is the same size as
+	/// `src`, with its top left corner at `x`, `y`. Pixels with partial
+	/// coverage are blended with the framebuffer. Any part of `src` that
+	/// falls outside the framebuffer is clipped.
+	pub fn blit_masked(&mut self, src: &Framebuffer565<impl AsRef<[u16]>>, mask: Mask, x: i32, y: i32) {
+		self.draw(src.width, src.height, x, y, |sx, sy, dst| Some(blend_subpixel(dst, src.pixel(sx, sy)?, mask.channels(src.width, sx, sy))));
+	}
+
+	/// Fills `rect` with copies of `tile`, starting from its top left corner.
+	/// Copies that run past the edges of `rect` are cut off, as is any part of
+	/// `rect` outside the framebuffer. An empty `tile` draws nothing.
+	///
+	/// ```
+	/// # use rgb565::{frame::Rect, Framebuffer565};
+	/// let stripes = Framebuffer565::new([1, 2], 2, 1).unwrap();
+	/// let mut frame = Framebuffer565::new([0u16; 5 * 2], 5, 2).unwrap();
+	/// frame.fill_pattern(Rect::new(1, 0, 3, 2), &stripes);
+	/// assert_eq!(frame.into_inner(), [0, 1, 2, 1, 0, 0, 1, 2, 1, 0]);
+	/// ```
+	pub fn fill_pattern(&mut self, rect: Rect, tile: &Framebuffer565<impl AsRef<[u16]>>) {
+		if tile.width == 0 || tile.height == 0 {
+			return;
+		}
+
+		let (x, y) = (i32::try_from(rect.x).unwrap_or(i32::MAX), i32::try_from(rect.y).unwrap_or(i32::MAX));
+		self.draw(rect.width, rect.height, x, y, |dx, dy, _| tile.pixel(dx % tile.width, dy % tile.height));
+	}
+
+	/// Fills `rect` with a gradient from `from` at one edge to `to` at the
+	/// other, in `direction`. Colors are interpolated in linear light with 8
+	/// bits per channel, then quantized to RGB565 through `dither` if it's
+	/// given, which hides the steps between levels that long gradients
+	/// otherwise show as bands. The threshold map is anchored to the
+	/// framebuffer rather than `rect`, so neighboring gradients line up.
+	///
+	/// ```
+	/// # use rgb565::{dither::ThresholdMap, framebuffer::Direction, frame::Rect, Framebuffer565, Rgb565};
+	/// let mut frame = Framebuffer565::new([0u16; 64], 64, 1).unwrap();
+	/// let (black, white) = (Rgb565::from_rgb565(0x0000), Rgb565::from_rgb565(0xFFFF));
+	/// frame.fill_gradient(Rect::new(0, 0, 64, 1), black, white, Direction::Horizontal, Some(&ThresholdMap::BAYER_4));
+	/// assert_eq!(frame.pixel(0, 0), Some(black));
+	/// assert_eq!(frame.pixel(63, 0), Some(white));
+	/// ```
+	pub fn fill_gradient(&mut self, rect: Rect, from: Rgb565, to: Rgb565, direction: Direction, dither: Option<&ThresholdMap>) {
+		let (from, to) = (from.to_rgb888_components(), to.to_rgb888_components());
+		let (x, y) = (i32::try_from(rect.x).unwrap_or(i32::MAX), i32::try_from(rect.y).unwrap_or(i32::MAX));
+		let steps = match direction {
+			Direction::Horizontal => rect.width,
+			Direction::Vertical => rect.height
+		}
+		.saturating_sub(1)
+		.max(1) as u64;
+
+		self.draw(rect.width, rect.height, x, y, |dx, dy, _| {
+			let step = match direction {
+				Direction::Horizontal => dx,
+				Direction::Vertical => dy
+			} as u64;
+
+			let color = [0, 1, 2].map(|c| ((from[c] as u64 * (steps - step) + to[c] as u64 * step + steps / 2) / steps) as u8);
+
+			Some(match dither {
+				Some(map) => dither::ordered(map, rect.x + dx, rect.y + dy, color),
+				None => Rgb565::from_rgb888_components(color[0], color[1], color[2])
+			})
+		});
+	}
+
+	/// Draws `src` as a nine-patch filling `rect`. The corners of `src`, as set
+	/// by `patch`, are copied as they are, while its edges and center fill the
+	/// space between them according to `fill`. Any part of `rect` outside the
+	/// framebuffer is clipped.
+	///
+	/// ```
+	/// # use rgb565::{framebuffer::{NinePatch, PatchFill}, frame::Rect, Framebuffer565};
+	/// let panel = Framebuffer565::new([1, 2, 3, 4, 5, 6, 7, 8, 9], 3, 3).unwrap();
+	/// let mut frame = Framebuffer565::new([0u16; 5 * 3], 5, 3).unwrap();
+	/// frame.blit_nine_patch(&panel, NinePatch::uniform(1), PatchFill::Stretch, Rect::new(0, 0, 5, 3));
+	/// assert_eq!(frame.into_inner(), [1, 2, 2, 2, 3, 4, 5, 5, 5, 6, 7, 8, 8, 8, 9]);
+	/// ```
+	pub fn blit_nine_patch(&mut self, src: &Framebuffer565<impl AsRef<[u16]>>, patch: NinePatch, fill: PatchFill, rect: Rect) {
+		let (x, y) = (i32::try_from(rect.x).unwrap_or(i32::MAX), i32::try_from(rect.y).unwrap_or(i32::MAX));
+
+		self.draw(rect.width, rect.height, x, y, |dx, dy, _| {
+			let sx = fill.map(dx, rect.width, src.width, patch.left, patch.right);
+			let sy = fill.map(dy, rect.height, src.height, patch.top, patch.bottom);
+			src.pixel(sx, sy)
+		});
+	}
+
+	/// Draws `color` through `mask`, which is `width` by `height` pixels, with
+	/// its top left corner at `x`, `y`. This is how glyphs and icons stored as
+	/// bare masks are drawn.
+	///
+	/// ```
+	/// # use rgb565::{framebuffer::Mask, Framebuffer565, Rgb565};
+	/// let mut frame = Framebuffer565::new([0u16; 8], 4, 2).unwrap();
+	/// frame.fill_masked(Rgb565::from_rgb565(0xFFFF), Mask::Bits(&[0b1001_0000, 0b0110_0000]), 4, 2, 0, 0);
+	/// assert_eq!(frame.into_inner(), [0xFFFF, 0, 0, 0xFFFF, 0, 0xFFFF, 0xFFFF, 0]);
+	/// ```
+	pub fn fill_masked(&mut self, color: Rgb565, mask: Mask, width: usize, height: usize, x: i32, y: i32) {
+		self.draw(width, height, x, y, |sx, sy, dst| Some(blend_subpixel(dst, color, mask.channels(width, sx, sy))));
+	}
+
+	/// Replaces the pixels of a `width` by `height` rectangle with its top left
+	/// corner at `x`, `y` with the result of `pixel`, which gets the
+	/// coordinates within the rectangle and the current color. Pixels it
+	/// returns `None` for are left alone, as is any part of the rectangle that
+	/// falls outside the framebuffer.
+	fn draw(&mut self, width: usize, height: usize, x: i32, y: i32, mut pixel: impl FnMut(usize, usize, Rgb565) -> Option<Rgb565>) {
+		let (left, top) = ((x as isize).unsigned_abs(), (y as isize).unsigned_abs());
+
+		// The part of the rectangle that's within the framebuffer, in the
+		// rectangle's own coordinates.
+		let (sx0, dx0) = if x < 0 { (left, 0) } else { (0, left) };
+		let (sy0, dy0) = if y < 0 { (top, 0) } else { (0, top) };
+		let columns = width.saturating_sub(sx0).min(self.width.saturating_sub(dx0));
+		let rows = height.saturating_sub(sy0).min(self.height.saturating_sub(dy0));
+
+		for row in 0..rows {
+			let Some(dst) = self.row_mut(dy0 + row) else { break };
+
+			for (column, dst) in dst[dx0..dx0 + columns].iter_mut().enumerate() {
+				if let Some(color) = pixel(sx0 + column, sy0 + row, Rgb565::from_rgb565(*dst)) {
+					*dst = color.to_rgb565();
+				}
+			}
+		}
+	}
+}
+
+/// Scales `src` up by the largest whole number that fits in `dst` and centers
+/// it, filling the bars left on either side with `fill`. This is the usual way
+/// to show the output of a game on a panel with a different size or aspect
+/// ratio, keeping pixels square and sharp. A `src` that doesn't fit even
+/// without scaling is shown at its original size, cut off evenly on each side.
+///
+/// Returns the part of `dst` the content went into, for mapping touches back
+/// to it.
+///
+/// ```
+/// # use rgb565::{framebuffer, frame::Rect, Framebuffer565, Rgb565};
+/// let src = Framebuffer565::new([1, 2, 3, 4], 2, 2).unwrap();
+/// let mut dst = Framebuffer565::new([0u16; 6 * 4], 6, 4).unwrap();
+/// let content = framebuffer::present_scaled(&src, &mut dst, Rgb565::from_rgb565(9));
+/// assert_eq!(content, Rect::new(1, 0, 4, 4));
+/// assert_eq!(dst.into_inner(), [
+///     9, 1, 1, 2, 2, 9,
+///     9, 1, 1, 2, 2, 9,
+///     9, 3, 3, 4, 4, 9,
+///     9, 3, 3, 4, 4, 9
+/// ]);
+/// ```
+pub fn present_scaled(src: &Framebuffer565<impl AsRef<[u16]>>, dst: &mut Framebuffer565<impl AsRef<[u16]> + AsMut<[u16]>>, fill: Rgb565) -> Rect {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	let scale = (dst.width / src.width.max(1)).min(dst.height / src.height.max(1)).max(1);
+
+	// Where the scaled content starts in `dst`, how much of it is cut off
+	// before that, and how much of it is left.
+	let center = |len: usize, dst_len: usize| match dst_len.checked_sub(len) {
+		Some(space) => (space / 2, 0, len),
+		None => (0, (len - dst_len) / 2, dst_len)
+	};
+
+	let (x, skip_x, width) = center(src.width * scale, dst.width);
+	let (y, skip_y, height) = center(src.height * scale, dst.height);
+	let (dst_width, dst_height) = (dst.width, dst.height);
+
+	dst.fill_rect(Rect::new(0, 0, dst_width, y), fill);
+	dst.fill_rect(Rect::new(0, y + height, dst_width, dst_height - y - height), fill);
+	dst.fill_rect(Rect::new(0, y, x, height), fill);
+	dst.fill_rect(Rect::new(x + width, y, dst_width - x - width, height), fill);
+
+	let stride = dst.stride;
+	let buffer = dst.buffer.as_mut();
+
+	for row in 0..height {
+		let (sy, start) = ((skip_y + row) / scale, (y + row) * stride + x);
+
+		// Each row of `src` is scaled once, then copied for the rest of the
+		// rows it covers.
+		if row > 0 && (skip_y + row - 1) / scale == sy {
+			buffer.copy_within(start - stride..start - stride + width, start);
+			continue;
+		}
+
+		let Some(src_row) = src.row(sy) else { break };
+
+		for (column, dst) in buffer[start..start + width].iter_mut().enumerate() {
+			*dst = src_row[(skip_x + column) / scale];
+		}
+	}
+
+	Rect::new(x, y, width, height)
+}
+
+/// Clips a span `len` pixels long starting at `start` to the part at or after
+/// 0, returning its new start and length.
+#[inline]
+fn clip(start: i32, len: usize) -> (usize, usize) {
+	match usize::try_from(start) {
+		Ok(start) => (start, len),
+		Err(_) => (0, len.saturating_sub(start.unsigned_abs() as usize))
+	}
+}
+
+/// Blends `color` over `dst` by `coverage`, from 0 for all of `dst` to 255 for
+/// all of `color`. This is how the anti-aliased shapes and masks are drawn,
+/// and works just as well for compositing the coverage of a glyph from a font
+/// rasterizer.
+///
+/// RGB565 is linear, so this interpolates the channels directly, rounding to
+/// the nearest level.
+///
+/// ```
+/// # use rgb565::{framebuffer::blend_coverage, Rgb565};
+/// let (black, white) = (Rgb565::from_rgb565(0x0000), Rgb565::from_rgb565(0xFFFF));
+/// assert_eq!(blend_coverage(black, white, 255), white);
+/// assert_eq!(blend_coverage(black, white, 128).to_rgb565_components(), [16, 32, 16]);
+/// ```
+#[inline]
+#[must_use]
+pub fn blend_coverage(dst: Rgb565, color: Rgb565, coverage: u8) -> Rgb565 {
+	match coverage {
+		0 => dst,
+		255 => color,
+		_ => blend_subpixel(dst, color, [coverage; 3])
+	}
+}
+
+/// Blends `color` over `dst` like [`blend_coverage`], but with a separate
+/// coverage for each channel, as subpixel rendering produces.
+#[inline]
+#[must_use]
+pub fn blend_subpixel(dst: Rgb565, color: Rgb565, coverage: [u8; 3]) -> Rgb565 {
+	let (c, d) = (color.to_rgb565_components(), dst.to_rgb565_components());
+	let [r, g, b] = [0, 1, 2].map(|i| {
+		let a = coverage[i] as u16;
+		((c[i] as u16 * a + d[i] as u16 * (255 - a) + 127) / 255) as u8
+	});
+
+	Rgb565::from_rgb565_components(r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Direction, Framebuffer565, Mask, NinePatch, PatchFill};
+	use crate::dither::ThresholdMap;
+	use crate::frame::Rect;
+	use crate::Rgb565;
+
+	#[test]
+	fn bounds() {
+		assert!(Framebuffer565::new([0u16; 11], 4, 3).is_none());
+		assert!(Framebuffer565::with_stride([0u16; 10], 4, 3, 3).is_none());
+		assert!(Framebuffer565::with_stride([0u16; 10], 2, 3, 4).is_some());
+		assert!(Framebuffer565::new([0u16; 0], 0, 0).is_some());
+
+		let mut frame = Framebuffer565::with_stride([0u16; 10], 2, 3, 4).unwrap();
+		frame.fill(Rgb565::from_rgb565(1));
+		frame.set_pixel(2, 0, Rgb565::from_rgb565(2));
+		frame.fill_rect(Rect::new(1, 2, 5, 5), Rgb565::from_rgb565(3));
+		assert_eq!(frame.pixel(2, 0), None);
+		assert_eq!(frame.into_inner(), [1, 1, 0, 0, 1, 1, 0, 0, 1, 3]);
+	}
+
+	#[test]
+	fn present_scaled() {
+		let pixels: Vec<u16> = (0..160 * 120).map(|i| i as u16).collect();
+		let mut dst = Framebuffer565::new(vec![0u16; 320 * 240], 320, 240).unwrap();
+
+		let src = Framebuffer565::new(&pixels[..], 160, 120).unwrap();
+		assert_eq!(super::present_scaled(&src, &mut dst, Rgb565::from_rgb565(1)), Rect::new(0, 0, 320, 240));
+		assert_eq!(dst.pixel(319, 239), src.pixel(159, 119));
+		assert_eq!(dst.pixel(3, 2), src.pixel(1, 1));
+
+		// 128 rows don't fit twice in 240, so this stays at its original size.
+		let src = Framebuffer565::new(vec![0xFFFF; 160 * 128], 160, 128).unwrap();
+		assert_eq!(super::present_scaled(&src, &mut dst, Rgb565::from_rgb565(1)), Rect::new(80, 56, 160, 128));
+		assert_eq!(dst.pixel(79, 56), Some(Rgb565::from_rgb565(1)));
+		assert_eq!(dst.pixel(80, 55), Some(Rgb565::from_rgb565(1)));
+		assert_eq!(dst.pixel(80, 56), Some(Rgb565::from_rgb565(0xFFFF)));
+		assert_eq!(dst.pixel(239, 183), Some(Rgb565::from_rgb565(0xFFFF)));
+		assert_eq!(dst.pixel(240, 183), Some(Rgb565::from_rgb565(1)));
+		assert_eq!(dst.pixel(239, 184), Some(Rgb565::from_rgb565(1)));
+
+		let src = Framebuffer565::new([1, 2, 3, 4, 5, 6, 7, 8, 9], 3, 3).unwrap();
+		let mut dst = Framebuffer565::with_stride([0u16; 5], 1, 2, 3).unwrap();
+		assert_eq!(super::present_scaled(&src, &mut dst, Rgb565::default()), Rect::new(0, 0, 1, 2));
+		assert_eq!(dst.into_inner(), [2, 0, 0, 5, 0]);
+	}
+
+	#[test]
+	fn blit() {
+		let sprite = Framebuffer565::new([1, 2, 3, 4, 5, 6], 3, 2).unwrap();
+
+		for (x, y) in [(-3, 0), (0, -2), (4, 0), (0, 3), (-1, -1), (2, 2)] {
+			let mut frame = Framebuffer565::new([0u16; 12], 4, 3).unwrap();
+			frame.blit(&sprite, x, y);
+
+			for fy in 0..3 {
+				for fx in 0..4 {
+					let (sx, sy) = (fx as i32 - x, fy as i32 - y);
+					let expected = if (0..3).contains(&sx) && (0..2).contains(&sy) { sprite.pixel(sx as usize, sy as usize) } else { Some(Rgb565::default()) };
+					assert_eq!(frame.pixel(fx, fy), expected, "sprite at {}, {}", x, y);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn lines() {
+		let color = Rgb565::from_rgb565(1);
+		let mut frame = Framebuffer565::new([0u16; 5 * 4], 5, 4).unwrap();
+		frame.hline(-2, 0, 4, color);
+		frame.vline(4, -1, 3, color);
+		frame.hline(3, 3, 10, color);
+		frame.vline(-1, 0, 4, color);
+		frame.hline(0, -1, 5, color);
+		assert_eq!(frame.into_inner(), [1, 1, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1]);
+
+		// Lines are the same drawn in either direction, and clip at the edges.
+		for (x0, y0, x1, y1) in [(0, 0, 4, 3), (-3, 5, 6, -2), (1, 3, 2, 0), (0, 0, 0, 3)] {
+			let mut forward = Framebuffer565::new([0u16; 5 * 4], 5, 4).unwrap();
+			let mut backward = forward;
+			forward.line(x0, y0, x1, y1, color);
+			backward.line(x1, y1, x0, y0, color);
+
+			let pixels = forward.into_inner().iter().filter(|pixel| **pixel == 1).count();
+			assert!(pixels >= 4, "{:?}", (x0, y0, x1, y1));
+			assert_eq!(pixels, backward.into_inner().iter().filter(|pixel| **pixel == 1).count());
+		}
+
+		let mut frame = Framebuffer565::new([0u16; 4 * 3], 4, 3).unwrap();
+		frame.stroke_rect(Rect::new(1, 1, 5, 5), color);
+		frame.stroke_rect(Rect::new(0, 0, 0, 3), color);
+		assert_eq!(frame.into_inner(), [0, 0, 0, 0, 0, 1, 1, 1, 0, 1, 0, 0]);
+
+		// Lines across the whole range of coordinates.
+		let mut frame = Framebuffer565::new([0u16; 3 * 2], 3, 2).unwrap();
+		frame.line(i32::MIN, 0, i32::MAX, 0, color);
+		frame.line(2, i32::MAX, 2, i32::MIN, color);
+		assert_eq!(frame.into_inner(), [1, 1, 1, 0, 0, 1]);
+	}
+
+	#[test]
+	fn gradient() {
+		let (from, to) = (Rgb565::from_rgb565_components(0, 10, 31), Rgb565::from_rgb565_components(31, 20, 0));
+		let mut frame = Framebuffer565::new(vec![0u16; 8 * 32], 8, 32).unwrap();
+		frame.fill_gradient(Rect::new(0, 0, 8, 32), from, to, Direction::Vertical, None);
+
+		for y in 0..32 {
+			let row = frame.row(y).unwrap();
+			assert!(row.iter().all(|pixel| *pixel == row[0]));
+			assert_eq!(frame.pixel(0, y).unwrap().to_rgb565_components()[0] as usize, y, "{}", y);
+		}
+
+		assert_eq!(frame.pixel(0, 0), Some(from));
+		assert_eq!(frame.pixel(0, 31), Some(to));
+
+		// Dithering mixes the two levels in proportion to where the gradient
+		// is, where plain quantization would round it off to one or the other.
+		let (black, red) = (Rgb565::from_rgb565(0x0000), Rgb565::from_rgb565(0x0800));
+		frame.fill_gradient(Rect::new(0, 0, 8, 32), black, red, Direction::Horizontal, Some(&ThresholdMap::BAYER_4));
+		let levels = (0..8).map(|x| (0..32).filter(|y| frame.pixel(x, *y) == Some(red)).count()).collect::<Vec<_>>();
+		assert_eq!(levels.first(), Some(&0));
+		assert_eq!(levels.last(), Some(&32));
+		assert!(levels[..4].iter().sum::<usize>() < levels[4..].iter().sum::<usize>(), "{:?}", levels);
+		assert!((96..=160).contains(&levels.iter().sum::<usize>()), "{:?}", levels);
+	}
+
+	#[test]
+	fn rounded() {
+		let white = Rgb565::from_rgb565(0xFFFF);
+		let coverage = |frame: &Framebuffer565<Vec<u16>>| {
+			(0..frame.height()).flat_map(|y| (0..frame.width()).map(move |x| (x, y))).map(|(x, y)| frame.pixel(x, y).unwrap().to_rgb565_components()[1] as f32 / 63.0).sum::<f32>()
+		};
+
+		let mut frame = Framebuffer565::new(vec![0u16; 41 * 41], 41, 41).unwrap();
+		frame.fill_circle(20, 20, 20, white);
+		let area = coverage(&frame);
+		assert!((area - core::f32::consts::PI * 400.0).abs() < 4.0, "{}", area);
+
+		for (x, y) in [(0, 20), (20, 0), (40, 20), (20, 40)] {
+			assert!(frame.pixel(x, y).unwrap().to_rgb565_components()[1] > 0);
+			assert_ne!(frame.pixel(x, y), Some(white));
+		}
+
+		assert_eq!(frame.pixel(20, 20), Some(white));
+		assert_eq!(frame.pixel(3, 3), Some(Rgb565::default()));
+
+		// A circle of radius 0 is one pixel, and off the edge is clipped.
+		let mut frame = Framebuffer565::new(vec![0u16; 9], 3, 3).unwrap();
+		frame.fill_circle(1, 1, 0, white);
+		frame.fill_circle(-10, 1, 5, white);
+		assert_eq!(frame.into_inner(), [0, 0, 0, 0, 0xFFFF, 0, 0, 0, 0]);
+
+		let mut frame = Framebuffer565::new(vec![0u16; 10 * 6], 10, 6).unwrap();
+		let mut square = frame.clone();
+		frame.fill_rounded_rect(Rect::new(1, 1, 8, 4), 0, white);
+		square.fill_rect(Rect::new(1, 1, 8, 4), white);
+		assert_eq!(frame, square);
+
+		// The straight edges are solid, and the radius is limited to a pill.
+		let mut frame = Framebuffer565::new(vec![0u16; 10 * 6], 10, 6).unwrap();
+		frame.fill_rounded_rect(Rect::new(0, 0, 10, 6), 100, white);
+		assert_eq!(frame.pixel(5, 0), Some(white));
+		assert_eq!(frame.pixel(0, 0), Some(Rgb565::default()));
+		assert!((coverage(&frame) - (24.0 + core::f32::consts::PI * 9.0)).abs() < 1.0);
+	}
+
+	#[test]
+	fn fill_pattern() {
+		let tile = Framebuffer565::with_stride([1, 2, 0, 3, 4], 2, 2, 3).unwrap();
+		let mut frame = Framebuffer565::new([0u16; 4 * 4], 4, 4).unwrap();
+		frame.fill_pattern(Rect::new(1, 1, 5, 5), &tile);
+		assert_eq!(frame.into_inner(), [0, 0, 0, 0, 0, 1, 2, 1, 0, 3, 4, 3, 0, 1, 2, 1]);
+
+		let mut frame = Framebuffer565::new([0u16; 4], 2, 2).unwrap();
+		frame.fill_pattern(Rect::new(0, 0, 2, 2), &Framebuffer565::new([0u16; 0], 0, 0).unwrap());
+		assert_eq!(frame.into_inner(), [0; 4]);
+	}
+
+	#[test]
+	fn nine_patch() {
+		let panel = Framebuffer565::new([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12], 4, 3).unwrap();
+		let patch = NinePatch::new(1, 1, 1, 1);
+
+		let mut frame = Framebuffer565::new([0u16; 7 * 3], 7, 3).unwrap();
+		frame.blit_nine_patch(&panel, patch, PatchFill::Tile, Rect::new(0, 0, 7, 3));
+		assert_eq!(frame.row(0), Some(&[1, 2, 3, 2, 3, 2, 4][..]));
+		assert_eq!(frame.row(2), Some(&[9, 10, 11, 10, 11, 10, 12][..]));
+
+		frame.blit_nine_patch(&panel, patch, PatchFill::Stretch, Rect::new(0, 0, 7, 3));
+		assert_eq!(frame.row(1), Some(&[5, 6, 6, 6, 7, 7, 8][..]));
+
+		// Smaller than the borders, the corners are cut off.
+		let mut small = Framebuffer565::new([0u16; 2 * 2], 2, 2).unwrap();
+		small.blit_nine_patch(&panel, patch, PatchFill::Stretch, Rect::new(0, 0, 2, 2));
+		assert_eq!(small.into_inner(), [1, 4, 9, 12]);
+	}
+
+	#[test]
+	fn coverage() {
+		let (red, blue) = (Rgb565::from_rgb565(0xF800), Rgb565::from_rgb565(0x001F));
+
+		for coverage in 0..=255 {
+			let blended = super::blend_coverage(blue, red, coverage).to_rgb565_components();
+			let swapped = super::blend_coverage(red, blue, 255 - coverage).to_rgb565_components();
+			assert_eq!(blended, swapped);
+			assert_eq!(blended[0] as u32, (coverage as u32 * 31 + 127) / 255);
+		}
+
+		let mut frame = Framebuffer565::new([0u16; 2], 2, 1).unwrap();
+		frame.blend_pixel(1, 0, red, 255);
+		frame.blend_pixel(2, 0, red, 255);
+		assert_eq!(frame.into_inner(), [0, 0xF800]);
+	}
+
+	#[test]
+	fn subpixel() {
+		let white = Rgb565::from_rgb565(0xFFFF);
+
+		// A solid run of subpixels is solid in the middle, and the filter
+		// spreads its edges a little into the neighboring channels.
+		let coverage = [0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0];
+		let mut frame = Framebuffer565::new([0u16; 4], 4, 1).unwrap();
+		frame.fill_masked(white, Mask::Subpixel(&coverage), 4, 1, 0, 0);
+		let [first, second, third, fourth] = frame.into_inner().map(|pixel| Rgb565::from_rgb565(pixel).to_rgb888_components());
+		assert_eq!(first, [0, 0, 0]);
+		assert!(second[2] > 128 && second[1] > 0 && second[1] < second[2]);
+		assert_eq!(third[1], 255);
+		assert!(fourth[0] > 128 && fourth[1] < fourth[0] && fourth[2] < fourth[1]);
+
+		// Coverage doesn't bleed from one row into the next.
+		let coverage = [255, 255, 255, 0, 0, 0];
+		let mut frame = Framebuffer565::new([0u16; 2], 1, 2).unwrap();
+		frame.fill_masked(white, Mask::Subpixel(&coverage), 1, 2, 0, 0);
+		assert_eq!(frame.into_inner()[1], 0);
+
+		let blended = super::blend_subpixel(Rgb565::default(), white, [255, 0, 128]).to_rgb565_components();
+		assert_eq!(blended, [31, 0, 16]);
+	}
+
+	#[test]
+	fn masks() {
+		let sprite = Framebuffer565::new([0xFFFF; 4], 2, 2).unwrap();
+		let mut frame = Framebuffer565::new([0u16; 4], 2, 2).unwrap();
+		frame.blit_masked(&sprite, Mask::Coverage(&[0, 255, 128, 64]), 0, 0);
+		assert_eq!(frame.into_inner().map(|pixel| Rgb565::from_rgb565(pixel).to_rgb565_components()), [[0, 0, 0], [31, 63, 31], [16, 32, 16], [8, 16, 8]]);
+
+		let mut frame = Framebuffer565::new([0u16; 4], 2, 2).unwrap();
+		frame.blit_masked(&sprite, Mask::Bits(&[0b0100_0000, 0b1000_0000]), 0, 0);
+		assert_eq!(frame.into_inner(), [0, 0xFFFF, 0xFFFF, 0]);
+	}
+}
@@ -0,0 +1,111 @@
+//! Pre-blending anti-aliased glyphs against the backgrounds they'll be drawn
+//! on, so that text can be blitted without blending it on the device.
+//!
+//! Font rasterizers on the host, like `fontdue` or `ab_glyph`, produce glyph
+//! atlases of 8-bit coverage. Drawing those means blending every pixel of
+//! every glyph with whatever is behind it, which is slow on a microcontroller.
+//! When text only ever goes on a handful of flat theme colors, each glyph can
+//! be blended against each of them ahead of time instead. The result, a
+//! strike per background, costs two bytes per pixel per background in flash,
+//! but drawing from it is a plain [`blit`](crate::Framebuffer565::blit).
+//!
+//! [`strikes`] stacks the strikes of an atlas top to bottom, in the order of
+//! the backgrounds, so a glyph at `rect` in the atlas is at
+//! [`in_strike`]`(rect, height, i)` against the `i`th background.
+//!
+//! ```
+//! # use rgb565::{glyphs, frame::Rect, Rgb565};
+//! # #[cfg(feature = "std")] {
+//! // A 2x2 atlas holding a single glyph.
+//! let atlas = [0, 128, 255, 0];
+//! let backgrounds = [Rgb565::BLACK, Rgb565::NAVY];
+//! let strikes = glyphs::strikes(&atlas, 2, 2, Rgb565::WHITE, &backgrounds).unwrap();
+//! assert_eq!((strikes.width(), strikes.height()), (2, 4));
+//!
+//! let glyph = strikes.view(glyphs::in_strike(Rect::new(0, 0, 2, 2), 2, 1)).unwrap();
+//! assert_eq!(glyph.pixel(0, 0), Some(Rgb565::NAVY));
+//! assert_eq!(glyph.pixel(0, 1), Some(Rgb565::WHITE));
+//! # }
+//! ```
+
+use crate::frame::Rect;
+use crate::framebuffer::blend_coverage;
+use crate::Rgb565;
+
+/// Blends `foreground` over `background` by each coverage value of `atlas`
+/// into `dst`, as many pixels as fit in both, like [`blend_coverage`].
+pub fn strike(atlas: &[u8], foreground: Rgb565, background: Rgb565, dst: &mut [u16]) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	for (dst, coverage) in dst.iter_mut().zip(atlas) {
+		*dst = blend_coverage(background, foreground, *coverage).to_rgb565();
+	}
+}
+
+/// Blends `foreground` over each of `backgrounds` by `atlas`, which is
+/// `width` by `height` pixels of coverage, into a framebuffer with one strike
+/// after another from top to bottom. Returns `None` if `atlas` is shorter than
+/// `width` by `height`.
+#[cfg(feature = "std")]
+#[must_use]
+pub fn strikes(atlas: &[u8], width: usize, height: usize, foreground: Rgb565, backgrounds: &[Rgb565]) -> Option<crate::Framebuffer565<Vec<u16>>> {
+	let atlas = atlas.get(..width.checked_mul(height)?)?;
+	let mut pixels = vec![0; atlas.len().checked_mul(backgrounds.len())?];
+
+	for (dst, background) in pixels.chunks_exact_mut(atlas.len().max(1)).zip(backgrounds) {
+		strike(atlas, foreground, *background, dst);
+	}
+
+	crate::Framebuffer565::new(pixels, width, height * backgrounds.len())
+}
+
+/// Where `glyph`, a rectangle of an atlas `height` pixels tall, is in the
+/// strike against the `index`th background of [`strikes`].
+#[inline]
+#[must_use]
+pub const fn in_strike(glyph: Rect, height: usize, index: usize) -> Rect { Rect::new(glyph.x, glyph.y + height * index, glyph.width, glyph.height) }
+
+#[cfg(test)]
+mod tests {
+	use super::strike;
+	use crate::framebuffer::blend_coverage;
+	use crate::Rgb565;
+
+	#[test]
+	fn blending() {
+		let atlas: Vec<u8> = (0..=255).collect();
+		let (foreground, background) = (Rgb565::YELLOW, Rgb565::PURPLE);
+		let mut dst = [0xAAAA; 257];
+		strike(&atlas, foreground, background, &mut dst);
+
+		for (coverage, pixel) in atlas.iter().zip(dst) {
+			assert_eq!(pixel, blend_coverage(background, foreground, *coverage).to_rgb565());
+		}
+
+		assert_eq!((dst[0], dst[255], dst[256]), (background.to_rgb565(), foreground.to_rgb565(), 0xAAAA));
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn stacking() {
+		use super::{in_strike, strikes};
+		use crate::frame::Rect;
+
+		// Two 3x2 glyphs side by side.
+		let atlas = [0, 255, 0, 64, 64, 64, 255, 0, 255, 128, 128, 128];
+		let backgrounds = [Rgb565::BLACK, Rgb565::TEAL, Rgb565::MAROON];
+		let frame = strikes(&atlas, 6, 2, Rgb565::WHITE, &backgrounds).unwrap();
+		assert_eq!((frame.width(), frame.height()), (6, 6));
+
+		for (i, background) in backgrounds.iter().enumerate() {
+			let glyph = frame.view(in_strike(Rect::new(0, 0, 3, 2), 2, i)).unwrap();
+			assert_eq!((glyph.pixel(1, 0), glyph.pixel(1, 1)), (Some(Rgb565::WHITE), Some(*background)));
+			let glyph = frame.view(in_strike(Rect::new(3, 0, 3, 2), 2, i)).unwrap();
+			assert_eq!(glyph.pixel(2, 1), Some(blend_coverage(*background, Rgb565::WHITE, 128)));
+		}
+
+		assert!(strikes(&atlas[..11], 6, 2, Rgb565::WHITE, &backgrounds).is_none());
+		assert_eq!(strikes(&atlas, 6, 2, Rgb565::WHITE, &[]).map(|frame| frame.height()), Some(0));
+	}
+}
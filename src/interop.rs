@@ -0,0 +1,158 @@
+//! Conversions to and from the pixel types of other crates.
+//!
+//! With the `rgb` feature, pixels of the `rgb` crate, which don't say how
+//! they're encoded, are treated as linear, like
+//! [`Rgb565::from_rgb888_components`]. Wrapping one in `Srgb`, which comes
+//! with the same feature, treats it as sRGB instead, which is what images and
+//! most other crates store.
+//!
+//! With the `palette` feature, colors of the `palette` crate carry their
+//! encoding in their type, so they're converted accordingly.
+
+#[cfg(feature = "palette")]
+use palette::LinSrgb;
+#[cfg(feature = "rgb")]
+use rgb::{alt::BGR8, RGB8};
+
+use crate::Rgb565;
+
+/// Marks a pixel of another crate as sRGB rather than linear, for converting
+/// it to or from [`Rgb565`].
+///
+/// ```
+/// # use rgb565::{Rgb565, Srgb};
+/// # #[cfg(all(has_srgb, has_srgb_decode))] {
+/// let white = Rgb565::from_rgb565(0xFFFF);
+/// let Srgb(srgb): Srgb<rgb::RGB8> = white.into();
+/// assert_eq!(Rgb565::from(Srgb(srgb)), white);
+/// # }
+/// ```
+#[cfg(feature = "rgb")]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct Srgb<P>(pub P);
+
+#[cfg(feature = "rgb")]
+macro_rules! rgb_crate {
+	($($pixel:ident),+) => {$(
+		impl From<$pixel> for Rgb565 {
+			#[inline]
+			fn from(pixel: $pixel) -> Self { Self::from_rgb888_components(pixel.r, pixel.g, pixel.b) }
+		}
+
+		impl From<Rgb565> for $pixel {
+			#[inline]
+			fn from(color: Rgb565) -> Self {
+				let [r, g, b] = color.to_rgb888_components();
+				Self { r, g, b }
+			}
+		}
+
+		#[cfg(has_srgb_decode)]
+		impl From<Srgb<$pixel>> for Rgb565 {
+			#[inline]
+			fn from(Srgb(pixel): Srgb<$pixel>) -> Self { Self::from_srgb888_components(pixel.r, pixel.g, pixel.b) }
+		}
+
+		#[cfg(has_srgb)]
+		impl From<Rgb565> for Srgb<$pixel> {
+			#[inline]
+			fn from(color: Rgb565) -> Self {
+				let [r, g, b] = color.to_srgb888_components();
+				Self($pixel { r, g, b })
+			}
+		}
+	)+};
+}
+
+#[cfg(feature = "rgb")]
+rgb_crate!(RGB8, BGR8);
+
+#[cfg(all(feature = "palette", has_srgb_decode))]
+impl From<palette::Srgb<u8>> for Rgb565 {
+	#[inline]
+	fn from(color: palette::Srgb<u8>) -> Self { Self::from_srgb888_components(color.red, color.green, color.blue) }
+}
+
+#[cfg(all(feature = "palette", has_srgb))]
+impl From<Rgb565> for palette::Srgb<u8> {
+	#[inline]
+	fn from(color: Rgb565) -> Self {
+		let [r, g, b] = color.to_srgb888_components();
+		Self::new(r, g, b)
+	}
+}
+
+/// Rounds to the nearest level, with channels outside of `0.0..=1.0` clamped
+/// and NaN taken as 0.
+#[cfg(feature = "palette")]
+impl From<LinSrgb<f32>> for Rgb565 {
+	#[inline]
+	fn from(color: LinSrgb<f32>) -> Self {
+		let channel = |value: f32, max: f32| (value.clamp(0.0, 1.0) * max + 0.5) as u8;
+		Self::from_rgb565_components(channel(color.red, 31.0), channel(color.green, 63.0), channel(color.blue, 31.0))
+	}
+}
+
+#[cfg(feature = "palette")]
+impl From<Rgb565> for LinSrgb<f32> {
+	#[inline]
+	fn from(color: Rgb565) -> Self {
+		let [r, g, b] = color.to_rgb565_components();
+		Self::new(r as f32 / 31.0, g as f32 / 63.0, b as f32 / 31.0)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	#[cfg(feature = "rgb")]
+	fn rgb_crate() {
+		use rgb::alt::BGR8;
+		use rgb::RGB8;
+
+		use crate::Rgb565;
+
+		for color in Rgb565::all().step_by(31) {
+			let [r, g, b] = color.to_rgb888_components();
+			assert_eq!(RGB8::from(color), RGB8 { r, g, b });
+			assert_eq!(BGR8::from(color), BGR8 { b, g, r });
+			assert_eq!(Rgb565::from(RGB8 { r, g, b }), color);
+			assert_eq!(Rgb565::from(BGR8 { b, g, r }), color);
+
+			#[cfg(has_srgb)]
+			{
+				use super::Srgb;
+
+				let [r, g, b] = color.to_srgb888_components();
+				assert_eq!(Srgb::<RGB8>::from(color), Srgb(RGB8 { r, g, b }));
+				#[cfg(has_srgb_decode)]
+				assert_eq!(Rgb565::from(Srgb(BGR8 { b, g, r })), Rgb565::from_srgb888_components(r, g, b));
+			}
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "palette")]
+	fn palette() {
+		use palette::LinSrgb;
+
+		use crate::Rgb565;
+
+		for color in Rgb565::all() {
+			assert_eq!(Rgb565::from(LinSrgb::<f32>::from(color)), color);
+		}
+
+		#[cfg(has_srgb)]
+		for color in Rgb565::all().step_by(31) {
+			use palette::Srgb;
+
+			let [r, g, b] = color.to_srgb888_components();
+			assert_eq!(Srgb::<u8>::from(color), Srgb::new(r, g, b));
+			#[cfg(has_srgb_decode)]
+			assert_eq!(Rgb565::from(Srgb::new(r, g, b)), Rgb565::from_srgb888_components(r, g, b));
+		}
+
+		assert_eq!(LinSrgb::<f32>::from(Rgb565::from_rgb565(0xFFE0)).into_components(), (1.0, 1.0, 0.0));
+		assert_eq!(Rgb565::from(LinSrgb::new(f32::NAN, 2.0, -1.0)).to_rgb565(), 0x07E0);
+	}
+}
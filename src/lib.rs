@@ -59,8 +59,260 @@
 //! That's because `l888_to_l565_lut` and `s888_to_l565_lut` both have to cover
 //! the entire 16.777216-million-color space of 24-bit "true color", and I don't
 //! think it would be very nice to add bloat like that by default.
+//!
+//! If `l565_to_l888_lut` or `l565_to_s888_lut` is disabled, conversions to
+//! 8-bit RGB or sRGB are composed from the per-channel LUTs instead, which
+//! saves 192 KiB of flash each for the cost of three small lookups instead of
+//! one. On a desktop CPU that's roughly three times slower than the full table,
+//! but still far faster than computing sRGB by hand. `cargo bench` reports the
+//! timings for whichever features it is built with.
+//!
+//! If you'd rather not pick LUTs by hand, the `auto_luts` feature picks them
+//! for you based on the target, on top of any LUT features that are enabled. It
+//! fills a budget of 2 KiB on bare-metal targets (enough for every per-channel
+//! LUT) and 1 MiB elsewhere, preferring the sRGB LUTs. Targets without an FPU
+//! always get the per-channel sRGB LUTs, since computing sRGB in software is
+//! painfully slow. You can steer it with environment variables at build time:
+//!
+//! - `RGB565_LUT_BUDGET` sets the budget in bytes
+//! - `RGB565_LUTS` lists the LUTs to include by name, separated by commas, and
+//!   turns off the automatic choice (set it to an empty string to include none)
+//! - `RGB565_SIMD` turns the vectorized conversions described below on or off
+//!
+//! With `auto_luts`, targets that have NEON or SSSE3 at compile time also get
+//! the vectorized conversions, and skip `l565_to_l888_lut`, which they make
+//! unnecessary for whole frames.
+//!
+//! Without `std` there's no `powf` to compute sRGB with, so the sRGB
+//! conversions only exist if the LUTs they need are included. The `libm`
+//! feature computes it with the `libm` crate instead, which makes every sRGB
+//! conversion available in `no_std` builds with no LUTs at all.
+//!
+//! The `fast_srgb` feature computes it with a polynomial approximation of
+//! `powf` instead, a handful of multiplications for microcontrollers without an
+//! FPU, and works without `std` or `libm`. Its relative error is below 1e-4,
+//! which is small enough that every conversion between 8-bit sRGB and 5- or
+//! 6-bit linear RGB comes out exactly as it would with `powf`. The display and
+//! panel calibration curves use it too, where the error can show up in the last
+//! bit.
+//!
+//! The `fixed_srgb` feature uses the integer math of the `fixed_srgb` module
+//! instead, which needs neither `std` nor `libm`, comes out the same on every
+//! target and gives exactly the same results as the float conversions. Its
+//! functions are all `const fn`s, so they can also compute sRGB colors at
+//! compile time, with or without the feature.
+//!
+//! Every LUT but `l888_to_l565_lut` and `s888_to_l565_lut` is computed at
+//! compile time by `const fn`s (the sRGB ones with those of `fixed_srgb`) and
+//! embedded as an array of its output type. Those two are far too large for
+//! that, so the build script generates them and they're embedded as raw bytes
+//! with `include_bytes!`.
+//!
+//! The build script still runs on every build to pick the LUTs and whether to
+//! vectorize, since `auto_luts` and the environment variables above can't be
+//! expressed as plain features, but the 32 MiB LUTs are all it generates.
+//!
+//! For those it takes a noticeable amount of time on every clean build. If the
+//! `RGB565_LUT_DIR` environment variable is set, LUTs are taken from (and, if
+//! the directory is writable, saved to) that directory instead, so CI can cache
+//! them between builds. Tables are stored in a subdirectory named after the
+//! crate version and sRGB mode.
+//!
+//! The linear segment of the sRGB curve has always used slightly different
+//! constants than IEC 61966-2-1 specifies, chosen so that the two segments of
+//! the curve meet. If you need bit-exact agreement with other libraries, the
+//! `exact_srgb` feature switches every sRGB conversion, and the LUTs, to the
+//! exact constants from the standard.
+//!
+//! The `simd` feature vectorizes the bulk conversions between RGB565 and 8-bit
+//! RGB of [`Converter`]s and the [`convert`] module, and the byte swapping of
+//! `esp_lcd`, on AArch64 and on x86 with SSSE3. Unless SSSE3 is enabled at
+//! compile time, it's detected at runtime, which needs `std`.
+//! [`Converter::simd`] says whether that worked out. `auto_luts` turns it on
+//! by itself where the target is known to support it, and `RGB565_SIMD`
+//! overrides either.
+//!
+//! With the `rgb` feature, [`Rgb565`] converts to and from the `RGB8` and
+//! `BGR8` pixels of the `rgb` crate with `From`. Those are taken as linear,
+//! like the other 8-bit conversions, unless they're wrapped in `Srgb`.
+//!
+//! With the `palette` feature, it converts to and from `Srgb<u8>` and
+//! `LinSrgb<f32>` of the `palette` crate the same way, for working in other
+//! color spaces.
+//!
+//! With the `image` feature, the [`convert`] module converts whole
+//! `RgbImage`s of the `image` crate to and from frames, treating them as sRGB.
+//! The `buildtools` feature builds on it with the `buildtools` module, which
+//! converts PNG and JPEG files into frames, assets and bundles from build
+//! scripts. The `webp` feature adds WebP to those, and the `gif` feature turns
+//! animated GIFs into animations.
+//!
+//! With the `zerocopy` feature, [`Rgb565`] and the byte-array pixels of the
+//! [`packed`] module implement the traits of the `zerocopy` crate, so received
+//! frames can be viewed as slices of pixels without copying.
+//!
+//! With the `ufmt` feature, [`Rgb565`] implements `uDebug` and `uDisplay` of
+//! the `ufmt` crate, for printing colors over a serial console without pulling
+//! in `core::fmt`.
+//!
+//! To check which of these paths actually run on a device, the `stats`
+//! feature keeps counters of conversions and frames in the `stats` module.
+//!
+//! The raw bytes of whichever LUTs are included are available from the
+//! [`luts`] module, for uploading to hardware lookup engines like the DMA2D
+//! CLUT on STM32 or the gamma tables of a display controller.
 
+pub mod acep;
+pub mod analysis;
+pub mod animation;
+pub mod asset;
+mod average;
+pub mod blend;
+pub mod bundle;
+#[cfg(feature = "buildtools")]
+pub mod buildtools;
+pub mod convert;
+mod converter;
+#[cfg(feature = "std")]
+pub mod debug;
+pub mod descriptor;
+#[cfg(feature = "dib")]
+pub mod dib;
+pub mod dither;
+#[cfg(feature = "std")]
+mod display;
+#[cfg(feature = "dma2d")]
+pub mod dma2d;
+pub mod epaper;
+#[cfg(feature = "esp_lcd")]
+pub mod esp_lcd;
+pub mod fixed_srgb;
+pub mod formats;
+pub mod frame;
+pub mod framebuffer;
+pub mod glyphs;
+#[cfg(any(feature = "palette", feature = "rgb"))]
+mod interop;
 mod lut;
+pub mod luts;
+pub mod packed;
+mod panel;
+#[cfg(feature = "std")]
+pub mod present;
+#[cfg(feature = "wire")]
+pub mod remote;
+pub mod rgb666;
+pub mod scanline;
+#[cfg(simd_backend)]
+mod simd;
+mod spectrum;
+pub mod sprite;
+#[cfg(feature = "stats")]
+pub mod stats;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod warp;
+#[cfg(feature = "wire")]
+pub mod wire;
+
+pub use average::ColorAverage;
+pub use converter::Converter;
+#[cfg(feature = "std")]
+pub use display::{DisplayProfile, Matrix3, Primaries, TransferFunction};
+pub use framebuffer::Framebuffer565;
+#[cfg(feature = "rgb")]
+pub use interop::Srgb;
+pub use packed::{Bgr565Be, Bgr565Le, Rgb565Be, Rgb565Le};
+pub use panel::PanelProfile;
+pub use rgb666::Rgb666;
+pub use sprite::SpriteSheet;
+
+/// One of the ways an RGB565 value can be stored in two bytes, for when the
+/// choice is only known at runtime.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Encoding {
+	/// Stored as `[gggbbbbb, rrrrrggg]`
+	Rgb565Le,
+	/// Stored as `[rrrrrggg, gggbbbbb]`
+	Rgb565Be,
+	/// Stored as `[gggrrrrr, bbbbbggg]`
+	Bgr565Le,
+	/// Stored as `[bbbbbggg, gggrrrrr]`
+	Bgr565Be
+}
+
+/// How channels are scaled between 8 bits and the 5 or 6 bits of RGB565, for
+/// matching what a particular display controller or image tool does.
+///
+/// The plain conversions like [`Rgb565::from_rgb888_components`] predate
+/// these and are kept as they are: they expand like `Truncate`, but reduce
+/// with `(l8 + 1) * 31 / 255`, which rounds up a little earlier than
+/// truncating would.
+///
+/// ```
+/// # use rgb565::{Rgb565, RoundingMode};
+/// let color = Rgb565::from_rgb565_components(16, 32, 16);
+/// assert_eq!(color.to_rgb888_components_rounded(RoundingMode::Truncate), [131, 129, 131]);
+/// assert_eq!(color.to_rgb888_components_rounded(RoundingMode::Nearest), [132, 130, 132]);
+/// assert_eq!(color.to_rgb888_components_rounded(RoundingMode::BitReplicate), [132, 130, 132]);
+///
+/// assert_eq!(Rgb565::from_rgb888_components_rounded(100, 100, 100, RoundingMode::Truncate).to_rgb565_components(), [12, 24, 12]);
+/// assert_eq!(Rgb565::from_rgb888_components_rounded(100, 100, 100, RoundingMode::Nearest).to_rgb565_components(), [12, 25, 12]);
+/// assert_eq!(Rgb565::from_rgb888_components_rounded(100, 100, 100, RoundingMode::BitReplicate).to_rgb565_components(), [12, 25, 12]);
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum RoundingMode {
+	/// Scales by `255 / 31` (or `63`) and its inverse, rounding down.
+	Truncate,
+	/// Scales like `Truncate`, but rounds to the nearest.
+	Nearest,
+	/// Expands by repeating the top bits of a channel in the bits below them,
+	/// and reduces by dropping the low bits, which is what most display
+	/// controllers and GPUs do in hardware.
+	BitReplicate
+}
+
+impl RoundingMode {
+	/// Expands a channel of `bits` bits to 8.
+	///
+	/// # Panics
+	///
+	/// Panics if `bits` isn't from 1 to 8, or from 4 to 8 for
+	/// `BitReplicate`, which needs at least half of the bits to repeat.
+	#[inline]
+	#[must_use]
+	pub const fn expand(self, value: u8, bits: u32) -> u8 {
+		assert!(bits >= 1 && bits <= 8, "channels must be from 1 to 8 bits");
+		assert!(bits >= 4 || !matches!(self, Self::BitReplicate), "bit replication needs at least 4 bits");
+		let max = (1 << bits) - 1;
+
+		match self {
+			Self::Truncate => (value as u16 * 255 / max) as u8,
+			Self::Nearest => ((value as u16 * 255 + max / 2) / max) as u8,
+			Self::BitReplicate => value << (8 - bits) | (value as u16 >> (2 * bits - 8)) as u8
+		}
+	}
+
+	/// Reduces an 8-bit channel to `bits` bits.
+	///
+	/// # Panics
+	///
+	/// Panics if `bits` isn't from 1 to 8.
+	#[inline]
+	#[must_use]
+	pub const fn reduce(self, value: u8, bits: u32) -> u8 {
+		assert!(bits >= 1 && bits <= 8, "channels must be from 1 to 8 bits");
+		let max = (1 << bits) - 1;
+
+		match self {
+			Self::Truncate => (value as u16 * max / 255) as u8,
+			Self::Nearest => ((value as u16 * max + 127) / 255) as u8,
+			Self::BitReplicate => value >> (8 - bits)
+		}
+	}
+}
 
 /// Represents an RGB565 color value.
 ///
@@ -69,15 +321,70 @@ mod lut;
 /// different color spaces alongside RGB565. Notably, it contains functions for
 /// converting to and from sRGB, which should be used when displaying RGB565
 /// colors on a modern computer monitor.
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Default)]
+///
+/// `{:?}` prints just the raw value, while `{:#?}` also prints the 5/6/5
+/// channels, the value expanded to 8-bit RGB and, when sRGB conversions are
+/// available, the CSS hex color it would display as.
+///
+/// The named constants like [`GRAY`](Self::GRAY) have the names and hex codes
+/// of the basic colors of CSS, but read the codes as linear 8-bit RGB, like
+/// [`from_rgb888_components`](Self::from_rgb888_components) does. Browsers
+/// read them as sRGB, so any channel that isn't `00` or `FF` comes out lighter
+/// than it does on the web. To match a browser, convert the codes with
+/// [`from_srgb888_components`](Self::from_srgb888_components) instead.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+#[cfg_attr(feature = "zerocopy", derive(zerocopy::FromBytes, zerocopy::IntoBytes, zerocopy::KnownLayout, zerocopy::Immutable))]
+#[repr(transparent)]
 pub struct Rgb565(u16);
 
 impl Rgb565 {
+	// The basic colors of CSS, converted the same way as
+	// `from_rgb888_components` so that they match what it gives for the same
+	// values.
+
+	/// `#000000`
+	pub const BLACK: Self = Self::from_rgb888_components_const(0, 0, 0);
+	/// `#C0C0C0`
+	pub const SILVER: Self = Self::from_rgb888_components_const(192, 192, 192);
+	/// `#808080`
+	pub const GRAY: Self = Self::from_rgb888_components_const(128, 128, 128);
+	/// `#FFFFFF`
+	pub const WHITE: Self = Self::from_rgb888_components_const(255, 255, 255);
+	/// `#800000`
+	pub const MAROON: Self = Self::from_rgb888_components_const(128, 0, 0);
+	/// `#FF0000`
+	pub const RED: Self = Self::from_rgb888_components_const(255, 0, 0);
+	/// `#800080`
+	pub const PURPLE: Self = Self::from_rgb888_components_const(128, 0, 128);
+	/// `#FF00FF`
+	pub const FUCHSIA: Self = Self::from_rgb888_components_const(255, 0, 255);
+	/// `#FF00FF`, the same as [`FUCHSIA`](Self::FUCHSIA)
+	pub const MAGENTA: Self = Self::FUCHSIA;
+	/// `#00FF00`
+	pub const LIME: Self = Self::from_rgb888_components_const(0, 255, 0);
+	/// `#008000`, which is what CSS calls green. The primary is
+	/// [`LIME`](Self::LIME).
+	pub const GREEN: Self = Self::from_rgb888_components_const(0, 128, 0);
+	/// `#808000`
+	pub const OLIVE: Self = Self::from_rgb888_components_const(128, 128, 0);
+	/// `#FFFF00`
+	pub const YELLOW: Self = Self::from_rgb888_components_const(255, 255, 0);
+	/// `#000080`
+	pub const NAVY: Self = Self::from_rgb888_components_const(0, 0, 128);
+	/// `#0000FF`
+	pub const BLUE: Self = Self::from_rgb888_components_const(0, 0, 255);
+	/// `#008080`
+	pub const TEAL: Self = Self::from_rgb888_components_const(0, 128, 128);
+	/// `#00FFFF`
+	pub const AQUA: Self = Self::from_rgb888_components_const(0, 255, 255);
+	/// `#00FFFF`, the same as [`AQUA`](Self::AQUA)
+	pub const CYAN: Self = Self::AQUA;
+
 	/// Unpacks r5, g6, and b5 values from a single RGB565 value. To unpack from
 	/// BGR565 instead, swap r5 and b5.
 	#[inline]
 	#[must_use]
-	pub fn unpack_565(packed: u16) -> (u8, u8, u8) { lut::unpack_565(packed) }
+	pub const fn unpack_565(packed: u16) -> (u8, u8, u8) { lut::unpack_565(packed) }
 
 	/// Packs r5, g6, and b5 values into a single RGB565 value. To pack into
 	/// BGR565 instead, swap r5 and b5.
@@ -86,115 +393,1089 @@ impl Rgb565 {
 	///
 	/// This function includes debug assertions to ensure that `r`, `g` and `b`
 	/// fit into the space allotted by the RGB565 format. If values are passed
-	/// that are too big, the function will panic.
+	/// that are too big, the function will panic. Release builds don't check,
+	/// so use [`try_pack_565`](Self::try_pack_565) for untrusted values.
 	#[inline]
 	#[must_use]
-	pub fn pack_565(unpacked: (u8, u8, u8)) -> u16 { lut::pack_565(unpacked) }
+	pub const fn pack_565(unpacked: (u8, u8, u8)) -> u16 { lut::pack_565(unpacked) }
+
+	/// The same as [`pack_565`](Self::pack_565), but fails instead of
+	/// panicking if a value is too big, in release builds too. The first
+	/// channel that doesn't fit is reported.
+	#[inline]
+	pub const fn try_pack_565((r5, g6, b5): (u8, u8, u8)) -> Result<u16, ChannelOverflowError> {
+		if r5 > 0b11111 {
+			Err(ChannelOverflowError::Red(r5))
+		} else if g6 > 0b111111 {
+			Err(ChannelOverflowError::Green(g6))
+		} else if b5 > 0b11111 {
+			Err(ChannelOverflowError::Blue(b5))
+		} else {
+			Ok(lut::pack_565((r5, g6, b5)))
+		}
+	}
 
 	/// From rgb565, where the colors are packed as `rrrrrggggggbbbbb`
 	#[inline]
 	#[must_use]
-	pub fn from_rgb565(packed: u16) -> Self { Self(packed) }
+	pub const fn from_rgb565(packed: u16) -> Self { Self(packed) }
 
 	/// From bgr565, where the colors are packed as `bbbbbggggggrrrrr`
 	#[inline]
 	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn from_bgr565(packed: u16) -> Self { Self(lut::SWAP_COMPONENTS_LUT.map(packed)) }
 
 	/// To rgb565, where the colors are packed as `rrrrrggggggbbbbb`
 	#[inline]
 	#[must_use]
-	pub fn to_rgb565(&self) -> u16 { self.0 }
+	pub const fn to_rgb565(&self) -> u16 { self.0 }
 
 	/// To bgr565, where the colors are packed as `bbbbbggggggrrrrr`
 	#[inline]
 	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn to_bgr565(&self) -> u16 { lut::SWAP_COMPONENTS_LUT.map(self.0) }
 
 	/// From rgb565_le, where the colors are stored as `[gggbbbbb, rrrrrggg]`
 	#[inline]
 	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn from_rgb565_le(bytes: [u8; 2]) -> Self { Self::from_rgb565(u16::from_le_bytes(bytes)) }
 
 	/// From rgb565_be, where the colors are stored as `[rrrrrggg, gggbbbbb]`
 	#[inline]
 	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn from_rgb565_be(bytes: [u8; 2]) -> Self { Self::from_rgb565(u16::from_be_bytes(bytes)) }
 
 	/// From bgr565_le, where the colors are stored as `[gggrrrrr, bbbbbggg]`
 	#[inline]
 	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn from_bgr565_le(bytes: [u8; 2]) -> Self { Self::from_bgr565(u16::from_le_bytes(bytes)) }
 
 	/// From bgr565_be, where the colors are stored as `[bbbbbggg, gggrrrrr]`
 	#[inline]
 	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn from_bgr565_be(bytes: [u8; 2]) -> Self { Self::from_bgr565(u16::from_be_bytes(bytes)) }
 
 	/// From rgb565_le, where the colors are stored as `[gggbbbbb, rrrrrggg]`
 	#[inline]
 	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn to_rgb565_le(&self) -> [u8; 2] { self.to_rgb565().to_le_bytes() }
 
 	/// From rgb565_be, where the colors are stored as `[rrrrrggg, gggbbbbb]`
 	#[inline]
 	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn to_rgb565_be(&self) -> [u8; 2] { self.to_rgb565().to_be_bytes() }
 
 	/// From bgr565_le, where the colors are stored as `[gggrrrrr, bbbbbggg]`
 	#[inline]
 	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn to_bgr565_le(&self) -> [u8; 2] { self.to_bgr565().to_le_bytes() }
 
 	/// From bgr565_be, where the colors are stored as `[bbbbbggg, gggrrrrr]`
 	#[inline]
 	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn to_bgr565_be(&self) -> [u8; 2] { self.to_bgr565().to_be_bytes() }
 
+	/// From two bytes stored in the given encoding
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn from_bytes(bytes: [u8; 2], encoding: Encoding) -> Self {
+		match encoding {
+			Encoding::Rgb565Le => Self::from_rgb565_le(bytes),
+			Encoding::Rgb565Be => Self::from_rgb565_be(bytes),
+			Encoding::Bgr565Le => Self::from_bgr565_le(bytes),
+			Encoding::Bgr565Be => Self::from_bgr565_be(bytes)
+		}
+	}
+
+	/// To two bytes stored in the given encoding
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn to_bytes(&self, encoding: Encoding) -> [u8; 2] {
+		match encoding {
+			Encoding::Rgb565Le => self.to_rgb565_le(),
+			Encoding::Rgb565Be => self.to_rgb565_be(),
+			Encoding::Bgr565Le => self.to_bgr565_le(),
+			Encoding::Bgr565Be => self.to_bgr565_be()
+		}
+	}
+
+	/// From a slice of rgb565 values, without copying
+	#[inline]
+	#[must_use]
+	pub fn from_rgb565_slice(packed: &[u16]) -> &[Self] {
+		// SAFETY: `Rgb565` is a `repr(transparent)` wrapper around `u16`.
+		unsafe { &*(packed as *const [u16] as *const [Self]) }
+	}
+
+	/// From a mutable slice of rgb565 values, without copying
+	#[inline]
+	#[must_use]
+	pub fn from_rgb565_slice_mut(packed: &mut [u16]) -> &mut [Self] {
+		// SAFETY: `Rgb565` is a `repr(transparent)` wrapper around `u16`.
+		unsafe { &mut *(packed as *mut [u16] as *mut [Self]) }
+	}
+
+	/// To a slice of rgb565 values, without copying
+	#[inline]
+	#[must_use]
+	pub fn to_rgb565_slice(colors: &[Self]) -> &[u16] {
+		// SAFETY: `Rgb565` is a `repr(transparent)` wrapper around `u16`.
+		unsafe { &*(colors as *const [Self] as *const [u16]) }
+	}
+
+	/// To a mutable slice of rgb565 values, without copying
+	#[inline]
+	#[must_use]
+	pub fn to_rgb565_slice_mut(colors: &mut [Self]) -> &mut [u16] {
+		// SAFETY: `Rgb565` is a `repr(transparent)` wrapper around `u16`.
+		unsafe { &mut *(colors as *mut [Self] as *mut [u16]) }
+	}
+
 	/// From rgb565 components, where r fits into 5 bits, g into 6 and b into 5
 	///
 	/// # Panics
 	///
 	/// This function includes debug assertions to ensure that `r`, `g` and `b`
 	/// fit into the space allotted by the RGB565 format. If values are passed
-	/// that are too big, the function will panic.
+	/// that are too big, the function will panic. Release builds don't check,
+	/// so use [`try_from_rgb565_components`](Self::try_from_rgb565_components)
+	/// for untrusted values.
 	#[inline]
 	#[must_use]
-	pub fn from_rgb565_components(r: u8, g: u8, b: u8) -> Self {
+	pub const fn from_rgb565_components(r: u8, g: u8, b: u8) -> Self {
 		Self(Self::pack_565((r, g, b)))
 	}
 
+	/// The same as [`from_rgb565_components`](Self::from_rgb565_components),
+	/// but components that are too big are clamped to the largest that fit.
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// assert_eq!(Rgb565::from_rgb565_components_clamped(40, 64, 3).to_rgb565_components(), [31, 63, 3]);
+	/// assert_eq!(Rgb565::from_rgb565_components_masked(40, 64, 3).to_rgb565_components(), [8, 0, 3]);
+	/// ```
+	#[inline]
+	#[must_use]
+	pub const fn from_rgb565_components_clamped(r: u8, g: u8, b: u8) -> Self {
+		const fn clamp(value: u8, max: u8) -> u8 { if value > max { max } else { value } }
+		Self(lut::pack_565((clamp(r, 0b11111), clamp(g, 0b111111), clamp(b, 0b11111))))
+	}
+
+	/// The same as [`from_rgb565_components`](Self::from_rgb565_components),
+	/// but components that are too big are cut down to their low bits, the way
+	/// a hardware register would take them.
+	#[inline]
+	#[must_use]
+	pub const fn from_rgb565_components_masked(r: u8, g: u8, b: u8) -> Self { Self(lut::pack_565((r & 0b11111, g & 0b111111, b & 0b11111))) }
+
+	/// The same as [`from_rgb565_components`](Self::from_rgb565_components),
+	/// but fails instead of panicking if a component is too big, in release
+	/// builds too.
+	///
+	/// ```
+	/// # use rgb565::{ChannelOverflowError, Rgb565};
+	/// assert_eq!(Rgb565::try_from_rgb565_components(31, 63, 0), Ok(Rgb565::YELLOW));
+	/// assert_eq!(Rgb565::try_from_rgb565_components(31, 64, 0), Err(ChannelOverflowError::Green(64)));
+	/// ```
+	#[inline]
+	pub const fn try_from_rgb565_components(r: u8, g: u8, b: u8) -> Result<Self, ChannelOverflowError> {
+		match Self::try_pack_565((r, g, b)) {
+			Ok(packed) => Ok(Self(packed)),
+			Err(error) => Err(error)
+		}
+	}
+
 	#[inline]
 	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn from_rgb888_components(r: u8, g: u8, b: u8) -> Self { Self(lut::L888_TO_L565_LUT.map([r, g, b])) }
 
-	#[cfg(any(feature = "std", feature = "s888_to_l565_lut"))]
+	/// The same as [`from_rgb888_components`](Self::from_rgb888_components),
+	/// but computed without LUTs so that it can be used in constants.
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// const ACCENT: Rgb565 = Rgb565::from_rgb888_components_const(255, 128, 0);
+	/// assert_eq!(ACCENT, Rgb565::from_rgb888_components(255, 128, 0));
+	/// ```
+	#[inline]
+	#[must_use]
+	pub const fn from_rgb888_components_const(r: u8, g: u8, b: u8) -> Self { Self(lut::transforms::l888_to_l565([r, g, b])) }
+
+	/// Converts 8-bit RGB to RGB565, scaling each channel as `rounding` says.
+	/// See [`RoundingMode`].
+	#[inline]
+	#[must_use]
+	pub const fn from_rgb888_components_rounded(r: u8, g: u8, b: u8, rounding: RoundingMode) -> Self {
+		Self(lut::pack_565((rounding.reduce(r, 5), rounding.reduce(g, 6), rounding.reduce(b, 5))))
+	}
+
+	/// Converts 8-bit RGB to RGB565 by dropping the low bits of each channel,
+	/// like most display controllers do. This undoes
+	/// [`to_rgb888_components_replicated`](Self::to_rgb888_components_replicated)
+	/// exactly.
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// assert_eq!(Rgb565::from_rgb888_components_truncated(0x87, 0x7F, 0xFF).to_rgb565_components(), [0x10, 0x1F, 0x1F]);
+	/// ```
+	#[inline]
+	#[must_use]
+	pub const fn from_rgb888_components_truncated(r: u8, g: u8, b: u8) -> Self { Self::from_rgb888_components_rounded(r, g, b, RoundingMode::BitReplicate) }
+
+	#[cfg(has_srgb_decode)]
 	#[inline]
 	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn from_srgb888_components(r: u8, g: u8, b: u8) -> Self { Self(lut::S888_TO_L565_LUT.map([r, g, b])) }
 
+	/// From 8-bit Display P3 components, as exported by most macOS and iOS
+	/// tooling. Colors outside of sRGB are clipped.
+	#[cfg(feature = "std")]
+	#[inline]
+	#[must_use]
+	pub fn from_display_p3_components(r: u8, g: u8, b: u8) -> Self { DisplayProfile::IDENTITY.from_display_p3(r, g, b) }
+
+	/// From 8-bit Adobe RGB (1998) components, as found in images from
+	/// photography tooling. Colors outside of sRGB are clipped.
+	#[cfg(feature = "std")]
+	#[inline]
+	#[must_use]
+	pub fn from_adobe_rgb_components(r: u8, g: u8, b: u8) -> Self { DisplayProfile::IDENTITY.from_adobe_rgb(r, g, b) }
+
+	/// From the color of a blackbody at the given temperature in kelvin, at full
+	/// brightness. Temperatures outside of 1667 K to 25000 K are clamped.
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn from_kelvin(kelvin: u32) -> Self { spectrum::kelvin_to_rgb565(kelvin) }
+
+	/// From the color of monochromatic light with the given wavelength in
+	/// nanometers. Wavelengths fade out towards the edges of the visible
+	/// spectrum, and anything outside of 380 nm to 780 nm is black.
+	#[cfg(feature = "std")]
+	#[inline]
+	#[must_use]
+	pub fn from_wavelength_nm(nm: u16) -> Self { spectrum::wavelength_to_rgb565(nm) }
+
+	/// Every RGB565 color, in order of their raw values.
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// assert_eq!(Rgb565::all().len(), 65536);
+	/// assert_eq!(Rgb565::all().step_by(0x800).nth(31), Some(Rgb565::from_rgb565(0xF800)));
+	/// ```
+	#[inline]
+	pub fn all() -> impl ExactSizeIterator<Item = Self> + DoubleEndedIterator + Clone { Self::range(..) }
+
+	/// The RGB565 colors whose raw values are within `range`, in order.
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// assert!(Rgb565::range(0xFFFE..).eq([0xFFFE, 0xFFFF].map(Rgb565::from_rgb565)));
+	/// ```
+	#[inline]
+	pub fn range(range: impl core::ops::RangeBounds<u16>) -> impl ExactSizeIterator<Item = Self> + DoubleEndedIterator + Clone {
+		use core::ops::Bound;
+
+		let start = match range.start_bound() {
+			Bound::Included(start) => *start as u32,
+			Bound::Excluded(start) => *start as u32 + 1,
+			Bound::Unbounded => 0
+		};
+
+		let end = match range.end_bound() {
+			Bound::Included(end) => *end as u32 + 1,
+			Bound::Excluded(end) => *end as u32,
+			Bound::Unbounded => 1 << 16
+		};
+
+		(start..end.max(start)).map(|raw| Self(raw as u16))
+	}
+
 	/// To rgb565 components, where r fits into 5 bits, g into 6 and b into 5
 	#[inline]
 	#[must_use]
-	pub fn to_rgb565_components(&self) -> [u8; 3] {
+	pub const fn to_rgb565_components(&self) -> [u8; 3] {
 		let (r, g, b) = Self::unpack_565(self.0);
 		[r, g, b]
 	}
 
+	/// The red channel, from 0 to 31.
 	#[inline]
 	#[must_use]
+	pub const fn r5(&self) -> u8 { (self.0 >> 11) as u8 }
+
+	/// The green channel, from 0 to 63.
+	#[inline]
+	#[must_use]
+	pub const fn g6(&self) -> u8 { (self.0 >> 5 & 0b111111) as u8 }
+
+	/// The blue channel, from 0 to 31.
+	#[inline]
+	#[must_use]
+	pub const fn b5(&self) -> u8 { (self.0 & 0b11111) as u8 }
+
+	/// The red channel, scaled to 8 bits like
+	/// [`to_rgb888_components`](Self::to_rgb888_components) but without
+	/// converting the other two.
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn r8(&self) -> u8 { lut::L5_TO_L8_LUT.map(self.r5()) }
+
+	/// The green channel, scaled to 8 bits like [`r8`](Self::r8).
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn g8(&self) -> u8 { lut::L6_TO_L8_LUT.map(self.g6()) }
+
+	/// The blue channel, scaled to 8 bits like [`r8`](Self::r8).
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn b8(&self) -> u8 { lut::L5_TO_L8_LUT.map(self.b5()) }
+
+	/// The red channel in 8-bit sRGB, like
+	/// [`to_srgb888_components`](Self::to_srgb888_components) but without
+	/// converting the other two.
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// let color = Rgb565::from_rgb565(0x8410);
+	/// assert_eq!([color.r8(), color.g8(), color.b8()], color.to_rgb888_components());
+	/// assert_eq!([color.r8_srgb(), color.g8_srgb(), color.b8_srgb()], color.to_srgb888_components());
+	/// ```
+	#[cfg(has_srgb_channels)]
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn r8_srgb(&self) -> u8 { lut::L5_TO_S8_LUT.map(self.r5()) }
+
+	/// The green channel in 8-bit sRGB, like [`r8_srgb`](Self::r8_srgb).
+	#[cfg(has_srgb_channels)]
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn g8_srgb(&self) -> u8 { lut::L6_TO_S8_LUT.map(self.g6()) }
+
+	/// The blue channel in 8-bit sRGB, like [`r8_srgb`](Self::r8_srgb).
+	#[cfg(has_srgb_channels)]
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn b8_srgb(&self) -> u8 { lut::L5_TO_S8_LUT.map(self.b5()) }
+
+	/// This color with its red channel replaced by `r5`, which fits into 5
+	/// bits.
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// let color = Rgb565::BLACK.with_r5(31).with_g6(63);
+	/// assert_eq!(color, Rgb565::YELLOW);
+	/// assert_eq!((color.r5(), color.g6(), color.b5()), (31, 63, 0));
+	/// ```
+	///
+	/// # Panics
+	///
+	/// Like [`from_rgb565_components`](Self::from_rgb565_components), this
+	/// panics in debug builds if `r5` is too big. In release builds, the extra
+	/// bits are dropped.
+	#[inline]
+	#[must_use]
+	pub const fn with_r5(self, r5: u8) -> Self {
+		debug_assert!(r5 & 0b11111 == r5, "r5 channel too wide");
+		Self(self.0 & 0x07FF | ((r5 & 0b11111) as u16) << 11)
+	}
+
+	/// This color with its green channel replaced by `g6`, which fits into 6
+	/// bits. Panics like [`with_r5`](Self::with_r5) if it doesn't.
+	#[inline]
+	#[must_use]
+	pub const fn with_g6(self, g6: u8) -> Self {
+		debug_assert!(g6 & 0b111111 == g6, "g6 channel too wide");
+		Self(self.0 & 0xF81F | ((g6 & 0b111111) as u16) << 5)
+	}
+
+	/// This color with its blue channel replaced by `b5`, which fits into 5
+	/// bits. Panics like [`with_r5`](Self::with_r5) if it doesn't.
+	#[inline]
+	#[must_use]
+	pub const fn with_b5(self, b5: u8) -> Self {
+		debug_assert!(b5 & 0b11111 == b5, "b5 channel too wide");
+		Self(self.0 & 0xFFE0 | (b5 & 0b11111) as u16)
+	}
+
+	/// Blends `over` on top of this color, from 0 for none of `over` to 255
+	/// for all of it.
+	///
+	/// This spreads the channels out across a `u32` and blends all three with
+	/// one multiply, which is about as fast as compositing gets, but only has
+	/// 33 levels of `alpha`, so each channel can be a level off from
+	/// [`blend_linear`](Self::blend_linear). 0 and 255 are always exact.
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// assert_eq!(Rgb565::BLACK.blend(Rgb565::WHITE, 255), Rgb565::WHITE);
+	/// assert_eq!(Rgb565::BLACK.blend(Rgb565::WHITE, 128).to_rgb565_components(), [16, 32, 16]);
+	/// ```
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn blend(self, over: Rgb565, alpha: u8) -> Self {
+		// Green goes in the top half, clear of red and blue, leaving room
+		// above each channel for it to be multiplied by up to 32.
+		const MASK: u32 = 0x07E0F81F;
+		const HALF: u32 = 16 << 21 | 16 << 11 | 16;
+
+		let spread = |color: Rgb565| (color.0 as u32 | (color.0 as u32) << 16) & MASK;
+		let a = (alpha as u32 + 4) >> 3;
+		let blended = (spread(over) * a + spread(self) * (32 - a) + HALF) >> 5 & MASK;
+		Self((blended | blended >> 16) as u16)
+	}
+
+	/// Blends `over` on top of this color like [`blend`](Self::blend), but
+	/// rounds each channel to the nearest level for every `alpha`, like
+	/// [`framebuffer::blend_coverage`]. The channels of RGB565 are linear
+	/// light, so this is correct compositing with no conversions needed.
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// assert_eq!(Rgb565::BLACK.blend_linear(Rgb565::WHITE, 100).to_rgb565_components(), [12, 25, 12]);
+	/// assert_eq!(Rgb565::BLACK.blend(Rgb565::WHITE, 100).to_rgb565_components(), [13, 26, 13]);
+	/// ```
+	#[inline]
+	#[must_use]
+	pub fn blend_linear(self, over: Rgb565, alpha: u8) -> Self { framebuffer::blend_coverage(self, over, alpha) }
+
+	/// The average of `a` and `b`, rounding each channel down, for
+	/// anti-aliasing or halving the size of a frame.
+	///
+	/// This averages all three channels at once without unpacking them:
+	/// `a & b` is the bits they share, and `(a ^ b) >> 1` is half of the bits
+	/// they don't. Masking off the lowest bit of each channel first stops the
+	/// shift from moving a bit into the channel below.
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// assert_eq!(Rgb565::average(Rgb565::BLACK, Rgb565::WHITE).to_rgb565_components(), [15, 31, 15]);
+	/// assert_eq!(Rgb565::average(Rgb565::RED, Rgb565::BLUE), Rgb565::from_rgb565_components(15, 0, 15));
+	/// ```
+	#[inline]
+	#[must_use]
+	pub const fn average(a: Rgb565, b: Rgb565) -> Rgb565 { Self((((a.0 ^ b.0) & 0xF7DE) >> 1) + (a.0 & b.0)) }
+
+	/// Interpolates from this color to `other`, from 0 for all of this color
+	/// to 255 for all of `other`, rounding each channel to the nearest level.
+	///
+	/// RGB565 is linear light, so this fades at an even brightness. It's the
+	/// same as blending `other` over this color with
+	/// [`blend_linear`](Self::blend_linear). For a fade that looks even to the
+	/// eye instead, see [`lerp_srgb`](Self::lerp_srgb).
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// assert_eq!(Rgb565::RED.lerp(Rgb565::BLUE, 0), Rgb565::RED);
+	/// assert_eq!(Rgb565::RED.lerp(Rgb565::BLUE, 255), Rgb565::BLUE);
+	/// assert_eq!(Rgb565::RED.lerp(Rgb565::BLUE, 128).to_rgb565_components(), [15, 0, 16]);
+	/// ```
+	#[inline]
+	#[must_use]
+	pub fn lerp(self, other: Rgb565, t: u8) -> Self { self.blend_linear(other, t) }
+
+	/// Interpolates from this color to `other` like [`lerp`](Self::lerp), but
+	/// with `t` from 0.0 to 1.0, for animations timed in seconds. `t` outside
+	/// of that range is clamped to it, and NaN counts as 0.0.
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// assert_eq!(Rgb565::BLACK.lerp_f32(Rgb565::WHITE, 0.5).to_rgb565_components(), [16, 32, 16]);
+	/// assert_eq!(Rgb565::BLACK.lerp_f32(Rgb565::WHITE, 2.0), Rgb565::WHITE);
+	/// ```
+	#[inline]
+	#[must_use]
+	pub fn lerp_f32(self, other: Rgb565, t: f32) -> Self {
+		let t = if t > 0.0 { t.min(1.0) } else { 0.0 };
+		let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t + 0.5) as u8;
+
+		let ([ar, ag, ab], [br, bg, bb]) = (self.to_rgb565_components(), other.to_rgb565_components());
+		Self::from_rgb565_components(channel(ar, br), channel(ag, bg), channel(ab, bb))
+	}
+
+	/// Interpolates from this color to `other` like [`lerp`](Self::lerp), but
+	/// in sRGB rather than linear light.
+	///
+	/// Eyes are more sensitive to changes in dark colors than bright ones, so
+	/// fades in linear light seem to rush through the dark end. sRGB spaces
+	/// its levels out closer to how they look, so fades through it look more
+	/// even, at the cost of passing through darker colors in between.
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// let (linear, srgb) = (Rgb565::BLACK.lerp(Rgb565::WHITE, 128), Rgb565::BLACK.lerp_srgb(Rgb565::WHITE, 128));
+	/// assert!(srgb.to_rgb565_components()[1] < linear.to_rgb565_components()[1]);
+	/// assert_eq!(Rgb565::RED.lerp_srgb(Rgb565::BLUE, 255), Rgb565::BLUE);
+	/// ```
+	#[cfg(all(has_srgb, has_srgb_decode))]
+	#[inline]
+	#[must_use]
+	pub fn lerp_srgb(self, other: Rgb565, t: u8) -> Self {
+		let (a, b) = (self.to_srgb888_components(), other.to_srgb888_components());
+		let [r, g, b] = [0, 1, 2].map(|i| ((a[i] as u16 * (255 - t as u16) + b[i] as u16 * t as u16 + 127) / 255) as u8);
+		Self::from_srgb888_components(r, g, b)
+	}
+
+	/// Interpolates from this color to `other` in sRGB like
+	/// [`lerp_srgb`](Self::lerp_srgb), with `t` from 0.0 to 1.0 like
+	/// [`lerp_f32`](Self::lerp_f32).
+	#[cfg(all(has_srgb, has_srgb_decode))]
+	#[inline]
+	#[must_use]
+	pub fn lerp_srgb_f32(self, other: Rgb565, t: f32) -> Self {
+		let t = if t > 0.0 { t.min(1.0) } else { 0.0 };
+		let (a, b) = (self.to_srgb888_components(), other.to_srgb888_components());
+		let [r, g, b] = [0, 1, 2].map(|i| (a[i] as f32 + (b[i] as f32 - a[i] as f32) * t + 0.5) as u8);
+		Self::from_srgb888_components(r, g, b)
+	}
+
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn to_rgb888_components(&self) -> [u8; 3] { lut::L565_TO_L888_LUT.map(self.0) }
 
-	#[cfg(any(feature = "std", feature = "l565_to_s888_lut"))]
+	/// The same as [`to_rgb888_components`](Self::to_rgb888_components), but
+	/// computed without LUTs so that it can be used in constants.
 	#[inline]
 	#[must_use]
+	pub const fn to_rgb888_components_const(&self) -> [u8; 3] { lut::transforms::l565_to_l888(self.0) }
+
+	/// Converts to 8-bit RGB, scaling each channel as `rounding` says. See
+	/// [`RoundingMode`].
+	#[inline]
+	#[must_use]
+	pub const fn to_rgb888_components_rounded(&self, rounding: RoundingMode) -> [u8; 3] {
+		let (r, g, b) = Self::unpack_565(self.0);
+		[rounding.expand(r, 5), rounding.expand(g, 6), rounding.expand(b, 5)]
+	}
+
+	/// Converts to 8-bit RGB by repeating the top bits of each channel in the
+	/// bits below them, as in `(r5 << 3) | (r5 >> 2)`. This is how most
+	/// display datasheets and GPU samplers expand RGB565, so it matches what
+	/// the hardware actually shows, where
+	/// [`to_rgb888_components`](Self::to_rgb888_components) can be off by one.
+	///
+	/// ```
+	/// # use rgb565::Rgb565;
+	/// let color = Rgb565::from_rgb565_components(0x10, 0x20, 0x1F);
+	/// assert_eq!(color.to_rgb888_components_replicated(), [0x84, 0x82, 0xFF]);
+	/// assert_eq!(color.to_rgb888_components(), [0x83, 0x81, 0xFF]);
+	/// ```
+	#[inline]
+	#[must_use]
+	pub const fn to_rgb888_components_replicated(&self) -> [u8; 3] { self.to_rgb888_components_rounded(RoundingMode::BitReplicate) }
+
+	#[cfg(has_srgb)]
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn to_srgb888_components(&self) -> [u8; 3] { lut::L565_TO_S888_LUT.map(self.0) }
 }
 
+impl core::fmt::Debug for Rgb565 {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		if !f.alternate() {
+			return f.debug_tuple("Rgb565").field(&self.0).finish();
+		}
+
+		let mut debug = f.debug_struct("Rgb565");
+		debug.field("raw", &format_args!("{:#06X}", self.0));
+		let [r, g, b] = self.to_rgb565_components();
+		debug.field("rgb565", &format_args!("({}, {}, {})", r, g, b));
+		let [r, g, b] = self.to_rgb888_components();
+		debug.field("rgb888", &format_args!("({}, {}, {})", r, g, b));
+
+		#[cfg(has_srgb)]
+		{
+			let [r, g, b] = self.to_srgb888_components();
+			debug.field("css", &format_args!("#{:02X}{:02X}{:02X}", r, g, b));
+		}
+
+		debug.finish()
+	}
+}
+
+/// Writes `color` as `#RRGGBB`, or as its raw value like `0xF800` for `{:#}`,
+/// padded and aligned as `f` asks.
+fn fmt_hex(color: &Rgb565, f: &mut core::fmt::Formatter, digits: &[u8; 16]) -> core::fmt::Result {
+	let (prefix, len, value) = match f.alternate() {
+		true => ("0x", 4, color.0 as u32),
+		false => {
+			let [r, g, b] = color.to_rgb888_components();
+			("#", 6, u32::from_be_bytes([0, r, g, b]))
+		}
+	};
+
+	let mut buffer = [0; 8];
+	buffer[..prefix.len()].copy_from_slice(prefix.as_bytes());
+	let hex = &mut buffer[prefix.len()..prefix.len() + len];
+
+	for (i, digit) in hex.iter_mut().rev().enumerate() {
+		*digit = digits[(value >> (i * 4) & 0xF) as usize];
+	}
+
+	f.pad(core::str::from_utf8(&buffer[..prefix.len() + len]).unwrap_or_default())
+}
+
+/// Prints the color as CSS-style hex after converting it to 8-bit RGB like
+/// [`Rgb565::to_rgb888_components`], or as its raw value with `{:#}`. Both
+/// forms parse back with [`FromStr`](core::str::FromStr).
+///
+/// The hex is linear 8-bit RGB, like the named constants, and not a CSS color:
+/// the hex a browser needs to show the same color is the sRGB one that `{:#?}`
+/// prints as `css`, which differs in any channel that isn't fully off or on.
+/// Linear is used here because it's always available and parses back exactly.
+///
+/// ```
+/// # use rgb565::Rgb565;
+/// assert_eq!(format!("{}", Rgb565::OLIVE), "#7B7D00");
+/// assert_eq!(format!("{:#}", Rgb565::OLIVE), "0x7BE0");
+/// assert_eq!(format!("{:x}", Rgb565::OLIVE), "#7b7d00");
+/// assert_eq!(format!("{:#x}", Rgb565::TEAL), "0x03ef");
+///
+/// let gray = Rgb565::from_rgb565(0x8410);
+/// assert_eq!(format!("{}", gray), "#838183");
+/// # #[cfg(has_srgb)]
+/// assert!(format!("{:#?}", gray).contains("css: #BEBCBE"));
+/// ```
+impl core::fmt::Display for Rgb565 {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result { core::fmt::UpperHex::fmt(self, f) }
+}
+
+/// The same as [`Display`](core::fmt::Display), in lowercase.
+impl core::fmt::LowerHex for Rgb565 {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result { fmt_hex(self, f, b"0123456789abcdef") }
+}
+
+/// The same as [`Display`](core::fmt::Display).
+impl core::fmt::UpperHex for Rgb565 {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result { fmt_hex(self, f, b"0123456789ABCDEF") }
+}
+
+/// An error from [`Rgb565::try_pack_565`] and
+/// [`Rgb565::try_from_rgb565_components`], holding the value of the first
+/// channel that doesn't fit.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ChannelOverflowError {
+	/// Red is above 31.
+	Red(u8),
+	/// Green is above 63.
+	Green(u8),
+	/// Blue is above 31.
+	Blue(u8)
+}
+
+impl core::fmt::Display for ChannelOverflowError {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match self {
+			Self::Red(r5) => write!(f, "red channel {} doesn't fit in 5 bits", r5),
+			Self::Green(g6) => write!(f, "green channel {} doesn't fit in 6 bits", g6),
+			Self::Blue(b5) => write!(f, "blue channel {} doesn't fit in 5 bits", b5)
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ChannelOverflowError {}
+
+/// An error from parsing an [`Rgb565`] from a string.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ParseColorError {
+	/// The string doesn't start with `#` or `0x`.
+	MissingPrefix,
+	/// The string has the wrong number of digits for its form.
+	InvalidLength,
+	/// The string contains a character that isn't a hex digit.
+	InvalidDigit(char)
+}
+
+impl core::fmt::Display for ParseColorError {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match self {
+			Self::MissingPrefix => f.write_str("color doesn't start with `#` or `0x`"),
+			Self::InvalidLength => f.write_str("wrong number of digits for a color"),
+			Self::InvalidDigit(c) => write!(f, "invalid hex digit {:?} in color", c)
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseColorError {}
+
+/// Parses `#RGB` and `#RRGGBB` as 8-bit RGB, converted like
+/// [`Rgb565::from_rgb888_components`] and the named colors, or `0xF800` as the
+/// raw RGB565 value with up to four digits. Digits may be either case.
+///
+/// ```
+/// # use rgb565::Rgb565;
+/// assert_eq!("#F00".parse(), Ok(Rgb565::RED));
+/// assert_eq!("#00ff00".parse(), Ok(Rgb565::LIME));
+/// assert_eq!("0x001F".parse(), Ok(Rgb565::BLUE));
+/// assert!("F800".parse::<Rgb565>().is_err());
+/// ```
+impl core::str::FromStr for Rgb565 {
+	type Err = ParseColorError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (digits, raw) = match (s.strip_prefix('#'), s.strip_prefix("0x").or_else(|| s.strip_prefix("0X"))) {
+			(Some(digits), _) => (digits, false),
+			(_, Some(digits)) => (digits, true),
+			_ => return Err(ParseColorError::MissingPrefix)
+		};
+
+		let value = digits.chars().try_fold(0u32, |value, c| Ok(value << 4 | c.to_digit(16).ok_or(ParseColorError::InvalidDigit(c))?))?;
+
+		match (raw, digits.len()) {
+			(true, 1..=4) => Ok(Self(value as u16)),
+			(false, 3) => {
+				let [r, g, b] = [8, 4, 0].map(|shift| (value >> shift & 0xF) as u8 * 0x11);
+				Ok(Self::from_rgb888_components(r, g, b))
+			}
+			(false, 6) => {
+				let [_, r, g, b] = value.to_be_bytes();
+				Ok(Self::from_rgb888_components(r, g, b))
+			}
+			_ => Err(ParseColorError::InvalidLength)
+		}
+	}
+}
+
+/// Prints the raw value, like `Debug` without `#`.
+#[cfg(feature = "ufmt")]
+impl ufmt::uDebug for Rgb565 {
+	fn fmt<W: ufmt::uWrite + ?Sized>(&self, f: &mut ufmt::Formatter<W>) -> Result<(), W::Error> { f.debug_tuple("Rgb565")?.field(&self.0)?.finish() }
+}
+
+/// Prints the raw value in hex, like `0x07E0`.
+#[cfg(feature = "ufmt")]
+impl ufmt::uDisplay for Rgb565 {
+	fn fmt<W: ufmt::uWrite + ?Sized>(&self, f: &mut ufmt::Formatter<W>) -> Result<(), W::Error> { ufmt::uwrite!(f, "0x{:04X}", self.0) }
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::Rgb565;
 
+	#[test]
+	#[cfg(has_srgb)]
+	fn debug() {
+		let color = Rgb565::from_rgb565(0x07E0);
+		assert_eq!(format!("{:?}", color), "Rgb565(2016)");
+		let [r, g, b] = color.to_srgb888_components();
+		let css = format!("#{:02X}{:02X}{:02X}", r, g, b);
+		assert_eq!(format!("{:#?}", color), format!("Rgb565 {{\n    raw: 0x07E0,\n    rgb565: (0, 63, 0),\n    rgb888: (0, 255, 0),\n    css: {},\n}}", css));
+	}
+
+	#[test]
+	#[cfg(feature = "ufmt")]
+	fn ufmt() {
+		struct Buffer(String);
+
+		impl ufmt::uWrite for Buffer {
+			type Error = core::convert::Infallible;
+
+			fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+				self.0.push_str(s);
+				Ok(())
+			}
+		}
+
+		let mut buffer = Buffer(String::new());
+		let color = Rgb565::from_rgb565(0x07E0);
+		ufmt::uwrite!(buffer, "{:?} {}", color, color).unwrap();
+		assert_eq!(buffer.0, "Rgb565(2016) 0x07E0");
+	}
+
+	#[test]
+	fn all() {
+		assert!(Rgb565::all().map(|color| color.to_rgb565() as u32).eq(0..65536));
+		assert_eq!(Rgb565::all().next_back(), Some(Rgb565::from_rgb565(0xFFFF)));
+		assert_eq!(Rgb565::range(0x10..=0x1F).len(), 16);
+		assert_eq!(Rgb565::range(5..5).len(), 0);
+		assert_eq!(Rgb565::range(..=u16::MAX).len(), 65536);
+	}
+
+	#[test]
+	fn try_pack() {
+		use super::ChannelOverflowError;
+
+		for (r, g, b) in [(0, 0, 0), (31, 63, 31), (17, 42, 9)] {
+			assert_eq!(Rgb565::try_pack_565((r, g, b)), Ok(Rgb565::pack_565((r, g, b))));
+			assert_eq!(Rgb565::try_from_rgb565_components(r, g, b), Ok(Rgb565::from_rgb565_components(r, g, b)));
+		}
+
+		assert_eq!(Rgb565::try_pack_565((32, 64, 32)), Err(ChannelOverflowError::Red(32)));
+		assert_eq!(Rgb565::try_pack_565((31, 255, 32)), Err(ChannelOverflowError::Green(255)));
+		assert_eq!(Rgb565::try_from_rgb565_components(0, 0, 200), Err(ChannelOverflowError::Blue(200)));
+		assert_eq!(ChannelOverflowError::Green(64).to_string(), "green channel 64 doesn't fit in 6 bits");
+	}
+
+	#[test]
+	fn clamped_and_masked() {
+		for (r, g, b) in [(0, 0, 0), (31, 63, 31), (17, 42, 9)] {
+			assert_eq!(Rgb565::from_rgb565_components_clamped(r, g, b), Rgb565::from_rgb565_components(r, g, b));
+			assert_eq!(Rgb565::from_rgb565_components_masked(r, g, b), Rgb565::from_rgb565_components(r, g, b));
+		}
+
+		assert_eq!(Rgb565::from_rgb565_components_clamped(255, 255, 255), Rgb565::WHITE);
+		assert_eq!(Rgb565::from_rgb565_components_clamped(32, 0, 200).to_rgb565_components(), [31, 0, 31]);
+		assert_eq!(Rgb565::from_rgb565_components_masked(255, 255, 255), Rgb565::WHITE);
+		assert_eq!(Rgb565::from_rgb565_components_masked(32, 65, 33).to_rgb565_components(), [0, 1, 1]);
+	}
+
+	#[test]
+	fn rounding() {
+		use super::RoundingMode;
+
+		let modes = [RoundingMode::Truncate, RoundingMode::Nearest, RoundingMode::BitReplicate];
+
+		for mode in modes {
+			assert_eq!(Rgb565::WHITE.to_rgb888_components_rounded(mode), [255; 3]);
+			assert_eq!(Rgb565::from_rgb888_components_rounded(255, 255, 255, mode), Rgb565::WHITE);
+			assert_eq!(Rgb565::from_rgb888_components_rounded(0, 0, 0, mode), Rgb565::BLACK);
+		}
+
+		// Truncating both ways loses a level on the way back, but the others
+		// reduce whatever they expand back to where it started.
+		for mode in &modes[1..] {
+			for color in Rgb565::all() {
+				let [r, g, b] = color.to_rgb888_components_rounded(*mode);
+				assert_eq!(Rgb565::from_rgb888_components_rounded(r, g, b, *mode), color, "{:?}", mode);
+			}
+		}
+
+		let one = Rgb565::from_rgb565_components(1, 1, 1);
+		let [r, g, b] = one.to_rgb888_components_rounded(RoundingMode::Truncate);
+		assert_eq!(Rgb565::from_rgb888_components_rounded(r, g, b, RoundingMode::Truncate), Rgb565::BLACK);
+
+		for value in 0..=255u8 {
+			let nearest = (0..32).min_by_key(|level| (*level as i32 * 255 - value as i32 * 31).abs()).unwrap();
+			assert_eq!(RoundingMode::Nearest.reduce(value, 5), nearest, "{}", value);
+			assert_eq!(RoundingMode::Truncate.reduce(value, 6), (value as f32 * 63.0 / 255.0) as u8);
+			assert_eq!(RoundingMode::BitReplicate.reduce(value, 5), value >> 3);
+		}
+
+		for mode in modes {
+			assert_eq!((mode.expand(0xF, 4), mode.reduce(255, 1)), (255, 1));
+			assert_eq!((mode.expand(0xAB, 8), mode.reduce(0xAB, 8)), (0xAB, 0xAB));
+
+			for bits in [0, 9] {
+				assert!(std::panic::catch_unwind(|| mode.expand(0, bits)).is_err());
+				assert!(std::panic::catch_unwind(|| mode.reduce(0, bits)).is_err());
+			}
+		}
+
+		assert_eq!(RoundingMode::Nearest.expand(1, 1), 255);
+		assert_eq!(RoundingMode::BitReplicate.expand(0b1001, 4), 0b10011001);
+		assert!(std::panic::catch_unwind(|| RoundingMode::BitReplicate.expand(0, 3)).is_err());
+
+		for level in 0..64u8 {
+			assert_eq!(RoundingMode::Truncate.expand(level, 6), crate::lut::transforms::l6_to_l8(level));
+			assert_eq!(RoundingMode::Nearest.expand(level, 6), (level as f32 * 255.0 / 63.0).round() as u8);
+			assert_eq!(RoundingMode::BitReplicate.expand(level, 6), level << 2 | level >> 4);
+		}
+	}
+
+	#[test]
+	fn replicated() {
+		for color in Rgb565::all() {
+			let [r5, g6, b5] = color.to_rgb565_components();
+			let [r, g, b] = color.to_rgb888_components_replicated();
+			assert_eq!([r, g, b], [r5 << 3 | r5 >> 2, g6 << 2 | g6 >> 4, b5 << 3 | b5 >> 2]);
+			assert_eq!(Rgb565::from_rgb888_components_truncated(r, g, b), color);
+		}
+
+		for value in 0..=255 {
+			assert_eq!(Rgb565::from_rgb888_components_truncated(value, value, value).to_rgb565_components(), [value >> 3, value >> 2, value >> 3]);
+		}
+	}
+
+	#[test]
+	fn blend() {
+		let colors = [Rgb565::BLACK, Rgb565::WHITE, Rgb565::OLIVE, Rgb565::TEAL, Rgb565::from_rgb565(0xA65D), Rgb565::from_rgb565(0x1234)];
+
+		for dst in colors {
+			for over in colors {
+				assert_eq!(dst.blend(over, 0), dst);
+				assert_eq!(dst.blend(over, 255), over);
+
+				for alpha in 0..=255 {
+					let fast = dst.blend(over, alpha).to_rgb565_components();
+					let linear = dst.blend_linear(over, alpha).to_rgb565_components();
+					assert!(fast.iter().zip(linear).all(|(fast, linear)| fast.abs_diff(linear) <= 1), "{:?} over {:?} at {}", over, dst, alpha);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn lerp() {
+		let colors = [Rgb565::BLACK, Rgb565::WHITE, Rgb565::OLIVE, Rgb565::TEAL, Rgb565::from_rgb565(0xA65D), Rgb565::from_rgb565(0x1234)];
+
+		for a in colors {
+			for b in colors {
+				for t in 0..=255 {
+					let float = a.lerp_f32(b, t as f32 / 255.0).to_rgb565_components();
+					assert!(float.iter().zip(a.lerp(b, t).to_rgb565_components()).all(|(float, int)| float.abs_diff(int) <= 1));
+				}
+
+				assert_eq!((a.lerp(b, 0), a.lerp(b, 255)), (a, b));
+				assert_eq!(a.lerp_f32(b, 1.0), b);
+				assert_eq!((a.lerp_f32(b, -1.0), a.lerp_f32(b, f32::NAN)), (a, a));
+
+				#[cfg(all(has_srgb, has_srgb_decode))]
+				{
+					assert_eq!((a.lerp_srgb(b, 0), a.lerp_srgb(b, 255)), (a, b));
+					assert_eq!((a.lerp_srgb_f32(b, 0.0), a.lerp_srgb_f32(b, 1.0)), (a, b));
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn average() {
+		// The only way one channel can affect another is by a carry out of
+		// the addition, which would leave that channel wrong. So if every
+		// pair of values of each channel averages right, with the other
+		// channels at both extremes, so does every pair of colors.
+		for (shift, max) in [(0, 31), (5, 63), (11, 31)] {
+			for others in [0, 0xFFFF] {
+				let others = others & !(max << shift);
+
+				for x in 0..=max {
+					for y in 0..=max {
+						let (a, b) = (Rgb565::from_rgb565(others | x << shift), Rgb565::from_rgb565(others | y << shift));
+						assert_eq!(Rgb565::average(a, b), Rgb565::from_rgb565(others | ((x + y) / 2) << shift), "{:?} {:?}", a, b);
+					}
+				}
+			}
+		}
+
+		for color in Rgb565::all() {
+			assert_eq!(Rgb565::average(color, color), color);
+			let [r, g, b] = color.to_rgb565_components();
+			let [r2, g2, b2] = Rgb565::from_rgb565(!color.to_rgb565()).to_rgb565_components();
+			assert_eq!(Rgb565::average(color, Rgb565::from_rgb565(!color.to_rgb565())).to_rgb565_components(), [(r + r2) / 2, (g + g2) / 2, (b + b2) / 2]);
+		}
+	}
+
+	#[test]
+	#[cfg_attr(debug_assertions, ignore = "takes minutes without optimizations, run it with --release")]
+	fn average_exhaustive() {
+		let channel = |a: u16, b: u16, mask: u16| ((u32::from(a & mask) + u32::from(b & mask)) / 2) as u16 & mask;
+
+		for a in 0..=u16::MAX {
+			for b in 0..=u16::MAX {
+				let expected = channel(a, b, 0xF800) | channel(a, b, 0x07E0) | channel(a, b, 0x001F);
+				assert_eq!(Rgb565::average(Rgb565::from_rgb565(a), Rgb565::from_rgb565(b)).to_rgb565(), expected, "{:04X} {:04X}", a, b);
+			}
+		}
+	}
+
+	#[test]
+	fn channels() {
+		for raw in [0x0000, 0x1234, 0x8410, 0xA65D, 0xFFFF] {
+			let color = Rgb565::from_rgb565(raw);
+			assert_eq!([color.r5(), color.g6(), color.b5()], color.to_rgb565_components());
+
+			for (r5, g6, b5) in [(0, 0, 0), (31, 63, 31), (17, 42, 9)] {
+				assert_eq!(color.with_r5(r5).to_rgb565_components(), [r5, color.g6(), color.b5()]);
+				assert_eq!(color.with_g6(g6).to_rgb565_components(), [color.r5(), g6, color.b5()]);
+				assert_eq!(color.with_b5(b5).to_rgb565_components(), [color.r5(), color.g6(), b5]);
+			}
+		}
+	}
+
+	#[test]
+	fn channels_8() {
+		for color in Rgb565::all() {
+			assert_eq!([color.r8(), color.g8(), color.b8()], color.to_rgb888_components());
+			#[cfg(has_srgb_channels)]
+			assert_eq!([color.r8_srgb(), color.g8_srgb(), color.b8_srgb()], color.to_srgb888_components());
+		}
+	}
+
+	#[test]
+	fn display() {
+		let color = Rgb565::from_rgb888_components(0xAB, 0xCD, 0xEF);
+		assert_eq!(format!("{}", color), "#A4CAEE");
+		assert_eq!(format!("{:x}", color), "#a4caee");
+		assert_eq!(format!("{:#}", color), "0xA65D");
+		assert_eq!(format!("{:#x}", color), "0xa65d");
+		assert_eq!(format!("{:#X}", Rgb565::BLUE), "0x001F");
+		assert_eq!(format!("{:>9}|{:<8}|", Rgb565::WHITE, Rgb565::BLACK), "  #FFFFFF|#000000 |");
+
+		for raw in [0x0000, 0x8410, 0xAE7D, 0xFFFF] {
+			let color = Rgb565::from_rgb565(raw);
+			assert_eq!(format!("{}", color).parse(), Ok(color));
+			assert_eq!(format!("{:#x}", color).parse(), Ok(color));
+		}
+	}
+
+	#[test]
+	fn from_str() {
+		use super::ParseColorError;
+
+		assert_eq!("#fff".parse(), Ok(Rgb565::WHITE));
+		assert_eq!("#808080".parse(), Ok(Rgb565::GRAY));
+		assert_eq!("#8A8".parse(), Ok(Rgb565::from_rgb888_components(0x88, 0xAA, 0x88)));
+		assert_eq!("0X7e0".parse(), Ok(Rgb565::LIME));
+		assert_eq!("0x0".parse(), Ok(Rgb565::BLACK));
+
+		assert_eq!("808080".parse::<Rgb565>(), Err(ParseColorError::MissingPrefix));
+		assert_eq!("#8080".parse::<Rgb565>(), Err(ParseColorError::InvalidLength));
+		assert_eq!("#".parse::<Rgb565>(), Err(ParseColorError::InvalidLength));
+		assert_eq!("0x".parse::<Rgb565>(), Err(ParseColorError::InvalidLength));
+		assert_eq!("0x12345".parse::<Rgb565>(), Err(ParseColorError::InvalidLength));
+		assert_eq!("#80808g".parse::<Rgb565>(), Err(ParseColorError::InvalidDigit('g')));
+		assert_eq!("#ÿÿÿ".parse::<Rgb565>(), Err(ParseColorError::InvalidDigit('ÿ')));
+	}
+
+	#[test]
+	fn named_colors() {
+		assert_eq!([Rgb565::BLACK, Rgb565::WHITE, Rgb565::RED, Rgb565::LIME, Rgb565::BLUE].map(|color| color.to_rgb565()), [0x0000, 0xFFFF, 0xF800, 0x07E0, 0x001F]);
+		assert_eq!([Rgb565::CYAN, Rgb565::MAGENTA, Rgb565::YELLOW].map(|color| color.to_rgb565()), [0x07FF, 0xF81F, 0xFFE0]);
+		assert_eq!(Rgb565::GRAY, Rgb565::from_rgb888_components(128, 128, 128));
+		assert_eq!(Rgb565::TEAL.to_rgb565_components(), [0, 31, 15]);
+		assert_eq!(Rgb565::GREEN.to_rgb565_components(), [0, 31, 0]);
+	}
+
+	#[test]
+	fn const_conversions() {
+		const RED: Rgb565 = Rgb565::from_rgb565_components(31, 0, 0);
+		assert_eq!(RED, Rgb565::from_rgb565(0xF800));
+
+		for color in Rgb565::all() {
+			assert_eq!(color.to_rgb888_components_const(), color.to_rgb888_components());
+		}
+
+		for i in (0..1 << 24).step_by(7) {
+			let [_, r, g, b] = u32::to_be_bytes(i);
+			assert_eq!(Rgb565::from_rgb888_components_const(r, g, b), Rgb565::from_rgb888_components(r, g, b));
+		}
+	}
+
 	#[test]
 	fn round_trip_rgb() {
 		for i in 0..=u16::MAX {
@@ -210,8 +1491,9 @@ mod tests {
 		}
 	}
 
-	// sRGB is weird. This test doesn't pass right now. I don't think it ever will
-	//#[test]
+	#[test]
+	#[ignore = "sRGB is weird. This test doesn't pass right now. I don't think it ever will"]
+	#[cfg(all(has_srgb, has_srgb_decode))]
 	fn round_trip_srgb() {
 		for i in 0..=u16::MAX {
 			let rgb565 = Rgb565::from_rgb565(i);
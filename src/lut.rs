@@ -1,103 +1,251 @@
 #![allow(unused)]
 
 pub use transforms::{pack_565, unpack_565};
-use with_std::{srgb_transfer, srgb_untransfer};
+pub use with_std::{srgb_transfer, srgb_untransfer};
 
-#[cfg(feature = "std")]
+#[cfg(any(feature = "std", feature = "libm", feature = "fast_srgb"))]
 #[path = "./std.rs"]
 mod with_std;
 
-#[cfg(not(feature = "std"))]
+#[cfg(not(any(feature = "std", feature = "libm", feature = "fast_srgb")))]
 mod with_std {
-	fn srgb_transfer(v: f32) -> f32 { unimplemented!() }
+	pub fn srgb_transfer(v: f32) -> f32 { unimplemented!() }
 
-	fn srgb_untransfer(v: f32) -> f32 { unimplemented!() }
+	pub fn srgb_untransfer(v: f32) -> f32 { unimplemented!() }
 }
 
+#[cfg(feature = "fast_srgb")]
+#[path = "./fast_srgb.rs"]
+mod fast_srgb;
+
+/// `f32::powf`, which needs `std`, or the one from `libm` without it, unless
+/// `fast_srgb` asks for the approximation instead.
+#[cfg(all(not(feature = "fast_srgb"), feature = "std"))]
+#[inline]
+fn powf(x: f32, y: f32) -> f32 { x.powf(y) }
+
+#[cfg(all(not(feature = "fast_srgb"), not(feature = "std"), feature = "libm"))]
+#[inline]
+fn powf(x: f32, y: f32) -> f32 { libm::powf(x, y) }
+
+#[cfg(feature = "fast_srgb")]
+use fast_srgb::powf;
+
+#[cfg(feature = "fixed_srgb")]
+use crate::fixed_srgb;
+
 #[path = "./transforms.rs"]
-mod transforms;
+pub mod transforms;
 
 /// `Lutable` represents a transformation that may or may not be backed by a
 /// look-up table (LUT) depending on the features that were enabled for this
 /// crate.
-pub struct Lutable<I: MapIn, O: MapOut<S>, const N: usize, const S: usize>(Result<&'static [u8; N], fn(I) -> O>);
+pub struct Lutable<I: MapIn, O: MapOut<S> + 'static, const N: usize, const S: usize>(Result<Table<O, N>, fn(I) -> O>);
+
+/// The look-up table backing a [`Lutable`]. This is an array of the output
+/// type for the tables computed at compile time, and the raw bytes of the
+/// table for the ones generated by the build script.
+pub enum Table<O: 'static, const N: usize> {
+	Bytes(&'static [u8; N]),
+	Typed(&'static [O])
+}
 
 /// Trait for values that can be looked up in a LUT.
 pub trait MapIn {
-	#[inline]
 	fn map_in(self) -> usize;
 }
 
 /// Trait for values that can be retrieved from a LUT.
-pub trait MapOut<const N: usize> {
-	#[inline]
+pub trait MapOut<const N: usize>: Sized {
 	fn map_out(bytes: [u8; N]) -> Self;
+
+	/// Returns a typed table as the bytes it would have been embedded as, or
+	/// `None` if its layout in memory is different.
+	fn table_bytes(table: &'static [Self]) -> Option<&'static [u8]>;
 }
 
 impl MapIn for u8 {
+	#[inline]
 	fn map_in(self) -> usize { self as usize }
 }
 
 impl MapIn for u16 {
+	#[inline]
 	fn map_in(self) -> usize { self as usize }
 }
 
 impl MapIn for [u8; 3] {
+	#[inline]
 	fn map_in(self) -> usize { u32::from_be_bytes([0, self[0], self[1], self[2]]) as usize }
 }
 
 impl MapOut<1> for u8 {
+	#[inline]
 	fn map_out(bytes: [u8; 1]) -> Self { bytes[0] }
+
+	#[inline]
+	fn table_bytes(table: &'static [Self]) -> Option<&'static [u8]> { Some(table) }
 }
 
 impl MapOut<2> for u16 {
+	#[inline]
 	fn map_out(bytes: [u8; 2]) -> Self { u16::from_le_bytes(bytes) }
+
+	#[inline]
+	fn table_bytes(table: &'static [Self]) -> Option<&'static [u8]> {
+		// SAFETY: `u16` has no padding and every byte of it is initialized, and
+		// `u8` has no alignment requirement. On little-endian targets the bytes
+		// are in the same order as the embedded ones.
+		#[cfg(target_endian = "little")]
+		return Some(unsafe { core::slice::from_raw_parts(table.as_ptr().cast(), core::mem::size_of_val(table)) });
+		#[cfg(target_endian = "big")]
+		return None;
+	}
 }
 
 impl MapOut<3> for [u8; 3] {
+	#[inline]
 	fn map_out(bytes: [u8; 3]) -> Self { bytes }
+
+	#[inline]
+	fn table_bytes(table: &'static [Self]) -> Option<&'static [u8]> { Some(table.as_flattened()) }
 }
 
-impl<I: MapIn, O: MapOut<S>, const N: usize, const S: usize> Lutable<I, O, N, S> {
+impl<I: MapIn, O: MapOut<S> + Copy + 'static, const N: usize, const S: usize> Lutable<I, O, N, S> {
+	/// Maps `value` through the LUT, or through the transform if there is no
+	/// LUT. Values that are out of range for the LUT wrap around instead of
+	/// panicking, so this never panics.
 	#[inline]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
 	pub fn map(&self, value: I) -> O {
 		match &self.0 {
-			Ok(lut) => {
-				let index = value.map_in() * S;
-				let bytes: [u8; S] = unsafe { lut[index..index + S].try_into().unwrap_unchecked() };
-				O::map_out(bytes)
+			Ok(Table::Bytes(lut)) => {
+				let index = value.map_in() % (N / S) * S;
+
+				match lut.get(index..).and_then(<[u8]>::first_chunk::<S>) {
+					Some(bytes) => O::map_out(*bytes),
+					None => O::map_out([0; S])
+				}
+			}
+
+			Ok(Table::Typed(lut)) => {
+				match lut.get(value.map_in() % (N / S)) {
+					Some(entry) => *entry,
+					None => O::map_out([0; S])
+				}
 			}
 
 			Err(transform) => transform(value)
 		}
 	}
+
+	/// Returns the raw bytes of the LUT, or `None` if the LUT isn't included.
+	#[inline]
+	pub fn bytes(&self) -> Option<&'static [u8]> {
+		match self.0 {
+			Ok(Table::Bytes(lut)) => Some(lut),
+			Ok(Table::Typed(lut)) => O::table_bytes(lut),
+			Err(_) => None
+		}
+	}
 }
 
-macro_rules! lutable {
-	{$($name:literal: $ident:ident[$size:literal $(* $mult:literal)?] => $i:ty, $o:ty => $func:expr);+;} => {
-$(
-#[cfg(feature = $name)]
-pub const $ident: Lutable<$i, $o, { $size $(* $mult)? }, { 1 $(- 1 + $mult)? }> = Lutable(Ok(include_bytes!(concat!(env!("OUT_DIR"), "/", $name, ".bin"))));
-#[cfg(not(feature = $name))]
-pub const $ident: Lutable<$i, $o, { $size $(* $mult)? }, { 1 $(- 1 + $mult)? }> = Lutable(Err($func));
-)+
+/// Converts RGB565 to 8-bit RGB using the per-channel LUTs, which is what
+/// `L565_TO_L888_LUT` falls back to when its 192 KiB table isn't included.
+fn l565_to_l888_composed(l565: u16) -> [u8; 3] {
+	let (r, g, b) = unpack_565(l565);
+	[L5_TO_L8_LUT.map(r), L6_TO_L8_LUT.map(g), L5_TO_L8_LUT.map(b)]
+}
+
+/// Converts RGB565 to 8-bit sRGB using the per-channel LUTs, which is what
+/// `L565_TO_S888_LUT` falls back to when its 192 KiB table isn't included.
+fn l565_to_s888_composed(l565: u16) -> [u8; 3] {
+	let (r, g, b) = unpack_565(l565);
+	[L5_TO_S8_LUT.map(r), L6_TO_S8_LUT.map(g), L5_TO_S8_LUT.map(b)]
+}
+
+/// The 8-bit sRGB of every 5-bit and 6-bit channel, from which
+/// `l565_to_s888_const` builds `L565_TO_S888_LUT` without evaluating the curve
+/// 65536 times over at compile time.
+const L5_TO_S8: [u8; 32] = {
+	let mut table = [0; 32];
+	let mut i = 0;
+
+	while i < 32 {
+		table[i] = crate::fixed_srgb::l5_to_s8(i as u8);
+		i += 1;
 	}
+
+	table
+};
+
+const L6_TO_S8: [u8; 64] = {
+	let mut table = [0; 64];
+	let mut i = 0;
+
+	while i < 64 {
+		table[i] = crate::fixed_srgb::l6_to_s8(i as u8);
+		i += 1;
+	}
+
+	table
+};
+
+const fn l565_to_s888_const(l565: u16) -> [u8; 3] {
+	let (r, g, b) = unpack_565(l565);
+	[L5_TO_S8[r as usize], L6_TO_S8[g as usize], L5_TO_S8[b as usize]]
+}
+
+macro_rules! lutable {
+	{$($name:literal: $ident:ident[$size:literal $(* $mult:literal)?] $(as $bytes:ident)? => $i:ty, $o:ty => $func:expr $(, const $gen:path)?);+;} => {$(
+		lutable!(@lut $($bytes)? $(const $gen)? [$name, $ident, $i, $o, $size, { $size $(* $mult)? }, { 1 $(- 1 + $mult)? }, $func]);
+	)+};
+
+	// Generated at compile time by calling `$gen` for every input, so these
+	// are always typed.
+	(@lut const $gen:path [$name:literal, $ident:ident, $i:ty, $o:ty, $entries:literal, $n:tt, $s:tt, $func:expr]) => {
+#[cfg(lut = $name)]
+pub const $ident: Lutable<$i, $o, $n, $s> = Lutable(Ok(Table::Typed({
+	static TABLE: [$o; $entries] = {
+		let mut table = [$gen(0); $entries];
+		let mut i = 0;
+
+		while i < $entries {
+			table[i] = $gen(i as $i);
+			i += 1;
+		}
+
+		table
+	};
+
+	&TABLE
+})));
+#[cfg(not(lut = $name))]
+pub const $ident: Lutable<$i, $o, $n, $s> = Lutable(Err($func));
+	};
+
+	(@lut bytes [$name:literal, $ident:ident, $i:ty, $o:ty, $entries:literal, $n:tt, $s:tt, $func:expr]) => {
+#[cfg(lut = $name)]
+pub const $ident: Lutable<$i, $o, $n, $s> = Lutable(Ok(Table::Bytes(include_bytes!(concat!(env!("OUT_DIR"), "/", $name, ".bin")))));
+#[cfg(not(lut = $name))]
+pub const $ident: Lutable<$i, $o, $n, $s> = Lutable(Err($func));
+	};
 }
 
 lutable! {
-	"swap_components_lut": SWAP_COMPONENTS_LUT[65536 * 2] => u16, u16 => transforms::swap_components;
-	"l5_to_l8_lut": L5_TO_L8_LUT[32] => u8, u8 => transforms::l5_to_l8;
-	"l6_to_l8_lut": L6_TO_L8_LUT[64] => u8, u8 => transforms::l6_to_l8;
-	"l5_to_s8_lut": L5_TO_S8_LUT[32] => u8, u8 => transforms::l5_to_s8;
-	"l6_to_s8_lut": L6_TO_S8_LUT[64] => u8, u8 => transforms::l6_to_s8;
-	"l565_to_l888_lut": L565_TO_L888_LUT[65536 * 3] => u16, [u8; 3] => transforms::l565_to_l888;
-	"l565_to_s888_lut": L565_TO_S888_LUT[65536 * 3] => u16, [u8; 3] => transforms::l565_to_s888;
-	"l8_to_l5_lut": L8_TO_L5_LUT[256] => u8, u8 => transforms::l8_to_l5;
-	"l8_to_l6_lut": L8_TO_L6_LUT[256] => u8, u8 => transforms::l8_to_l6;
-	"s8_to_l5_lut": S8_TO_L5_LUT[256] => u8, u8 => transforms::s8_to_l5;
-	"s8_to_l6_lut": S8_TO_L6_LUT[256] => u8, u8 => transforms::s8_to_l6;
-	"l888_to_l565_lut": L888_TO_L565_LUT[16777216 * 2] => [u8; 3], u16 => transforms::l888_to_l565;
-	"s888_to_l565_lut": S888_TO_L565_LUT[16777216 * 2] => [u8; 3], u16 => transforms::s888_to_l565;
+	"swap_components_lut": SWAP_COMPONENTS_LUT[65536 * 2] => u16, u16 => transforms::swap_components, const transforms::swap_components;
+	"l5_to_l8_lut": L5_TO_L8_LUT[32] => u8, u8 => transforms::l5_to_l8, const transforms::l5_to_l8;
+	"l6_to_l8_lut": L6_TO_L8_LUT[64] => u8, u8 => transforms::l6_to_l8, const transforms::l6_to_l8;
+	"l5_to_s8_lut": L5_TO_S8_LUT[32] => u8, u8 => transforms::l5_to_s8, const crate::fixed_srgb::l5_to_s8;
+	"l6_to_s8_lut": L6_TO_S8_LUT[64] => u8, u8 => transforms::l6_to_s8, const crate::fixed_srgb::l6_to_s8;
+	"l565_to_l888_lut": L565_TO_L888_LUT[65536 * 3] => u16, [u8; 3] => l565_to_l888_composed, const transforms::l565_to_l888;
+	"l565_to_s888_lut": L565_TO_S888_LUT[65536 * 3] => u16, [u8; 3] => l565_to_s888_composed, const l565_to_s888_const;
+	"l8_to_l5_lut": L8_TO_L5_LUT[256] => u8, u8 => transforms::l8_to_l5, const transforms::l8_to_l5;
+	"l8_to_l6_lut": L8_TO_L6_LUT[256] => u8, u8 => transforms::l8_to_l6, const transforms::l8_to_l6;
+	"s8_to_l5_lut": S8_TO_L5_LUT[256] => u8, u8 => transforms::s8_to_l5, const crate::fixed_srgb::s8_to_l5;
+	"s8_to_l6_lut": S8_TO_L6_LUT[256] => u8, u8 => transforms::s8_to_l6, const crate::fixed_srgb::s8_to_l6;
+	"l888_to_l565_lut": L888_TO_L565_LUT[16777216 * 2] as bytes => [u8; 3], u16 => transforms::l888_to_l565;
+	"s888_to_l565_lut": S888_TO_L565_LUT[16777216 * 2] as bytes => [u8; 3], u16 => transforms::s888_to_l565;
 }
 
 #[cfg(test)]
@@ -113,4 +261,37 @@ mod tests {
 		assert_eq!(SWAP_COMPONENTS_LUT.map(0b1111111111111111), 0b1111111111111111);
 		assert_eq!(SWAP_COMPONENTS_LUT.map(0b0000000000000000), 0b0000000000000000);
 	}
+
+	#[test]
+	#[cfg(lut = "l5_to_l8_lut")]
+	fn out_of_range_wraps() {
+		use super::L5_TO_L8_LUT;
+
+		assert_eq!(L5_TO_L8_LUT.map(0b100000), L5_TO_L8_LUT.map(0));
+		assert_eq!(L5_TO_L8_LUT.map(0b111111), L5_TO_L8_LUT.map(0b11111));
+	}
+
+	#[test]
+	fn composed_matches_transforms() {
+		for i in 0..=u16::MAX {
+			assert_eq!(super::l565_to_l888_composed(i), super::transforms::l565_to_l888(i));
+			#[cfg(has_srgb_math)]
+			assert_eq!(super::l565_to_s888_composed(i), super::transforms::l565_to_s888(i));
+		}
+	}
+
+	#[test]
+	#[cfg(all(has_srgb_math, lut = "l565_to_s888_lut", lut = "s8_to_l5_lut", lut = "s8_to_l6_lut"))]
+	fn const_srgb_luts_match_transforms() {
+		use super::{L565_TO_S888_LUT, S8_TO_L5_LUT, S8_TO_L6_LUT};
+
+		for i in 0..=u16::MAX {
+			assert_eq!(L565_TO_S888_LUT.map(i), super::transforms::l565_to_s888(i));
+		}
+
+		for i in 0..=u8::MAX {
+			assert_eq!(S8_TO_L5_LUT.map(i), super::transforms::s8_to_l5(i));
+			assert_eq!(S8_TO_L6_LUT.map(i), super::transforms::s8_to_l6(i));
+		}
+	}
 }
@@ -0,0 +1,109 @@
+//! Raw access to the look-up tables, for uploading them to hardware lookup
+//! engines such as the DMA2D CLUT on STM32 or the gamma tables of a display
+//! controller.
+//!
+//! Each function returns the table as bytes, or `None` if the table isn't
+//! included in this build (see the crate documentation for how LUTs are
+//! picked). Tables are indexed by their input value. Tables of 16-bit values
+//! store each entry as two little-endian bytes, and tables of 8-bit RGB store
+//! each entry as three bytes in the order red, green, blue. The tables that
+//! take 8-bit RGB are indexed by `(r << 16) | (g << 8) | b`.
+//!
+//! Tables that are embedded as typed arrays (see the crate documentation)
+//! store 16-bit values in the target's byte order, so on big-endian targets
+//! `swap_components` is never available as bytes.
+//!
+//! ```
+//! # use rgb565::luts;
+//! if let Some(table) = luts::l5_to_l8() {
+//!     assert_eq!(table.len(), 32);
+//!     assert_eq!(table[31], 255);
+//! }
+//! ```
+
+use crate::lut;
+
+/// The table behind swapping red and blue, with 65536 16-bit entries.
+#[inline]
+#[must_use]
+pub fn swap_components() -> Option<&'static [u8]> { lut::SWAP_COMPONENTS_LUT.bytes() }
+
+/// The table from 5-bit to 8-bit RGB, with 32 8-bit entries.
+#[inline]
+#[must_use]
+pub fn l5_to_l8() -> Option<&'static [u8]> { lut::L5_TO_L8_LUT.bytes() }
+
+/// The table from 6-bit to 8-bit RGB, with 64 8-bit entries.
+#[inline]
+#[must_use]
+pub fn l6_to_l8() -> Option<&'static [u8]> { lut::L6_TO_L8_LUT.bytes() }
+
+/// The table from 5-bit RGB to 8-bit sRGB, with 32 8-bit entries.
+#[inline]
+#[must_use]
+pub fn l5_to_s8() -> Option<&'static [u8]> { lut::L5_TO_S8_LUT.bytes() }
+
+/// The table from 6-bit RGB to 8-bit sRGB, with 64 8-bit entries.
+#[inline]
+#[must_use]
+pub fn l6_to_s8() -> Option<&'static [u8]> { lut::L6_TO_S8_LUT.bytes() }
+
+/// The table from RGB565 to 8-bit RGB, with 65536 3-byte entries.
+#[inline]
+#[must_use]
+pub fn l565_to_l888() -> Option<&'static [u8]> { lut::L565_TO_L888_LUT.bytes() }
+
+/// The table from RGB565 to 8-bit sRGB, with 65536 3-byte entries.
+#[inline]
+#[must_use]
+pub fn l565_to_s888() -> Option<&'static [u8]> { lut::L565_TO_S888_LUT.bytes() }
+
+/// The table from 8-bit to 5-bit RGB, with 256 8-bit entries.
+#[inline]
+#[must_use]
+pub fn l8_to_l5() -> Option<&'static [u8]> { lut::L8_TO_L5_LUT.bytes() }
+
+/// The table from 8-bit to 6-bit RGB, with 256 8-bit entries.
+#[inline]
+#[must_use]
+pub fn l8_to_l6() -> Option<&'static [u8]> { lut::L8_TO_L6_LUT.bytes() }
+
+/// The table from 8-bit sRGB to 5-bit RGB, with 256 8-bit entries.
+#[inline]
+#[must_use]
+pub fn s8_to_l5() -> Option<&'static [u8]> { lut::S8_TO_L5_LUT.bytes() }
+
+/// The table from 8-bit sRGB to 6-bit RGB, with 256 8-bit entries.
+#[inline]
+#[must_use]
+pub fn s8_to_l6() -> Option<&'static [u8]> { lut::S8_TO_L6_LUT.bytes() }
+
+/// The table from 8-bit RGB to RGB565, with 16777216 16-bit entries.
+#[inline]
+#[must_use]
+pub fn l888_to_l565() -> Option<&'static [u8]> { lut::L888_TO_L565_LUT.bytes() }
+
+/// The table from 8-bit sRGB to RGB565, with 16777216 16-bit entries.
+#[inline]
+#[must_use]
+pub fn s888_to_l565() -> Option<&'static [u8]> { lut::S888_TO_L565_LUT.bytes() }
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn layout() {
+		if let Some(table) = super::l6_to_s8() {
+			assert!((0..64).all(|i| table[i as usize] == crate::fixed_srgb::l6_to_s8(i)));
+		}
+
+		if let Some(table) = super::swap_components() {
+			assert_eq!(table.len(), 65536 * 2);
+			assert_eq!(table[0xF800 * 2..0xF800 * 2 + 2], 0x001Fu16.to_le_bytes());
+		}
+
+		if let Some(table) = super::l565_to_l888() {
+			assert_eq!(table.len(), 65536 * 3);
+			assert_eq!(table[0x07E0 * 3..0x07E0 * 3 + 3], [0, 255, 0]);
+		}
+	}
+}
@@ -0,0 +1,104 @@
+//! Pixels stored as two bytes in a fixed [`Encoding`], for viewing buffers
+//! received over SPI, UART or the like in place.
+//!
+//! [`Rgb565`] wraps a native-endian `u16`, so a slice of them has to be
+//! aligned to two bytes and in the byte order of the target. The types here
+//! wrap `[u8; 2]` instead, so they have no alignment requirement and read the
+//! same on any target, and decode to `Rgb565` one pixel at a time.
+//!
+//! With the `zerocopy` feature, they implement `FromBytes`, `IntoBytes` and
+//! `Unaligned`, so a byte buffer can be viewed as a slice of them without
+//! copying. `Rgb565` itself implements all but `Unaligned`.
+//!
+//! ```
+//! # use rgb565::{Rgb565, Rgb565Be};
+//! let received = [0xF8, 0x00, 0x07, 0xE0];
+//! let (pixels, _) = received.as_chunks::<2>();
+//! let pixels = pixels.iter().map(|bytes| Rgb565::from(Rgb565Be(*bytes)));
+//! assert!(pixels.eq([Rgb565::from_rgb565(0xF800), Rgb565::from_rgb565(0x07E0)]));
+//! ```
+
+use crate::Rgb565;
+#[cfg(doc)]
+use crate::Encoding;
+
+macro_rules! packed {
+	($($(#[$attr:meta])* $name:ident, $from:ident, $to:ident;)+) => {$(
+		$(#[$attr])*
+		#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+		#[cfg_attr(feature = "zerocopy", derive(zerocopy::FromBytes, zerocopy::IntoBytes, zerocopy::KnownLayout, zerocopy::Immutable, zerocopy::Unaligned))]
+		#[repr(transparent)]
+		pub struct $name(pub [u8; 2]);
+
+		impl $name {
+			/// Encodes `color` into two bytes.
+			#[inline]
+			#[must_use]
+			pub fn new(color: Rgb565) -> Self { Self(color.$to()) }
+
+			/// Decodes the color from the two bytes.
+			#[inline]
+			#[must_use]
+			pub fn get(&self) -> Rgb565 { Rgb565::$from(self.0) }
+		}
+
+		impl From<Rgb565> for $name {
+			#[inline]
+			fn from(color: Rgb565) -> Self { Self::new(color) }
+		}
+
+		impl From<$name> for Rgb565 {
+			#[inline]
+			fn from(pixel: $name) -> Self { pixel.get() }
+		}
+	)+};
+}
+
+packed! {
+	/// A pixel stored as `[gggbbbbb, rrrrrggg]`, like [`Encoding::Rgb565Le`].
+	Rgb565Le, from_rgb565_le, to_rgb565_le;
+	/// A pixel stored as `[rrrrrggg, gggbbbbb]`, like [`Encoding::Rgb565Be`].
+	Rgb565Be, from_rgb565_be, to_rgb565_be;
+	/// A pixel stored as `[gggrrrrr, bbbbbggg]`, like [`Encoding::Bgr565Le`].
+	Bgr565Le, from_bgr565_le, to_bgr565_le;
+	/// A pixel stored as `[bbbbbggg, gggrrrrr]`, like [`Encoding::Bgr565Be`].
+	Bgr565Be, from_bgr565_be, to_bgr565_be;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Bgr565Be, Bgr565Le, Rgb565Be, Rgb565Le};
+	use crate::{Encoding, Rgb565};
+
+	#[test]
+	fn round_trip() {
+		for color in Rgb565::all() {
+			assert_eq!(Rgb565Le::new(color).0, color.to_bytes(Encoding::Rgb565Le));
+			assert_eq!(Rgb565Be::new(color).0, color.to_bytes(Encoding::Rgb565Be));
+			assert_eq!(Bgr565Le::new(color).0, color.to_bytes(Encoding::Bgr565Le));
+			assert_eq!(Bgr565Be::new(color).0, color.to_bytes(Encoding::Bgr565Be));
+			assert_eq!(Rgb565::from(Rgb565Le::from(color)), color);
+			assert_eq!(Rgb565::from(Rgb565Be::from(color)), color);
+			assert_eq!(Rgb565::from(Bgr565Le::from(color)), color);
+			assert_eq!(Rgb565::from(Bgr565Be::from(color)), color);
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "zerocopy")]
+	fn zerocopy() {
+		use zerocopy::{FromBytes, IntoBytes};
+
+		// Starting at an odd offset, which `Rgb565` couldn't.
+		let received = [0x00, 0xF8, 0x00, 0x07, 0xE0];
+		let pixels = <[Rgb565Be]>::ref_from_bytes(&received[1..]).unwrap();
+		assert_eq!(pixels, [Rgb565Be([0xF8, 0x00]), Rgb565Be([0x07, 0xE0])]);
+		assert_eq!(pixels.as_bytes(), &received[1..]);
+		assert!(<[Rgb565Be]>::ref_from_bytes(&received).is_err());
+
+		let native = [0xF800u16, 0x07E0];
+		let colors = <[Rgb565]>::ref_from_bytes(native.as_bytes()).unwrap();
+		assert_eq!(colors, [Rgb565::from_rgb565(0xF800), Rgb565::from_rgb565(0x07E0)]);
+		assert_eq!(colors.as_bytes(), native.as_bytes());
+	}
+}
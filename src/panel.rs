@@ -0,0 +1,135 @@
+use crate::Rgb565;
+
+/// Corrects sRGB content for the response curve of a particular panel.
+///
+/// The conversions on [`Rgb565`] assume that the panel turns RGB565 values into
+/// light linearly. Cheap TFTs rarely do, so content authored for sRGB comes out
+/// too dark or washed out. A `PanelProfile` holds tables, built from the
+/// panel's measured gamma or from a handful of measured points, that map each
+/// 8-bit sRGB channel straight to the RGB565 code that makes the panel emit the
+/// intended amount of light.
+///
+/// Building a profile needs the `std` feature, but the tables can be taken out
+/// with [`PanelProfile::tables`] and baked into firmware with
+/// [`PanelProfile::from_tables`]. Whole frames are converted with
+/// [`frame::convert_srgb888_slice_with_profile`](crate::frame::convert_srgb888_slice_with_profile).
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub struct PanelProfile {
+	r5: [u8; 256],
+	g6: [u8; 256],
+	b5: [u8; 256]
+}
+
+impl PanelProfile {
+	/// Creates a profile from tables that map 8-bit sRGB channels to 5-bit red,
+	/// 6-bit green and 5-bit blue. Out-of-range entries are masked.
+	#[must_use]
+	pub const fn from_tables(mut r5: [u8; 256], mut g6: [u8; 256], mut b5: [u8; 256]) -> Self {
+		let mut i = 0;
+
+		while i < 256 {
+			r5[i] &= 0b11111;
+			g6[i] &= 0b111111;
+			b5[i] &= 0b11111;
+			i += 1;
+		}
+
+		Self { r5, g6, b5 }
+	}
+
+	/// Returns the tables that map 8-bit sRGB channels to 5-bit red, 6-bit
+	/// green and 5-bit blue, in that order.
+	#[must_use]
+	pub const fn tables(&self) -> (&[u8; 256], &[u8; 256], &[u8; 256]) { (&self.r5, &self.g6, &self.b5) }
+
+	/// Creates a profile for a panel whose light output is its input raised to
+	/// the power of `gamma`, which is what most panel datasheets quote.
+	#[cfg(feature = "std")]
+	#[must_use]
+	pub fn from_gamma(gamma: f32) -> Self { Self::from_response(|level| level.powf(gamma)) }
+
+	/// Creates a profile from measurements of the panel. Each point is a drive
+	/// level and the light the panel emitted at that level, relative to full
+	/// drive, both between 0 and 1. The response is interpolated linearly
+	/// between the points, and between 0 and 1 at either end.
+	#[cfg(feature = "std")]
+	#[must_use]
+	pub fn from_points(points: &[(f32, f32)]) -> Self {
+		let mut points = points.to_vec();
+		points.sort_by(|a, b| a.0.total_cmp(&b.0));
+		points.insert(0, (0.0, 0.0));
+		points.push((1.0, 1.0));
+
+		Self::from_response(|level| {
+			let i = points.partition_point(|point| point.0 < level).clamp(1, points.len() - 1);
+			let ((x0, y0), (x1, y1)) = (points[i - 1], points[i]);
+
+			if x1 > x0 {
+				y0 + (y1 - y0) * (level - x0) / (x1 - x0)
+			} else {
+				y1
+			}
+		})
+	}
+
+	/// Creates a profile from a function giving the light emitted by the panel
+	/// for a drive level, both between 0 and 1. Each sRGB value is mapped to
+	/// the code whose light output is closest to what the value stands for.
+	#[cfg(feature = "std")]
+	#[must_use]
+	pub fn from_response(response: impl Fn(f32) -> f32) -> Self {
+		let table = |max: u8| -> [u8; 256] {
+			let light: Vec<f32> = (0..=max).map(|code| response(code as f32 / max as f32)).collect();
+
+			core::array::from_fn(|s8| {
+				let target = crate::lut::srgb_untransfer(s8 as f32 / 255.0);
+				(0..=max).min_by(|a, b| (light[*a as usize] - target).abs().total_cmp(&(light[*b as usize] - target).abs())).unwrap_or(0)
+			})
+		};
+
+		Self { r5: table(31), g6: table(63), b5: table(31) }
+	}
+
+	/// Converts 8-bit sRGB to the RGB565 value that looks right on the panel.
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn convert_srgb888(&self, r: u8, g: u8, b: u8) -> Rgb565 {
+		Rgb565::from_rgb565_components(self.r5[r as usize], self.g6[g as usize], self.b5[b as usize])
+	}
+}
+
+// Building profiles needs `powf`, which is only there with `std`.
+#[cfg(all(test, feature = "std"))]
+mod tests {
+	use super::PanelProfile;
+	use crate::Rgb565;
+
+	#[test]
+	fn linear_panel_matches_srgb_conversion() {
+		let profile = PanelProfile::from_gamma(1.0);
+
+		for s8 in 0..=255 {
+			let expected = Rgb565::from_srgb888_components(s8, s8, s8).to_rgb565_components();
+			let actual = profile.convert_srgb888(s8, s8, s8).to_rgb565_components();
+
+			for (expected, actual) in expected.into_iter().zip(actual) {
+				assert!(expected.abs_diff(actual) <= 1, "{}: {} vs {}", s8, expected, actual);
+			}
+		}
+	}
+
+	#[test]
+	fn points_match_gamma() {
+		let points: Vec<_> = (1..16).map(|i| i as f32 / 16.0).map(|level| (level, level.powf(2.2))).collect();
+		let from_points = PanelProfile::from_points(&points);
+		let from_gamma = PanelProfile::from_gamma(2.2);
+
+		for (a, b) in from_points.tables().1.iter().zip(from_gamma.tables().1) {
+			assert!(a.abs_diff(*b) <= 1);
+		}
+
+		assert_eq!(from_gamma.convert_srgb888(255, 255, 255), Rgb565::from_rgb565(0xFFFF));
+		assert_eq!(from_gamma.convert_srgb888(0, 0, 0), Rgb565::from_rgb565(0));
+	}
+}
@@ -0,0 +1,92 @@
+//! Presenting frames in a window on the host, for firmware simulators that
+//! want a "virtual panel".
+//!
+//! These write into the surfaces of the `pixels` and `softbuffer` crates
+//! without depending on either: [`present_rgba`] fills the RGBA bytes from
+//! `Pixels::frame_mut`, and [`present_0rgb`] fills the `0RGB` words of a
+//! `softbuffer::Buffer`. Each pixel of the frame becomes a `scale` by `scale`
+//! square of the surface, which must therefore be `width * scale` pixels wide.
+//! Colors are converted to sRGB, since that's what the window expects.
+//!
+//! ```
+//! # use rgb565::{present, Converter, Rgb565};
+//! let frame = [Rgb565::from_rgb565(0x07E0), Rgb565::from_rgb565(0x0000)];
+//! let mut surface = [0u32; 2 * 2 * 2];
+//! present::present_0rgb(&Converter::builtin(), &frame, 2, 2, &mut surface);
+//! assert_eq!(surface[0], surface[1]);
+//! assert_eq!(surface[0], surface[4]);
+//! assert_eq!(surface[2], 0);
+//! ```
+
+use crate::{Converter, Rgb565};
+
+/// Writes `frame`, which is `width` pixels wide, into a surface of RGBA bytes
+/// scaled up by `scale`. Rows that don't fit in `dst` are skipped.
+pub fn present_rgba(converter: &Converter, frame: &[Rgb565], width: usize, scale: usize, dst: &mut [u8]) {
+	present(frame, width, scale, dst, 4, |color, dst| {
+		let [r, g, b] = converter.to_srgb888(color);
+		dst.copy_from_slice(&[r, g, b, 255]);
+	});
+}
+
+/// Writes `frame`, which is `width` pixels wide, into a surface of `0RGB`
+/// words scaled up by `scale`. Rows that don't fit in `dst` are skipped.
+pub fn present_0rgb(converter: &Converter, frame: &[Rgb565], width: usize, scale: usize, dst: &mut [u32]) {
+	present(frame, width, scale, dst, 1, |color, dst| {
+		let [r, g, b] = converter.to_srgb888(color);
+		dst[0] = u32::from_be_bytes([0, r, g, b]);
+	});
+}
+
+/// Scales `frame` into `dst`, whose pixels are `size` elements long, writing
+/// each pixel with `write`. Each scaled row is written once and then copied.
+fn present<T: Copy>(frame: &[Rgb565], width: usize, scale: usize, dst: &mut [T], size: usize, mut write: impl FnMut(Rgb565, &mut [T])) {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	if width == 0 || scale == 0 {
+		return;
+	}
+
+	let row_len = width * scale * size;
+
+	for (row, dst) in frame.chunks_exact(width).zip(dst.chunks_exact_mut(row_len * scale)) {
+		let (first, rest) = dst.split_at_mut(row_len);
+
+		for (color, dst) in row.iter().zip(first.chunks_exact_mut(scale * size)) {
+			for dst in dst.chunks_exact_mut(size) {
+				write(*color, dst);
+			}
+		}
+
+		for dst in rest.chunks_exact_mut(row_len) {
+			dst.copy_from_slice(first);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{present_0rgb, present_rgba};
+	use crate::{Converter, Rgb565};
+
+	#[test]
+	fn scaling() {
+		let frame = [0xF800, 0x07E0, 0x001F, 0xFFFF].map(Rgb565::from_rgb565);
+		let converter = Converter::builtin();
+
+		let mut words = [0u32; 4 * 4];
+		present_0rgb(&converter, &frame, 2, 2, &mut words);
+
+		for (i, word) in words.iter().enumerate() {
+			let color = frame[i / 8 * 2 + i % 4 / 2];
+			let [r, g, b] = color.to_srgb888_components();
+			assert_eq!(*word, u32::from_be_bytes([0, r, g, b]), "{}", i);
+		}
+
+		let mut bytes = [0u8; 2 * 4];
+		present_rgba(&converter, &frame, 2, 1, &mut bytes);
+		let [r, g, b] = frame[1].to_srgb888_components();
+		assert_eq!(bytes[4..], [r, g, b, 255]);
+	}
+}
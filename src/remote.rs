@@ -0,0 +1,624 @@
+//! A small protocol for streaming frames from a device to a viewer on a host,
+//! over any transport that carries bytes, like a serial port or a socket.
+//!
+//! A session starts with the [`Device`] saying hello with the size of its
+//! screen and the encodings it can send. The [`Host`] picks the one it likes
+//! best and accepts, or rejects the device if they have none in common. After
+//! that, the device sends whole frames, or delta frames with only a rectangle
+//! that changed, as [wire frames](crate::wire) in the encoding they agreed on.
+//!
+//! Neither side does any I/O. Messages are written into buffers to be sent
+//! however the transport likes, and bytes received are fed to a [`Decoder`],
+//! in whatever pieces they arrive, until it has a whole message.
+//!
+//! Every message is an 8-byte header followed by its payload. All multi-byte
+//! fields are little-endian:
+//!
+//! | Offset | Size | Field                                                  |
+//! |--------|------|--------------------------------------------------------|
+//! | 0      | 1    | Kind of message                                        |
+//! | 1      | 3    | Reserved, 0                                            |
+//! | 4      | 4    | Length of the payload in bytes                         |
+//!
+//! The payload depends on the kind:
+//!
+//! | Kind | Message | Payload                                                |
+//! |------|---------|--------------------------------------------------------|
+//! | 1    | Hello   | Version, currently 1, then a bit per encoding the device can send, then the width and height as 2 bytes each |
+//! | 2    | Accept  | The encoding chosen, as a [format descriptor](crate::descriptor) |
+//! | 3    | Reject  | Nothing                                                |
+//! | 4    | Full    | A wire frame the size of the screen                    |
+//! | 5    | Delta   | The left and top edges as 2 bytes each, then a wire frame to draw there |
+//!
+//! In the hello, encodings are numbered 0 to 3, in the order of [`Encoding`]:
+//! `Rgb565Le`, `Rgb565Be`, `Bgr565Le` and `Bgr565Be`.
+//!
+//! ```
+//! # use rgb565::{frame::Rect, remote::{Decoder, Device, EncodingSet, Host, HostEvent}, Encoding, Rgb565};
+//! let mut device = Device::new(4, 2, EncodingSet::ALL);
+//! let mut host = Host::new(&[Encoding::Rgb565Be]);
+//! let mut decoder = Decoder::new([0; 64]);
+//! let mut buffer = [0; 64];
+//!
+//! let len = device.hello(&mut buffer).unwrap();
+//! let (_, hello) = decoder.feed(&buffer[..len]).unwrap();
+//! assert_eq!(host.receive(hello.unwrap()), Ok(HostEvent::Hello { width: 4, height: 2, encoding: Some(Encoding::Rgb565Be) }));
+//!
+//! let len = host.reply(&mut buffer).unwrap();
+//! let (_, accept) = decoder.feed(&buffer[..len]).unwrap();
+//! assert_eq!(device.receive(&accept.unwrap()), Ok(Encoding::Rgb565Be));
+//!
+//! let mut frame = [Rgb565::BLACK; 8];
+//! frame[6] = Rgb565::RED;
+//! let len = device.delta(&frame, Rect::new(2, 1, 1, 1), &mut buffer).unwrap();
+//! let (_, delta) = decoder.feed(&buffer[..len]).unwrap();
+//!
+//! match host.receive(delta.unwrap()).unwrap() {
+//!     HostEvent::Frame { x, y, frame } => {
+//!         assert_eq!((x, y), (2, 1));
+//!         assert!(frame.pixels().eq([Rgb565::RED]));
+//!     }
+//!
+//!     _ => unreachable!()
+//! }
+//! ```
+
+use core::fmt::{self, Display, Formatter};
+
+use zerocopy::byteorder::little_endian::U32;
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
+
+use crate::descriptor::FormatDescriptor;
+use crate::frame::Rect;
+use crate::wire::{self, WireError, WireFrame};
+use crate::{Encoding, Rgb565};
+
+/// The version of the protocol this crate speaks.
+pub const VERSION: u8 = 1;
+
+/// The length of the header of each message.
+pub const HEADER_LEN: usize = 8;
+
+const HELLO: u8 = 1;
+const ACCEPT: u8 = 2;
+const REJECT: u8 = 3;
+const FULL: u8 = 4;
+const DELTA: u8 = 5;
+
+/// An error from a session.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ProtocolError {
+	/// A message is malformed, doesn't fit in the buffer, or has a frame that
+	/// doesn't fit the screen or the encoding agreed on.
+	Wire(WireError),
+	/// A message came, or a frame was sent, that doesn't belong at this point
+	/// of the session, like a frame before the handshake.
+	OutOfOrder,
+	/// The host has no encoding in common with the device.
+	Rejected
+}
+
+impl Display for ProtocolError {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::Wire(error) => error.fmt(f),
+			Self::OutOfOrder => f.write_str("message out of order"),
+			Self::Rejected => f.write_str("host rejected the handshake")
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ProtocolError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::Wire(error) => Some(error),
+			_ => None
+		}
+	}
+}
+
+impl From<WireError> for ProtocolError {
+	fn from(error: WireError) -> Self { Self::Wire(error) }
+}
+
+/// The number of `encoding` on the wire.
+const fn index(encoding: Encoding) -> u8 {
+	match encoding {
+		Encoding::Rgb565Le => 0,
+		Encoding::Rgb565Be => 1,
+		Encoding::Bgr565Le => 2,
+		Encoding::Bgr565Be => 3
+	}
+}
+
+/// A set of encodings, for saying which ones a device can send.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash)]
+pub struct EncodingSet(u8);
+
+impl EncodingSet {
+	/// No encodings.
+	pub const NONE: Self = Self(0);
+	/// Every encoding.
+	pub const ALL: Self = Self(0b1111);
+
+	/// This set, with `encoding` added.
+	#[inline]
+	#[must_use]
+	pub const fn with(self, encoding: Encoding) -> Self { Self(self.0 | 1 << index(encoding)) }
+
+	/// Whether `encoding` is in this set.
+	#[inline]
+	#[must_use]
+	pub const fn contains(self, encoding: Encoding) -> bool { self.0 & 1 << index(encoding) != 0 }
+}
+
+/// The header of a message.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned)]
+#[repr(C)]
+pub struct MessageHeader {
+	/// Which kind of message this is, as in the [module documentation](self).
+	pub kind: u8,
+	/// Always zero, so that it can be given a meaning later.
+	pub reserved: [u8; 3],
+	/// The length of the payload after the header, in bytes.
+	pub len: U32
+}
+
+/// A message, borrowing any frame it has from the buffer it was parsed from.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Message<'a> {
+	/// The device starting a session.
+	Hello {
+		/// The version of the protocol the device speaks.
+		version: u8,
+		/// The encodings the device can send.
+		encodings: EncodingSet,
+		/// The width of the screen, in pixels.
+		width: u16,
+		/// The height of the screen, in pixels.
+		height: u16
+	},
+	/// The host accepting the device, and the encoding it chose.
+	Accept(Encoding),
+	/// The host rejecting the device.
+	Reject,
+	/// A whole frame.
+	Full(WireFrame<'a>),
+	/// A rectangle of a frame, to draw at `x`, `y` over the frame before.
+	Delta {
+		/// The column of the rectangle's left edge.
+		x: u16,
+		/// The row of the rectangle's top edge.
+		y: u16,
+		/// The pixels of the rectangle.
+		frame: WireFrame<'a>
+	}
+}
+
+/// Parses a wire frame that takes up all of `bytes`.
+fn frame(bytes: &[u8]) -> Result<WireFrame<'_>, WireError> {
+	match WireFrame::parse(bytes)? {
+		(frame, []) => Ok(frame),
+		_ => Err(WireError::LengthMismatch)
+	}
+}
+
+impl<'a> Message<'a> {
+	/// Parses a message from the start of `bytes`, returning it and whatever
+	/// comes after it.
+	pub fn parse(bytes: &'a [u8]) -> Result<(Self, &'a [u8]), ProtocolError> {
+		let (header, rest) = MessageHeader::ref_from_prefix(bytes).map_err(|_| WireError::TooShort)?;
+
+		if header.reserved != [0; 3] {
+			return Err(WireError::UnknownFormat.into());
+		}
+
+		let (payload, rest) = rest.split_at_checked(header.len.get() as usize).ok_or(WireError::TooShort)?;
+		let field = |at: usize| u16::from_le_bytes([payload[at], payload[at + 1]]);
+
+		let message = match (header.kind, payload.len()) {
+			(HELLO, 6) => Self::Hello { version: payload[0], encodings: EncodingSet(payload[1] & EncodingSet::ALL.0), width: field(2), height: field(4) },
+			(ACCEPT, 2) => Self::Accept(FormatDescriptor::from_bytes([payload[0], payload[1]]).and_then(|format| format.encoding()).ok_or(WireError::UnknownFormat)?),
+			(REJECT, 0) => Self::Reject,
+			(FULL, _) => Self::Full(frame(payload)?),
+			(DELTA, 4..) => Self::Delta { x: field(0), y: field(2), frame: frame(&payload[4..])? },
+			(HELLO | ACCEPT | REJECT | DELTA, _) => return Err(WireError::LengthMismatch.into()),
+			_ => return Err(WireError::UnknownFormat.into())
+		};
+
+		Ok((message, rest))
+	}
+}
+
+/// Writes a message of `kind` to the start of `dst`, with the payload written
+/// by `payload`, which returns its length. Returns the length of the message.
+fn write(kind: u8, dst: &mut [u8], payload: impl FnOnce(&mut [u8]) -> Result<usize, WireError>) -> Result<usize, ProtocolError> {
+	let (header, rest) = MessageHeader::mut_from_prefix(dst).map_err(|_| WireError::TooShort)?;
+	let len = payload(rest)?;
+	*header = MessageHeader { kind, reserved: [0; 3], len: U32::new(len as u32) };
+	Ok(HEADER_LEN + len)
+}
+
+/// Writes `bytes` as the payload of a message.
+fn fixed<const N: usize>(bytes: [u8; N]) -> impl FnOnce(&mut [u8]) -> Result<usize, WireError> {
+	move |dst| {
+		dst.get_mut(..N).ok_or(WireError::TooShort)?.copy_from_slice(&bytes);
+		Ok(N)
+	}
+}
+
+/// Puts messages back together from bytes received in pieces.
+///
+/// Each message has to fit in the buffer, so it needs [`HEADER_LEN`] bytes,
+/// plus the length of a wire frame the size of the screen for a host.
+pub struct Decoder<B> {
+	buffer: B,
+	len: usize,
+	complete: bool
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> Decoder<B> {
+	/// Starts decoding messages into `buffer`.
+	#[must_use]
+	pub fn new(buffer: B) -> Self { Self { buffer, len: 0, complete: false } }
+
+	/// The number of bytes the message being put together needs, as far as
+	/// can be told from what's been received.
+	fn needed(&self) -> usize {
+		match MessageHeader::ref_from_prefix(&self.buffer.as_ref()[..self.len]) {
+			Ok((header, _)) => HEADER_LEN.saturating_add(header.len.get() as usize),
+			Err(_) => HEADER_LEN
+		}
+	}
+
+	/// Feeds the decoder bytes received, up to the end of the next message.
+	/// Returns how many of them it took, and the message if that finished it.
+	/// Bytes it didn't take belong to the messages after, and should be fed
+	/// to it again.
+	///
+	/// After an error, the decoder starts over with the next byte it's fed,
+	/// but since there's no telling where the next message starts, the
+	/// session should normally start over too.
+	pub fn feed(&mut self, bytes: &[u8]) -> Result<(usize, Option<Message<'_>>), ProtocolError> {
+		if self.complete {
+			(self.len, self.complete) = (0, false);
+		}
+
+		let mut taken = 0;
+
+		loop {
+			let needed = self.needed();
+
+			if needed > self.buffer.as_ref().len() {
+				self.complete = true;
+				return Err(WireError::TooShort.into());
+			}
+
+			let take = (needed - self.len).min(bytes.len() - taken);
+			self.buffer.as_mut()[self.len..][..take].copy_from_slice(&bytes[taken..][..take]);
+			(self.len, taken) = (self.len + take, taken + take);
+
+			if self.len < needed {
+				return Ok((taken, None));
+			}
+
+			if self.needed() == needed {
+				break;
+			}
+		}
+
+		self.complete = true;
+		let (message, _) = Message::parse(&self.buffer.as_ref()[..self.len])?;
+		Ok((taken, Some(message)))
+	}
+
+	/// Drops whatever part of a message has been received.
+	pub fn reset(&mut self) { (self.len, self.complete) = (0, false); }
+}
+
+/// The device end of a session, which sends frames of a screen `width` by
+/// `height` pixels.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Device {
+	width: u16,
+	height: u16,
+	encodings: EncodingSet,
+	encoding: Option<Encoding>
+}
+
+impl Device {
+	/// Starts a session for a screen of `width` by `height` pixels, which can
+	/// send frames in any of `encodings`.
+	#[must_use]
+	pub const fn new(width: u16, height: u16, encodings: EncodingSet) -> Self { Self { width, height, encodings, encoding: None } }
+
+	/// The encoding the host chose, once it has accepted.
+	#[inline]
+	#[must_use]
+	pub const fn encoding(&self) -> Option<Encoding> { self.encoding }
+
+	/// Writes the hello that starts the session into `dst`, and returns its
+	/// length. This starts the session over if it had already started.
+	pub fn hello(&mut self, dst: &mut [u8]) -> Result<usize, ProtocolError> {
+		self.encoding = None;
+		let [width, height] = [self.width.to_le_bytes(), self.height.to_le_bytes()];
+		write(HELLO, dst, fixed([VERSION, self.encodings.0, width[0], width[1], height[0], height[1]]))
+	}
+
+	/// Handles the reply of the host to the hello, returning the encoding it
+	/// chose. Fails with [`ProtocolError::Rejected`] if it rejected the device.
+	pub fn receive(&mut self, message: &Message) -> Result<Encoding, ProtocolError> {
+		match (self.encoding, message) {
+			(None, Message::Accept(encoding)) if self.encodings.contains(*encoding) => {
+				self.encoding = Some(*encoding);
+				Ok(*encoding)
+			}
+
+			(None, Message::Accept(_)) => Err(WireError::UnknownFormat.into()),
+			(None, Message::Reject) => Err(ProtocolError::Rejected),
+			_ => Err(ProtocolError::OutOfOrder)
+		}
+	}
+
+	/// Writes `frame`, the whole screen, into `dst`, and returns the length of
+	/// the message.
+	pub fn full(&self, frame: &[Rgb565], dst: &mut [u8]) -> Result<usize, ProtocolError> {
+		let encoding = self.encoding.ok_or(ProtocolError::OutOfOrder)?;
+
+		if frame.len() != self.width as usize * self.height as usize {
+			return Err(WireError::LengthMismatch.into());
+		}
+
+		write(FULL, dst, |dst| wire::encode(frame, self.width, encoding, dst))
+	}
+
+	/// Writes `rect` of `frame`, the whole screen, into `dst`, and returns the
+	/// length of the message.
+	pub fn delta(&self, frame: &[Rgb565], rect: Rect, dst: &mut [u8]) -> Result<usize, ProtocolError> {
+		let encoding = self.encoding.ok_or(ProtocolError::OutOfOrder)?;
+		let (width, height) = (self.width as usize, self.height as usize);
+
+		let outside = |start: usize, len: usize, max: usize| start.checked_add(len).is_none_or(|end| end > max);
+
+		if frame.len() != width * height || outside(rect.x, rect.width, width) || outside(rect.y, rect.height, height) {
+			return Err(WireError::LengthMismatch.into());
+		}
+
+		let pixel = |i: usize| frame[(rect.y + i / rect.width) * width + rect.x + i % rect.width];
+
+		write(DELTA, dst, |dst| {
+			let (position, dst) = dst.split_first_chunk_mut::<4>().ok_or(WireError::TooShort)?;
+			position[..2].copy_from_slice(&(rect.x as u16).to_le_bytes());
+			position[2..].copy_from_slice(&(rect.y as u16).to_le_bytes());
+			Ok(4 + wire::encode_with(pixel, rect.width * rect.height, rect.width as u16, encoding, dst)?)
+		})
+	}
+}
+
+/// Something that happened in a session, from the end of the host.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum HostEvent<'a> {
+	/// A device said hello. `encoding` is the one the host chose, or `None` if
+	/// it has none in common with the device or speaks another version. Either
+	/// way, [`Host::reply`] writes the answer.
+	Hello {
+		/// The width of the screen, in pixels.
+		width: u16,
+		/// The height of the screen, in pixels.
+		height: u16,
+		/// The encoding chosen, if any.
+		encoding: Option<Encoding>
+	},
+	/// A frame to draw at `x`, `y` over the one before, or over nothing if
+	/// it's the whole screen.
+	Frame {
+		/// The column of the frame's left edge.
+		x: u16,
+		/// The row of the frame's top edge.
+		y: u16,
+		/// The pixels of the frame.
+		frame: WireFrame<'a>
+	}
+}
+
+/// The host end of a session, which receives frames from a device.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Host<'p> {
+	preferred: &'p [Encoding],
+	screen: Option<(u16, u16, Encoding)>
+}
+
+impl<'p> Host<'p> {
+	/// Starts a session that accepts devices which can send any of
+	/// `preferred`, choosing the earliest one they can.
+	#[must_use]
+	pub const fn new(preferred: &'p [Encoding]) -> Self { Self { preferred, screen: None } }
+
+	/// Handles a message from the device. Frames are checked against the size
+	/// of the screen and the encoding chosen.
+	pub fn receive<'m>(&mut self, message: Message<'m>) -> Result<HostEvent<'m>, ProtocolError> {
+		match (self.screen, message) {
+			(_, Message::Hello { version, encodings, width, height }) => {
+				let encoding = self.preferred.iter().copied().find(|encoding| version == VERSION && encodings.contains(*encoding));
+				self.screen = encoding.map(|encoding| (width, height, encoding));
+				Ok(HostEvent::Hello { width, height, encoding })
+			}
+
+			(Some((width, height, encoding)), Message::Full(frame)) => {
+				check(frame, encoding, (frame.header.width.get(), frame.header.height.get()) == (width, height))?;
+				Ok(HostEvent::Frame { x: 0, y: 0, frame })
+			}
+
+			(Some((width, height, encoding)), Message::Delta { x, y, frame }) => {
+				let fits = x as u32 + frame.header.width.get() as u32 <= width as u32 && y as u32 + frame.header.height.get() as u32 <= height as u32;
+				check(frame, encoding, fits)?;
+				Ok(HostEvent::Frame { x, y, frame })
+			}
+
+			_ => Err(ProtocolError::OutOfOrder)
+		}
+	}
+
+	/// Writes the answer to the last hello into `dst`, accepting the device if
+	/// an encoding was chosen and rejecting it otherwise, and returns its
+	/// length.
+	pub fn reply(&self, dst: &mut [u8]) -> Result<usize, ProtocolError> {
+		match self.screen {
+			Some((_, _, encoding)) => write(ACCEPT, dst, fixed(FormatDescriptor::from_encoding(encoding).to_bytes())),
+			None => write(REJECT, dst, fixed([]))
+		}
+	}
+}
+
+/// Checks that `frame` is in `encoding` and `fits`.
+fn check(frame: WireFrame, encoding: Encoding, fits: bool) -> Result<(), WireError> {
+	match (frame.encoding == encoding, fits) {
+		(false, _) => Err(WireError::UnknownFormat),
+		(_, false) => Err(WireError::LengthMismatch),
+		_ => Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Decoder, Device, EncodingSet, Host, HostEvent, Message, ProtocolError, HEADER_LEN};
+	use crate::descriptor::FormatDescriptor;
+	use crate::frame::Rect;
+	use crate::wire::WireError;
+	use crate::{Encoding, Rgb565};
+
+	/// Feeds `bytes` to `decoder` `chunk` bytes at a time, and returns the
+	/// lengths of the messages it put together.
+	fn decode(decoder: &mut Decoder<Vec<u8>>, bytes: &[u8], chunk: usize) -> Vec<usize> {
+		let mut lens = Vec::new();
+
+		for mut chunk in bytes.chunks(chunk) {
+			while !chunk.is_empty() {
+				let (taken, message) = decoder.feed(chunk).unwrap();
+
+				if message.is_some() {
+					lens.push(decoder.len);
+				}
+
+				chunk = &chunk[taken..];
+			}
+		}
+
+		lens
+	}
+
+	#[test]
+	fn session() {
+		let frame: Vec<Rgb565> = (0..12).map(|i| Rgb565::from_rgb565(i * 0x1111)).collect();
+		let mut device = Device::new(4, 3, EncodingSet::NONE.with(Encoding::Bgr565Le).with(Encoding::Rgb565Be));
+		let mut host = Host::new(&[Encoding::Rgb565Le, Encoding::Bgr565Le, Encoding::Rgb565Be]);
+		let mut buffer = [0; 128];
+
+		assert_eq!(device.full(&frame, &mut buffer), Err(ProtocolError::OutOfOrder));
+
+		let len = device.hello(&mut buffer).unwrap();
+		let (hello, _) = Message::parse(&buffer[..len]).unwrap();
+		assert_eq!(host.receive(hello), Ok(HostEvent::Hello { width: 4, height: 3, encoding: Some(Encoding::Bgr565Le) }));
+
+		let len = host.reply(&mut buffer).unwrap();
+		assert_eq!(buffer[HEADER_LEN..len], FormatDescriptor::from_encoding(Encoding::Bgr565Le).to_bytes());
+		let (accept, _) = Message::parse(&buffer[..len]).unwrap();
+		assert_eq!(device.receive(&accept), Ok(Encoding::Bgr565Le));
+		assert_eq!(device.receive(&accept), Err(ProtocolError::OutOfOrder));
+
+		let len = device.full(&frame, &mut buffer).unwrap();
+		let (full, _) = Message::parse(&buffer[..len]).unwrap();
+
+		match host.receive(full) {
+			Ok(HostEvent::Frame { x: 0, y: 0, frame: full }) => assert!(full.pixels().eq(frame.iter().copied())),
+			event => panic!("{:?}", event)
+		}
+
+		let len = device.delta(&frame, Rect::new(1, 1, 3, 2), &mut buffer).unwrap();
+		let (delta, _) = Message::parse(&buffer[..len]).unwrap();
+
+		match host.receive(delta) {
+			Ok(HostEvent::Frame { x: 1, y: 1, frame: delta }) => assert!(delta.pixels().eq([5, 6, 7, 9, 10, 11].map(|i| frame[i]))),
+			event => panic!("{:?}", event)
+		}
+
+		let len = device.delta(&frame, Rect::new(0, 0, 0, 0), &mut buffer).unwrap();
+		assert!(matches!(host.receive(Message::parse(&buffer[..len]).unwrap().0), Ok(HostEvent::Frame { .. })));
+		assert_eq!(device.delta(&frame, Rect::new(2, 0, 3, 1), &mut buffer), Err(WireError::LengthMismatch.into()));
+		assert_eq!(device.delta(&frame, Rect::new(usize::MAX, 0, 1, 1), &mut buffer), Err(WireError::LengthMismatch.into()));
+		assert_eq!(device.delta(&frame, Rect::new(0, 1, 1, usize::MAX), &mut buffer), Err(WireError::LengthMismatch.into()));
+		assert_eq!(device.full(&frame[1..], &mut buffer), Err(WireError::LengthMismatch.into()));
+		assert_eq!(device.full(&frame, &mut buffer[..40]), Err(WireError::TooShort.into()));
+		assert_eq!(host.receive(Message::Accept(Encoding::Bgr565Le)), Err(ProtocolError::OutOfOrder));
+	}
+
+	#[test]
+	fn rejection() {
+		let mut device = Device::new(4, 3, EncodingSet::NONE.with(Encoding::Rgb565Be));
+		let mut host = Host::new(&[Encoding::Rgb565Le]);
+		let mut buffer = [0; 64];
+
+		let len = device.hello(&mut buffer).unwrap();
+		assert_eq!(host.receive(Message::parse(&buffer[..len]).unwrap().0), Ok(HostEvent::Hello { width: 4, height: 3, encoding: None }));
+
+		let len = host.reply(&mut buffer).unwrap();
+		assert_eq!(len, HEADER_LEN);
+		assert_eq!(device.receive(&Message::parse(&buffer[..len]).unwrap().0), Err(ProtocolError::Rejected));
+
+		// A host that knows the encoding but not the version.
+		let mut host = Host::new(&[Encoding::Rgb565Be]);
+		let len = device.hello(&mut buffer).unwrap();
+		buffer[HEADER_LEN] = 2;
+		assert_eq!(host.receive(Message::parse(&buffer[..len]).unwrap().0), Ok(HostEvent::Hello { width: 4, height: 3, encoding: None }));
+
+		// Frames from a device that never said hello, or in the wrong encoding.
+		let mut stranger = Device::new(4, 3, EncodingSet::ALL);
+		stranger.receive(&Message::Accept(Encoding::Rgb565Le)).unwrap();
+		let len = stranger.full(&[Rgb565::BLACK; 12], &mut buffer).unwrap();
+		let (full, _) = Message::parse(&buffer[..len]).unwrap();
+		assert_eq!(host.receive(full), Err(ProtocolError::OutOfOrder));
+
+		let len = device.hello(&mut buffer).unwrap();
+		host.receive(Message::parse(&buffer[..len]).unwrap().0).unwrap();
+		let len = stranger.full(&[Rgb565::BLACK; 12], &mut buffer).unwrap();
+		assert_eq!(host.receive(Message::parse(&buffer[..len]).unwrap().0), Err(WireError::UnknownFormat.into()));
+	}
+
+	#[test]
+	fn decoding() {
+		let mut device = Device::new(3, 2, EncodingSet::ALL);
+		let mut stream = vec![0; 256];
+		let mut len = device.hello(&mut stream).unwrap();
+		device.receive(&Message::Accept(Encoding::Rgb565Le)).unwrap();
+		len += device.full(&[Rgb565::RED; 6], &mut stream[len..]).unwrap();
+		len += device.delta(&[Rgb565::RED; 6], Rect::new(0, 0, 0, 0), &mut stream[len..]).unwrap();
+		let host = Host::new(&[]);
+		len += host.reply(&mut stream[len..]).unwrap();
+		stream.truncate(len);
+
+		let lens = [HEADER_LEN + 6, HEADER_LEN + 16 + 12, HEADER_LEN + 4 + 16, HEADER_LEN];
+		assert_eq!(lens.iter().sum::<usize>(), stream.len());
+
+		for chunk in [1, 3, 7, 1000] {
+			let mut decoder = Decoder::new(vec![0; 64]);
+			assert_eq!(decode(&mut decoder, &stream, chunk), lens, "{}", chunk);
+		}
+
+		let mut decoder = Decoder::new(vec![0; 30]);
+		assert_eq!(decoder.feed(&stream[..HEADER_LEN + 6]).map(|(taken, _)| taken), Ok(HEADER_LEN + 6));
+		assert_eq!(decoder.feed(&stream[HEADER_LEN + 6..]).map(|_| ()), Err(WireError::TooShort.into()));
+
+		let mut bad = stream.clone();
+		bad[0] = 9;
+		assert_eq!(Decoder::new(vec![0; 64]).feed(&bad).map(|_| ()), Err(WireError::UnknownFormat.into()));
+		bad[0] = 1;
+		bad[1] = 1;
+		assert_eq!(Decoder::new(vec![0; 64]).feed(&bad).map(|_| ()), Err(WireError::UnknownFormat.into()));
+
+		let mut decoder = Decoder::new(vec![0; 64]);
+		assert_eq!(decoder.feed(&stream[..5]).map(|(taken, _)| taken), Ok(5));
+		decoder.reset();
+		assert_eq!(decoder.feed(&stream).map(|(taken, message)| (taken, message.is_some())), Ok((HEADER_LEN + 6, true)));
+	}
+}
@@ -0,0 +1,102 @@
+//! RGB666, the 18-bit format that controllers like the ILI9488 and ILI9486
+//! insist on in 4-wire SPI mode.
+//!
+//! Over SPI, each pixel takes three bytes, one per channel, with the channel's
+//! 6 bits at the top of the byte. [`pack_spi`] produces that stream from
+//! [`Rgb666`] pixels, and [`pack_rgb565_spi`] produces it straight from RGB565
+//! so no intermediate buffer of `Rgb666` is needed.
+//!
+//! ```
+//! # use rgb565::{rgb666, Rgb565};
+//! let frame = [Rgb565::from_rgb565(0xF800), Rgb565::from_rgb565(0x07E0)];
+//! let mut wire = [0; 6];
+//! rgb666::pack_rgb565_spi(&frame, &mut wire);
+//! assert_eq!(wire, [0xFC, 0x00, 0x00, 0x00, 0xFC, 0x00]);
+//! ```
+
+use crate::Rgb565;
+
+/// An RGB666 color, with 6 bits for each of red, green and blue.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Default)]
+pub struct Rgb666 {
+	r: u8,
+	g: u8,
+	b: u8
+}
+
+impl Rgb666 {
+	/// From rgb666 components, which are masked to 6 bits.
+	#[inline]
+	#[must_use]
+	pub const fn from_rgb666_components(r: u8, g: u8, b: u8) -> Self { Self { r: r & 0b111111, g: g & 0b111111, b: b & 0b111111 } }
+
+	/// To rgb666 components, which each fit into 6 bits.
+	#[inline]
+	#[must_use]
+	pub const fn to_rgb666_components(&self) -> [u8; 3] { [self.r, self.g, self.b] }
+
+	/// From an RGB565 color. Red and blue are widened by repeating their top
+	/// bit, so that full intensity stays full intensity.
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn from_rgb565(color: Rgb565) -> Self {
+		let [r, g, b] = color.to_rgb565_components();
+		Self { r: r << 1 | r >> 4, g, b: b << 1 | b >> 4 }
+	}
+
+	/// To an RGB565 color, dropping the lowest bit of red and blue.
+	#[inline]
+	#[must_use]
+	#[cfg_attr(all(test, feature = "no-panic", not(debug_assertions)), no_panic::no_panic)]
+	pub fn to_rgb565(&self) -> Rgb565 { Rgb565::from_rgb565_components(self.r >> 1, self.g, self.b >> 1) }
+
+	/// To the three bytes sent over SPI, with each channel's 6 bits at the top
+	/// of its byte.
+	#[inline]
+	#[must_use]
+	pub const fn to_spi_bytes(&self) -> [u8; 3] { [self.r << 2, self.g << 2, self.b << 2] }
+}
+
+/// Packs `pixels` into `dst` as three bytes each, as many pixels as fit.
+#[inline]
+pub fn pack_spi(pixels: &[Rgb666], dst: &mut [u8]) {
+	for (dst, pixel) in dst.chunks_exact_mut(3).zip(pixels) {
+		dst.copy_from_slice(&pixel.to_spi_bytes());
+	}
+}
+
+/// Converts `pixels` to RGB666 and packs them into `dst` as three bytes each,
+/// as many pixels as fit.
+#[inline]
+pub fn pack_rgb565_spi(pixels: &[Rgb565], dst: &mut [u8]) {
+	for (dst, pixel) in dst.chunks_exact_mut(3).zip(pixels) {
+		dst.copy_from_slice(&Rgb666::from_rgb565(*pixel).to_spi_bytes());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{pack_rgb565_spi, pack_spi, Rgb666};
+	use crate::Rgb565;
+
+	#[test]
+	fn round_trip() {
+		for i in 0..=u16::MAX {
+			let color = Rgb565::from_rgb565(i);
+			assert_eq!(Rgb666::from_rgb565(color).to_rgb565(), color);
+		}
+	}
+
+	#[test]
+	fn packing() {
+		let frame = [0xFFFF, 0x0000, 0x8410].map(Rgb565::from_rgb565);
+		let mut fused = [0; 9];
+		pack_rgb565_spi(&frame, &mut fused);
+		assert_eq!(fused, [0xFC, 0xFC, 0xFC, 0, 0, 0, 0x84, 0x80, 0x84]);
+
+		let mut separate = [0; 9];
+		pack_spi(&frame.map(Rgb666::from_rgb565), &mut separate);
+		assert_eq!(separate, fused);
+	}
+}
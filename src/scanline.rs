@@ -0,0 +1,169 @@
+//! Rendering frames one scanline at a time, for devices that can't hold a
+//! whole frame in RAM.
+//!
+//! Instead of drawing into a frame and sending it to the display afterwards,
+//! [`render`] asks a callback for each row of the frame in turn, encodes it
+//! and hands the bytes to another callback for transmission. Only a single row
+//! ever has to exist in memory.
+//!
+//! For frames that do exist in memory but have to go out through a DMA buffer
+//! smaller than the frame, [`chunks`] encodes them a buffer at a time instead.
+
+use crate::{Encoding, Rgb565};
+
+/// Renders a frame `row.len()` pixels wide and `height` pixels tall, one row at
+/// a time.
+///
+/// For each row, `generate` is called with the row's y coordinate to fill in
+/// `row`, which is then encoded in place and passed to `transmit` as bytes.
+/// Since the encoding overwrites the row, `generate` must fill in every pixel
+/// each time it's called. Rendering stops at the first error from `transmit`.
+///
+/// ```
+/// # use rgb565::{scanline, Encoding, Rgb565};
+/// let mut row = [Rgb565::default(); 2];
+/// let mut sent = Vec::new();
+///
+/// scanline::render(&mut row, 2, Encoding::Rgb565Be, |y, row| {
+///     row.fill(Rgb565::from_rgb565(y as u16 + 1));
+/// }, |bytes| {
+///     sent.extend_from_slice(bytes);
+///     Ok::<(), ()>(())
+/// }).unwrap();
+///
+/// assert_eq!(sent, [0, 1, 0, 1, 0, 2, 0, 2]);
+/// ```
+pub fn render<E>(row: &mut [Rgb565], height: usize, encoding: Encoding, mut generate: impl FnMut(usize, &mut [Rgb565]), mut transmit: impl FnMut(&[u8]) -> Result<(), E>) -> Result<(), E> {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	for y in 0..height {
+		generate(y, row);
+
+		let raw = Rgb565::to_rgb565_slice_mut(row);
+
+		for pixel in raw.iter_mut() {
+			*pixel = u16::from_ne_bytes(Rgb565::from_rgb565(*pixel).to_bytes(encoding));
+		}
+
+		// SAFETY: any `u16` is two valid `u8`s, and `u8` has no alignment.
+		let bytes = unsafe { core::slice::from_raw_parts(raw.as_ptr().cast::<u8>(), raw.len() * 2) };
+		transmit(bytes)?;
+	}
+
+	Ok(())
+}
+
+/// Sends a frame of raw RGB565 values through `buffer` as many times as it
+/// takes, encoding each pixel into it and passing it to `transmit`.
+///
+/// Every chunk ends on a pixel boundary: if `buffer` is an odd number of bytes
+/// long, the last byte is never used, so a pixel is never split across two
+/// transfers and the bytes of every chunk after it don't end up swapped. The
+/// last chunk is shorter than the rest if the frame doesn't fill it, and only
+/// the part of `buffer` holding pixels is passed for it. Sending stops at the
+/// first error from `transmit`.
+///
+/// # Panics
+///
+/// Panics if `buffer` is too short to hold a single pixel and `frame` isn't
+/// empty.
+///
+/// ```
+/// # use rgb565::{scanline, Encoding};
+/// let mut buffer = [0; 5];
+/// let mut sent = Vec::new();
+///
+/// scanline::chunks(&[0x0102, 0x0304, 0x0506], Encoding::Rgb565Be, &mut buffer, |bytes| {
+///     sent.push(bytes.to_vec());
+///     Ok::<(), ()>(())
+/// }).unwrap();
+///
+/// assert_eq!(sent, [vec![1, 2, 3, 4], vec![5, 6]]);
+/// ```
+pub fn chunks<E>(frame: &[u16], encoding: Encoding, buffer: &mut [u8], mut transmit: impl FnMut(&[u8]) -> Result<(), E>) -> Result<(), E> {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	if frame.is_empty() {
+		return Ok(());
+	}
+
+	let pixels = buffer.len() / 2;
+	assert!(pixels > 0, "buffer must hold at least one pixel");
+
+	for chunk in frame.chunks(pixels) {
+		let bytes = &mut buffer[..chunk.len() * 2];
+
+		for (dst, pixel) in bytes.chunks_exact_mut(2).zip(chunk) {
+			dst.copy_from_slice(&Rgb565::from_rgb565(*pixel).to_bytes(encoding));
+		}
+
+		transmit(bytes)?;
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{Encoding, Rgb565};
+
+	#[test]
+	fn render() {
+		let mut row = [Rgb565::default(); 3];
+		let mut sent = Vec::new();
+
+		let result = super::render(&mut row, 4, Encoding::Bgr565Le, |y, row| {
+			for (x, pixel) in row.iter_mut().enumerate() {
+				*pixel = Rgb565::from_rgb565_components(x as u8, y as u8, 0);
+			}
+		}, |bytes| {
+			sent.push(bytes.to_vec());
+			if sent.len() == 3 { Err("full") } else { Ok(()) }
+		});
+
+		assert_eq!(result, Err("full"));
+		assert_eq!(sent.len(), 3);
+
+		for (y, bytes) in sent.iter().enumerate() {
+			for (x, bytes) in bytes.chunks_exact(2).enumerate() {
+				assert_eq!(Rgb565::from_bgr565_le([bytes[0], bytes[1]]).to_rgb565_components(), [x as u8, y as u8, 0]);
+			}
+		}
+	}
+
+	#[test]
+	fn chunks() {
+		let frame: Vec<u16> = (0..7).map(|n| 0x0100 * n + 0x10 + n).collect();
+
+		for encoding in [Encoding::Rgb565Le, Encoding::Rgb565Be, Encoding::Bgr565Le, Encoding::Bgr565Be] {
+			for size in 2..20 {
+				let mut buffer = vec![0; size];
+				let mut sent = Vec::new();
+
+				super::chunks(&frame, encoding, &mut buffer, |bytes| {
+					sent.push(bytes.to_vec());
+					Ok::<(), ()>(())
+				}).unwrap();
+
+				let chunk = size / 2 * 2;
+				assert!(sent.iter().all(|bytes| bytes.len() <= chunk && bytes.len() % 2 == 0));
+				assert!(sent[..sent.len() - 1].iter().all(|bytes| bytes.len() == chunk));
+				assert_eq!(sent.len(), frame.len().div_ceil(size / 2));
+
+				let expected: Vec<u8> = frame.iter().flat_map(|pixel| Rgb565::from_rgb565(*pixel).to_bytes(encoding)).collect();
+				assert_eq!(sent.concat(), expected, "{:?}, {} bytes", encoding, size);
+			}
+		}
+
+		let mut calls = 0;
+		let result = super::chunks(&frame, Encoding::Rgb565Le, &mut [0; 4], |_| {
+			calls += 1;
+			if calls == 2 { Err("full") } else { Ok(()) }
+		});
+		assert_eq!((result, calls), (Err("full"), 2));
+
+		assert_eq!(super::chunks(&[], Encoding::Rgb565Le, &mut [], |_| Err(())), Ok(()));
+	}
+}
@@ -0,0 +1,372 @@
+//! Vectorized kernels for the bulk conversions, used with the `simd` feature.
+//!
+//! Each kernel converts as many whole blocks of 16 pixels as fit in both
+//! slices and returns the parts it didn't get to, for the scalar loop to
+//! finish. On targets without a kernel, or on CPUs without the instructions
+//! for it, they return their arguments untouched.
+//!
+//! Kernels exist for AArch64, which always has NEON, and for x86 and x86-64
+//! with SSSE3. That's either enabled at compile time (for example with
+//! `-C target-cpu=x86-64-v2`) or, with `std`, detected at runtime. They
+//! produce exactly the same results as the methods on
+//! [`Rgb565`](crate::Rgb565).
+
+#[cfg(target_arch = "aarch64")]
+use core::arch::aarch64::*;
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+#[cfg(any(target_arch = "aarch64", target_arch = "x86", target_arch = "x86_64"))]
+type Pair<'a, 'b, A, B> = (&'a [A], &'b mut [B]);
+
+/// Splits off as many whole blocks of pixels as fit in both `src` and `dst`,
+/// where a block is `a` elements of `src` and `b` elements of `dst`.
+#[cfg(any(target_arch = "aarch64", target_arch = "x86", target_arch = "x86_64"))]
+#[inline]
+fn split<'a, 'b, A, B>(src: &'a [A], dst: &'b mut [B], a: usize, b: usize) -> (Pair<'a, 'b, A, B>, Pair<'a, 'b, A, B>) {
+	let blocks = (src.len() / a).min(dst.len() / b);
+	let (src, src_rest) = src.split_at(blocks * a);
+	let (dst, dst_rest) = dst.split_at_mut(blocks * b);
+	((src, dst), (src_rest, dst_rest))
+}
+
+/// Whether the CPU has the instructions the kernels need. AArch64 always has
+/// NEON, and x86 has SSSE3 if it's enabled at compile time. Otherwise it's
+/// probed for at runtime, which needs `std`.
+#[inline]
+pub(crate) fn detected() -> bool {
+	#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(target_feature = "ssse3"), feature = "std"))]
+	if std::is_x86_feature_detected!("ssse3") {
+		return true;
+	}
+
+	cfg!(any(target_arch = "aarch64", all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "ssse3")))
+}
+
+/// The 5- or 6-bit widening table, which NEON can look up in registers.
+#[cfg(target_arch = "aarch64")]
+const fn widen_table<const N: usize>() -> [u8; N] {
+	let mut table = [0; N];
+	let mut i = 0;
+
+	while i < N {
+		table[i] = match N {
+			32 => crate::lut::transforms::l5_to_l8(i as u8),
+			_ => crate::lut::transforms::l6_to_l8(i as u8)
+		};
+
+		i += 1;
+	}
+
+	table
+}
+
+#[cfg(target_arch = "aarch64")]
+const L5_TO_L8: [u8; 32] = widen_table();
+#[cfg(target_arch = "aarch64")]
+const L6_TO_L8: [u8; 64] = widen_table();
+
+/// Narrows 8 lanes of 8-bit values to `max`, like `transforms::l8_to_l5`.
+/// Dividing by 255 is done as `(x + 1 + (x >> 8)) >> 8`, which is exact for
+/// every `x` below 65535.
+#[cfg(target_arch = "aarch64")]
+#[inline]
+#[target_feature(enable = "neon")]
+fn narrow(v: uint8x8_t, max: u16) -> uint16x8_t {
+	let x = vmulq_n_u16(vaddw_u8(vdupq_n_u16(1), v), max);
+	vshrq_n_u16::<8>(vaddq_u16(vaddq_u16(x, vdupq_n_u16(1)), vshrq_n_u16::<8>(x)))
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline]
+#[target_feature(enable = "neon")]
+fn pack(r: uint8x8_t, g: uint8x8_t, b: uint8x8_t) -> uint16x8_t {
+	let (r, g, b) = (narrow(r, 31), narrow(g, 63), narrow(b, 31));
+	vorrq_u16(vshlq_n_u16::<11>(r), vorrq_u16(vshlq_n_u16::<5>(g), b))
+}
+
+#[cfg(target_arch = "aarch64")]
+pub(crate) fn rgb888_to_rgb565<'a, 'b>(src: &'a [u8], dst: &'b mut [u16]) -> (&'a [u8], &'b mut [u16]) {
+	let ((src, dst), rest) = split(src, dst, 48, 16);
+
+	for (src, dst) in src.as_chunks::<48>().0.iter().zip(dst.as_chunks_mut::<16>().0) {
+		// SAFETY: `src` is 48 bytes long, `dst` is 16 pixels long and the
+		// target features are enabled.
+		unsafe {
+			let uint8x16x3_t(r, g, b) = vld3q_u8(src.as_ptr());
+			vst1q_u16(dst.as_mut_ptr(), pack(vget_low_u8(r), vget_low_u8(g), vget_low_u8(b)));
+			vst1q_u16(dst.as_mut_ptr().add(8), pack(vget_high_u8(r), vget_high_u8(g), vget_high_u8(b)));
+		}
+	}
+
+	rest
+}
+
+#[cfg(target_arch = "aarch64")]
+pub(crate) fn rgb565_to_rgb888<'a, 'b>(src: &'a [u16], dst: &'b mut [u8]) -> (&'a [u16], &'b mut [u8]) {
+	let ((src, dst), rest) = split(src, dst, 16, 48);
+
+	// SAFETY: the tables are 32 and 64 bytes long.
+	let (l5, l6) = unsafe { (vld1q_u8_x2(L5_TO_L8.as_ptr()), vld1q_u8_x4(L6_TO_L8.as_ptr())) };
+
+	for (src, dst) in src.as_chunks::<16>().0.iter().zip(dst.as_chunks_mut::<48>().0) {
+		// SAFETY: `src` is 16 pixels long, `dst` is 48 bytes long and the
+		// target features are enabled.
+		unsafe {
+			let (lo, hi) = (vld1q_u16(src.as_ptr()), vld1q_u16(src.as_ptr().add(8)));
+			let r = vcombine_u8(vshrn_n_u16::<11>(lo), vshrn_n_u16::<11>(hi));
+			let g = vandq_u8(vcombine_u8(vshrn_n_u16::<5>(lo), vshrn_n_u16::<5>(hi)), vdupq_n_u8(0x3F));
+			let b = vandq_u8(vcombine_u8(vmovn_u16(lo), vmovn_u16(hi)), vdupq_n_u8(0x1F));
+			vst3q_u8(dst.as_mut_ptr(), uint8x16x3_t(vqtbl2q_u8(l5, r), vqtbl4q_u8(l6, g), vqtbl2q_u8(l5, b)));
+		}
+	}
+
+	rest
+}
+
+#[cfg(all(target_arch = "aarch64", feature = "esp_lcd"))]
+pub(crate) fn swap_bytes(frame: &mut [u16]) -> &mut [u16] {
+	let (blocks, rest) = frame.as_chunks_mut::<8>();
+
+	for block in blocks {
+		// SAFETY: `block` is 8 pixels long.
+		unsafe {
+			let swapped = vrev16q_u8(vreinterpretq_u8_u16(vld1q_u16(block.as_ptr())));
+			vst1q_u16(block.as_mut_ptr(), vreinterpretq_u16_u8(swapped));
+		}
+	}
+
+	rest
+}
+
+/// `pshufb` masks that gather `channel` of 16 packed 8-bit pixels out of the
+/// 16 bytes of them in `register`, or the other way around if `interleave`.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+const fn shuffle(interleave: bool, channel: usize, register: usize) -> [u8; 16] {
+	let mut mask = [0x80; 16];
+	let mut i = 0;
+
+	while i < 16 {
+		if interleave && (register * 16 + i) % 3 == channel {
+			mask[i] = ((register * 16 + i) / 3) as u8;
+		} else if !interleave && (3 * i + channel) / 16 == register {
+			mask[i] = ((3 * i + channel) % 16) as u8;
+		}
+
+		i += 1;
+	}
+
+	mask
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+const DEINTERLEAVE: [[[u8; 16]; 3]; 3] = {
+	let mut masks = [[[0; 16]; 3]; 3];
+	let mut i = 0;
+
+	while i < 9 {
+		masks[i / 3][i % 3] = shuffle(false, i / 3, i % 3);
+		i += 1;
+	}
+
+	masks
+};
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+const INTERLEAVE: [[[u8; 16]; 3]; 3] = {
+	let mut masks = [[[0; 16]; 3]; 3];
+	let mut i = 0;
+
+	while i < 9 {
+		masks[i / 3][i % 3] = shuffle(true, i % 3, i / 3);
+		i += 1;
+	}
+
+	masks
+};
+
+/// Ors together `registers` shuffled by `masks`.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[inline]
+#[target_feature(enable = "ssse3")]
+fn gather(registers: [__m128i; 3], masks: &[[u8; 16]; 3]) -> __m128i {
+	// SAFETY: each mask is 16 bytes long.
+	let [a, b, c] = [0, 1, 2].map(|i| unsafe { _mm_shuffle_epi8(registers[i], _mm_loadu_si128(masks[i].as_ptr().cast())) });
+	_mm_or_si128(a, _mm_or_si128(b, c))
+}
+
+/// Narrows 8 lanes of 8-bit values to `max`, like `transforms::l8_to_l5`.
+/// Dividing by 255 is done as `(x + 1 + (x >> 8)) >> 8`, which is exact for
+/// every `x` below 65535.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[inline]
+#[target_feature(enable = "ssse3")]
+fn narrow(v: __m128i, max: i16) -> __m128i {
+	let x = _mm_mullo_epi16(_mm_add_epi16(v, _mm_set1_epi16(1)), _mm_set1_epi16(max));
+	_mm_srli_epi16::<8>(_mm_add_epi16(_mm_add_epi16(x, _mm_set1_epi16(1)), _mm_srli_epi16::<8>(x)))
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[inline]
+#[target_feature(enable = "ssse3")]
+fn pack(r: __m128i, g: __m128i, b: __m128i) -> __m128i {
+	let (r, g, b) = (narrow(r, 31), narrow(g, 63), narrow(b, 31));
+	_mm_or_si128(_mm_slli_epi16::<11>(r), _mm_or_si128(_mm_slli_epi16::<5>(g), b))
+}
+
+/// Widens 8 lanes of values up to `max` to 8 bits, like
+/// `transforms::l5_to_l8`, by multiplying by the reciprocal of `max`.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[inline]
+#[target_feature(enable = "ssse3")]
+fn widen(v: __m128i, max: u16) -> __m128i {
+	let x = _mm_mullo_epi16(v, _mm_set1_epi16(255));
+
+	match max {
+		31 => _mm_srli_epi16::<2>(_mm_mulhi_epu16(x, _mm_set1_epi16(8457))),
+		_ => _mm_srli_epi16::<3>(_mm_mulhi_epu16(x, _mm_set1_epi16(8323)))
+	}
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "ssse3")]
+fn rgb888_to_rgb565_ssse3<'a, 'b>(src: &'a [u8], dst: &'b mut [u16]) -> (&'a [u8], &'b mut [u16]) {
+	let ((src, dst), rest) = split(src, dst, 48, 16);
+	let zero = _mm_setzero_si128();
+
+	for (src, dst) in src.as_chunks::<48>().0.iter().zip(dst.as_chunks_mut::<16>().0) {
+		// SAFETY: `src` is 48 bytes long and `dst` is 16 pixels long.
+		unsafe {
+			let registers = [0, 16, 32].map(|i| _mm_loadu_si128(src.as_ptr().add(i).cast()));
+			let [r, g, b] = DEINTERLEAVE.each_ref().map(|masks| gather(registers, masks));
+			let lo = pack(_mm_unpacklo_epi8(r, zero), _mm_unpacklo_epi8(g, zero), _mm_unpacklo_epi8(b, zero));
+			let hi = pack(_mm_unpackhi_epi8(r, zero), _mm_unpackhi_epi8(g, zero), _mm_unpackhi_epi8(b, zero));
+			_mm_storeu_si128(dst.as_mut_ptr().cast(), lo);
+			_mm_storeu_si128(dst.as_mut_ptr().add(8).cast(), hi);
+		}
+	}
+
+	rest
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "ssse3")]
+fn rgb565_to_rgb888_ssse3<'a, 'b>(src: &'a [u16], dst: &'b mut [u8]) -> (&'a [u16], &'b mut [u8]) {
+	let ((src, dst), rest) = split(src, dst, 16, 48);
+	let (mask5, mask6) = (_mm_set1_epi16(0x1F), _mm_set1_epi16(0x3F));
+
+	for (src, dst) in src.as_chunks::<16>().0.iter().zip(dst.as_chunks_mut::<48>().0) {
+		// SAFETY: `src` is 16 pixels long and `dst` is 48 bytes long.
+		unsafe {
+			let [lo, hi] = [0, 8].map(|i| _mm_loadu_si128(src.as_ptr().add(i).cast()));
+			let r = _mm_packus_epi16(widen(_mm_srli_epi16::<11>(lo), 31), widen(_mm_srli_epi16::<11>(hi), 31));
+			let g = _mm_packus_epi16(widen(_mm_and_si128(_mm_srli_epi16::<5>(lo), mask6), 63), widen(_mm_and_si128(_mm_srli_epi16::<5>(hi), mask6), 63));
+			let b = _mm_packus_epi16(widen(_mm_and_si128(lo, mask5), 31), widen(_mm_and_si128(hi, mask5), 31));
+
+			for (i, masks) in INTERLEAVE.iter().enumerate() {
+				_mm_storeu_si128(dst.as_mut_ptr().add(i * 16).cast(), gather([r, g, b], masks));
+			}
+		}
+	}
+
+	rest
+}
+
+#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "esp_lcd"))]
+#[target_feature(enable = "ssse3")]
+fn swap_bytes_ssse3(frame: &mut [u16]) -> &mut [u16] {
+	let (blocks, rest) = frame.as_chunks_mut::<8>();
+
+	for block in blocks {
+		// SAFETY: `block` is 8 pixels long.
+		unsafe {
+			let pixels = _mm_loadu_si128(block.as_ptr().cast());
+			let swapped = _mm_or_si128(_mm_slli_epi16::<8>(pixels), _mm_srli_epi16::<8>(pixels));
+			_mm_storeu_si128(block.as_mut_ptr().cast(), swapped);
+		}
+	}
+
+	rest
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub(crate) fn rgb888_to_rgb565<'a, 'b>(src: &'a [u8], dst: &'b mut [u16]) -> (&'a [u8], &'b mut [u16]) {
+	if !detected() {
+		return (src, dst);
+	}
+
+	// SAFETY: the CPU has SSSE3.
+	unsafe { rgb888_to_rgb565_ssse3(src, dst) }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub(crate) fn rgb565_to_rgb888<'a, 'b>(src: &'a [u16], dst: &'b mut [u8]) -> (&'a [u16], &'b mut [u8]) {
+	if !detected() {
+		return (src, dst);
+	}
+
+	// SAFETY: the CPU has SSSE3.
+	unsafe { rgb565_to_rgb888_ssse3(src, dst) }
+}
+
+#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "esp_lcd"))]
+pub(crate) fn swap_bytes(frame: &mut [u16]) -> &mut [u16] {
+	if !detected() {
+		return frame;
+	}
+
+	// SAFETY: the CPU has SSSE3.
+	unsafe { swap_bytes_ssse3(frame) }
+}
+
+#[cfg(not(any(target_arch = "aarch64", target_arch = "x86", target_arch = "x86_64")))]
+#[inline]
+pub(crate) fn rgb888_to_rgb565<'a, 'b>(src: &'a [u8], dst: &'b mut [u16]) -> (&'a [u8], &'b mut [u16]) { (src, dst) }
+
+#[cfg(not(any(target_arch = "aarch64", target_arch = "x86", target_arch = "x86_64")))]
+#[inline]
+pub(crate) fn rgb565_to_rgb888<'a, 'b>(src: &'a [u16], dst: &'b mut [u8]) -> (&'a [u16], &'b mut [u8]) { (src, dst) }
+
+#[cfg(all(not(any(target_arch = "aarch64", target_arch = "x86", target_arch = "x86_64")), feature = "esp_lcd"))]
+#[inline]
+pub(crate) fn swap_bytes(frame: &mut [u16]) -> &mut [u16] { frame }
+
+#[cfg(test)]
+mod tests {
+	use crate::Rgb565;
+
+	#[test]
+	fn conversions() {
+		let frame: Vec<u16> = (0..=u16::MAX).collect();
+		let mut rgb888 = vec![0; frame.len() * 3];
+		let rest = super::rgb565_to_rgb888(&frame, &mut rgb888).0.len();
+		let done = frame.len() - rest;
+
+		for (i, pixel) in frame[..done].iter().enumerate() {
+			assert_eq!(rgb888[i * 3..i * 3 + 3], Rgb565::from_rgb565(*pixel).to_rgb888_components());
+		}
+
+		let bytes: Vec<u8> = (0..=u8::MAX).cycle().take(256 * 3 * 3 + 5).collect();
+		let mut back = vec![0; bytes.len() / 3];
+		let rest = super::rgb888_to_rgb565(&bytes, &mut back).1.len();
+
+		for (i, pixel) in back[..back.len() - rest].iter().enumerate() {
+			let [r, g, b] = [0, 1, 2].map(|c| bytes[i * 3 + c]);
+			assert_eq!(*pixel, Rgb565::from_rgb888_components(r, g, b).to_rgb565());
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "esp_lcd")]
+	fn swap_bytes() {
+		let frame: Vec<u16> = (0..=u16::MAX).collect();
+		let mut swapped = frame.clone();
+		let rest = super::swap_bytes(&mut swapped).len();
+
+		for (pixel, swapped) in frame.iter().zip(&swapped[..frame.len() - rest]) {
+			assert_eq!(pixel.swap_bytes(), *swapped);
+		}
+	}
+}
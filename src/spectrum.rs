@@ -0,0 +1,121 @@
+#![allow(clippy::excessive_precision)]
+
+use crate::Rgb565;
+
+/// Converts CIE XYZ to linear sRGB, for a D65 white point.
+const XYZ_TO_SRGB: [[f32; 3]; 3] = [
+	[3.2404542, -1.5371385, -0.4985314],
+	[-0.9692660, 1.8760108, 0.0415560],
+	[0.0556434, -0.2040259, 1.0572252]
+];
+
+/// Converts a CIE XYZ color to RGB565, scaled so that its brightest channel is
+/// at `intensity`. Colors outside of sRGB are clipped.
+#[inline]
+fn xyz_to_rgb565(xyz: [f32; 3], intensity: f32) -> Rgb565 {
+	let rgb = XYZ_TO_SRGB.map(|row| (row[0] * xyz[0] + row[1] * xyz[1] + row[2] * xyz[2]).max(0.0));
+	let max = rgb[0].max(rgb[1]).max(rgb[2]);
+
+	if max <= 0.0 {
+		return Rgb565::default();
+	}
+
+	let [r, g, b] = rgb.map(|channel| channel / max * intensity);
+	Rgb565::from_rgb565_components((r * 31.0 + 0.5) as u8, (g * 63.0 + 0.5) as u8, (b * 31.0 + 0.5) as u8)
+}
+
+/// Returns the color of a blackbody at `kelvin`, using the cubic spline
+/// approximation of the Planckian locus by Kim et al. Temperatures are clamped
+/// to the 1667 K to 25000 K range the approximation covers.
+#[inline]
+pub fn kelvin_to_rgb565(kelvin: u32) -> Rgb565 {
+	let t = kelvin.clamp(1667, 25000) as f32;
+	let (t1, t2, t3) = (1e3 / t, 1e6 / (t * t), 1e9 / (t * t * t));
+
+	let x = if t <= 4000.0 {
+		-0.2661239 * t3 - 0.2343589 * t2 + 0.8776956 * t1 + 0.179910
+	} else {
+		-3.0258469 * t3 + 2.1070379 * t2 + 0.2226347 * t1 + 0.240390
+	};
+
+	let (x2, x3) = (x * x, x * x * x);
+
+	let y = if t <= 2222.0 {
+		-1.1063814 * x3 - 1.34811020 * x2 + 2.18555832 * x - 0.20219683
+	} else if t <= 4000.0 {
+		-0.9549476 * x3 - 1.37418593 * x2 + 2.09137015 * x - 0.16748867
+	} else {
+		3.0817580 * x3 - 5.87338670 * x2 + 3.75112997 * x - 0.37001483
+	};
+
+	xyz_to_rgb565([x / y, 1.0, (1.0 - x - y) / y], 1.0)
+}
+
+/// A piecewise Gaussian lobe, as used by the fit below.
+#[cfg(feature = "std")]
+#[inline]
+fn lobe(nm: f32, mean: f32, below: f32, above: f32) -> f32 {
+	let t = (nm - mean) / if nm < mean { below } else { above };
+	(-0.5 * t * t).exp()
+}
+
+/// Returns the color of monochromatic light at `nm`, using the multi-lobe fit
+/// of the CIE 1931 color matching functions by Wyman, Sloan and Shirley.
+/// Spectral colors are all outside of sRGB, so they're clipped, and fade to
+/// black towards the edges of the visible spectrum (380 nm to 780 nm).
+#[cfg(feature = "std")]
+#[inline]
+pub fn wavelength_to_rgb565(nm: u16) -> Rgb565 {
+	let intensity = match nm {
+		380..=419 => 0.3 + 0.7 * (nm - 380) as f32 / 40.0,
+		420..=700 => 1.0,
+		701..=780 => 0.3 + 0.7 * (780 - nm) as f32 / 80.0,
+		_ => return Rgb565::default()
+	};
+
+	let nm = nm as f32;
+	let x = 1.056 * lobe(nm, 599.8, 37.9, 31.0) + 0.362 * lobe(nm, 442.0, 16.0, 26.7) - 0.065 * lobe(nm, 501.1, 20.4, 26.2);
+	let y = 0.821 * lobe(nm, 568.8, 46.9, 40.5) + 0.286 * lobe(nm, 530.9, 16.3, 31.1);
+	let z = 1.217 * lobe(nm, 437.0, 11.8, 36.0) + 0.681 * lobe(nm, 459.0, 26.0, 13.8);
+	xyz_to_rgb565([x, y, z], intensity)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::Rgb565;
+
+	#[test]
+	fn kelvin() {
+		// The Planckian locus passes just below D65, so this is slightly magenta.
+		let [r, g, b] = Rgb565::from_kelvin(6504).to_rgb888_components();
+		assert!(r >= 240 && g >= 224 && b >= 240, "{:?}", [r, g, b]);
+
+		let [r, g, b] = Rgb565::from_kelvin(2000).to_rgb888_components();
+		assert!(r == 255 && g < r && b < g, "{:?}", [r, g, b]);
+
+		let [r, g, b] = Rgb565::from_kelvin(20000).to_rgb888_components();
+		assert!(b == 255 && r < b, "{:?}", [r, g, b]);
+
+		assert_eq!(Rgb565::from_kelvin(0), Rgb565::from_kelvin(1667));
+		assert_eq!(Rgb565::from_kelvin(u32::MAX), Rgb565::from_kelvin(25000));
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn wavelength() {
+		assert_eq!(Rgb565::from_wavelength_nm(379), Rgb565::default());
+		assert_eq!(Rgb565::from_wavelength_nm(781), Rgb565::default());
+
+		let dominant = |nm| {
+			let [r, g, b] = Rgb565::from_wavelength_nm(nm).to_rgb565_components();
+			[r * 2, g, b * 2].iter().enumerate().max_by_key(|(_, channel)| **channel).unwrap().0
+		};
+
+		assert_eq!(dominant(450), 2);
+		assert_eq!(dominant(530), 1);
+		assert_eq!(dominant(650), 0);
+
+		let [r, g, b] = Rgb565::from_wavelength_nm(580).to_srgb888_components();
+		assert!(r > 224 && g > 160 && b < 32, "{:?}", [r, g, b]);
+	}
+}
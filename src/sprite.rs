@@ -0,0 +1,172 @@
+//! Sprite sheets, for blitting sprites by index instead of by rectangle.
+//!
+//! A [`SpriteSheet`] pairs a framebuffer with a [`Layout`] saying where each
+//! sprite is in it, either as a [`Grid`] of equally sized cells or as a list
+//! of rectangles exported by an atlas packer.
+//!
+//! ```
+//! # use rgb565::{sprite::{Grid, Layout, SpriteSheet}, Framebuffer565};
+//! // Four 2x2 sprites side by side.
+//! let pixels: Vec<u16> = (0..8 * 2).collect();
+//! let sheet = SpriteSheet::new(Framebuffer565::new(pixels, 8, 2).unwrap(), Layout::Grid(Grid::new(2, 2)));
+//! assert_eq!(sheet.len(), 4);
+//!
+//! let mut frame = Framebuffer565::new([0u16; 2 * 2], 2, 2).unwrap();
+//! frame.blit(&sheet.sprite(2).unwrap(), 0, 0);
+//! assert_eq!(frame.into_inner(), [4, 5, 12, 13]);
+//! ```
+
+use crate::frame::Rect;
+use crate::Framebuffer565;
+
+/// Cells of the same size, numbered left to right and then top to bottom.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct Grid {
+	/// The width of each cell, in pixels.
+	pub width: usize,
+	/// The height of each cell, in pixels.
+	pub height: usize,
+	/// The pixels before the first column and row.
+	pub margin: usize,
+	/// The pixels between neighboring cells.
+	pub spacing: usize
+}
+
+impl Grid {
+	/// Cells of `width` by `height` pixels, packed together from the top left.
+	#[inline]
+	#[must_use]
+	pub const fn new(width: usize, height: usize) -> Self { Self { width, height, margin: 0, spacing: 0 } }
+
+	/// How many cells fit along an axis `len` pixels long, given the size of
+	/// a cell along it.
+	#[inline]
+	fn count(&self, len: usize, cell: usize) -> usize {
+		match len.checked_sub(self.margin + cell) {
+			Some(rest) if cell > 0 => rest / (cell + self.spacing) + 1,
+			_ => 0
+		}
+	}
+}
+
+/// Where the sprites of a [`SpriteSheet`] are.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Layout<'a> {
+	/// A grid of cells, as many as fit in the sheet.
+	Grid(Grid),
+	/// One rectangle per sprite, in order.
+	Atlas(&'a [Rect])
+}
+
+/// A framebuffer full of sprites, along with where each one is.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct SpriteSheet<'a, B> {
+	frame: Framebuffer565<B>,
+	layout: Layout<'a>
+}
+
+impl<'a, B: AsRef<[u16]>> SpriteSheet<'a, B> {
+	/// Wraps `frame` as a sheet of sprites laid out as `layout` says.
+	#[inline]
+	#[must_use]
+	pub fn new(frame: Framebuffer565<B>, layout: Layout<'a>) -> Self { Self { frame, layout } }
+
+	/// The framebuffer the sheet wraps.
+	#[inline]
+	#[must_use]
+	pub fn frame(&self) -> &Framebuffer565<B> { &self.frame }
+
+	/// Where the sprites are in the sheet.
+	#[inline]
+	#[must_use]
+	pub fn layout(&self) -> Layout<'a> { self.layout }
+
+	/// Returns the framebuffer the sheet wraps.
+	#[inline]
+	#[must_use]
+	pub fn into_inner(self) -> Framebuffer565<B> { self.frame }
+
+	/// The number of sprites in the sheet.
+	#[must_use]
+	pub fn len(&self) -> usize {
+		match self.layout {
+			Layout::Grid(grid) => grid.count(self.frame.width(), grid.width) * grid.count(self.frame.height(), grid.height),
+			Layout::Atlas(rects) => rects.len()
+		}
+	}
+
+	/// Whether the sheet has no sprites.
+	#[inline]
+	#[must_use]
+	pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+	/// Where sprite `index` is in the sheet, or `None` if there's no such
+	/// sprite.
+	#[must_use]
+	pub fn rect(&self, index: usize) -> Option<Rect> {
+		match self.layout {
+			Layout::Grid(grid) => {
+				let columns = grid.count(self.frame.width(), grid.width);
+				let (column, row) = (index.checked_rem(columns)?, index / columns);
+				(row < grid.count(self.frame.height(), grid.height)).then(|| {
+					let at = |i: usize, cell: usize| grid.margin + i * (cell + grid.spacing);
+					Rect::new(at(column, grid.width), at(row, grid.height), grid.width, grid.height)
+				})
+			}
+
+			Layout::Atlas(rects) => rects.get(index).copied()
+		}
+	}
+
+	/// Borrows sprite `index` as a framebuffer to blit from, or returns `None`
+	/// if there's no such sprite or its rectangle doesn't fit in the sheet.
+	#[inline]
+	#[must_use]
+	pub fn sprite(&self, index: usize) -> Option<Framebuffer565<&[u16]>> { self.frame.view(self.rect(index)?) }
+
+	/// All of the sprites in order, skipping any whose rectangle doesn't fit
+	/// in the sheet.
+	pub fn sprites(&self) -> impl Iterator<Item = Framebuffer565<&[u16]>> + '_ { (0..self.len()).filter_map(|index| self.sprite(index)) }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Grid, Layout, SpriteSheet};
+	use crate::frame::Rect;
+	use crate::Framebuffer565;
+
+	#[test]
+	fn grid() {
+		// Three columns and two rows of 2x2 cells, with a margin of 1 and 1
+		// pixel between cells, in an 11x8 sheet with room left over.
+		let pixels: Vec<u16> = (0..11 * 8).collect();
+		let grid = Grid { margin: 1, spacing: 1, ..Grid::new(2, 2) };
+		let sheet = SpriteSheet::new(Framebuffer565::new(&pixels[..], 11, 8).unwrap(), Layout::Grid(grid));
+
+		assert_eq!(sheet.len(), 6);
+		assert_eq!(sheet.rect(0), Some(Rect::new(1, 1, 2, 2)));
+		assert_eq!(sheet.rect(4), Some(Rect::new(4, 4, 2, 2)));
+		assert_eq!(sheet.rect(6), None);
+
+		let sprite = sheet.sprite(5).unwrap();
+		assert_eq!((sprite.row(0), sprite.row(1)), (Some(&[51, 52][..]), Some(&[62, 63][..])));
+		assert_eq!(sheet.sprites().count(), 6);
+
+		let empty = SpriteSheet::new(Framebuffer565::new(&pixels[..], 11, 8).unwrap(), Layout::Grid(Grid::new(0, 2)));
+		assert!(empty.is_empty());
+		assert_eq!(empty.rect(0), None);
+	}
+
+	#[test]
+	fn atlas() {
+		let pixels: Vec<u16> = (0..4 * 4).collect();
+		let rects = [Rect::new(0, 0, 4, 1), Rect::new(3, 1, 1, 3), Rect::new(3, 3, 2, 2)];
+		let sheet = SpriteSheet::new(Framebuffer565::new(pixels, 4, 4).unwrap(), Layout::Atlas(&rects));
+
+		assert_eq!(sheet.len(), 3);
+		assert_eq!(sheet.sprite(0).unwrap().row(0), Some(&[0, 1, 2, 3][..]));
+		assert_eq!(sheet.sprite(1).unwrap().pixel(0, 2), Some(crate::Rgb565::from_rgb565(15)));
+		assert!(sheet.sprite(2).is_none());
+		assert_eq!(sheet.sprites().count(), 2);
+	}
+}
@@ -0,0 +1,140 @@
+//! Counters of how much work the crate has done, for confirming which code
+//! paths actually run on a device without attaching a profiler.
+//!
+//! Every conversion through a [`Converter`](crate::Converter) is counted
+//! against the backend that performed it. Every call to a routine that writes
+//! a whole frame, such as those in [`frame`](crate::frame),
+//! [`scanline::render`](crate::scanline::render) and the frame routines of
+//! [`dither`](crate::dither) and `present`, counts as one frame. The methods
+//! on [`Rgb565`](crate::Rgb565) itself are not counted, since they're used
+//! everywhere, including by the crate itself.
+//!
+//! The counters are global, shared by every thread, and wrap around on
+//! overflow. Each one is a relaxed atomic increment, which is cheap but not
+//! free, so this is only compiled in with the `stats` feature.
+//!
+//! ```
+//! # use rgb565::{stats, Converter, Rgb565};
+//! stats::reset();
+//! Converter::arithmetic().to_rgb888(Rgb565::from_rgb565(0xF800));
+//! assert!(stats::snapshot().arithmetic >= 1);
+//! ```
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// A copy of the counters at one point in time.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub struct Stats {
+	/// Pixels converted through a `Converter`, by any backend.
+	pub pixels_converted: usize,
+	/// Frames processed by the whole-frame routines.
+	pub frames_processed: usize,
+	/// Pixels converted by the `Builtin` backend, with the crate's own LUTs or
+	/// arithmetic.
+	pub builtin: usize,
+	/// Pixels converted by the `Arithmetic` backend.
+	pub arithmetic: usize,
+	/// Pixels converted by a table supplied at runtime.
+	pub table: usize,
+	/// Pixels converted a block at a time by the vectorized kernels.
+	pub simd: usize
+}
+
+/// Which backend of a `Converter` performed a conversion.
+#[derive(Copy, Clone)]
+pub(crate) enum Backend {
+	Builtin,
+	Arithmetic,
+	Table,
+	#[cfg(simd_backend)]
+	Simd
+}
+
+static FRAMES: AtomicUsize = AtomicUsize::new(0);
+static BUILTIN: AtomicUsize = AtomicUsize::new(0);
+static ARITHMETIC: AtomicUsize = AtomicUsize::new(0);
+static TABLE: AtomicUsize = AtomicUsize::new(0);
+static SIMD: AtomicUsize = AtomicUsize::new(0);
+
+#[inline]
+fn add(counter: &AtomicUsize, count: usize) {
+	#[cfg(target_has_atomic = "ptr")]
+	counter.fetch_add(count, Ordering::Relaxed);
+
+	// Targets like ARMv6-M can't increment atomically. The counters are only
+	// for profiling, so an increment lost to an interrupt is acceptable.
+	#[cfg(not(target_has_atomic = "ptr"))]
+	counter.store(counter.load(Ordering::Relaxed).wrapping_add(count), Ordering::Relaxed);
+}
+
+/// Counts `count` conversions by `backend`.
+#[inline]
+pub(crate) fn conversions(backend: Backend, count: usize) {
+	add(match backend {
+		Backend::Builtin => &BUILTIN,
+		Backend::Arithmetic => &ARITHMETIC,
+		Backend::Table => &TABLE,
+		#[cfg(simd_backend)]
+		Backend::Simd => &SIMD
+	}, count);
+}
+
+/// Counts one conversion by `backend`.
+#[inline]
+pub(crate) fn conversion(backend: Backend) { conversions(backend, 1); }
+
+/// Counts one frame.
+#[inline]
+pub(crate) fn frame() { add(&FRAMES, 1); }
+
+/// Returns the current value of every counter.
+#[must_use]
+pub fn snapshot() -> Stats {
+	let builtin = BUILTIN.load(Ordering::Relaxed);
+	let arithmetic = ARITHMETIC.load(Ordering::Relaxed);
+	let table = TABLE.load(Ordering::Relaxed);
+	let simd = SIMD.load(Ordering::Relaxed);
+
+	Stats {
+		pixels_converted: builtin.wrapping_add(arithmetic).wrapping_add(table).wrapping_add(simd),
+		frames_processed: FRAMES.load(Ordering::Relaxed),
+		builtin,
+		arithmetic,
+		table,
+		simd
+	}
+}
+
+/// Resets every counter to zero.
+pub fn reset() {
+	for counter in [&FRAMES, &BUILTIN, &ARITHMETIC, &TABLE, &SIMD] {
+		counter.store(0, Ordering::Relaxed);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{frame, Converter, Rgb565};
+
+	// The counters are global and tests run in parallel, so this only checks
+	// that they go up by at least as much as expected.
+	#[test]
+	fn counts() {
+		let before = super::snapshot();
+		let table = [[0u8; 3]; 65536];
+		let converter = Converter::builtin().with_rgb888_table(&table);
+
+		for i in 0..10 {
+			let _ = converter.to_rgb888(Rgb565::from_rgb565(i));
+			let _ = converter.from_rgb888([0; 3]);
+		}
+
+		frame::swap_components(&mut [0; 4]);
+
+		let after = super::snapshot();
+		assert!(after.table - before.table >= 10);
+		assert!(after.builtin - before.builtin >= 10);
+		assert!(after.pixels_converted - before.pixels_converted >= 20);
+		assert!(after.frames_processed > before.frames_processed);
+	}
+}
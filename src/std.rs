@@ -1,15 +1,55 @@
+#![allow(clippy::excessive_precision)]
+
+use super::powf;
+
+/// The slope of the linear segment of the sRGB curve. IEC 61966-2-1 says 12.92,
+/// but this crate has always used a value that makes the two segments of the
+/// curve meet, unless the `exact_srgb` feature is enabled.
+#[cfg(not(feature = "exact_srgb"))]
+const LINEAR_SLOPE: f32 = 12.9232102;
+#[cfg(feature = "exact_srgb")]
+const LINEAR_SLOPE: f32 = 12.92;
+
+/// The encoded value where the sRGB curve switches from the linear segment to
+/// the power segment, which goes along with `LINEAR_SLOPE`.
+#[cfg(not(feature = "exact_srgb"))]
+const ENCODED_THRESHOLD: f32 = 0.0404599;
+#[cfg(feature = "exact_srgb")]
+const ENCODED_THRESHOLD: f32 = 0.04045;
+
 pub fn srgb_transfer(v: f32) -> f32 {
 	if v < 0.0031308 {
-		v * 12.9232102
+		v * LINEAR_SLOPE
 	} else {
-		1.055 * v.powf(1.0 / 2.4) - 0.055
+		1.055 * powf(v, 1.0 / 2.4) - 0.055
 	}
 }
 
 pub fn srgb_untransfer(v: f32) -> f32 {
-	if v < 0.0404599 {
-		v / 12.9232102
+	if v < ENCODED_THRESHOLD {
+		v / LINEAR_SLOPE
 	} else {
-		((v + 0.055) / 1.055).powf(2.4)
+		powf((v + 0.055) / 1.055, 2.4)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn round_trip() {
+		let tolerance = if cfg!(feature = "fast_srgb") { 2e-4 } else { 1e-5 };
+
+		for i in 0..=1000 {
+			let v = i as f32 / 1000.0;
+			assert!((super::srgb_untransfer(super::srgb_transfer(v)) - v).abs() < tolerance, "{}", v);
+		}
+	}
+
+	#[test]
+	#[cfg(all(feature = "exact_srgb", not(feature = "fast_srgb")))]
+	fn exact() {
+		assert_eq!(super::srgb_transfer(0.003), 0.003 * 12.92);
+		assert_eq!(super::srgb_untransfer(0.04), 0.04 / 12.92);
+		assert_eq!(super::srgb_untransfer(0.04045), ((0.04045f32 + 0.055) / 1.055).powf(2.4));
 	}
 }
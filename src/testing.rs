@@ -0,0 +1,288 @@
+//! Helpers for testing rendering code that produces RGB565 frames.
+//!
+//! The main attraction is [`assert_frames_similar!`](crate::assert_frames_similar),
+//! which compares two frames channel by channel and, when they differ by more
+//! than the allowed amount, writes PNGs of both frames side by side along with
+//! a heatmap of the differences to a temporary directory, so that you can
+//! actually see what went wrong.
+//!
+//! For single colors, [`assert_color_eq!`](crate::assert_color_eq) and
+//! [`assert_color_near!`](crate::assert_color_near) print both colors as raw
+//! values, channels and 8-bit RGB, along with how far apart each channel is.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::panic::Location;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::Rgb565;
+
+/// A rectangular grid of pixels that can be compared by
+/// [`assert_frames_similar!`](crate::assert_frames_similar).
+///
+/// This is implemented for slices, arrays and `Vec`s of rows, where each row
+/// is anything that can be borrowed as a `[u16]` of raw RGB565 values.
+pub trait Frame {
+	/// Returns the width and height of the frame, in pixels.
+	fn dimensions(&self) -> (usize, usize);
+
+	/// Returns the pixel at `x`, `y`.
+	fn pixel(&self, x: usize, y: usize) -> Rgb565;
+}
+
+impl<R: AsRef<[u16]>> Frame for [R] {
+	fn dimensions(&self) -> (usize, usize) {
+		let width = self.first().map_or(0, |row| row.as_ref().len());
+		assert!(self.iter().all(|row| row.as_ref().len() == width), "frame rows are not all the same length");
+		(width, self.len())
+	}
+
+	fn pixel(&self, x: usize, y: usize) -> Rgb565 { Rgb565::from_rgb565(self[y].as_ref()[x]) }
+}
+
+impl<R: AsRef<[u16]>, const H: usize> Frame for [R; H] {
+	fn dimensions(&self) -> (usize, usize) { self.as_slice().dimensions() }
+
+	fn pixel(&self, x: usize, y: usize) -> Rgb565 { self.as_slice().pixel(x, y) }
+}
+
+impl<R: AsRef<[u16]>> Frame for Vec<R> {
+	fn dimensions(&self) -> (usize, usize) { self.as_slice().dimensions() }
+
+	fn pixel(&self, x: usize, y: usize) -> Rgb565 { self.as_slice().pixel(x, y) }
+}
+
+impl<F: Frame + ?Sized> Frame for &F {
+	fn dimensions(&self) -> (usize, usize) { (**self).dimensions() }
+
+	fn pixel(&self, x: usize, y: usize) -> Rgb565 { (**self).pixel(x, y) }
+}
+
+/// Asserts that two [`Frame`]s have the same dimensions and that no channel of
+/// any pixel differs by more than `max_delta` once expanded to 8 bits.
+///
+/// On failure, the expected and actual frames are written side by side to
+/// `side_by_side.png`, and a heatmap of the differences to `diff.png`, in a
+/// fresh directory under [`std::env::temp_dir`]. The panic message says where.
+/// In the heatmap, differences within `max_delta` are shades of gray and
+/// differences beyond it are shades of red.
+///
+/// ```
+/// # use rgb565::assert_frames_similar;
+/// let expected = [[0xF800, 0x07E0], [0x001F, 0xFFFF]];
+/// let actual = [[0xF800, 0x07E0], [0x001F, 0xFFDF]];
+/// assert_frames_similar!(actual, expected, 8);
+/// ```
+#[macro_export]
+macro_rules! assert_frames_similar {
+	($actual:expr, $expected:expr, $max_delta:expr $(,)?) => {
+		$crate::testing::assert_frames_similar(&$actual, &$expected, $max_delta)
+	};
+}
+
+/// The function behind [`assert_frames_similar!`](crate::assert_frames_similar).
+#[track_caller]
+pub fn assert_frames_similar(actual: &impl Frame, expected: &impl Frame, max_delta: u8) {
+	let (width, height) = actual.dimensions();
+
+	if expected.dimensions() != (width, height) {
+		let dir = write_failure(Location::caller(), actual, expected, max_delta);
+		panic!("frames differ in size: actual is {:?}, expected is {:?} (see {})", (width, height), expected.dimensions(), dir.display());
+	}
+
+	let mut mismatches = 0usize;
+	let mut worst = 0u8;
+
+	for y in 0..height {
+		for x in 0..width {
+			let delta = pixel_delta(actual.pixel(x, y), expected.pixel(x, y));
+			worst = worst.max(delta);
+
+			if delta > max_delta {
+				mismatches += 1;
+			}
+		}
+	}
+
+	if mismatches > 0 {
+		let dir = write_failure(Location::caller(), actual, expected, max_delta);
+		panic!("{} of {} pixels differ by more than {} (worst is {}) (see {})", mismatches, width * height, max_delta, worst, dir.display());
+	}
+}
+
+/// A single color that can be compared by
+/// [`assert_color_eq!`](crate::assert_color_eq) and
+/// [`assert_color_near!`](crate::assert_color_near). This is implemented for
+/// [`Rgb565`] and raw RGB565 values.
+pub trait Color {
+	/// Returns the color as an [`Rgb565`].
+	fn color(&self) -> Rgb565;
+}
+
+impl Color for Rgb565 {
+	fn color(&self) -> Rgb565 { *self }
+}
+
+impl Color for u16 {
+	fn color(&self) -> Rgb565 { Rgb565::from_rgb565(*self) }
+}
+
+impl<C: Color + ?Sized> Color for &C {
+	fn color(&self) -> Rgb565 { (**self).color() }
+}
+
+/// Asserts that two [`Color`]s are equal, printing their channels and the
+/// difference between them on failure.
+///
+/// ```should_panic
+/// # use rgb565::assert_color_eq;
+/// assert_color_eq!(0x4DAB, 0x4DCB);
+/// ```
+#[macro_export]
+macro_rules! assert_color_eq {
+	($actual:expr, $expected:expr $(,)?) => {
+		$crate::testing::assert_color_near(&$actual, &$expected, 0)
+	};
+}
+
+/// Asserts that no channel of two [`Color`]s differs by more than `max_delta`
+/// once expanded to 8 bits, printing their channels and the difference between
+/// them on failure.
+///
+/// ```
+/// # use rgb565::assert_color_near;
+/// assert_color_near!(0x4DAB, 0x4DCB, 4);
+/// ```
+#[macro_export]
+macro_rules! assert_color_near {
+	($actual:expr, $expected:expr, $max_delta:expr $(,)?) => {
+		$crate::testing::assert_color_near(&$actual, &$expected, $max_delta)
+	};
+}
+
+/// The function behind [`assert_color_eq!`](crate::assert_color_eq) and
+/// [`assert_color_near!`](crate::assert_color_near).
+#[track_caller]
+pub fn assert_color_near(actual: &impl Color, expected: &impl Color, max_delta: u8) {
+	let (actual, expected) = (actual.color(), expected.color());
+
+	if pixel_delta(actual, expected) > max_delta {
+		let describe = |color: Rgb565| {
+			let [r5, g6, b5] = color.to_rgb565_components();
+			let [r8, g8, b8] = color.to_rgb888_components();
+			format!("{:#06X} (r5 {}, g6 {}, b5 {}) (rgb888 #{:02X}{:02X}{:02X})", color.to_rgb565(), r5, g6, b5, r8, g8, b8)
+		};
+
+		let delta = |a: [u8; 3], b: [u8; 3]| [0, 1, 2].map(|i| a[i] as i16 - b[i] as i16);
+		let delta565 = delta(actual.to_rgb565_components(), expected.to_rgb565_components());
+		let delta888 = delta(actual.to_rgb888_components(), expected.to_rgb888_components());
+
+		panic!("colors differ by more than {}\n  actual: {}\nexpected: {}\n   delta: {:?} in rgb565, {:?} in rgb888", max_delta, describe(actual), describe(expected), delta565, delta888);
+	}
+}
+
+/// Returns the largest difference between any channel of `a` and `b`, in 8-bit
+/// RGB.
+fn pixel_delta(a: Rgb565, b: Rgb565) -> u8 {
+	let a = a.to_rgb888_components();
+	let b = b.to_rgb888_components();
+	a.iter().zip(b.iter()).map(|(a, b)| a.abs_diff(*b)).max().unwrap_or(0)
+}
+
+fn write_failure(location: &Location, actual: &impl Frame, expected: &impl Frame, max_delta: u8) -> PathBuf {
+	static FAILURES: AtomicUsize = AtomicUsize::new(0);
+
+	let stem = Path::new(location.file()).file_stem().and_then(|stem| stem.to_str()).unwrap_or("frame");
+	let name = format!("{}-{}-{}-{}", stem, location.line(), std::process::id(), FAILURES.fetch_add(1, Ordering::Relaxed));
+	let dir = std::env::temp_dir().join("rgb565-frames").join(name);
+	std::fs::create_dir_all(&dir).expect("couldn't create directory for frame diff");
+
+	let (aw, ah) = actual.dimensions();
+	let (ew, eh) = expected.dimensions();
+	let (width, height) = (ew + aw, eh.max(ah));
+	let mut side_by_side = vec![0u8; width * height * 3];
+
+	for (frame, (fw, fh), offset) in [(expected as &dyn Frame, (ew, eh), 0), (actual as &dyn Frame, (aw, ah), ew)] {
+		for y in 0..fh {
+			for x in 0..fw {
+				let index = (y * width + offset + x) * 3;
+				side_by_side[index..index + 3].copy_from_slice(&frame.pixel(x, y).to_rgb888_components());
+			}
+		}
+	}
+
+	write_png(&dir.join("side_by_side.png"), width, height, &side_by_side).expect("couldn't write PNG for frame diff");
+
+	let (width, height) = (ew.min(aw), eh.min(ah));
+	let mut diff = vec![0u8; width * height * 3];
+
+	for y in 0..height {
+		for x in 0..width {
+			let delta = pixel_delta(actual.pixel(x, y), expected.pixel(x, y));
+			let index = (y * width + x) * 3;
+
+			diff[index..index + 3].copy_from_slice(&if delta > max_delta {
+				[128 + delta / 2, 0, 0]
+			} else {
+				[delta, delta, delta]
+			});
+		}
+	}
+
+	write_png(&dir.join("diff.png"), width, height, &diff).expect("couldn't write PNG for frame diff");
+	dir
+}
+
+/// Writes 8-bit RGB pixels as a PNG.
+pub(crate) fn write_png(path: &Path, width: usize, height: usize, rgb888: &[u8]) -> std::io::Result<()> {
+	let file = File::create(path)?;
+	let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+	encoder.set_color(png::ColorType::Rgb);
+	encoder.set_depth(png::BitDepth::Eight);
+
+	let mut writer = encoder.write_header()?;
+	writer.write_image_data(rgb888)?;
+	Ok(writer.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Frame;
+	use crate::Rgb565;
+
+	#[test]
+	fn similar_frames_pass() {
+		let expected = [[0xF800, 0x07E0], [0x001F, 0xFFFF]];
+		let actual = vec![vec![0xF800, 0x07E0], vec![0x001F, 0xFFDF]];
+		assert_eq!(actual.dimensions(), (2, 2));
+		assert_frames_similar!(actual, expected, 8);
+	}
+
+	#[test]
+	fn different_frames_write_pngs() {
+		let expected = [[0xF800, 0x07E0], [0x001F, 0xFFFF]];
+		let actual = [[0xF800, 0x07E0], [0x001F, 0x0000]];
+
+		let message = std::panic::catch_unwind(|| assert_frames_similar!(actual, expected, 8)).unwrap_err();
+		let message = message.downcast_ref::<String>().unwrap();
+		let dir = message.rsplit_once("(see ").unwrap().1.trim_end_matches(')');
+
+		assert!(message.starts_with("1 of 4 pixels differ by more than 8"));
+		assert!(std::path::Path::new(dir).join("side_by_side.png").is_file());
+		assert!(std::path::Path::new(dir).join("diff.png").is_file());
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn color_assertions() {
+		assert_color_eq!(Rgb565::from_rgb565(0x4DAB), 0x4DAB);
+		assert_color_near!(0x4DAB, 0x4DCB, 4);
+
+		let message = std::panic::catch_unwind(|| assert_color_eq!(0x4DAB, 0x4DCB)).unwrap_err();
+		let message = message.downcast_ref::<String>().unwrap();
+		assert!(message.contains("  actual: 0x4DAB (r5 9, g6 45, b5 11)"), "{}", message);
+		assert!(message.contains("expected: 0x4DCB (r5 9, g6 46, b5 11)"), "{}", message);
+		assert!(message.contains("delta: [0, -1, 0] in rgb565"), "{}", message);
+	}
+}
@@ -3,18 +3,19 @@
 use super::{srgb_transfer, srgb_untransfer};
 
 macro_rules! transforms {
-	{$($func:ident($arg:ident: $ty:ty) -> $ret:ty $calc:block)+} => {
+	{const {$($cfunc:ident($carg:ident: $cty:ty) -> $cret:ty $ccalc:block)*} $($func:ident($arg:ident: $ty:ty) -> $ret:ty $calc:block)+} => {
+		$(pub const fn $cfunc($carg: $cty) -> $cret $ccalc)*
 		$(pub fn $func($arg: $ty) -> $ret $calc)+
 	}
 }
 
 #[inline]
-pub fn unpack_565(packed: u16) -> (u8, u8, u8) {
+pub const fn unpack_565(packed: u16) -> (u8, u8, u8) {
 	((packed >> 11 & 0b11111) as u8, (packed >> 5 & 0b111111) as u8, (packed & 0b11111) as u8)
 }
 
 #[inline]
-pub fn pack_565((r5, g6, b5): (u8, u8, u8)) -> u16 {
+pub const fn pack_565((r5, g6, b5): (u8, u8, u8)) -> u16 {
 	debug_assert!(r5 & 0b11111 == r5, "r5 channel too wide");
 	debug_assert!(g6 & 0b111111 == g6, "g6 channel too wide");
 	debug_assert!(b5 & 0b11111 == b5, "b5 channel too wide");
@@ -23,16 +24,30 @@ pub fn pack_565((r5, g6, b5): (u8, u8, u8)) -> u16 {
 }
 
 transforms! {
-	swap_components(rgb565: u16) -> u16 { rgb565 & 0b11111100000 | rgb565 >> 11 | rgb565 << 11 }
+	// These are `const` so that the linear LUTs can be generated at compile
+	// time instead of by the build script.
+	const {
+		swap_components(rgb565: u16) -> u16 { rgb565 & 0b11111100000 | rgb565 >> 11 | rgb565 << 11 }
 
-	l5_to_l8(l5: u8) -> u8 { (l5 as u16 * 255 / 0b11111) as u8 }
-	l6_to_l8(l6: u8) -> u8 { (l6 as u16 * 255 / 0b111111) as u8 }
-	l5_to_s8(l5: u8) -> u8 { (srgb_transfer(l5 as f32 / 31.0) * 255.0) as u8 }
-	l6_to_s8(l6: u8) -> u8 { (srgb_transfer(l6 as f32 / 63.0) * 255.0) as u8 }
+		swap_components_x4(rgb565x4: u64) -> u64 {
+			rgb565x4 & 0x07E0_07E0_07E0_07E0 | rgb565x4 >> 11 & 0x001F_001F_001F_001F | rgb565x4 << 11 & 0xF800_F800_F800_F800
+		}
 
-	l565_to_l888(l565: u16) -> [u8; 3] {
-		let (r, g, b) = unpack_565(l565);
-		[l5_to_l8(r), l6_to_l8(g), l5_to_l8(b)]
+		l5_to_l8(l5: u8) -> u8 { (l5 as u16 * 255 / 0b11111) as u8 }
+		l6_to_l8(l6: u8) -> u8 { (l6 as u16 * 255 / 0b111111) as u8 }
+
+		l565_to_l888(l565: u16) -> [u8; 3] {
+			let (r, g, b) = unpack_565(l565);
+			[l5_to_l8(r), l6_to_l8(g), l5_to_l8(b)]
+		}
+
+		l8_to_l5(l8: u8) -> u8 { ((l8 as u16 + 1) * 0b11111 / 255) as u8 }
+		l8_to_l6(l8: u8) -> u8 { ((l8 as u16 + 1) * 0b111111 / 255) as u8 }
+
+		l888_to_l565(l888: [u8; 3]) -> u16 {
+			let [r, g, b] = l888;
+			pack_565((l8_to_l5(r), l8_to_l6(g), l8_to_l5(b)))
+		}
 	}
 
 	l565_to_s888(l565: u16) -> [u8; 3] {
@@ -40,22 +55,27 @@ transforms! {
 		[l5_to_s8(r), l6_to_s8(g), l5_to_s8(b)]
 	}
 
-	l8_to_l5(l8: u8) -> u8 { ((l8 as u16 + 1) * 0b11111 / 255) as u8 }
-	l8_to_l6(l8: u8) -> u8 { ((l8 as u16 + 1) * 0b111111 / 255) as u8 }
-	s8_to_l5(s8: u8) -> u8 { (srgb_untransfer(s8 as f32 / 255.0) * 31.999) as u8 }
-	s8_to_l6(s8: u8) -> u8 { (srgb_untransfer(s8 as f32 / 255.0) * 63.999) as u8 }
-
-	l888_to_l565(l888: [u8; 3]) -> u16 {
-		let [r, g, b] = l888;
-		pack_565((l8_to_l5(r), l8_to_l6(g), l8_to_l5(b)))
-	}
-
 	s888_to_l565(s888: [u8; 3]) -> u16 {
 		let [r, g, b] = s888;
 		pack_565((s8_to_l5(r), s8_to_l6(g), s8_to_l5(b)))
 	}
 }
 
+// With `fixed_srgb`, the sRGB channels are converted with integer math, which
+// comes out the same as these everywhere.
+#[cfg(not(feature = "fixed_srgb"))]
+transforms! {
+	const {}
+
+	l5_to_s8(l5: u8) -> u8 { (srgb_transfer(l5 as f32 / 31.0) * 255.0) as u8 }
+	l6_to_s8(l6: u8) -> u8 { (srgb_transfer(l6 as f32 / 63.0) * 255.0) as u8 }
+	s8_to_l5(s8: u8) -> u8 { (srgb_untransfer(s8 as f32 / 255.0) * 31.999) as u8 }
+	s8_to_l6(s8: u8) -> u8 { (srgb_untransfer(s8 as f32 / 255.0) * 63.999) as u8 }
+}
+
+#[cfg(feature = "fixed_srgb")]
+pub use super::fixed_srgb::{l5_to_s8, l6_to_s8, s8_to_l5, s8_to_l6};
+
 #[cfg(test)]
 mod tests {
 	#[test]
@@ -67,4 +87,17 @@ mod tests {
 		assert_eq!(super::swap_components(0b1111111111111111), 0b1111111111111111);
 		assert_eq!(super::swap_components(0b0000000000000000), 0b0000000000000000);
 	}
+
+	#[test]
+	fn swap_components_x4() {
+		for i in (0..=u16::MAX).step_by(3) {
+			let lanes = [i, !i, i.rotate_left(5), i ^ 0x5A5A];
+			let x4 = lanes.iter().rev().fold(0u64, |x4, lane| x4 << 16 | *lane as u64);
+			let swapped = super::swap_components_x4(x4);
+
+			for (n, lane) in lanes.into_iter().enumerate() {
+				assert_eq!((swapped >> (n * 16)) as u16, super::swap_components(lane));
+			}
+		}
+	}
 }
@@ -0,0 +1,249 @@
+//! Resampling frames through geometric transforms, for preparing assets on a
+//! host rather than for running on a device.
+//!
+//! Every transform maps the center of each destination pixel back into the
+//! source and samples it with [`sample`], which interpolates bilinearly.
+//! RGB565 is linear, so interpolating its channels directly blends in linear
+//! light, and edges come out as smooth as they would on the real panel. Areas
+//! outside the source are filled with a background color, which the edges of
+//! the source blend into.
+
+use crate::display::{invert, mul_vector};
+use crate::{Framebuffer565, Matrix3, Rgb565};
+
+/// The largest value of each channel.
+const MAX: [f32; 3] = [31.0, 63.0, 31.0];
+
+/// The channels of the pixel at `x`, `y` of `src`, or of `background` if it's
+/// out of bounds.
+#[inline]
+fn fetch(src: &Framebuffer565<impl AsRef<[u16]>>, x: isize, y: isize, background: [f32; 3]) -> [f32; 3] {
+	let pixel = usize::try_from(x).ok().zip(usize::try_from(y).ok()).and_then(|(x, y)| src.pixel(x, y));
+	pixel.map_or(background, |color| color.to_rgb565_components().map(f32::from))
+}
+
+/// Samples `src` at `x`, `y`, in pixels from its top left corner, so that the
+/// center of the top left pixel is at 0.5, 0.5. Between pixel centers, the
+/// four nearest pixels are interpolated bilinearly, with pixels outside the
+/// source taken to be `background`. Coordinates that aren't finite sample
+/// `background` too.
+///
+/// ```
+/// # use rgb565::{warp, Framebuffer565, Rgb565};
+/// let src = Framebuffer565::new([0x0000, 0xF800], 2, 1).unwrap();
+/// let halfway = warp::sample(&src, 1.0, 0.5, Rgb565::default());
+/// assert_eq!(halfway.to_rgb565_components(), [16, 0, 0]);
+/// ```
+#[must_use]
+pub fn sample(src: &Framebuffer565<impl AsRef<[u16]>>, x: f32, y: f32, background: Rgb565) -> Rgb565 {
+	if !x.is_finite() || !y.is_finite() {
+		return background;
+	}
+
+	let background = background.to_rgb565_components().map(f32::from);
+	let (x, y) = (x - 0.5, y - 0.5);
+	let (left, top) = (x.floor(), y.floor());
+	let (tx, ty) = (x - left, y - top);
+	// Coordinates too far out for an `isize` saturate, and stay out of bounds.
+	let (left, top) = (left as isize, top as isize);
+
+	let [a, b, c, d] = [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| fetch(src, left.saturating_add(dx), top.saturating_add(dy), background));
+	let [r, g, b] = [0, 1, 2].map(|i| {
+		let value = (a[i] * (1.0 - tx) + b[i] * tx) * (1.0 - ty) + (c[i] * (1.0 - tx) + d[i] * tx) * ty;
+		value.clamp(0.0, MAX[i]).round() as u8
+	});
+
+	Rgb565::from_rgb565_components(r, g, b)
+}
+
+/// Fills a new `width` by `height` frame by sampling `src` wherever `map`
+/// takes the center of each pixel, or with `background` where it doesn't take
+/// it anywhere.
+fn resample(src: &Framebuffer565<impl AsRef<[u16]>>, width: usize, height: usize, background: Rgb565, map: impl Fn(f32, f32) -> Option<(f32, f32)>) -> Framebuffer565<Vec<u16>> {
+	#[cfg(feature = "stats")]
+	crate::stats::frame();
+
+	let mut pixels = Vec::with_capacity(width * height);
+
+	for y in 0..height {
+		for x in 0..width {
+			let color = map(x as f32 + 0.5, y as f32 + 0.5).map_or(background, |(sx, sy)| sample(src, sx, sy, background));
+			pixels.push(color.to_rgb565());
+		}
+	}
+
+	Framebuffer565::new(pixels, width, height).expect("buffer is exactly the right size")
+}
+
+/// Rotates `src` clockwise by `angle` radians about its center. The result is
+/// just large enough to hold all of the rotated frame, with the corners it
+/// doesn't cover filled with `background`.
+///
+/// ```
+/// # use rgb565::{warp, Framebuffer565, Rgb565};
+/// let src = Framebuffer565::new(vec![0xFFFF; 20 * 10], 20, 10).unwrap();
+/// let rotated = warp::rotate(&src, core::f32::consts::FRAC_PI_4, Rgb565::default());
+/// assert_eq!((rotated.width(), rotated.height()), (22, 22));
+/// ```
+#[must_use]
+pub fn rotate(src: &Framebuffer565<impl AsRef<[u16]>>, angle: f32, background: Rgb565) -> Framebuffer565<Vec<u16>> {
+	let (sin, cos) = angle.sin_cos();
+	let (width, height) = (src.width() as f32, src.height() as f32);
+
+	// Rounding errors in `sin` and `cos` would otherwise add a column or row
+	// to rotations by multiples of 90 degrees.
+	let size = |extent: f32| (extent - 1e-3).ceil().max(0.0) as usize;
+	let (rotated_width, rotated_height) = (size(width * cos.abs() + height * sin.abs()), size(width * sin.abs() + height * cos.abs()));
+	let (center_x, center_y) = (rotated_width as f32 / 2.0, rotated_height as f32 / 2.0);
+
+	resample(src, rotated_width, rotated_height, background, |x, y| {
+		let (x, y) = (x - center_x, y - center_y);
+		Some((cos * x + sin * y + width / 2.0, cos * y - sin * x + height / 2.0))
+	})
+}
+
+/// Warps `src` into a new `width` by `height` frame with an affine transform,
+/// which can scale, rotate, shear and translate it. `matrix` takes points of
+/// `src` to points of the result, as `[x', y'] = matrix * [x, y, 1]`, with
+/// both measured in pixels from the top left corner like in [`sample`].
+/// Returns `None` if `matrix` can't be inverted.
+///
+/// ```
+/// # use rgb565::{warp, Framebuffer565, Rgb565};
+/// let src = Framebuffer565::new([0xFFFF; 4], 2, 2).unwrap();
+/// let moved = warp::affine(&src, [[1.0, 0.0, 1.0], [0.0, 1.0, 0.0]], 3, 2, Rgb565::default()).unwrap();
+/// assert_eq!(moved.into_inner(), [0x0000, 0xFFFF, 0xFFFF, 0x0000, 0xFFFF, 0xFFFF]);
+/// ```
+#[must_use]
+pub fn affine(src: &Framebuffer565<impl AsRef<[u16]>>, matrix: [[f32; 3]; 2], width: usize, height: usize, background: Rgb565) -> Option<Framebuffer565<Vec<u16>>> {
+	let [row_x, row_y] = matrix;
+	perspective(src, [row_x, row_y, [0.0, 0.0, 1.0]], width, height, background)
+}
+
+/// Warps `src` into a new `width` by `height` frame with a perspective
+/// transform (a homography), which can also make it recede into the distance
+/// or pre-distort it for a lens. `matrix` takes points of `src` to points of
+/// the result in homogeneous coordinates, as `[x' w, y' w, w] = matrix * [x,
+/// y, 1]`. Points that end up behind the viewer, where `w` isn't positive,
+/// are left as `background`. Returns `None` if `matrix` can't be inverted.
+#[must_use]
+pub fn perspective(src: &Framebuffer565<impl AsRef<[u16]>>, matrix: Matrix3, width: usize, height: usize, background: Rgb565) -> Option<Framebuffer565<Vec<u16>>> {
+	let inverse = invert(&matrix);
+
+	if !inverse.iter().flatten().all(|value| value.is_finite()) {
+		return None;
+	}
+
+	Some(resample(src, width, height, background, |x, y| {
+		let [sx, sy, w] = mul_vector(&inverse, [x, y, 1.0]);
+		(w > 0.0).then(|| (sx / w, sy / w))
+	}))
+}
+
+/// Warps `src` into a new `width` by `height` frame so that its top left, top
+/// right, bottom right and bottom left corners land on `corners`, in that
+/// order, with the perspective transform that does that. Returns `None` if
+/// the corners don't make a quadrilateral, like when three are in a line.
+///
+/// ```
+/// # use rgb565::{warp, Framebuffer565, Rgb565};
+/// let src = Framebuffer565::new(vec![0xFFFF; 16 * 16], 16, 16).unwrap();
+/// let corners = [[4.0, 0.0], [12.0, 0.0], [16.0, 16.0], [0.0, 16.0]];
+/// let keystoned = warp::quad(&src, corners, 16, 16, Rgb565::default()).unwrap();
+/// assert_eq!(keystoned.pixel(0, 0), Some(Rgb565::default()));
+/// assert_eq!(keystoned.pixel(8, 8), Some(Rgb565::from_rgb565(0xFFFF)));
+/// ```
+#[must_use]
+pub fn quad(src: &Framebuffer565<impl AsRef<[u16]>>, corners: [[f32; 2]; 4], width: usize, height: usize, background: Rgb565) -> Option<Framebuffer565<Vec<u16>>> {
+	// Heckbert's mapping from the unit square to a quadrilateral, from
+	// "Fundamentals of Texture Mapping and Image Warping", section 2.2.3.
+	let [[x0, y0], [x1, y1], [x2, y2], [x3, y3]] = corners;
+	let (dx1, dx2, dx3) = (x1 - x2, x3 - x2, x0 - x1 + x2 - x3);
+	let (dy1, dy2, dy3) = (y1 - y2, y3 - y2, y0 - y1 + y2 - y3);
+	let det = dx1 * dy2 - dx2 * dy1;
+	let (g, h) = ((dx3 * dy2 - dx2 * dy3) / det, (dx1 * dy3 - dx3 * dy1) / det);
+
+	let (sx, sy) = (1.0 / src.width() as f32, 1.0 / src.height() as f32);
+	let matrix = [
+		[(x1 - x0 + g * x1) * sx, (x3 - x0 + h * x3) * sy, x0],
+		[(y1 - y0 + g * y1) * sx, (y3 - y0 + h * y3) * sy, y0],
+		[g * sx, h * sy, 1.0]
+	];
+
+	perspective(src, matrix, width, height, background)
+}
+
+#[cfg(test)]
+mod tests {
+	use core::f32::consts::{FRAC_PI_2, PI};
+
+	use crate::frame::{self, ColumnOrder};
+	use crate::{Framebuffer565, Rgb565};
+
+	#[test]
+	fn rotate() {
+		let pixels: Vec<u16> = (0..7 * 4).map(|i| i * 2377).collect();
+		let src = Framebuffer565::new(pixels.clone(), 7, 4).unwrap();
+		let background = Rgb565::from_rgb565(0x1234);
+
+		assert_eq!(super::rotate(&src, 0.0, background), src);
+
+		let mut clockwise = vec![0; pixels.len()];
+		frame::column_major_into(&pixels, 7, &mut clockwise, ColumnOrder::CLOCKWISE);
+		assert_eq!(super::rotate(&src, FRAC_PI_2, background), Framebuffer565::new(clockwise, 4, 7).unwrap());
+
+		let mut upside_down = pixels.clone();
+		upside_down.reverse();
+		assert_eq!(super::rotate(&src, PI, background), Framebuffer565::new(upside_down, 7, 4).unwrap());
+
+		// The corners of a rotation by 45 degrees aren't covered.
+		let rotated = super::rotate(&src, PI / 4.0, background);
+		assert_eq!(rotated.pixel(0, 0), Some(background));
+		assert_ne!(rotated.pixel(rotated.width() / 2, rotated.height() / 2), Some(background));
+	}
+
+	#[test]
+	fn warps() {
+		let pixels: Vec<u16> = (0..6 * 5).map(|i: u16| i.wrapping_mul(2377)).collect();
+		let src = Framebuffer565::new(pixels.clone(), 6, 5).unwrap();
+		let background = Rgb565::from_rgb565(0x1234);
+
+		assert_eq!(super::affine(&src, [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]], 6, 5, background), Some(src.clone()));
+		assert_eq!(super::quad(&src, [[0.0, 0.0], [6.0, 0.0], [6.0, 5.0], [0.0, 5.0]], 6, 5, background), Some(src.clone()));
+		assert_eq!(super::affine(&src, [[1.0, 2.0, 0.0], [0.5, 1.0, 0.0]], 6, 5, background), None);
+		assert_eq!(super::quad(&src, [[0.0, 0.0], [1.0, 1.0], [2.0, 2.0], [0.0, 5.0]], 6, 5, background), None);
+
+		// Doubling the size puts each pixel's center where four pixels meet.
+		let doubled = super::affine(&src, [[2.0, 0.0, 0.0], [0.0, 2.0, 0.0]], 12, 10, background).unwrap();
+		assert_eq!(doubled.pixel(3, 5), Some(super::sample(&src, 1.75, 2.75, background)));
+
+		// Moving the corners to where a transposition takes them transposes.
+		let transposed = super::quad(&src, [[0.0, 0.0], [0.0, 6.0], [5.0, 6.0], [5.0, 0.0]], 5, 6, background).unwrap();
+		let mut expected = vec![0; 30];
+		frame::column_major_into(&pixels, 6, &mut expected, ColumnOrder::TRANSPOSE);
+		assert_eq!(transposed.into_inner(), expected);
+
+		// Everything maps behind the viewer.
+		let behind = super::perspective(&src, [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, -1.0]], 2, 2, background).unwrap();
+		assert_eq!(behind.into_inner(), [0x1234; 4]);
+	}
+
+	#[test]
+	fn sample() {
+		let src = Framebuffer565::new([0x0000, 0xFFFF, 0xFFFF, 0x0000], 2, 2).unwrap();
+		let black = Rgb565::default();
+
+		assert_eq!(super::sample(&src, 1.5, 0.5, black), Rgb565::from_rgb565(0xFFFF));
+		assert_eq!(super::sample(&src, 1.0, 1.0, black).to_rgb565_components(), [16, 32, 16]);
+		assert_eq!(super::sample(&src, -5.0, 1.0, black), black);
+
+		// Half a pixel off the edge is halfway into the background.
+		let white = Framebuffer565::new([0xFFFF], 1, 1).unwrap();
+		assert_eq!(super::sample(&white, 0.5, 0.0, black).to_rgb565_components(), [16, 32, 16]);
+
+		for far in [1e30, -1e30, f32::INFINITY, f32::NEG_INFINITY, f32::NAN] {
+			assert_eq!(super::sample(&src, far, 1.0, Rgb565::TEAL), Rgb565::TEAL);
+			assert_eq!(super::sample(&src, 1.0, far, Rgb565::TEAL), Rgb565::TEAL);
+		}
+	}
+}
@@ -0,0 +1,271 @@
+//! A minimal wire format for shipping frames between a host and a device, with
+//! zero-copy parsing through the `zerocopy` crate.
+//!
+//! A frame on the wire is a 16-byte [`FrameHeader`] followed by its pixels,
+//! two bytes each, in rows from the top left. All multi-byte header fields are
+//! little-endian:
+//!
+//! | Offset | Size | Field                                     |
+//! |--------|------|-------------------------------------------|
+//! | 0      | 4    | Magic, `R565`                             |
+//! | 4      | 1    | Version, currently 1                      |
+//! | 5      | 1    | Component order, 0 for RGB and 1 for BGR  |
+//! | 6      | 1    | Byte order of the pixels, 0 for little-endian and 1 for big-endian |
+//! | 7      | 1    | Reserved, 0                               |
+//! | 8      | 2    | Width in pixels                           |
+//! | 10     | 2    | Height in pixels                          |
+//! | 12     | 4    | Length of the pixels in bytes, `width * height * 2` |
+//!
+//! ```
+//! # use rgb565::{wire::{self, WireFrame}, Encoding, Rgb565};
+//! let frame = [Rgb565::from_rgb565(0xF800); 6];
+//! let mut buffer = [0; 64];
+//! let len = wire::encode(&frame, 3, Encoding::Rgb565Be, &mut buffer).unwrap();
+//!
+//! let (parsed, rest) = WireFrame::parse(&buffer[..len]).unwrap();
+//! assert_eq!((parsed.header.width.get(), parsed.header.height.get()), (3, 2));
+//! assert!(parsed.pixels().eq(frame));
+//! assert!(rest.is_empty());
+//! ```
+
+use core::fmt::{self, Display, Formatter};
+
+use zerocopy::byteorder::little_endian::{U16, U32};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
+
+use crate::{Encoding, Rgb565};
+
+/// The magic bytes every frame starts with.
+pub const MAGIC: [u8; 4] = *b"R565";
+
+/// The version of the wire format this crate reads and writes.
+pub const VERSION: u8 = 1;
+
+/// An error from parsing or encoding a frame.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum WireError {
+	/// The buffer is too short for the header or the pixels.
+	TooShort,
+	/// The frame doesn't start with [`MAGIC`].
+	BadMagic,
+	/// The frame is in a version of the format this crate doesn't know.
+	UnsupportedVersion(u8),
+	/// The component order or byte order isn't one this crate knows.
+	UnknownFormat,
+	/// The length of the pixels doesn't match the width and height.
+	LengthMismatch
+}
+
+impl Display for WireError {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::TooShort => f.write_str("buffer is too short for the frame"),
+			Self::BadMagic => f.write_str("frame doesn't start with the magic bytes"),
+			Self::UnsupportedVersion(version) => write!(f, "unsupported version {}", version),
+			Self::UnknownFormat => f.write_str("unknown component order or byte order"),
+			Self::LengthMismatch => f.write_str("length of the pixels doesn't match the width and height")
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WireError {}
+
+/// The header of a frame on the wire.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned)]
+#[repr(C)]
+pub struct FrameHeader {
+	/// Always [`MAGIC`].
+	pub magic: [u8; 4],
+	/// The version of the format, currently [`VERSION`].
+	pub version: u8,
+	/// The component order, 0 for RGB and 1 for BGR.
+	pub order: u8,
+	/// The byte order of the pixels, 0 for little-endian and 1 for big-endian.
+	pub endianness: u8,
+	/// Always zero.
+	pub reserved: u8,
+	/// The width of the frame, in pixels.
+	pub width: U16,
+	/// The height of the frame, in pixels.
+	pub height: U16,
+	/// The length of the pixels after the header, in bytes.
+	pub payload_len: U32
+}
+
+impl FrameHeader {
+	/// The header for a frame of the given size, with pixels in `encoding`, or
+	/// `None` if its pixels would be too long for `payload_len`.
+	#[must_use]
+	pub fn new(width: u16, height: u16, encoding: Encoding) -> Option<Self> {
+		let (order, endianness) = match encoding {
+			Encoding::Rgb565Le => (0, 0),
+			Encoding::Rgb565Be => (0, 1),
+			Encoding::Bgr565Le => (1, 0),
+			Encoding::Bgr565Be => (1, 1)
+		};
+
+		let payload_len = u32::try_from(u64::from(width) * u64::from(height) * 2).ok()?;
+
+		Some(Self {
+			magic: MAGIC,
+			version: VERSION,
+			order,
+			endianness,
+			reserved: 0,
+			width: U16::new(width),
+			height: U16::new(height),
+			payload_len: U32::new(payload_len)
+		})
+	}
+
+	/// The encoding of the pixels, or `None` if it's not one this crate knows.
+	#[must_use]
+	pub fn encoding(&self) -> Option<Encoding> {
+		match (self.order, self.endianness) {
+			(0, 0) => Some(Encoding::Rgb565Le),
+			(0, 1) => Some(Encoding::Rgb565Be),
+			(1, 0) => Some(Encoding::Bgr565Le),
+			(1, 1) => Some(Encoding::Bgr565Be),
+			_ => None
+		}
+	}
+}
+
+/// A frame parsed from the wire, borrowing from the buffer it was parsed from.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct WireFrame<'a> {
+	/// The header, as it is in the buffer.
+	pub header: &'a FrameHeader,
+	/// The raw pixels, in the encoding from the header.
+	pub raw: &'a [[u8; 2]],
+	/// The encoding from the header.
+	pub encoding: Encoding
+}
+
+impl<'a> WireFrame<'a> {
+	/// Parses a frame from the start of `bytes`, returning it and whatever
+	/// comes after it.
+	pub fn parse(bytes: &'a [u8]) -> Result<(Self, &'a [u8]), WireError> {
+		let (header, rest) = FrameHeader::ref_from_prefix(bytes).map_err(|_| WireError::TooShort)?;
+
+		if header.magic != MAGIC {
+			return Err(WireError::BadMagic);
+		}
+
+		if header.version != VERSION {
+			return Err(WireError::UnsupportedVersion(header.version));
+		}
+
+		let encoding = header.encoding().ok_or(WireError::UnknownFormat)?;
+		let pixels = header.width.get() as usize * header.height.get() as usize;
+
+		// `pixels * 2` can overflow on 32-bit targets, and never matches
+		// `payload_len` when it would.
+		if u64::from(header.payload_len.get()) != pixels as u64 * 2 {
+			return Err(WireError::LengthMismatch);
+		}
+
+		let (raw, rest) = <[[u8; 2]]>::ref_from_prefix_with_elems(rest, pixels).map_err(|_| WireError::TooShort)?;
+		Ok((Self { header, raw, encoding }, rest))
+	}
+
+	/// Returns the pixels of the frame, decoded.
+	pub fn pixels(&self) -> impl Iterator<Item = Rgb565> + 'a {
+		let encoding = self.encoding;
+		self.raw.iter().map(move |bytes| Rgb565::from_bytes(*bytes, encoding))
+	}
+}
+
+/// Encodes `frame`, which is `width` pixels wide, into the start of `dst` with
+/// pixels in `encoding`. Returns the number of bytes written.
+///
+/// Fails with [`WireError::TooShort`] if `dst` is too short, or with
+/// [`WireError::LengthMismatch`] if `frame` isn't a whole number of rows or is
+/// too large to describe in the header.
+pub fn encode(frame: &[Rgb565], width: u16, encoding: Encoding, dst: &mut [u8]) -> Result<usize, WireError> { encode_with(|i| frame[i], frame.len(), width, encoding, dst) }
+
+/// Encodes `len` pixels like [`encode`], taking the `i`th from `pixel`.
+pub(crate) fn encode_with(pixel: impl Fn(usize) -> Rgb565, len: usize, width: u16, encoding: Encoding, dst: &mut [u8]) -> Result<usize, WireError> {
+	let height = match len.checked_div(width as usize) {
+		Some(height) if len.is_multiple_of(width as usize) => u16::try_from(height).map_err(|_| WireError::LengthMismatch)?,
+		None if len == 0 => 0,
+		_ => return Err(WireError::LengthMismatch)
+	};
+
+	let header = FrameHeader::new(width, height, encoding).ok_or(WireError::LengthMismatch)?;
+	let (header_dst, pixels_dst) = FrameHeader::mut_from_prefix(dst).map_err(|_| WireError::TooShort)?;
+	let pixels_dst = pixels_dst.get_mut(..len * 2).ok_or(WireError::TooShort)?;
+	*header_dst = header;
+
+	for (i, dst) in pixels_dst.chunks_exact_mut(2).enumerate() {
+		dst.copy_from_slice(&pixel(i).to_bytes(encoding));
+	}
+
+	Ok(size_of::<FrameHeader>() + len * 2)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{encode, FrameHeader, WireError, WireFrame};
+	use crate::{Encoding, Rgb565};
+	use zerocopy::IntoBytes;
+
+	#[test]
+	fn layout() {
+		let header = FrameHeader::new(320, 240, Encoding::Bgr565Be).unwrap();
+		assert_eq!(header.as_bytes(), b"R565\x01\x01\x01\x00\x40\x01\xF0\x00\x00\x58\x02\x00");
+
+		assert_eq!(FrameHeader::new(65535, 65535, Encoding::Rgb565Le), None);
+		assert_eq!(FrameHeader::new(65535, 32768, Encoding::Rgb565Le).map(|header| header.payload_len.get()), Some(65535 * 32768 * 2));
+	}
+
+	#[test]
+	fn round_trip() {
+		let frame = [0x1234, 0x5678, 0x9ABC, 0xDEF0].map(Rgb565::from_rgb565);
+
+		for encoding in [Encoding::Rgb565Le, Encoding::Rgb565Be, Encoding::Bgr565Le, Encoding::Bgr565Be] {
+			let mut buffer = [0; 32];
+			let len = encode(&frame, 2, encoding, &mut buffer).unwrap();
+			assert_eq!(len, 24);
+
+			let (parsed, rest) = WireFrame::parse(&buffer).unwrap();
+			assert_eq!(parsed.encoding, encoding);
+			assert!(parsed.pixels().eq(frame));
+			assert_eq!(rest.len(), 8);
+		}
+	}
+
+	#[test]
+	fn errors() {
+		let frame = [Rgb565::default(); 4];
+		let mut buffer = [0; 24];
+		assert_eq!(encode(&frame, 3, Encoding::Rgb565Le, &mut buffer), Err(WireError::LengthMismatch));
+		assert_eq!(encode(&frame, 2, Encoding::Rgb565Le, &mut buffer[..23]), Err(WireError::TooShort));
+		assert_eq!(super::encode_with(|_| Rgb565::default(), 65535 * 65535, 65535, Encoding::Rgb565Le, &mut buffer), Err(WireError::LengthMismatch));
+		encode(&frame, 2, Encoding::Rgb565Le, &mut buffer).unwrap();
+
+		assert_eq!(WireFrame::parse(&buffer[..23]).map(|_| ()), Err(WireError::TooShort));
+
+		let mut bad = buffer;
+		bad[0] = b'X';
+		assert_eq!(WireFrame::parse(&bad).map(|_| ()), Err(WireError::BadMagic));
+
+		let mut bad = buffer;
+		bad[4] = 2;
+		assert_eq!(WireFrame::parse(&bad).map(|_| ()), Err(WireError::UnsupportedVersion(2)));
+
+		let mut bad = buffer;
+		bad[6] = 2;
+		assert_eq!(WireFrame::parse(&bad).map(|_| ()), Err(WireError::UnknownFormat));
+
+		let mut bad = buffer;
+		bad[12] = 7;
+		assert_eq!(WireFrame::parse(&bad).map(|_| ()), Err(WireError::LengthMismatch));
+
+		// 65535 by 65535 pixels take more bytes than `payload_len` can hold.
+		let mut bad = buffer;
+		bad[8..16].copy_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFE, 0xFF, 0xFF, 0xFF]);
+		assert_eq!(WireFrame::parse(&bad).map(|_| ()), Err(WireError::LengthMismatch));
+	}
+}